@@ -17,9 +17,9 @@ fn main() -> std::io::Result<()> {
 
     println!("Blocking Consumer: Waiting for messages...");
 
-    loop {
-        match consumer.receive_blocking() {
-            Ok(data) => {
+    for message in consumer.blocking_iter() {
+        match message {
+            Ok((_meta, data)) => {
                 let msg = String::from_utf8_lossy(&data);
                 println!("Received: {}", msg);
             }
@@ -30,5 +30,6 @@ fn main() -> std::io::Result<()> {
         }
     }
 
+    println!("Blocking Consumer: Producer closed the channel");
     Ok(())
 }
@@ -16,8 +16,14 @@ typedef struct {
     uint32_t payload_len;
 } FFIMessageMeta;
 
+typedef struct {
+    const uint8_t* data;
+    size_t len;
+} DmxpMsg;
+
+int32_t dmxp_init(size_t size_bytes);
 void* dmxp_producer_new(uint32_t channel_id, uint32_t capacity);
-int32_t dmxp_producer_send_batch(void* handle, const uint8_t** data_ptrs, const size_t* data_lens, size_t count);
+int32_t dmxp_producer_send_batch(void* handle, const DmxpMsg* msgs, size_t count, size_t* out_sent);
 void dmxp_producer_free(void* handle);
 
 void* dmxp_consumer_new(uint32_t channel_id);
@@ -37,6 +43,9 @@ int main() {
 
     printf("Starting C Batch Benchmark (Batch Size: %d)...\n", BATCH_SIZE);
     
+    // 0. Attach to (or create) the shared memory segment
+    if (dmxp_init(128 * 1024 * 1024) != DMXP_SUCCESS) { fprintf(stderr, "Failed to init shared memory\n"); return 1; }
+
     // 1. Setup Producer
     void* producer = dmxp_producer_new(channel_id, capacity);
     if (!producer) { fprintf(stderr, "Failed to create producer\n"); return 1; }
@@ -46,21 +55,21 @@ int main() {
     if (!consumer) { fprintf(stderr, "Failed to create consumer\n"); return 1; }
 
     // Prepare Batch
-    const uint8_t* payloads[BATCH_SIZE];
-    size_t lengths[BATCH_SIZE];
+    DmxpMsg msgs[BATCH_SIZE];
     uint8_t buffer_pool[BATCH_SIZE][32];
+    size_t sent = 0;
 
     for(int i=0; i<BATCH_SIZE; i++) {
         memset(buffer_pool[i], 'B', 32);
-        payloads[i] = buffer_pool[i];
-        lengths[i] = 32;
+        msgs[i].data = buffer_pool[i];
+        msgs[i].len = 32;
     }
 
     uint8_t rx_buf[128];
     size_t rx_len = 128;
     
     // Heat up
-    dmxp_producer_send_batch(producer, payloads, lengths, BATCH_SIZE);
+    dmxp_producer_send_batch(producer, msgs, BATCH_SIZE, &sent);
     for(int k=0; k<BATCH_SIZE; k++) {
         rx_len = 128;
         dmxp_consumer_receive_ext(consumer, 0, rx_buf, &rx_len, NULL);
@@ -72,8 +81,8 @@ int main() {
 
     for (int i = 0; i < ITERATIONS; i++) {
         // Batch Send
-        int res_send = dmxp_producer_send_batch(producer, payloads, lengths, BATCH_SIZE);
-        if (res_send != DMXP_SUCCESS) { printf("Batch Send failed at %d: %d\n", i, res_send); break; }
+        int res_send = dmxp_producer_send_batch(producer, msgs, BATCH_SIZE, &sent);
+        if (res_send != DMXP_SUCCESS || sent != BATCH_SIZE) { printf("Batch Send failed at %d: %d\n", i, res_send); break; }
 
         // Individual Receives (Consumer doesn't have batch receive yet)
         for(int k=0; k<BATCH_SIZE; k++) {
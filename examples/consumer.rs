@@ -22,17 +22,7 @@ fn main() -> std::io::Result<()> {
     match dmxp_kvcache::Core::alloc::SharedMemoryAllocator::attach(128 * 1024 * 1024) {
         Ok(allocator) => {
             println!("Consumer: Successfully attached to shared memory");
-            println!(
-                "Consumer: Found {} active channels",
-                allocator.channel_count()
-            );
-
-            // List all available channels
-            let all_channels = allocator.get_channels();
-            println!(
-                "Consumer: Available channel IDs: {:?}",
-                all_channels.iter().map(|c| c.id()).collect::<Vec<_>>()
-            );
+            println!("{}", allocator.diagnostics());
         }
         Err(e) => {
             eprintln!("Consumer: Failed to attach to shared memory: {}", e);
@@ -41,30 +31,22 @@ fn main() -> std::io::Result<()> {
         }
     }
 
-    // Create consumers for all channels
-    let mut consumers = Vec::new();
-    for channel_id in 0..num_channels {
-        match ChannelBuilder::new()
-            .with_channel_id(channel_id as u32)
-            .build_consumer()
-        {
-            Ok(consumer) => {
-                consumers.push(consumer);
-            }
-            Err(e) => {
-                eprintln!(
-                    "Failed to create consumer for channel {}: {}",
-                    channel_id, e
-                );
-                return Ok(());
-            }
+    // One consumer for all channels, scanned in a fair rotation
+    let channel_ids: Vec<u32> = (0..num_channels as u32).collect();
+    let consumer = match ChannelBuilder::new().build_multi_consumer(&channel_ids) {
+        Ok(consumer) => consumer,
+        Err(e) => {
+            eprintln!(
+                "Failed to create consumer for channels {:?}: {}",
+                channel_ids, e
+            );
+            return Ok(());
         }
-    }
+    };
 
-    println!("Consumer: Found {} channels", consumers.len());
+    println!("Consumer: Found {} channels", channel_ids.len());
     println!("Consumer: Receiving messages from all channels...");
 
-    // Start receiving from all channels in round-robin
     let start = std::time::Instant::now();
     let mut total_received = 0;
     let mut channel_received = vec![0; num_channels];
@@ -72,36 +54,26 @@ fn main() -> std::io::Result<()> {
     let max_consecutive_empty = 1000; // Stop if no messages for 1000 iterations
 
     while total_received < total_expected {
-        let mut received_this_round = false;
-
-        // Try to receive from each channel
-        for (channel_id, consumer) in consumers.iter().enumerate() {
-            match consumer.receive() {
-                Ok(Some(_data)) => {
-                    total_received += 1;
-                    channel_received[channel_id] += 1;
-                    received_this_round = true;
-                    consecutive_empty = 0;
-                }
-                Ok(None) => {
-                    // No message available
-                }
-                Err(e) => {
-                    eprintln!("Channel {}: Error receiving message: {}", channel_id, e);
+        match consumer.receive_any() {
+            Ok(Some((channel_id, _meta, _data))) => {
+                total_received += 1;
+                channel_received[channel_id as usize] += 1;
+                consecutive_empty = 0;
+            }
+            Ok(None) => {
+                consecutive_empty += 1;
+                if consecutive_empty >= max_consecutive_empty {
+                    eprintln!(
+                        "No messages received for {} iterations, stopping...",
+                        max_consecutive_empty
+                    );
+                    break;
                 }
+                std::thread::yield_now();
             }
-        }
-
-        if !received_this_round {
-            consecutive_empty += 1;
-            if consecutive_empty >= max_consecutive_empty {
-                eprintln!(
-                    "No messages received for {} iterations, stopping...",
-                    max_consecutive_empty
-                );
-                break;
+            Err(e) => {
+                eprintln!("Error receiving message: {}", e);
             }
-            std::thread::yield_now();
         }
 
         // Progress indicator
@@ -23,7 +23,7 @@ class FFIMessageMeta(Structure):
     ]
 
 class DMXP:
-    def __init__(self, lib_path=None):
+    def __init__(self, lib_path=None, shm_size=128 * 1024 * 1024):
         if lib_path is None:
             paths = [
                 "./target/debug/libdmxp_kvcache.so",
@@ -41,6 +41,9 @@ class DMXP:
         self.lib = ctypes.CDLL(lib_path)
         
         # Init functions
+        self.lib.dmxp_init.argtypes = [c_size_t]
+        self.lib.dmxp_init.restype = c_int
+
         self.lib.dmxp_channel_count.argtypes = []
         self.lib.dmxp_channel_count.restype = c_int
         
@@ -65,6 +68,11 @@ class DMXP:
         self.lib.dmxp_consumer_free.argtypes = [c_void_p]
         self.lib.dmxp_consumer_free.restype = None
 
+        # Attach to (or create) the shared memory segment once per process
+        rc = self.lib.dmxp_init(shm_size)
+        if rc != DMXP_SUCCESS:
+            raise RuntimeError(f"dmxp_init failed with code {rc}")
+
     def channel_count(self):
         return self.lib.dmxp_channel_count()
 
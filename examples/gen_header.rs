@@ -0,0 +1,23 @@
+// Regenerates include/dmxp.h from the FFI in src/ffi.rs using cbindgen.toml.
+//
+// Usage: cargo run --example gen_header [output path]
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate_dir.join("include").join("dmxp.h"));
+
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate bindings")
+        .write_to_file(&output);
+
+    println!("Wrote {}", output.display());
+}
@@ -17,6 +17,7 @@ typedef struct {
 } FFIMessageMeta;
 
 // Forward declarations of Rust FFI functions
+int32_t dmxp_init(size_t size_bytes);
 void* dmxp_consumer_new(uint32_t channel_id);
 int32_t dmxp_consumer_receive_ext(void* handle, int32_t timeout_ms, uint8_t* out_buf, size_t* out_len, FFIMessageMeta* out_meta);
 void dmxp_consumer_free(void* handle);
@@ -32,6 +33,12 @@ func main() {
     channelID := uint32(100)
 	fmt.Printf("Go Consumer connecting to channel %d...\n", channelID)
 
+	// Attach to the shared memory segment once per process
+	if rc := C.dmxp_init(C.size_t(128 * 1024 * 1024)); rc != 0 {
+		fmt.Printf("dmxp_init failed with code %d\n", rc)
+		os.Exit(1)
+	}
+
 	// Create Consumer
 	handle := C.dmxp_consumer_new(C.uint32_t(channelID))
 	if handle == nil {
@@ -1,6 +1,6 @@
 // In examples/producer.rs
 use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
-use dmxp_kvcache::MPMC::ChannelBuilder;
+use dmxp_kvcache::MPMC::{ChannelBuilder, Policy};
 use sha2::{Digest, Sha256};
 use std::env;
 use std::sync::atomic::Ordering;
@@ -61,15 +61,14 @@ fn main() -> std::io::Result<()> {
     // Try to attach to existing shared memory first to see what channels exist
     let existing_channels = match SharedMemoryAllocator::attach(128 * 1024 * 1024) {
         Ok(allocator) => {
-            let channels = allocator.get_channels();
-            println!(
-                "Producer: Found existing shared memory with {} channels",
-                channels.len()
-            );
-            for ch in &channels {
-                println!("  - Channel {}: {} slots", ch.id(), ch.capacity);
-            }
-            channels.into_iter().map(|ch| ch.id()).collect::<Vec<_>>()
+            let diagnostics = allocator.diagnostics();
+            println!("Producer: Found existing shared memory");
+            println!("{}", diagnostics);
+            diagnostics
+                .channels
+                .iter()
+                .map(|ch| ch.info.id)
+                .collect::<Vec<_>>()
         }
         Err(_) => {
             println!("Producer: No existing shared memory found, will create new channels");
@@ -82,40 +81,25 @@ fn main() -> std::io::Result<()> {
     for channel_id in 0..num_channels {
         let channel_id_u32 = channel_id as u32;
 
-        if existing_channels.contains(&channel_id_u32) {
-            // Channel exists, connect to it
-            match ChannelBuilder::new()
-                .with_channel_id(channel_id_u32)
-                .with_capacity(capacity)
-                .build_producer()
-            {
-                Ok(producer) => {
+        let existed = existing_channels.contains(&channel_id_u32);
+
+        match ChannelBuilder::new()
+            .with_channel_id(channel_id_u32)
+            .with_capacity(capacity)
+            .with_create_policy(Policy::CreateOrAttach)
+            .build_producer()
+        {
+            Ok(producer) => {
+                if existed {
                     println!("Producer: Connected to existing channel {}", channel_id);
-                    producers.push(producer);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Producer: Failed to connect to channel {}: {}",
-                        channel_id, e
-                    );
-                    return Err(e);
-                }
-            }
-        } else {
-            // Channel doesn't exist, create it
-            match ChannelBuilder::new()
-                .with_channel_id(channel_id_u32)
-                .with_capacity(capacity)
-                .build_producer()
-            {
-                Ok(producer) => {
+                } else {
                     println!("Producer: Created new channel {}", channel_id);
-                    producers.push(producer);
-                }
-                Err(e) => {
-                    eprintln!("Producer: Failed to create channel {}: {}", channel_id, e);
-                    return Err(e);
                 }
+                producers.push(producer);
+            }
+            Err(e) => {
+                eprintln!("Producer: Failed to open channel {}: {}", channel_id, e);
+                return Err(e);
             }
         }
     }
@@ -150,35 +134,23 @@ fn main() -> std::io::Result<()> {
             let hash = &hashes[msg_idx];
             let message = format!("{}:{}:{}", channel_id, msg_idx, hash);
 
-            // Try to send with limited retries
-            let mut retries = 0;
-            let max_retries = 10;
-
-            loop {
-                match producer.send(&message) {
-                    Ok(()) => {
-                        total_sent += 1;
-                        channel_sent[channel_id] += 1;
-                        break;
-                    }
-                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        retries += 1;
-                        if retries >= max_retries {
-                            println!("Channel {}: Buffer full, stopping sends to this channel (sent {} messages)", 
-                                     channel_id, channel_sent[channel_id]);
-                            channel_full[channel_id] = true;
-                            break;
-                        }
-                        std::thread::sleep(std::time::Duration::from_micros(100));
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "Channel {}: Failed to send message {}: {}",
-                            channel_id, msg_idx, e
-                        );
-                        channel_full[channel_id] = true;
-                        break;
-                    }
+            // Give a full channel a moment to drain before giving up on it
+            match producer.send_timeout(&message, std::time::Duration::from_millis(1)) {
+                Ok(()) => {
+                    total_sent += 1;
+                    channel_sent[channel_id] += 1;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                    println!("Channel {}: Buffer full, stopping sends to this channel (sent {} messages)", 
+                             channel_id, channel_sent[channel_id]);
+                    channel_full[channel_id] = true;
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Channel {}: Failed to send message {}: {}",
+                        channel_id, msg_idx, e
+                    );
+                    channel_full[channel_id] = true;
                 }
             }
         }
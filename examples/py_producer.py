@@ -7,6 +7,10 @@ import os
 lib_path = os.path.abspath("target/debug/libdmxp_kvcache.so")
 lib = ctypes.CDLL(lib_path)
 
+# Define Init
+lib.dmxp_init.argtypes = [c_size_t]
+lib.dmxp_init.restype = ctypes.c_int32
+
 # Define Producer New
 lib.dmxp_producer_new.argtypes = [c_uint32, c_uint32]
 lib.dmxp_producer_new.restype = c_void_p
@@ -19,6 +23,11 @@ def main():
     channel_id = 100
     print(f"Python Producer creating channel {channel_id}...")
     
+    # Attach to (or create) the 128MB shared memory segment
+    if lib.dmxp_init(128 * 1024 * 1024) != 0:
+        print("Failed to initialize shared memory")
+        return
+
     # Create producer (Capacity 1024)
     producer = lib.dmxp_producer_new(channel_id, 1024)
     if not producer:
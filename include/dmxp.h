@@ -0,0 +1,346 @@
+/*
+ * C interface to the DMXP shared-memory channels.
+ *
+ * Call dmxp_init(size_bytes) once per process before anything else: it
+ * creates or attaches the shared memory segment that every other function
+ * uses. Until then they fail with DMXP_ERROR_INVALID_ARG (or return NULL).
+ * The dmxp_allocator_* functions are the exception: they work on their own
+ * handle from dmxp_allocator_open.
+ *
+ * Every function returns one of the DMXP_* codes unless documented otherwise.
+ * On failure, dmxp_last_error() describes what went wrong on that thread.
+ * Handles are opaque; free them with the matching dmxp_*_free function.
+ */
+
+#ifndef DMXP_H
+#define DMXP_H
+
+/* Generated by `cargo run --example gen_header` from src/ffi.rs. Do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ The default size of the inline payload per slot, for channels created
+ without `ChannelSpec::with_inline_size`.
+ */
+#define MSG_INLINE 1024
+
+/*
+ Alignment of every slot, and granularity of the slot stride.
+ */
+#define SLOT_ALIGN 64
+
+/*
+ Byte offset of `Slot::sequence` (a little-endian `u64`) within a slot.
+
+ A producer that finds `sequence == tail` may claim the slot by advancing
+ `ChannelEntry::tail` from `tail` to `tail + 1` with a compare-and-swap. It
+ then writes the metadata and payload and publishes them by storing
+ `tail + 1` into `sequence` with release ordering. A consumer reads the slot
+ once `sequence == head + 1` (loaded with acquire ordering) and it has
+ advanced `head`, and hands it back by storing `head + capacity`.
+ */
+#define SLOT_SEQUENCE_OFFSET 0
+
+/*
+ Byte offset of the `MessageMeta` within a slot.
+ */
+#define SLOT_META_OFFSET 8
+
+/*
+ Byte offset of the inline payload within a slot. The payload holds
+ `MessageMeta::payload_len` bytes, at most the channel's inline size.
+ */
+#define SLOT_PAYLOAD_OFFSET 56
+
+/*
+ Version of the shared memory layout, stored in `GlobalHeader::version` by
+ the creating process. `attach` refuses segments with any other version
+ rather than misreading them. Version 2 sizes the channel table from
+ `GlobalHeader::max_channels` instead of a fixed 256 entries; version 3
+ records each channel's inline payload size in `ChannelEntry::inline_size`;
+ version 4 adds the channel ID bitmap after the channel table; version 5
+ counts parked consumers in `ChannelEntry::data_waiters`, without which
+ producers would skip their wakeups; version 6 stamps
+ `GlobalHeader::byte_order`; version 7 adds the `EventLog` after the ID
+ bitmap; version 8 adds `ChannelEntry::layout_generation`, which views
+ check before touching slots.
+ */
+#define LAYOUT_VERSION 8
+
+/*
+ The call succeeded.
+ */
+#define DMXP_SUCCESS 0
+
+/*
+ A required pointer argument was null.
+ */
+#define DMXP_ERROR_NULL_POINTER -1
+
+/*
+ An argument was out of range, e.g. an output buffer too small for the message.
+ */
+#define DMXP_ERROR_INVALID_ARG -2
+
+/*
+ The channel is full; retry the send later.
+ */
+#define DMXP_ERROR_CHANNEL_FULL -4
+
+/*
+ No message was available.
+ */
+#define DMXP_ERROR_EMPTY -5
+
+/*
+ Any other failure.
+ */
+#define DMXP_ERROR_INTERNAL -6
+
+/*
+ No message arrived before the timeout.
+ */
+#define DMXP_ERROR_TIMEOUT -7
+
+/*
+ Handle to an attached shared memory segment (opaque pointer)
+ */
+typedef struct AllocatorHandle AllocatorHandle;
+
+/*
+ Handle to a consumer instance (opaque pointer)
+ */
+typedef struct ConsumerHandle ConsumerHandle;
+
+/*
+ Handle to a producer instance (opaque pointer)
+ */
+typedef struct ProducerHandle ProducerHandle;
+
+/*
+ Capacity and occupancy of a channel, filled by `dmxp_get_channel_info`.
+ */
+typedef struct DmxpChannelInfo {
+  /*
+   The channel ID.
+   */
+  uint32_t id;
+  /*
+   Number of slots in the ring.
+   */
+  uint64_t capacity;
+  /*
+   Messages waiting to be received.
+   */
+  uint64_t used_slots;
+  /*
+   Byte offset of the channel's region in the shared memory segment.
+   */
+  uint64_t offset;
+} DmxpChannelInfo;
+
+/*
+ One message of a `dmxp_producer_send_batch` call.
+ */
+typedef struct DmxpMsg {
+  /*
+   The payload; must not be null.
+   */
+  const uint8_t *data;
+  /*
+   Payload length in bytes.
+   */
+  size_t len;
+} DmxpMsg;
+
+/*
+ Structure to return metadata to FFI caller
+ Must match simple layout for C
+ */
+typedef struct FFIMessageMeta {
+  uint64_t message_id;
+  uint64_t timestamp_ns;
+  uint32_t channel_id;
+  uint32_t message_type;
+  uint32_t sender_pid;
+  uint16_t sender_runtime;
+  uint16_t flags;
+  uint32_t payload_len;
+  uint32_t checksum;
+} FFIMessageMeta;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Copy the message for the last error returned on this thread into `buf`.
+
+ Every `dmxp_*` function that returns a negative code (or NULL) records why;
+ successful calls leave the message untouched. The message is truncated to
+ fit `len - 1` bytes and always null-terminated, unless `buf` is NULL or
+ `len` is 0, in which case nothing is written.
+
+ # Returns
+ * The full message length in bytes, excluding the terminator. A value
+   `>= len` means the message was truncated. 0 if no error was recorded.
+ */
+size_t dmxp_last_error(char *buf, size_t len);
+
+/*
+ Attach to the shared memory segment, creating it if it does not exist yet.
+
+ Must be called once per process before any other `dmxp_*` function; they
+ all use the segment set up here. `size_bytes` is the size of the whole
+ segment (at least a few MB; 128MB is the Rust default). Calling it again
+ with the same size is a no-op.
+
+ # Returns
+ * 0 on success.
+ * `DMXP_ERROR_INVALID_ARG` if `size_bytes` is 0, or the process was already
+   initialized with a different size.
+ * `DMXP_ERROR_INTERNAL` if the segment could not be created or attached,
+   e.g. it exists with a smaller size.
+ */
+int32_t dmxp_init(size_t size_bytes);
+
+/*
+ Get the number of active channels.
+
+ # Returns
+ * The channel count, or `DMXP_ERROR_INVALID_ARG` if `dmxp_init` was not called.
+ */
+int32_t dmxp_channel_count(void);
+
+/*
+ Get a list of active channel IDs.
+
+ # Arguments
+ * `out_buf` - Buffer to write u32 channel IDs into.
+ * `max_count` - Maximum number of IDs to write.
+ * `out_count` - Output: Actual number of IDs written.
+
+ # Returns
+ * 0 on success.
+ * `DMXP_ERROR_INVALID_ARG` if `dmxp_init` was not called.
+ */
+int32_t dmxp_list_channels(uint32_t *out_buf, size_t max_count, size_t *out_count);
+
+/*
+ Describe channel `channel_id`.
+
+ `used_slots` is a snapshot; producers and consumers may change it at any time.
+
+ # Returns
+ * 0 on success.
+ * `DMXP_ERROR_INVALID_ARG` if the channel does not exist or `dmxp_init` was
+   not called.
+ */
+int32_t dmxp_get_channel_info(uint32_t channel_id, struct DmxpChannelInfo *out);
+
+/*
+ Attach to the existing shared memory segment of `size_bytes` and keep it
+ mapped until `dmxp_allocator_close`.
+
+ Meant for monitors: the handle needs no `dmxp_init`, never creates the
+ segment, and queries through it reuse the one mapping. Returns null if the
+ segment does not exist or is smaller than `size_bytes`.
+ */
+struct AllocatorHandle *dmxp_allocator_open(size_t size_bytes);
+
+/*
+ `dmxp_channel_count` through an allocator handle.
+ */
+int32_t dmxp_allocator_channel_count(struct AllocatorHandle *handle);
+
+/*
+ `dmxp_list_channels` through an allocator handle.
+ */
+int32_t dmxp_allocator_list_channels(struct AllocatorHandle *handle, uint32_t *out_buf, size_t max_count, size_t *out_count);
+
+/*
+ `dmxp_get_channel_info` through an allocator handle.
+ */
+int32_t dmxp_allocator_get_channel_info(struct AllocatorHandle *handle, uint32_t channel_id, struct DmxpChannelInfo *out);
+
+/*
+ Unmap the segment opened by `dmxp_allocator_open`. Channels are unaffected.
+ */
+void dmxp_allocator_close(struct AllocatorHandle *handle);
+
+/*
+ Create a producer on `channel_id`, creating the channel with `capacity`
+ slots if it does not exist. Returns null on failure, including when
+ `dmxp_init` was not called.
+ */
+struct ProducerHandle *dmxp_producer_new(uint32_t channel_id, uint32_t capacity);
+
+/*
+ Send a batch of messages, in order, with one call.
+
+ `msgs` points to `count` messages; each `data` must be non-null. As many
+ messages as fit are sent, always a prefix of the batch, and the number
+ accepted is written to `out_sent` (also on error).
+
+ # Returns
+ * 0 if at least one message was sent (check `out_sent` for the rest).
+ * `DMXP_ERROR_CHANNEL_FULL` if none fit.
+ * `DMXP_ERROR_NULL_POINTER` if `msgs`, `out_sent` or any `data` is null;
+   nothing is sent.
+ * `DMXP_ERROR_INVALID_ARG` if a message exceeds the channel's maximum
+   message size; nothing is sent.
+ */
+int32_t dmxp_producer_send_batch(struct ProducerHandle *handle, const struct DmxpMsg *msgs, size_t count, size_t *out_sent);
+
+int32_t dmxp_producer_send(struct ProducerHandle *handle, const uint8_t *data, size_t len);
+
+void dmxp_producer_free(struct ProducerHandle *handle);
+
+/*
+ Create a consumer on the existing channel `channel_id`. Returns null on
+ failure, including when `dmxp_init` was not called.
+ */
+struct ConsumerHandle *dmxp_consumer_new(uint32_t channel_id);
+
+/*
+ Receive a message with full metadata.
+
+ # Arguments
+ * `timeout_ms` -
+     - -1: Blocking (wait forever)
+     -  0: Non-blocking (return immediately)
+     - >0: Wait for X milliseconds
+ * `out_meta` - Pointer to `FFIMessageMeta` struct to fill.
+ */
+int32_t dmxp_consumer_receive_ext(struct ConsumerHandle *handle, int32_t timeout_ms, uint8_t *out_buf, size_t *out_len, struct FFIMessageMeta *out_meta);
+
+/*
+ Same as above but strictly for backwards compatibility/simplicity
+ */
+int32_t dmxp_consumer_receive(struct ConsumerHandle *handle, bool blocking, uint8_t *out_buf, size_t *out_len);
+
+/*
+ Fill `out_meta` with the metadata of the next message without consuming it.
+
+ The payload is not copied; `payload_len` tells how large a buffer the
+ following `dmxp_consumer_receive_ext` needs. Peeking reserves nothing:
+ another consumer on the channel may take the message between the peek and
+ the receive, which then returns the next message (or none).
+
+ # Returns
+ * 0 if a message is waiting.
+ * `DMXP_ERROR_EMPTY` if nothing is ready.
+ * `DMXP_ERROR_INTERNAL` if the producer has terminated.
+ */
+int32_t dmxp_consumer_peek(struct ConsumerHandle *handle, struct FFIMessageMeta *out_meta);
+
+void dmxp_consumer_free(struct ConsumerHandle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DMXP_H */
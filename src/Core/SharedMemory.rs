@@ -20,6 +20,27 @@ pub trait SharedMemoryBackend: Send + Sync + Debug {
 
     /// Returns a platform-specific handle to the shared memory.
     fn raw_handle(&self) -> RawHandle;
+
+    /// Returns whether this handle created the region, as opposed to attaching
+    /// to one created elsewhere.
+    fn is_creator(&self) -> bool;
+
+    /// Sets whether dropping this handle removes the region's name, so no
+    /// other process can attach to it any more. Only honored by the creating
+    /// handle of a named region; existing mappings stay valid either way.
+    fn set_unlink_on_drop(&mut self, enabled: bool);
+
+    /// Returns how many bytes of `len` starting at `offset` are backed by
+    /// pages currently resident in RAM. Pages straddling the range count only
+    /// for the part inside it. Fails with `InvalidInput` if the range does not
+    /// lie within the region.
+    fn resident_bytes_in(&self, offset: usize, len: usize) -> io::Result<usize>;
+
+    /// Returns how many bytes of the whole region are currently resident in RAM,
+    /// e.g. to check that prefaulting worked or to watch the working set grow.
+    fn resident_bytes(&self) -> io::Result<usize> {
+        self.resident_bytes_in(0, self.size())
+    }
 }
 
 /// Platform-specific handle type
@@ -44,6 +65,15 @@ pub fn attach_shared_memory(name: &str, size: usize) -> io::Result<Box<dyn Share
     Ok(Box::new(linux::LinuxSharedMemory::attach(name, size)?))
 }
 
+/// Attaches to a shared memory region through `fd`, an open descriptor of it
+/// received from another process (for example over a Unix socket), without
+/// opening any path. The region takes ownership of `fd` and closes it when
+/// dropped, or right away if attaching fails; pass a duplicate to keep one.
+#[cfg(target_os = "linux")]
+pub fn attach_shared_memory_fd(fd: RawFd, size: usize) -> io::Result<Box<dyn SharedMemoryBackend>> {
+    Ok(Box::new(linux::LinuxSharedMemory::attach_fd(fd, size)?))
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn create_shared_memory(
     _size: usize,
@@ -63,6 +93,17 @@ pub fn attach_shared_memory(_name: &str, _size: usize) -> io::Result<Box<dyn Sha
     ))
 }
 
+#[cfg(not(target_os = "linux"))]
+pub fn attach_shared_memory_fd(
+    _fd: RawFd,
+    _size: usize,
+) -> io::Result<Box<dyn SharedMemoryBackend>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Shared memory only supported on Linux",
+    ))
+}
+
 #[cfg(target_os = "linux")]
 mod linux {
     use super::*;
@@ -79,6 +120,10 @@ mod linux {
         fd: RawFd,
         // Stores the original pointer and size if we had to align it manually
         original_ptr: Option<(*mut u8, usize)>,
+        // The /dev/shm file backing the region; None for anonymous memory
+        path: Option<String>,
+        creator: bool,
+        unlink_on_drop: bool,
     }
 
     unsafe impl Send for LinuxSharedMemory {}
@@ -107,7 +152,7 @@ mod linux {
                 return Err(err);
             }
 
-            Self::mmap(fd, size)
+            Self::mmap(fd, size, None, true)
         }
 
         fn create_file_backed(size: usize, name: &str) -> io::Result<Self> {
@@ -133,7 +178,7 @@ mod linux {
 
             // We need to keep the fd open, so we convert it to raw and forget the File
             let fd = file.into_raw_fd();
-            Self::mmap(fd, size)
+            Self::mmap(fd, size, Some(path), true)
         }
 
         pub fn attach(name: &str, expected_size: usize) -> io::Result<Self> {
@@ -161,10 +206,34 @@ mod linux {
             }
 
             let fd = file.into_raw_fd();
-            Self::mmap(fd, size)
+            Self::mmap(fd, size, Some(path), false)
         }
 
-        fn mmap(fd: RawFd, size: usize) -> io::Result<Self> {
+        /// Map the region behind `fd`, taking ownership of it.
+        pub fn attach_fd(fd: RawFd, expected_size: usize) -> io::Result<Self> {
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+                let err = io::Error::last_os_error();
+                unsafe { libc::close(fd) };
+                return Err(err);
+            }
+
+            let size = stat.st_size as usize;
+            if size < expected_size {
+                unsafe { libc::close(fd) };
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Shared memory size too small: expected at least {}, got {}",
+                        expected_size, size
+                    ),
+                ));
+            }
+
+            Self::mmap(fd, size, None, false)
+        }
+
+        fn mmap(fd: RawFd, size: usize, path: Option<String>, creator: bool) -> io::Result<Self> {
             // We map slightly more to ensure we can align to 128 bytes if needed
             // But mmap usually returns page-aligned memory (4096 bytes), which is > 128.
             // So we just map the size.
@@ -195,6 +264,9 @@ mod linux {
                 size,
                 fd,
                 original_ptr: Some((ptr as *mut u8, map_size)),
+                path,
+                creator,
+                unlink_on_drop: false,
             })
         }
     }
@@ -207,6 +279,11 @@ mod linux {
                 }
                 libc::close(self.fd);
             }
+            if self.unlink_on_drop && self.creator {
+                if let Some(path) = &self.path {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
         }
     }
 
@@ -222,5 +299,56 @@ mod linux {
         fn raw_handle(&self) -> RawHandle {
             RawHandle::Fd(self.fd)
         }
+
+        fn is_creator(&self) -> bool {
+            self.creator
+        }
+
+        fn set_unlink_on_drop(&mut self, enabled: bool) {
+            self.unlink_on_drop = enabled;
+        }
+
+        fn resident_bytes_in(&self, offset: usize, len: usize) -> io::Result<usize> {
+            if offset.checked_add(len).is_none_or(|end| end > self.size) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Range {}+{} is outside the {}-byte region",
+                        offset, len, self.size
+                    ),
+                ));
+            }
+            if len == 0 {
+                return Ok(0);
+            }
+
+            // mincore wants a page-aligned start; widen the range to whole pages
+            let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+            let start = self.ptr.as_ptr() as usize + offset;
+            let end = start + len;
+            let first = start & !(page - 1);
+            let pages = (end - first).div_ceil(page);
+            let mut residency = vec![0u8; pages];
+            if unsafe {
+                libc::mincore(
+                    first as *mut libc::c_void,
+                    end - first,
+                    residency.as_mut_ptr(),
+                )
+            } != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(residency
+                .iter()
+                .enumerate()
+                .filter(|(_, state)| *state & 1 != 0)
+                .map(|(i, _)| {
+                    let page_start = first + i * page;
+                    (page_start + page).min(end) - page_start.max(start)
+                })
+                .sum())
+        }
     }
 }
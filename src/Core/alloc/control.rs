@@ -0,0 +1,194 @@
+// The segment's control channel: a broadcast channel in the last entry of the
+// channel table that carries operational commands to every process watching it.
+
+use super::*;
+use crate::MPMC::Buffer::layout::CHANNEL_FLAG_CONTROL;
+use crate::MPMC::ClockSource;
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+use std::marker::PhantomData;
+
+/// Slots in the control channel's ring.
+const CONTROL_CAPACITY: usize = 64;
+
+/// An operational command sent over the control channel. Its payload is the
+/// command's code as a little-endian `u32`.
+///
+/// The commands carry no target: what pausing or draining means is up to the
+/// processes that poll the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlMessage {
+    /// Stop processing messages until `Resume`
+    Pause,
+    /// Carry on after a `Pause`
+    Resume,
+    /// Finish the messages already received, then stop
+    Drain,
+    /// Report statistics
+    Stats,
+}
+
+impl ControlMessage {
+    /// The payload this command is sent as.
+    pub fn to_bytes(self) -> [u8; 4] {
+        let code: u32 = match self {
+            ControlMessage::Pause => 1,
+            ControlMessage::Resume => 2,
+            ControlMessage::Drain => 3,
+            ControlMessage::Stats => 4,
+        };
+        code.to_le_bytes()
+    }
+
+    /// Decode a payload written by [`to_bytes`](Self::to_bytes). Fails with
+    /// `InvalidData` on anything else.
+    pub fn from_bytes(payload: &[u8]) -> io::Result<Self> {
+        let code = <[u8; 4]>::try_from(payload).map(u32::from_le_bytes);
+        match code {
+            Ok(1) => Ok(ControlMessage::Pause),
+            Ok(2) => Ok(ControlMessage::Resume),
+            Ok(3) => Ok(ControlMessage::Drain),
+            Ok(4) => Ok(ControlMessage::Stats),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Not a control message: {:02x?}", payload),
+            )),
+        }
+    }
+}
+
+/// Sends [`ControlMessage`]s, returned by
+/// [`SharedMemoryAllocator::control_producer`].
+pub struct ControlProducer<'a> {
+    channel: ChannelPartition,
+    attached: Option<usize>,
+    _allocator: PhantomData<&'a SharedMemoryAllocator>,
+}
+
+impl ControlProducer<'_> {
+    /// Send `message` to every control consumer subscribed right now. Fails
+    /// with `WouldBlock` while the slowest of them has 64 messages unread.
+    pub fn send(&self, message: ControlMessage) -> io::Result<()> {
+        let buffer = &self.channel.buffer;
+        let meta = MessageMeta {
+            message_id: buffer.reserve_message_ids(1),
+            timestamp_ns: ClockSource::Realtime.now_ns(),
+            channel_id: self.channel.channel_id,
+            message_type: 1,
+            sender_pid: std::process::id(),
+            sender_runtime: 1, // Rust
+            ..Default::default()
+        };
+        if buffer.enqueue(meta, &message.to_bytes()).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "Control channel full",
+            ));
+        }
+        buffer.signal_consumer();
+        Ok(())
+    }
+}
+
+impl Drop for ControlProducer<'_> {
+    fn drop(&mut self) {
+        if let Some(index) = self.attached {
+            self.channel.buffer.unregister_attached(index);
+        }
+    }
+}
+
+/// Receives the [`ControlMessage`]s sent after it was created, returned by
+/// [`SharedMemoryAllocator::control_consumer`]. Every control consumer gets
+/// every message.
+pub struct ControlConsumer<'a> {
+    channel: ChannelPartition,
+    attached: Option<usize>,
+    _allocator: PhantomData<&'a SharedMemoryAllocator>,
+}
+
+impl ControlConsumer<'_> {
+    /// The next command, if one is waiting. Never blocks, so a worker can
+    /// call it between messages of its data channels.
+    pub fn poll(&self) -> io::Result<Option<ControlMessage>> {
+        match self.channel.buffer.dequeue() {
+            Some((_, payload)) => ControlMessage::from_bytes(&payload).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for ControlConsumer<'_> {
+    fn drop(&mut self) {
+        // Free the subscriber slot so control producers stop waiting on it
+        self.channel.buffer.unsubscribe();
+        if let Some(index) = self.attached {
+            self.channel.buffer.unregister_attached(index);
+        }
+    }
+}
+
+impl SharedMemoryAllocator {
+    /// ID of the segment's control channel: the last entry of the channel
+    /// table (255 with [`DEFAULT_MAX_CHANNELS`](crate::MPMC::Buffer::layout::DEFAULT_MAX_CHANNELS)).
+    /// Ordinary channels never get it: channels created without an ID skip
+    /// it, and asking for it fails with `InvalidInput`.
+    pub fn control_channel_id(&self) -> u32 {
+        self.max_channels as u32 - 1
+    }
+
+    /// A handle for sending [`ControlMessage`]s, creating the control
+    /// channel if it does not exist yet. Messages sent while no
+    /// [`ControlConsumer`] is subscribed are lost.
+    pub fn control_producer(&self) -> io::Result<ControlProducer<'_>> {
+        let channel = self.control_channel()?;
+        let attached = channel.buffer.register_attached(false);
+        Ok(ControlProducer {
+            channel,
+            attached,
+            _allocator: PhantomData,
+        })
+    }
+
+    /// A handle receiving every [`ControlMessage`] sent from now on, creating
+    /// the control channel if it does not exist yet. Takes one of the
+    /// channel's broadcast subscriber slots until dropped; fails with
+    /// `OutOfMemory` once every slot is taken.
+    pub fn control_consumer(&self) -> io::Result<ControlConsumer<'_>> {
+        let mut channel = self.control_channel()?;
+        channel.buffer.subscribe()?;
+        let attached = channel.buffer.register_attached(true);
+        Ok(ControlConsumer {
+            channel,
+            attached,
+            _allocator: PhantomData,
+        })
+    }
+
+    /// The control channel, created if missing.
+    fn control_channel(&self) -> io::Result<ChannelPartition> {
+        let id = self.control_channel_id();
+        let existing = match self.get_channel(id) {
+            Some(channel) => Some(channel),
+            None => {
+                let _guard = self.allocation_mutex.lock();
+                let spec = ChannelSpec::new(CONTROL_CAPACITY)
+                    .with_inline_size(8)
+                    .with_broadcast(true);
+                match self.create_locked(&spec, Some(id), CHANNEL_FLAG_CONTROL) {
+                    Ok(channel) => return Ok(channel),
+                    // Another process created it first
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => self.get_channel(id),
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        match existing {
+            Some(channel) if channel.buffer.is_control() => Ok(channel),
+            _ => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Channel {} is in use by an ordinary channel", id),
+            )),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// One-call dump of the allocator state for diagnosing a deployment.
+
+use super::*;
+use std::fmt;
+
+/// Everything known about one channel, as listed in [`AllocatorDiagnostics`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelDiagnostics {
+    /// Shape and occupancy
+    pub info: ChannelInfo,
+    /// Traffic counters
+    pub metrics: ChannelMetrics,
+}
+
+/// A snapshot of the whole shared memory region, as returned by
+/// [`SharedMemoryAllocator::diagnostics`]
+///
+/// `Display` prints it as a table with one row per channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllocatorDiagnostics {
+    /// Magic number in the header ("DMXP_MEM" when valid)
+    pub magic: u64,
+    /// Memory layout version
+    pub version: u32,
+    /// Number of channels the header records as active
+    pub channel_count: u32,
+    /// Bytes used by the header and channel bands
+    pub used_memory: usize,
+    /// Bytes still free in the region
+    pub available_memory: usize,
+    /// Every active channel, by ascending id
+    pub channels: Vec<ChannelDiagnostics>,
+}
+
+impl SharedMemoryAllocator {
+    /// Snapshot the header and every channel's layout, occupancy and metrics.
+    ///
+    /// The values are read without locking while the region may be in use, so
+    /// counters of different channels can be from slightly different moments.
+    pub fn diagnostics(&self) -> AllocatorDiagnostics {
+        let header = unsafe { &*self.header };
+        let channels = self
+            .channel_infos()
+            .into_iter()
+            .map(|info| ChannelDiagnostics {
+                info,
+                metrics: self.channel_metrics(info.id).unwrap_or_default(),
+            })
+            .collect();
+
+        AllocatorDiagnostics {
+            magic: header.magic,
+            version: header.version,
+            channel_count: header.channel_count,
+            used_memory: self.used_memory(),
+            available_memory: self.available_memory(),
+            channels,
+        }
+    }
+}
+
+impl fmt::Display for AllocatorDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "DMXP region: magic {:#018x}, version {}, {} channels, {} bytes used, {} bytes free",
+            self.magic, self.version, self.channel_count, self.used_memory, self.available_memory
+        )?;
+        write!(
+            f,
+            "{:>7} {:>12} {:>9} {:>9} {:>9} {:>9} {:>12} {:>12} {:>10} {:>12} {:>10}",
+            "channel",
+            "offset",
+            "capacity",
+            "used",
+            "producers",
+            "consumers",
+            "enqueued",
+            "dequeued",
+            "full",
+            "empty",
+            "expired"
+        )?;
+        for channel in &self.channels {
+            let (info, metrics) = (&channel.info, &channel.metrics);
+            write!(
+                f,
+                "\n{:>7} {:>12} {:>9} {:>9} {:>9} {:>9} {:>12} {:>12} {:>10} {:>12} {:>10}",
+                info.id,
+                info.offset,
+                info.capacity,
+                info.used_slots,
+                info.producers,
+                info.consumers,
+                metrics.enqueue_count,
+                metrics.dequeue_count,
+                metrics.enqueue_full_failures,
+                metrics.dequeue_empty,
+                metrics.expired_count
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+// The segment's event log: the last `EVENT_LOG_LEN` channel lifecycle events,
+// kept in shared memory so tools attaching to the segment can read them.
+
+use super::*;
+use crate::MPMC::Buffer::layout::{EventLog, EVENT_LOG_LEN};
+use crate::MPMC::ClockSource;
+
+/// What happened to a channel, in an [`AllocatorEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AllocatorEventKind {
+    /// Created by `create_channel` or one of its variants
+    Created,
+    /// Removed by `remove_channel`, `gc_abandoned_channels`, or the rollback
+    /// of a failed `create_channels`
+    Removed,
+    /// Sealed by `seal_channel`
+    Sealed,
+    /// Found holding a corrupted slot
+    Poisoned,
+    /// Given a new capacity by `resize_channel`
+    Resized,
+}
+
+impl AllocatorEventKind {
+    fn code(self) -> u32 {
+        match self {
+            AllocatorEventKind::Created => 1,
+            AllocatorEventKind::Removed => 2,
+            AllocatorEventKind::Sealed => 3,
+            AllocatorEventKind::Poisoned => 4,
+            AllocatorEventKind::Resized => 5,
+        }
+    }
+
+    fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(AllocatorEventKind::Created),
+            2 => Some(AllocatorEventKind::Removed),
+            3 => Some(AllocatorEventKind::Sealed),
+            4 => Some(AllocatorEventKind::Poisoned),
+            5 => Some(AllocatorEventKind::Resized),
+            _ => None,
+        }
+    }
+}
+
+/// A channel lifecycle event, as returned by
+/// [`SharedMemoryAllocator::recent_events`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllocatorEvent {
+    /// What happened
+    pub kind: AllocatorEventKind,
+    /// The channel it happened to
+    pub channel_id: u32,
+    /// When, in `CLOCK_REALTIME` nanoseconds
+    pub timestamp_ns: u64,
+    /// The process that caused it
+    pub pid: u32,
+}
+
+impl EventLog {
+    /// Record an event, overwriting the oldest once the log is full.
+    pub(crate) fn append(&self, kind: AllocatorEventKind, channel_id: u32) {
+        let position = self.next.fetch_add(1, Ordering::AcqRel);
+        let record = &self.records[(position % EVENT_LOG_LEN as u64) as usize];
+        record.sequence.store(0, Ordering::Relaxed);
+        std::sync::atomic::fence(Ordering::Release);
+        record
+            .timestamp_ns
+            .store(ClockSource::Realtime.now_ns(), Ordering::Relaxed);
+        record.channel_id.store(channel_id, Ordering::Relaxed);
+        record.kind.store(kind.code(), Ordering::Relaxed);
+        record.pid.store(std::process::id(), Ordering::Relaxed);
+        record.sequence.store(position + 1, Ordering::Release);
+    }
+
+    /// The event recorded at `position`, or None if it was overwritten, is
+    /// still being written, or was written by a newer version of this crate.
+    fn read(&self, position: u64) -> Option<AllocatorEvent> {
+        let record = &self.records[(position % EVENT_LOG_LEN as u64) as usize];
+        if record.sequence.load(Ordering::Acquire) != position + 1 {
+            return None;
+        }
+        let kind = record.kind.load(Ordering::Relaxed);
+        let channel_id = record.channel_id.load(Ordering::Relaxed);
+        let timestamp_ns = record.timestamp_ns.load(Ordering::Relaxed);
+        let pid = record.pid.load(Ordering::Relaxed);
+        // A writer that started since the first check changed the sequence
+        std::sync::atomic::fence(Ordering::Acquire);
+        if record.sequence.load(Ordering::Relaxed) != position + 1 {
+            return None;
+        }
+        Some(AllocatorEvent {
+            kind: AllocatorEventKind::from_code(kind)?,
+            channel_id,
+            timestamp_ns,
+            pid,
+        })
+    }
+}
+
+impl SharedMemoryAllocator {
+    /// The last `max` channel lifecycle events recorded in the segment, by
+    /// any process, oldest first.
+    ///
+    /// The log holds the last
+    /// [`EVENT_LOG_LEN`](crate::MPMC::Buffer::layout::EVENT_LOG_LEN) events;
+    /// older ones are gone. It is kept across [`reset`](Self::reset). An
+    /// event still being written when this runs is left out.
+    pub fn recent_events(&self, max: usize) -> Vec<AllocatorEvent> {
+        let log = self.event_log();
+        let end = log.next.load(Ordering::Acquire);
+        let start = end.saturating_sub(max.min(EVENT_LOG_LEN) as u64);
+        (start..end)
+            .filter_map(|position| log.read(position))
+            .collect()
+    }
+
+    pub(super) fn event_log(&self) -> &EventLog {
+        unsafe { &*self.event_log }
+    }
+}
@@ -1,5 +1,4 @@
 use super::*;
-use std::sync::atomic::Ordering;
 
 /// Extension trait providing getter methods for SharedMemoryAllocator
 /// 
@@ -14,6 +13,22 @@ impl SharedMemoryAllocator {
         &*self.shm
     }
 
+    /// Get the base address and size in bytes of the whole mapped segment
+    /// 
+    /// For handing the segment to a foreign runtime, such as a JVM wrapping it
+    /// in a `DirectByteBuffer` through JNI. Nothing about the memory is
+    /// enforced once it leaves Rust:
+    /// 
+    /// # Safety
+    /// The pointer is only valid while this allocator (or another handle on the
+    /// same mapping) is alive; it dangles once the last one is dropped. The
+    /// memory is shared with other threads and processes, so every access
+    /// must follow the segment's atomic protocols (see `docs/MEMORY_LAYOUT.md`);
+    /// plain writes to the header, channel table or live slots corrupt it.
+    pub fn as_raw_parts(&self) -> (*mut u8, usize) {
+        (self.shm.as_ptr(), self.shm.size())
+    }
+
     /// Get the raw pointer to the GlobalHeader
     /// 
     /// # Safety
@@ -22,13 +37,23 @@ impl SharedMemoryAllocator {
         self.header
     }
 
+    /// Get the raw pointer to the first entry of the channel table
+    /// 
+    /// The table directly follows the GlobalHeader and holds `max_channels()`
+    /// entries, indexed by channel ID.
+    pub fn channel_table_ptr(&self) -> *mut ChannelEntry {
+        self.channels
+    }
+
     /// Get the next available channel ID
     /// 
-    /// Returns the next channel ID that will be assigned to a new channel.
-    /// The value is loaded with relaxed ordering since this is primarily used
+    /// Returns the lowest free channel ID, the one the next channel created
+    /// without an explicit ID receives, or `max_channels()` if every ID is
+    /// taken. Another creator may claim it first, so this is primarily used
     /// for debugging and monitoring.
     pub fn next_channel_id(&self) -> u64 {
-        self.next_channel_id.load(Ordering::Relaxed)
+        self.lowest_free_id()
+            .map_or(self.max_channels as u64, u64::from)
     }
 
     /// Check if the allocator has been properly initialized
@@ -1,17 +1,43 @@
 use crate::Core::SharedMemory::SharedMemoryBackend;
-use crate::MPMC::Buffer::layout::{GlobalHeader, MAX_CHANNELS};
-use crate::MPMC::Buffer::RingBuffer;
+use crate::MPMC::Buffer::layout::{
+    ChannelEntry, EventLog, GlobalHeader, BYTE_ORDER_MARKER, CHANNEL_FLAG_BROADCAST,
+    CHANNEL_FLAG_CHECKSUM, CHANNEL_FLAG_CONTROL, CHANNEL_FLAG_EXACT_CAPACITY,
+    CHANNEL_FLAG_OVERWRITE, CHANNEL_FLAG_SINGLE_CONSUMER, CHANNEL_FLAG_SINGLE_PRODUCER,
+    DEFAULT_SEGMENT_NAME,
+};
+use crate::MPMC::Buffer::overflow::OverflowBand;
+use crate::MPMC::Buffer::{RingBuffer, Slot, MSG_INLINE, SLOT_ALIGN};
 use crossbeam_utils::CachePadded;
 use std::io;
 use std::sync::atomic::{AtomicU64, Ordering};
+mod control;
 mod debug;
+mod diagnostics;
+mod events;
 mod getters;
 
+pub use control::{ControlConsumer, ControlMessage, ControlProducer};
+pub use diagnostics::{AllocatorDiagnostics, ChannelDiagnostics};
+pub use events::{AllocatorEvent, AllocatorEventKind};
+
 // Use parking_lot's Mutex for better performance
 use parking_lot::Mutex;
 
 const MAGIC_NUMBER: u64 = 0x444D58505F4D454D; // "DMXP_MEM"
 
+/// Version of the shared memory layout, stored in `GlobalHeader::version` by
+/// the creating process. `attach` refuses segments with any other version
+/// rather than misreading them. Version 2 sizes the channel table from
+/// `GlobalHeader::max_channels` instead of a fixed 256 entries; version 3
+/// records each channel's inline payload size in `ChannelEntry::inline_size`;
+/// version 4 adds the channel ID bitmap after the channel table; version 5
+/// counts parked consumers in `ChannelEntry::data_waiters`, without which
+/// producers would skip their wakeups; version 6 stamps
+/// `GlobalHeader::byte_order`; version 7 adds the `EventLog` after the ID
+/// bitmap; version 8 adds `ChannelEntry::layout_generation`, which views
+/// check before touching slots.
+pub const LAYOUT_VERSION: u32 = 8;
+
 /// Represents a single channel's memory region
 pub struct ChannelPartition {
     /// The underlying ring buffer for this channel
@@ -22,22 +48,454 @@ pub struct ChannelPartition {
     pub capacity: usize,
 }
 
+/// A snapshot of one channel, as returned by [`SharedMemoryAllocator::channel_infos`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelInfo {
+    /// The channel's ID
+    pub id: u32,
+    /// Number of slots in the ring
+    pub capacity: usize,
+    /// Messages waiting in the ring (see [`RingBuffer::len`])
+    pub used_slots: usize,
+    /// Byte offset of the channel's region in the shared memory segment
+    pub offset: usize,
+    /// Live producers attached (see [`SharedMemoryAllocator::producer_count`])
+    pub producers: usize,
+    /// Live consumers attached (see [`SharedMemoryAllocator::consumer_count`])
+    pub consumers: usize,
+}
+
+/// Traffic counters of one channel, as returned by
+/// [`SharedMemoryAllocator::channel_metrics`]
+///
+/// The counters live in shared memory, so they cover every process using the
+/// channel since it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelMetrics {
+    /// Messages enqueued
+    pub enqueue_count: u64,
+    /// Messages dequeued (each broadcast delivery counts)
+    pub dequeue_count: u64,
+    /// Enqueue attempts that failed because the channel was full
+    pub enqueue_full_failures: u64,
+    /// Receive attempts that found the channel empty
+    pub dequeue_empty: u64,
+    /// Messages dropped on receive because their TTL had passed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub expired_count: u64,
+    /// Wake syscalls producers issued for consumers parked on an empty channel
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub consumer_wakes: u64,
+}
+
+/// How the free space of a segment is split up, as returned by
+/// [`SharedMemoryAllocator::fragmentation`]
+///
+/// A channel needs one free block of its band size, so a `largest_free_block`
+/// well below `total_free` means removed channels left space only a
+/// compaction could reclaim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FragmentationReport {
+    /// Bytes in the largest free block (the biggest band that still fits)
+    pub largest_free_block: usize,
+    /// Bytes free in all blocks together (equals `available_memory`)
+    pub total_free: usize,
+    /// Number of free blocks, counting the space after the last band
+    pub free_block_count: usize,
+}
+
+/// Describes the shape of a channel to be created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelSpec {
+    /// Number of slots in the ring (a power of two unless `exact_capacity` is set)
+    pub capacity: usize,
+    /// Inline payload bytes per slot (`MSG_INLINE` by default)
+    pub inline_size: usize,
+    /// Number of overflow blocks for payloads larger than `inline_size` (0 disables spilling)
+    pub overflow_blocks: usize,
+    /// Size in bytes of each overflow block
+    pub overflow_block_size: usize,
+    /// Stamp and verify a CRC32C of every payload
+    pub checksum: bool,
+    /// Number of slots in the high-priority ring (0 disables the priority band)
+    pub priority_capacity: usize,
+    /// Deliver every message to every registered subscriber
+    pub broadcast: bool,
+    /// Allow capacities that are not a power of two
+    pub exact_capacity: bool,
+    /// Drop the oldest unread message instead of failing when the ring is full
+    pub overwrite: bool,
+    /// Claim slots without a CAS, for channels with only one producer
+    pub single_producer: bool,
+    /// Refuse to attach a second consumer while one is attached
+    pub single_consumer: bool,
+}
+
+impl ChannelSpec {
+    /// A channel with `capacity` slots and no overflow band
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inline_size: MSG_INLINE,
+            overflow_blocks: 0,
+            overflow_block_size: 0,
+            checksum: false,
+            priority_capacity: 0,
+            broadcast: false,
+            exact_capacity: false,
+            overwrite: false,
+            single_producer: false,
+            single_consumer: false,
+        }
+    }
+
+    /// Hold up to `bytes` of payload inline in every slot instead of
+    /// `MSG_INLINE`. Must be a non-zero multiple of 8; each slot takes
+    /// `Slot::stride(bytes)` bytes, so small sizes make dense rings for small
+    /// messages and large ones avoid the overflow band for big messages.
+    pub fn with_inline_size(mut self, bytes: usize) -> Self {
+        self.inline_size = bytes;
+        self
+    }
+
+    /// Reserve `blocks` overflow blocks of `block_size` bytes each.
+    ///
+    /// Payloads larger than the inline size (up to `block_size`) are copied into a
+    /// free block and the slot carries a reference to it. Sizing `blocks` below
+    /// `capacity` is fine when large payloads are occasional; a send that finds
+    /// every block in use behaves like a full ring.
+    pub fn with_overflow(mut self, blocks: usize, block_size: usize) -> Self {
+        self.overflow_blocks = blocks;
+        self.overflow_block_size = block_size;
+        self
+    }
+
+    /// Enable per-message CRC32C integrity checks.
+    ///
+    /// Costs one pass over the payload on both send and receive, so
+    /// latency-sensitive channels may prefer to leave it off.
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Add a high-priority ring of `capacity` slots (a power of two, or 0 for none).
+    ///
+    /// Messages sent with `Producer::send_priority` land there and are dequeued
+    /// before anything in the normal ring, so ordering is only FIFO within a band.
+    pub fn with_priority(mut self, capacity: usize) -> Self {
+        self.priority_capacity = capacity;
+        self
+    }
+
+    /// Make this a broadcast channel.
+    ///
+    /// Consumers register with `Consumer::subscribe_broadcast` and each gets its
+    /// own read cursor, so every subscriber sees the full stream. Producers wait
+    /// for the slowest subscriber, which can stall them if it stops reading.
+    /// Cannot be combined with overflow or priority bands.
+    pub fn with_broadcast(mut self, enabled: bool) -> Self {
+        self.broadcast = enabled;
+        self
+    }
+
+    /// Allow `capacity` and the priority capacity to be any non-zero number of
+    /// slots instead of a power of two, so a channel that needs 3000 slots does
+    /// not have to reserve 4096.
+    ///
+    /// Slots are then indexed with a modulo instead of a mask, which costs a
+    /// division per operation; power-of-two capacities stay on the mask path
+    /// either way.
+    pub fn with_exact_capacity(mut self, enabled: bool) -> Self {
+        self.exact_capacity = enabled;
+        self
+    }
+
+    /// Make the channel "last value wins": a send that finds the ring full
+    /// drops the oldest unread message and takes its slot instead of failing,
+    /// so producers never see backpressure and consumers read the newest
+    /// `capacity` messages.
+    ///
+    /// The oldest message is dropped by claiming it through the consumer cursor,
+    /// so a concurrent reader either gets it whole or not at all. A send still
+    /// fails as full while that slot is held by a consumer (an unacked lease or
+    /// a `MessageRef`). Cannot be combined with broadcast.
+    pub fn with_overwrite(mut self, enabled: bool) -> Self {
+        self.overwrite = enabled;
+        self
+    }
+
+    /// Promise that only one producer, in any process, sends on the channel at
+    /// a time, letting sends claim their slot with a plain store instead of a
+    /// compare-and-swap on the tail.
+    ///
+    /// Concurrent producers on such a channel overwrite each other's messages.
+    /// Debug builds panic when a send finds more than one producer attached;
+    /// release builds do not check. Batches still claim their slots with a CAS.
+    pub fn with_single_producer(mut self, enabled: bool) -> Self {
+        self.single_producer = enabled;
+        self
+    }
+
+    /// Allow only one consumer on the channel at a time. Building a second
+    /// consumer while the first is alive fails with `AlreadyExists` instead of
+    /// the two silently splitting the messages between them. Consumers of
+    /// processes that died without dropping them do not count.
+    pub fn with_single_consumer(mut self, enabled: bool) -> Self {
+        self.single_consumer = enabled;
+        self
+    }
+
+    /// Where the bands of this channel go in its data region. Fails with
+    /// `InvalidInput` if the sizes overflow `usize`.
+    fn layout(&self) -> io::Result<BandLayout> {
+        let stride = RingBuffer::slot_stride(self.inline_size);
+        let layout = (|| {
+            let priority_offset = align_band(self.capacity.checked_mul(stride)?)?;
+            let priority_size = self.priority_capacity.checked_mul(stride)?;
+            let overflow_offset = align_band(priority_offset.checked_add(priority_size)?)?;
+            let overflow =
+                OverflowBand::required_size(self.overflow_blocks, self.overflow_block_size)?;
+            let size = align_band(overflow_offset.checked_add(overflow)?)?;
+            Some(BandLayout {
+                priority_offset,
+                overflow_offset,
+                size,
+            })
+        })();
+        layout.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Channel size overflows the address space",
+            )
+        })
+    }
+
+    /// Total bytes this channel occupies in the data region, aligned to `Slot`'s alignment.
+    /// `usize::MAX` if that overflows, which no shared memory region can fit.
+    pub fn band_size(&self) -> usize {
+        self.layout().map_or(usize::MAX, |layout| layout.size)
+    }
+
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        if self.exact_capacity {
+            if self.capacity == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Capacity must be greater than zero",
+                ));
+            }
+        } else if self.capacity == 0 || (self.capacity & (self.capacity - 1)) != 0 {
+            // Validate capacity is a power of two and non-zero
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Capacity must be a power of two and greater than zero",
+            ));
+        }
+
+        if !self.exact_capacity
+            && self.priority_capacity & self.priority_capacity.wrapping_sub(1) != 0
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Priority capacity must be zero or a power of two",
+            ));
+        }
+
+        if self.broadcast && (self.priority_capacity != 0 || self.overflow_blocks != 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Broadcast channels do not support priority or overflow bands",
+            ));
+        }
+
+        if self.broadcast && self.single_consumer {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Broadcast channels cannot be limited to a single consumer",
+            ));
+        }
+
+        if self.broadcast && self.overwrite {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Broadcast channels cannot overwrite unread messages",
+            ));
+        }
+
+        if (self.overflow_blocks == 0) != (self.overflow_block_size == 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Overflow blocks and block size must both be zero or both be non-zero",
+            ));
+        }
+
+        if !Slot::is_valid_inline_size(self.inline_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Inline size must be a non-zero multiple of 8 (got {})",
+                    self.inline_size
+                ),
+            ));
+        }
+
+        if self.overflow_blocks != 0
+            && (self.overflow_block_size <= self.inline_size
+                || self.overflow_block_size > u32::MAX as usize)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Overflow block size must be larger than the inline payload ({} bytes)",
+                    self.inline_size
+                ),
+            ));
+        }
+
+        self.layout().map(|_| ())
+    }
+}
+
+/// Byte offsets of a channel's bands from the start of its data region
+struct BandLayout {
+    priority_offset: usize,
+    overflow_offset: usize,
+    /// Total size of the region
+    size: usize,
+}
+
+/// Alignment of every channel region and band within it, so every slot starts
+/// on a `SLOT_ALIGN` boundary (strides are multiples of it). Offsets are
+/// relative to the segment base, which is checked against the header's
+/// alignment, so that must cover `SLOT_ALIGN` too.
+const BAND_ALIGN: usize = SLOT_ALIGN;
+const _: () = assert!(std::mem::align_of::<GlobalHeader>().is_multiple_of(BAND_ALIGN));
+
+/// Round `n` up to a multiple of `BAND_ALIGN`, or None if that overflows.
+fn align_band(n: usize) -> Option<usize> {
+    Some(n.checked_add(BAND_ALIGN - 1)? & !(BAND_ALIGN - 1))
+}
+
+/// Whether the initialized entry at `index` describes bands that a ring buffer
+/// view can safely use in a segment of `shm_size` bytes whose channel table
+/// ends at `control_size`: a power-of-two capacity (so the ring's mask is
+/// `capacity - 1`) unless the channel was created with exact capacities, and
+/// every band inside the channel's region, which itself lies past the table
+/// and inside the segment.
+///
+/// A process that dies inside `create_channel_with` can leave an entry with a
+/// capacity but without a complete band description.
+fn entry_is_sound(
+    entry: &ChannelEntry,
+    index: usize,
+    control_size: usize,
+    shm_size: usize,
+) -> bool {
+    let inline_size = match entry.inline_size {
+        0 => MSG_INLINE,
+        size => size as usize,
+    };
+    let stride = RingBuffer::slot_stride(inline_size);
+    let band_offset = entry.band_offset as usize;
+    let band_size = entry.band_size as usize;
+    let capacity = entry.capacity as usize;
+    let priority_capacity = entry.priority_capacity as usize;
+    let exact = entry.flags.load(Ordering::Relaxed) & CHANNEL_FLAG_EXACT_CAPACITY != 0;
+    let fits = |offset: usize, len: Option<usize>| {
+        len.and_then(|len| offset.checked_add(len))
+            .is_some_and(|end| end <= band_size)
+    };
+
+    entry.channel_id as usize == index
+        && Slot::is_valid_inline_size(inline_size)
+        && (capacity.is_power_of_two() || exact && capacity != 0)
+        && band_offset >= control_size
+        && band_offset.is_multiple_of(BAND_ALIGN)
+        && band_offset
+            .checked_add(band_size)
+            .is_some_and(|end| end <= shm_size)
+        && fits(0, capacity.checked_mul(stride))
+        && (priority_capacity == 0 || priority_capacity.is_power_of_two() || exact)
+        && (entry.priority_offset as usize).is_multiple_of(BAND_ALIGN)
+        && fits(
+            entry.priority_offset as usize,
+            priority_capacity.checked_mul(stride),
+        )
+        && fits(
+            entry.overflow_offset as usize,
+            OverflowBand::required_size(
+                entry.overflow_blocks as usize,
+                entry.overflow_block_size as usize,
+            ),
+        )
+}
+
+/// Segment names become a file name under `/dev/shm`.
+fn check_segment_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name.contains(['/', '\0']) || name == "." || name == ".." {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid shared memory segment name {:?}", name),
+        ));
+    }
+    Ok(())
+}
+
 /// Global allocator for managing shared memory channels
 pub struct SharedMemoryAllocator {
     shm: Box<dyn SharedMemoryBackend>,
     header: *mut GlobalHeader,
-    next_channel_id: AtomicU64,
+    // The channel table right after the header, `max_channels` entries long
+    channels: *mut ChannelEntry,
+    max_channels: usize,
+    // The channel ID bitmap after the table, `max_channels.div_ceil(64)` words
+    id_bitmap: *const AtomicU64,
+    // The event log after the bitmap
+    event_log: *const EventLog,
     allocation_mutex: Mutex<()>, // For thread-safe channel creation
 }
 
 impl SharedMemoryAllocator {
-    // Create a new shared memory allocator with the specified total size
-    pub fn new(size: usize) -> io::Result<Self> {
+    /// Create a new shared memory allocator with the specified total size and
+    /// a channel table of `max_channels` entries (channel IDs
+    /// `0..max_channels`), usually
+    /// [`DEFAULT_MAX_CHANNELS`](crate::MPMC::Buffer::layout::DEFAULT_MAX_CHANNELS). The table takes
+    /// `size_of::<ChannelEntry>()` bytes per entry at the start of the region.
+    /// The last entry is reserved for the
+    /// [control channel](Self::control_channel_id), so `max_channels` must be
+    /// at least 2.
+    pub fn new(size: usize, max_channels: usize) -> io::Result<Self> {
+        Self::new_named(DEFAULT_SEGMENT_NAME, size, max_channels)
+    }
+
+    /// Like [`new`](Self::new), but backed by `/dev/shm/<name>` instead of the
+    /// default segment. Allocators with different names share nothing: each
+    /// has its own header, channel IDs and allocation lock, so one process can
+    /// keep independent arenas side by side.
+    pub fn new_named(name: &str, size: usize, max_channels: usize) -> io::Result<Self> {
+        check_segment_name(name)?;
         // Ensure the shared memory size is a multiple of the cache line size
         let aligned_size = (size + 127) & !127; // Align to 128 bytes
-        let control_size = std::mem::size_of::<GlobalHeader>();
 
-        // Ensure there's enough space after the header
+        // The last entry is the control channel's, so one more is needed
+        let control_size = (max_channels >= 2 && max_channels <= u32::MAX as usize)
+            .then(|| GlobalHeader::control_size(max_channels))
+            .flatten()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "SharedMemoryAllocator::new(): invalid channel limit {}",
+                        max_channels
+                    ),
+                )
+            })?;
+
+        // Ensure there's enough space for the header and channel table
         if aligned_size < control_size {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -46,7 +504,7 @@ impl SharedMemoryAllocator {
         }
 
         // --- Create shared memory ---
-        let shm = crate::Core::SharedMemory::create_shared_memory(aligned_size, Some("dmxp_alloc"))
+        let shm = crate::Core::SharedMemory::create_shared_memory(aligned_size, Some(name))
             .map_err(|e| {
                 io::Error::new(
                     e.kind(),
@@ -74,32 +532,85 @@ impl SharedMemoryAllocator {
                 header_ptr,
                 GlobalHeader {
                     magic: MAGIC_NUMBER,
-                    version: 1,
-                    max_channels: MAX_CHANNELS as u32,
+                    version: LAYOUT_VERSION,
+                    max_channels: max_channels as u32,
                     channel_count: 0,
-                    reserved: 0,
-                    channels: std::mem::zeroed(),
+                    byte_order: BYTE_ORDER_MARKER,
                 },
             );
         }
+        let channels = unsafe { header_ptr.add(1) } as *mut ChannelEntry;
+        unsafe { std::ptr::write_bytes(channels, 0, max_channels) };
+        let id_bitmap = unsafe { channels.add(max_channels) } as *mut AtomicU64;
+        unsafe { std::ptr::write_bytes(id_bitmap, 0, max_channels.div_ceil(64)) };
+        let event_log = unsafe {
+            shm.as_ptr()
+                .add(GlobalHeader::event_log_offset(max_channels).unwrap_or(0))
+        } as *mut EventLog;
+        unsafe { std::ptr::write_bytes(event_log, 0, 1) };
 
         Ok(Self {
             shm,
             header: header_ptr,
-            next_channel_id: AtomicU64::new(0),
+            channels,
+            max_channels,
+            id_bitmap,
+            event_log,
             allocation_mutex: Mutex::new(()),
         })
     }
 
-    /// Attach to an existing shared memory allocator
+    /// The segment size `new` needs to hold a channel table of `max_channels`
+    /// entries and one channel for each of `channels`: the header, table and
+    /// ID bitmap, every channel's band with its alignment padding, rounded up
+    /// to the 128 bytes `new` rounds sizes to. `usize::MAX` if that overflows.
+    ///
+    /// Channels created in the order given fill a segment of this size exactly.
+    pub fn required_size(max_channels: usize, channels: &[ChannelSpec]) -> usize {
+        (|| {
+            let size = channels.iter().try_fold(
+                align_band(GlobalHeader::control_size(max_channels)?)?,
+                |size, spec| size.checked_add(spec.band_size()),
+            )?;
+            Some(size.checked_add(127)? & !127)
+        })()
+        .unwrap_or(usize::MAX)
+    }
+
+    /// Attach to an existing shared memory allocator, using the channel limit
+    /// its creator chose
     pub fn attach(size: usize) -> io::Result<Self> {
+        Self::attach_named(DEFAULT_SEGMENT_NAME, size)
+    }
+
+    /// Attach to the allocator a [`new_named`](Self::new_named) call with the
+    /// same `name` created.
+    pub fn attach_named(name: &str, size: usize) -> io::Result<Self> {
+        check_segment_name(name)?;
         // Align the size
         let aligned_size = (size + 127) & !127;
-        let min_required_size = std::mem::size_of::<GlobalHeader>();
 
         // Attach to shared memory
-        let shm = crate::Core::SharedMemory::attach_shared_memory("dmxp_alloc", aligned_size)?;
+        let shm = crate::Core::SharedMemory::attach_shared_memory(name, aligned_size)?;
+        Self::from_segment(shm)
+    }
 
+    /// Attach to an existing shared memory allocator through `fd`, an open
+    /// descriptor of its segment passed in by another process, for workers
+    /// that cannot open `/dev/shm` themselves. The segment must be at least
+    /// `size` bytes and is validated like in [`attach`](Self::attach).
+    ///
+    /// The allocator takes ownership of `fd` and closes it when dropped, or
+    /// right away if attaching fails.
+    #[cfg(unix)]
+    pub fn attach_fd(fd: std::os::fd::RawFd, size: usize) -> io::Result<Self> {
+        let aligned_size = (size + 127) & !127;
+        let shm = crate::Core::SharedMemory::attach_shared_memory_fd(fd, aligned_size)?;
+        Self::from_segment(shm)
+    }
+
+    /// Check the header of an attached segment and take over its channel table.
+    fn from_segment(shm: Box<dyn SharedMemoryBackend>) -> io::Result<Self> {
         // Get header pointer and verify alignment
         let header = shm.as_ptr() as *mut GlobalHeader;
         if (header as usize) % 128 != 0 {
@@ -109,8 +620,24 @@ impl SharedMemoryAllocator {
             ));
         }
 
-        // Verify magic number and size
+        if shm.size() < std::mem::size_of::<GlobalHeader>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Shared memory too small for header",
+            ));
+        }
+
+        // Verify byte order, magic number and version. A segment written by a
+        // process of the other endianness fails the magic check too, so the
+        // byte order is checked first to say why.
         unsafe {
+            if (*header).byte_order == BYTE_ORDER_MARKER.swap_bytes() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Segment was written by a process of the opposite byte order",
+                ));
+            }
+
             if (*header).magic != MAGIC_NUMBER {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -118,149 +645,409 @@ impl SharedMemoryAllocator {
                 ));
             }
 
-            if shm.size() < min_required_size {
+            if (*header).version != LAYOUT_VERSION {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
-                    "Shared memory too small for header",
+                    format!(
+                        "layout version mismatch: segment={}, expected={}",
+                        (*header).version,
+                        LAYOUT_VERSION
+                    ),
+                ));
+            }
+
+            if (*header).byte_order != BYTE_ORDER_MARKER {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid byte-order marker {:#010x}", (*header).byte_order),
                 ));
             }
         }
 
-        // Find the next available channel ID
-        let next_id = unsafe {
-            let header = &*header;
-            (0..MAX_CHANNELS)
-                .filter(|&i| header.channels[i].capacity > 0)
-                .max()
-                .map(|max| max + 1)
-                .unwrap_or(0) as u64
-        };
+        let max_channels = unsafe { (*header).max_channels } as usize;
+        if max_channels < 2
+            || GlobalHeader::control_size(max_channels).is_none_or(|size| size > shm.size())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Channel table of {} entries does not fit the {}-byte segment",
+                    max_channels,
+                    shm.size()
+                ),
+            ));
+        }
 
+        let channels = unsafe { header.add(1) } as *mut ChannelEntry;
         Ok(Self {
+            channels,
+            max_channels,
+            id_bitmap: unsafe { channels.add(max_channels) } as *const AtomicU64,
+            event_log: unsafe {
+                shm.as_ptr()
+                    .add(GlobalHeader::event_log_offset(max_channels).unwrap_or(0))
+            } as *const EventLog,
             shm,
             header,
-            next_channel_id: AtomicU64::new(next_id),
             allocation_mutex: Mutex::new(()),
         })
     }
 
+    /// Remove the segment's `/dev/shm` file when this allocator is dropped, if
+    /// it created the segment; allocators that attached never remove it.
+    ///
+    /// Off by default, so the segment outlives its creator. Processes that
+    /// still have it mapped keep working after the file is gone, but no new
+    /// process can attach.
+    pub fn with_unlink_on_drop(mut self, enabled: bool) -> Self {
+        self.shm.set_unlink_on_drop(enabled);
+        self
+    }
+
+    /// Whether this allocator created the segment with `new`, rather than
+    /// attaching to an existing one.
+    pub fn is_creator(&self) -> bool {
+        self.shm.is_creator()
+    }
+
+    /// Number of entries in the channel table, fixed when the segment was
+    /// created; channel IDs run from 0 to one less than this.
+    pub fn max_channels(&self) -> usize {
+        self.max_channels
+    }
+
+    /// The channel table entry at `index`, which must be below `max_channels`.
+    fn entry(&self, index: usize) -> &ChannelEntry {
+        debug_assert!(index < self.max_channels);
+        unsafe { &*self.channels.add(index) }
+    }
+
+    /// Bytes taken by the header, channel table and ID bitmap, where channel
+    /// bands begin.
+    fn control_size(&self) -> usize {
+        // Checked against the segment size in `new` and `attach`
+        GlobalHeader::control_size(self.max_channels).unwrap_or(usize::MAX)
+    }
+
+    /// The channel ID bitmap, one bit per table entry.
+    fn id_words(&self) -> &[AtomicU64] {
+        unsafe { std::slice::from_raw_parts(self.id_bitmap, self.max_channels.div_ceil(64)) }
+    }
+
+    /// Bits of bitmap word `word` that stand for IDs past the end of the
+    /// table, or for the last ID, reserved for the control channel; they
+    /// always read as taken.
+    fn past_end_bits(&self, word: usize) -> u64 {
+        let valid = self.control_channel_id() as usize - word * 64;
+        if valid >= 64 {
+            0
+        } else {
+            !0 << valid
+        }
+    }
+
+    /// The lowest channel ID whose bitmap bit is clear, if any.
+    pub(crate) fn lowest_free_id(&self) -> Option<u32> {
+        self.id_words().iter().enumerate().find_map(|(word, bits)| {
+            let taken = bits.load(Ordering::Acquire) | self.past_end_bits(word);
+            (taken != !0).then(|| (word * 64) as u32 + (!taken).trailing_zeros())
+        })
+    }
+
+    /// Atomically claim the lowest free channel ID, so concurrent creators in
+    /// any process never receive the same one.
+    fn claim_free_id(&self) -> Option<u32> {
+        loop {
+            let id = self.lowest_free_id()?;
+            if self.claim_id(id) {
+                return Some(id);
+            }
+        }
+    }
+
+    /// Set the bitmap bit for `id`; false if it was already set.
+    fn claim_id(&self, id: u32) -> bool {
+        let bit = 1u64 << (id % 64);
+        self.id_words()[id as usize / 64].fetch_or(bit, Ordering::AcqRel) & bit == 0
+    }
+
+    /// Clear the bitmap bit for `id`, making it available to `claim_free_id`.
+    fn release_id(&self, id: u32) {
+        let bit = 1u64 << (id % 64);
+        self.id_words()[id as usize / 64].fetch_and(!bit, Ordering::AcqRel);
+    }
+
+    /// The entry at `index` if it is an initialized channel that passes
+    /// [`entry_is_sound`].
+    fn sound_entry(&self, index: usize) -> Option<&ChannelEntry> {
+        let entry = self.entry(index);
+        (entry.capacity != 0 && entry_is_sound(entry, index, self.control_size(), self.shm.size()))
+            .then_some(entry)
+    }
+
+    /// IDs of initialized channels whose entries are inconsistent, typically
+    /// because their creator died partway through `create_channel_with`. They
+    /// are invisible to `get_channel` and friends; [`repair`](Self::repair)
+    /// clears them.
+    pub fn damaged_channels(&self) -> Vec<u32> {
+        (0..self.max_channels)
+            .filter(|&i| {
+                let initialized = self.entry(i).capacity != 0;
+                initialized && self.sound_entry(i).is_none()
+            })
+            .map(|i| i as u32)
+            .collect()
+    }
+
+    /// Clear every damaged channel entry (see
+    /// [`damaged_channels`](Self::damaged_channels)), free the IDs claimed by
+    /// creators that died before initializing their entry, and recount
+    /// `channel_count` from the channels that remain. Returns how many entries
+    /// were cleared.
+    ///
+    /// Only call this while no other process is creating channels; sound
+    /// channels and their contents are left untouched.
+    pub fn repair(&self) -> usize {
+        let _guard = self.allocation_mutex.lock();
+        let damaged = self.damaged_channels();
+        for &id in &damaged {
+            let entry = unsafe { &mut *self.channels.add(id as usize) };
+            *entry = ChannelEntry::default();
+        }
+        for id in 0..self.max_channels {
+            if self.entry(id).capacity == 0 {
+                self.release_id(id as u32);
+            }
+        }
+
+        let live = (0..self.max_channels)
+            .filter(|&i| self.sound_entry(i).is_some())
+            .count();
+        unsafe {
+            (*self.header).channel_count = live as u32;
+        }
+        damaged.len()
+    }
+
+    /// Remove every channel at once, leaving the segment as `new` created it:
+    /// all table entries and the ID bitmap cleared, `channel_count` 0 and the
+    /// whole data region free. The header's magic, version and channel limit
+    /// are kept, so attached allocators stay valid, and so is the event log.
+    ///
+    /// Only call this while no producer or consumer, in any process, is using
+    /// the segment: their views would point at channels that no longer exist,
+    /// and their bands are handed to the next channels created.
+    pub fn reset(&self) {
+        let _guard = self.allocation_mutex.lock();
+        unsafe {
+            std::ptr::write_bytes(self.channels, 0, self.max_channels);
+            (*self.header).channel_count = 0;
+        }
+        for word in self.id_words() {
+            word.store(0, Ordering::Release);
+        }
+    }
+
     // Create a new channel with the specified capacity
     pub fn create_channel(
         &self,
         capacity: usize,
         requested_id: Option<u32>,
     ) -> io::Result<ChannelPartition> {
-        // Validate capacity is a power of two and non-zero
-        if capacity == 0 || (capacity & (capacity - 1)) != 0 {
+        self.create_channel_with(ChannelSpec::new(capacity), requested_id)
+    }
+
+    /// Create a new channel described by `spec`
+    pub fn create_channel_with(
+        &self,
+        spec: ChannelSpec,
+        requested_id: Option<u32>,
+    ) -> io::Result<ChannelPartition> {
+        // Use a mutex to prevent multiple threads from allocating overlapping memory
+        let _guard = self.allocation_mutex.lock();
+        self.create_locked(&spec, requested_id, 0)
+    }
+
+    /// Create one channel for every spec, with the lowest free IDs in order
+    /// (`0..specs.len()` on a fresh segment), or none of them.
+    ///
+    /// Fails before creating anything if a spec is invalid, or with
+    /// `OutOfMemory` if the channels could not fit even an empty segment (see
+    /// [`required_size`](Self::required_size)). Otherwise they are created
+    /// under one hold of the allocation lock, so no other channel is created
+    /// or removed in between, and if any of them fails the ones already
+    /// created are removed again before its error is returned.
+    pub fn create_channels(&self, specs: &[ChannelSpec]) -> io::Result<Vec<ChannelPartition>> {
+        for spec in specs {
+            spec.validate()?;
+        }
+        let required = Self::required_size(self.max_channels, specs);
+        if required > self.shm.size() {
             return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Capacity must be a power of two and greater than zero",
+                io::ErrorKind::OutOfMemory,
+                format!(
+                    "{} channels need a {}-byte segment, this one has {} bytes",
+                    specs.len(),
+                    required,
+                    self.shm.size()
+                ),
             ));
         }
 
-        let slot_size = RingBuffer::slot_stride();
-        let channel_size = (capacity * slot_size + 127) & !127; // Align to 128 bytes
+        let _guard = self.allocation_mutex.lock();
+        let mut created = Vec::with_capacity(specs.len());
+        for spec in specs {
+            match self.create_locked(spec, None, 0) {
+                Ok(channel) => created.push(channel),
+                Err(e) => {
+                    for channel in created {
+                        self.release_entry(channel.channel_id as usize);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(created)
+    }
 
-        // Get next available channel ID
+    /// Create a channel described by `spec`, with `extra_flags` set in its
+    /// `ChannelEntry::flags` besides those of the spec. Called with the
+    /// allocation mutex held.
+    fn create_locked(
+        &self,
+        spec: &ChannelSpec,
+        requested_id: Option<u32>,
+        extra_flags: u32,
+    ) -> io::Result<ChannelPartition> {
+        spec.validate()?;
+        let capacity = spec.capacity;
+        let layout = spec.layout()?;
+        let channel_size = layout.size;
+
+        // Claim the channel ID: the requested one, or the lowest free one
         let channel_id = if let Some(id) = requested_id {
-            if id >= MAX_CHANNELS as u32 {
+            if id as usize >= self.max_channels {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Requested channel ID {} exceeds maximum {}",
-                        id, MAX_CHANNELS
+                        id, self.max_channels
                     ),
                 ));
             }
+            if id == self.control_channel_id() && extra_flags & CHANNEL_FLAG_CONTROL == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Channel ID {} is reserved for the control channel", id),
+                ));
+            }
+            if !self.claim_id(id) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "Channel ID already in use",
+                ));
+            }
             id
         } else {
-            loop {
-                let current_id = self.next_channel_id.load(Ordering::Acquire);
-                if current_id >= MAX_CHANNELS as u64 {
-                    return Err(io::Error::new(
-                        io::ErrorKind::OutOfMemory,
-                        "Maximum number of channels reached",
-                    ));
-                }
-
-                // Try to claim this ID
-                if self
-                    .next_channel_id
-                    .compare_exchange_weak(
-                        current_id,
-                        current_id + 1,
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    )
-                    .is_ok()
-                {
-                    break current_id as u32;
-                }
-            }
+            self.claim_free_id().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::OutOfMemory,
+                    "Maximum number of channels reached",
+                )
+            })?
         };
 
-        // Use a mutex to prevent multiple threads from allocating overlapping memory
-        let _guard = self.allocation_mutex.lock();
-
         // Get channel entry
-        let channel = unsafe { &mut (*self.header).channels[channel_id as usize] };
-
-        // Check if channel is already in use
-        if channel.capacity != 0 {
-            return Err(io::Error::new(
-                io::ErrorKind::AlreadyExists,
-                "Channel ID already in use",
-            ));
-        }
-
-        // Calculate offset for this channel's data
-        let control_size = std::mem::size_of::<GlobalHeader>();
-        let mut offset = control_size;
+        let channel = unsafe { &mut *self.channels.add(channel_id as usize) };
 
-        // Find the end of the last channel's data
-        unsafe {
-            for i in 0..MAX_CHANNELS {
-                let ch = &(*self.header).channels[i];
-                if ch.capacity != 0 {
-                    let ch_end = ch.band_offset as usize + ch.capacity as usize * slot_size;
-                    offset = offset.max(ch_end);
-                }
-            }
-        }
-
-        // Align the offset
-        offset = (offset + 127) & !127;
-
-        // Check if we have enough space
-        if offset + channel_size > self.shm.size() {
+        // This channel's data goes into the first free range it fits in,
+        // reusing the bands of removed channels before growing past the others
+        let Some(offset) = self
+            .free_ranges()
+            .into_iter()
+            .find(|&(_, len)| len >= channel_size)
+            .map(|(offset, _)| offset)
+        else {
+            self.release_id(channel_id);
             return Err(io::Error::new(
                 io::ErrorKind::OutOfMemory,
                 "Not enough space in shared memory",
             ));
-        }
+        };
 
         // Initialize channel metadata
         channel.channel_id = channel_id;
-        channel.flags = 0;
+        let mut flags = extra_flags;
+        if spec.checksum {
+            flags |= CHANNEL_FLAG_CHECKSUM;
+        }
+        if spec.broadcast {
+            flags |= CHANNEL_FLAG_BROADCAST;
+        }
+        if spec.exact_capacity {
+            flags |= CHANNEL_FLAG_EXACT_CAPACITY;
+        }
+        if spec.overwrite {
+            flags |= CHANNEL_FLAG_OVERWRITE;
+        }
+        if spec.single_producer {
+            flags |= CHANNEL_FLAG_SINGLE_PRODUCER;
+        }
+        if spec.single_consumer {
+            flags |= CHANNEL_FLAG_SINGLE_CONSUMER;
+        }
+        channel.flags = std::sync::atomic::AtomicU32::new(flags);
         channel.band_offset = offset as u64;
         channel.capacity = capacity as u64;
+        channel.band_size = channel_size as u64;
+        channel.inline_size = spec.inline_size as u32;
+        channel.overflow_offset = layout.overflow_offset as u64;
+        channel.overflow_blocks = spec.overflow_blocks as u64;
+        channel.overflow_block_size = spec.overflow_block_size as u32;
+        channel.priority_capacity = spec.priority_capacity as u64;
+        channel.priority_offset = layout.priority_offset as u64;
         channel.signal = std::sync::atomic::AtomicU32::new(0);
+        channel.space_signal = std::sync::atomic::AtomicU32::new(0);
+        channel.space_waiters = std::sync::atomic::AtomicU32::new(0);
+        channel.data_waiters = std::sync::atomic::AtomicU32::new(0);
+        channel.consumer_wakes = AtomicU64::new(0);
+        channel.high_water = AtomicU64::new(0);
+        channel.producer_pid = std::sync::atomic::AtomicU32::new(0);
+        channel.heartbeat = AtomicU64::new(0);
+        channel.open_producers = std::sync::atomic::AtomicU32::new(0);
+        channel.closed = std::sync::atomic::AtomicU32::new(0);
+        channel.next_message_id = AtomicU64::new(0);
+        channel.enqueue_count = AtomicU64::new(0);
+        channel.enqueue_full_failures = AtomicU64::new(0);
+        channel.dequeue_count = AtomicU64::new(0);
+        channel.dequeue_empty = AtomicU64::new(0);
+        channel.expired_count = AtomicU64::new(0);
+        channel.subscriber_mask = std::sync::atomic::AtomicU32::new(0);
+        for pid in channel.attached.iter_mut() {
+            *pid = std::sync::atomic::AtomicU32::new(0);
+        }
+        for cursor in channel.subscribers.iter_mut() {
+            *cursor = CachePadded::new(AtomicU64::new(0));
+        }
         channel.tail = CachePadded::new(AtomicU64::new(0));
         channel.head = CachePadded::new(AtomicU64::new(0));
+        channel.priority_tail = CachePadded::new(AtomicU64::new(0));
+        channel.priority_head = CachePadded::new(AtomicU64::new(0));
 
         // Initialize ring buffer view
         let buffer_ptr = unsafe { self.shm.as_ptr().add(offset) };
-        let ring_buffer = unsafe { RingBuffer::new(channel, buffer_ptr) };
+        let ring_buffer =
+            unsafe { RingBuffer::new(channel, buffer_ptr) }.with_event_log(self.event_log);
 
-        // Initialize slots (only done by creator)
-        unsafe {
-            ring_buffer.init_slots();
-        }
+        // Initialize slots (only done by creator); the cursors were just zeroed
+        unsafe { ring_buffer.init_slots()? };
 
         // Update channel count
         unsafe {
             (*self.header).channel_count += 1;
         }
+        self.event_log()
+            .append(AllocatorEventKind::Created, channel_id);
 
         Ok(ChannelPartition {
             buffer: ring_buffer,
@@ -271,57 +1058,100 @@ impl SharedMemoryAllocator {
 
     /// Get a channel by ID
     pub fn get_channel(&self, channel_id: u32) -> Option<ChannelPartition> {
-        if channel_id >= MAX_CHANNELS as u32 {
+        if channel_id as usize >= self.max_channels {
             return None;
         }
 
-        let channel = unsafe { &(*self.header).channels[channel_id as usize] };
-        if channel.capacity == 0 {
-            return None; // Channel not initialized
-        }
-
-        let buffer_ptr = unsafe { self.shm.as_ptr().add(channel.band_offset as usize) };
-        let ring_buffer = unsafe { RingBuffer::new(channel, buffer_ptr) };
-
-        Some(ChannelPartition {
-            buffer: ring_buffer,
-            channel_id,
-            capacity: channel.capacity as usize,
-        })
+        self.view(channel_id as usize)
     }
 
-    // Get the total used memory in bytes
-    pub fn used_memory(&self) -> usize {
-        let control_size = std::mem::size_of::<GlobalHeader>();
-        let mut max_offset = control_size;
-
-        unsafe {
-            for i in 0..MAX_CHANNELS as usize {
-                let ch = &(*self.header).channels[i];
-                let ch_end =
-                    ch.band_offset as usize + ch.capacity as usize * RingBuffer::slot_stride();
-                max_offset = max_offset.max(ch_end);
+    /// A view of the channel at `index`, None if it is not initialized or is
+    /// damaged.
+    fn view(&self, index: usize) -> Option<ChannelPartition> {
+        let generation = &self.entry(index).layout_generation;
+        loop {
+            let before = generation.load(Ordering::Acquire);
+            let channel = self.sound_entry(index)?;
+            let buffer_ptr = unsafe { self.shm.as_ptr().add(channel.band_offset as usize) };
+            let ring_buffer =
+                unsafe { RingBuffer::new(channel, buffer_ptr) }.with_event_log(self.event_log);
+            // A resize in between may have mixed old and new geometry
+            if ring_buffer.generation == before {
+                return Some(ChannelPartition {
+                    buffer: ring_buffer,
+                    channel_id: index as u32,
+                    capacity: channel.capacity as usize,
+                });
             }
         }
+    }
 
-        max_offset
+    /// Bytes taken by the header, the channel table and the bands of live
+    /// channels, counting the alignment padding after each band.
+    /// `used_memory() + available_memory()` is the segment size.
+    pub fn used_memory(&self) -> usize {
+        self.shm.size() - self.available_memory()
     }
 
-    // Get the total available memory in bytes
+    /// Bytes not taken by any channel, including the ranges freed by removed
+    /// channels. A new channel needs one free range of its size, so after
+    /// removals this can exceed the largest channel that still fits.
     pub fn available_memory(&self) -> usize {
-        self.shm.size().saturating_sub(self.used_memory())
+        self.free_ranges().iter().map(|&(_, len)| len).sum()
+    }
+
+    /// Reports how the free space is split between the gaps left by removed
+    /// channels and the space after the last band.
+    ///
+    /// Walks the channel table under the allocation lock, so the report is
+    /// consistent with concurrent creates and removals in this process.
+    pub fn fragmentation(&self) -> FragmentationReport {
+        let _guard = self.allocation_mutex.lock();
+        let ranges = self.free_ranges();
+        FragmentationReport {
+            largest_free_block: ranges.iter().map(|&(_, len)| len).max().unwrap_or(0),
+            total_free: ranges.iter().map(|&(_, len)| len).sum(),
+            free_block_count: ranges.len(),
+        }
+    }
+
+    /// The gaps between the channel table and live channel bands, and after
+    /// the last band, as `(offset, len)` in address order. Every offset is
+    /// aligned to `BAND_ALIGN`.
+    fn free_ranges(&self) -> Vec<(usize, usize)> {
+        let size = self.shm.size();
+        // Sound entries end inside the segment, so these sums cannot overflow
+        let mut bands: Vec<(usize, usize)> = (0..self.max_channels)
+            .filter_map(|i| self.sound_entry(i))
+            .map(|ch| {
+                let start = ch.band_offset as usize;
+                (start, start + ch.band_size as usize)
+            })
+            .collect();
+        bands.sort_unstable();
+
+        let mut ranges = Vec::new();
+        let mut cursor = align_band(self.control_size()).unwrap_or(usize::MAX);
+        for (start, end) in bands.into_iter().chain([(size, size)]) {
+            if start > cursor {
+                ranges.push((cursor, start - cursor));
+            }
+            cursor = cursor.max(align_band(end).unwrap_or(usize::MAX));
+        }
+        ranges
     }
 
     // function to remove a channel
     pub fn remove_channel(&self, channel_id: u32) -> io::Result<()> {
-        if channel_id >= MAX_CHANNELS as u32 {
+        if channel_id as usize >= self.max_channels {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Channel ID out of bounds",
             ));
         }
 
-        let channel = unsafe { &mut (*self.header).channels[channel_id as usize] };
+        let _guard = self.allocation_mutex.lock();
+        let channel = self.entry(channel_id as usize);
         if channel.capacity == 0 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -329,32 +1159,361 @@ impl SharedMemoryAllocator {
             ));
         }
 
-        // Set capacity to 0 to mark the channel as free
-        channel.capacity = 0;
+        self.release_entry(channel_id as usize);
+        Ok(())
+    }
+
+    /// Remove every channel nobody is using any more: no live producer or
+    /// consumer attached (see [`producer_count`](Self::producer_count)), nothing
+    /// left to receive in either band, and not sealed. Returns how many were
+    /// removed.
+    ///
+    /// Lets a long-running process reclaim channels whose clients crashed
+    /// without calling `remove_channel`. Only producers and consumers count as
+    /// users: a channel accessed solely through `get_channel` views looks
+    /// abandoned, and so does one whose users are about to attach.
+    pub fn gc_abandoned_channels(&self) -> usize {
+        let _guard = self.allocation_mutex.lock();
+        let abandoned: Vec<u32> = self
+            .get_channels()
+            .iter()
+            .filter(|channel| {
+                let buffer = &channel.buffer;
+                !buffer.is_sealed()
+                    && buffer.attached_count(false) == 0
+                    && buffer.attached_count(true) == 0
+                    && buffer.is_empty()
+                    && buffer.priority_band().is_none_or(RingBuffer::is_empty)
+            })
+            .map(|channel| channel.channel_id)
+            .collect();
+
+        for &channel_id in &abandoned {
+            self.release_entry(channel_id as usize);
+        }
+        abandoned.len()
+    }
+
+    /// Move every channel's band down to the front of the data region, in
+    /// address order, so the free space left by removed channels becomes one
+    /// block after the last band. Returns how many channels were moved.
+    ///
+    /// Bands are copied whole, so messages waiting in them are kept. Fails with
+    /// `ResourceBusy`, moving nothing, while any live producer, consumer or
+    /// multi-consumer is attached to a channel (see
+    /// [`producer_count`](Self::producer_count)).
+    ///
+    /// Views of a moved channel made before, from `get_channel`,
+    /// `get_channels` or a [`TailConsumer`](crate::MPMC::TailConsumer) in any
+    /// process, are not rebased: they turn stale like after
+    /// [`resize_channel`](Self::resize_channel) and stop touching the old
+    /// band. [`RingBuffer::try_dequeue`] then fails with
+    /// [`DequeueError::Stale`](crate::MPMC::Buffer::DequeueError::Stale) and
+    /// `TailConsumer::receive` with `NotConnected`, so fetch them again.
+    pub fn compact(&self) -> io::Result<usize> {
+        let _guard = self.allocation_mutex.lock();
+        let channels = self.get_channels();
+        if let Some(busy) = channels.iter().find(|channel| {
+            channel.buffer.attached_count(false) != 0 || channel.buffer.attached_count(true) != 0
+        }) {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!(
+                    "Channel {} has attached producers or consumers",
+                    busy.channel_id
+                ),
+            ));
+        }
 
+        let mut bands: Vec<(usize, usize)> = channels
+            .iter()
+            .map(|channel| {
+                let entry = self.entry(channel.channel_id as usize);
+                (entry.band_offset as usize, channel.channel_id as usize)
+            })
+            .collect();
+        drop(channels);
+        bands.sort_unstable();
+
+        let mut moved = 0;
+        let mut cursor = align_band(self.control_size()).unwrap_or(usize::MAX);
+        for (offset, index) in bands {
+            // Sound entries end inside the segment, so the sums cannot overflow
+            let band_size = self.entry(index).band_size as usize;
+            if offset > cursor {
+                self.relayout(index, || unsafe {
+                    let base = self.shm.as_ptr();
+                    // Bands only move down, possibly over their own old start
+                    std::ptr::copy(base.add(offset), base.add(cursor), band_size);
+                    (*self.channels.add(index)).band_offset = cursor as u64;
+                });
+                moved += 1;
+            }
+            let start = offset.min(cursor);
+            cursor = cursor.max(align_band(start + band_size).unwrap_or(usize::MAX));
+        }
+        Ok(moved)
+    }
+
+    /// Give an idle channel a ring of `new_capacity` slots, keeping its ID,
+    /// its other settings and the start of its band. Shrinking returns the
+    /// tail of the band to the free space; growing needs free space right
+    /// after the band, and fails with `OutOfMemory` otherwise. The cursors
+    /// start again from 0; message ids carry on.
+    ///
+    /// Fails with `NotFound` for a channel that does not exist, with
+    /// `InvalidInput` for a capacity it could not have been created with, and
+    /// with `ResourceBusy` while a live producer or consumer is attached (see
+    /// [`producer_count`](Self::producer_count)) or either band still holds
+    /// messages.
+    ///
+    /// Views made before the resize, from `get_channel`, `get_channels` or a
+    /// [`TailConsumer`](crate::MPMC::TailConsumer) in any process, turn stale
+    /// (see [`RingBuffer::is_stale`]): they stop touching the ring, and their
+    /// sends, `try_dequeue` and tail receives fail, so fetch them again. Only
+    /// an operation already under way on such a view when the resize starts
+    /// is not waited for.
+    pub fn resize_channel(&self, channel_id: u32, new_capacity: usize) -> io::Result<()> {
+        let _guard = self.allocation_mutex.lock();
+        let channel = self.get_channel(channel_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Channel {} does not exist", channel_id),
+            )
+        })?;
+        let buffer = &channel.buffer;
+        if buffer.attached_count(false) != 0 || buffer.attached_count(true) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!("Channel {} has attached producers or consumers", channel_id),
+            ));
+        }
+        if !buffer.is_empty() || buffer.priority_band().is_some_and(|p| !p.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!("Channel {} still holds messages", channel_id),
+            ));
+        }
+        drop(channel);
+
+        let entry = self.entry(channel_id as usize);
+        let flags = entry.flags.load(Ordering::Acquire);
+        let spec = ChannelSpec::new(new_capacity)
+            .with_inline_size(match entry.inline_size {
+                0 => MSG_INLINE,
+                size => size as usize,
+            })
+            .with_overflow(
+                entry.overflow_blocks as usize,
+                entry.overflow_block_size as usize,
+            )
+            .with_priority(entry.priority_capacity as usize)
+            .with_exact_capacity(flags & CHANNEL_FLAG_EXACT_CAPACITY != 0);
+        spec.validate()?;
+        let layout = spec.layout()?;
+
+        // The band may grow up to the next one, or the end of the segment
+        let offset = entry.band_offset as usize;
+        let room = (0..self.max_channels)
+            .filter_map(|i| self.sound_entry(i))
+            .map(|other| other.band_offset as usize)
+            .filter(|&start| start > offset)
+            .min()
+            .unwrap_or(self.shm.size())
+            - offset;
+        if layout.size > room {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!(
+                    "Channel {} needs {} bytes but only {} are free at its band",
+                    channel_id, layout.size, room
+                ),
+            ));
+        }
+
+        self.relayout(channel_id as usize, || {
+            unsafe {
+                let entry = &mut *self.channels.add(channel_id as usize);
+                entry.capacity = new_capacity as u64;
+                entry.band_size = layout.size as u64;
+                entry.priority_offset = layout.priority_offset as u64;
+                entry.overflow_offset = layout.overflow_offset as u64;
+            }
+            let channel = self
+                .get_channel(channel_id)
+                .expect("a resized channel stays sound");
+            // Nobody is attached and both bands are empty
+            unsafe { channel.buffer.reinit_slots() };
+        });
+        self.event_log()
+            .append(AllocatorEventKind::Resized, channel_id);
         Ok(())
     }
 
-    pub fn get_channels(&self) -> Vec<ChannelPartition> {
-        let mut channels = Vec::new();
+    /// Run `rewrite`, which changes where or how the ring of the channel at
+    /// `index` is laid out, between two bumps of its layout generation: views
+    /// made before stop touching slots before the rewrite starts, and views
+    /// made during it are stale once it ends. Called with the allocation
+    /// mutex held.
+    fn relayout<R>(&self, index: usize, rewrite: impl FnOnce() -> R) -> R {
+        let generation = &self.entry(index).layout_generation;
+        generation.fetch_add(1, Ordering::AcqRel);
+        let result = rewrite();
+        generation.fetch_add(1, Ordering::AcqRel);
+        result
+    }
+
+    /// Mark an initialized entry free. Called with the allocation mutex held.
+    fn release_entry(&self, index: usize) {
+        // Set capacity to 0 to mark the channel as free and clear its ID bit;
+        // its ID and its band are reused by later channels
         unsafe {
-            for i in 0..MAX_CHANNELS as usize {
-                let ch = &(*self.header).channels[i];
-                if ch.capacity != 0 {
-                    let buffer_ptr = self.shm.as_ptr().add(ch.band_offset as usize);
-                    let ring_buffer = RingBuffer::new(ch, buffer_ptr);
-                    channels.push(ChannelPartition {
-                        buffer: ring_buffer,
-                        channel_id: ch.channel_id,
-                        capacity: ch.capacity as usize,
-                    });
+            let channel = &mut *self.channels.add(index);
+            channel.capacity = 0;
+            channel.band_size = 0;
+            let count = &mut (*self.header).channel_count;
+            *count = count.saturating_sub(1);
+        }
+        self.release_id(index as u32);
+        self.event_log()
+            .append(AllocatorEventKind::Removed, index as u32);
+    }
+
+    /// Seal a channel so it becomes read-only: every later send on it fails with
+    /// `PermissionDenied`, while consumers keep receiving what it holds. A send
+    /// racing with the seal may still get through. Sealing cannot be undone;
+    /// remove and recreate the channel to reuse the ID.
+    pub fn seal_channel(&self, channel_id: u32) -> io::Result<()> {
+        let channel = self.get_channel(channel_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Channel {} does not exist", channel_id),
+            )
+        })?;
+        if !channel.buffer.is_sealed() {
+            channel.buffer.seal();
+            self.event_log()
+                .append(AllocatorEventKind::Sealed, channel_id);
+        }
+        Ok(())
+    }
+
+    /// Whether a channel was sealed with [`seal_channel`](Self::seal_channel).
+    /// False for a channel that does not exist.
+    pub fn is_sealed(&self, channel_id: u32) -> bool {
+        self.get_channel(channel_id)
+            .is_some_and(|channel| channel.buffer.is_sealed())
+    }
+
+    pub fn get_channels(&self) -> Vec<ChannelPartition> {
+        (0..self.max_channels)
+            .filter_map(|i| self.view(i))
+            .collect()
+    }
+
+    /// Capacity, occupancy and placement of every initialized channel, in ID order
+    pub fn channel_infos(&self) -> Vec<ChannelInfo> {
+        self.get_channels()
+            .iter()
+            .map(|partition| {
+                let entry = self.entry(partition.channel_id as usize);
+                ChannelInfo {
+                    id: partition.channel_id,
+                    capacity: partition.capacity,
+                    used_slots: partition.buffer.len(),
+                    offset: entry.band_offset as usize,
+                    producers: partition.buffer.attached_count(false),
+                    consumers: partition.buffer.attached_count(true),
                 }
-            }
+            })
+            .collect()
+    }
+
+    /// Read the traffic counters of a channel, or None if it does not exist
+    pub fn channel_metrics(&self, channel_id: u32) -> Option<ChannelMetrics> {
+        if channel_id as usize >= self.max_channels {
+            return None;
+        }
+        let entry = self.sound_entry(channel_id as usize)?;
+        Some(ChannelMetrics {
+            enqueue_count: entry.enqueue_count.load(Ordering::Relaxed),
+            dequeue_count: entry.dequeue_count.load(Ordering::Relaxed),
+            enqueue_full_failures: entry.enqueue_full_failures.load(Ordering::Relaxed),
+            dequeue_empty: entry.dequeue_empty.load(Ordering::Relaxed),
+            expired_count: entry.expired_count.load(Ordering::Relaxed),
+            consumer_wakes: entry.consumer_wakes.load(Ordering::Relaxed),
+        })
+    }
+
+    /// The most messages a channel ever held at once (in its fuller band, if
+    /// it has a priority band) since it was created or last reset, or None if
+    /// it does not exist. Sampled by producers after each enqueue, so useful
+    /// for sizing `capacity` from observed peaks.
+    pub fn channel_high_water(&self, channel_id: u32) -> Option<u64> {
+        if channel_id as usize >= self.max_channels {
+            return None;
         }
-        channels
+        let entry = self.sound_entry(channel_id as usize)?;
+        Some(entry.high_water.load(Ordering::Relaxed))
+    }
+
+    /// Start a channel's high-water mark over from 0. Fails with `NotFound` if
+    /// the channel does not exist.
+    pub fn reset_high_water(&self, channel_id: u32) -> io::Result<()> {
+        let entry = ((channel_id as usize) < self.max_channels)
+            .then(|| self.sound_entry(channel_id as usize))
+            .flatten()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Channel {} does not exist", channel_id),
+                )
+            })?;
+        entry.high_water.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Bytes of a channel's data band (ring, priority band and overflow blocks)
+    /// currently resident in RAM, as counted by
+    /// [`SharedMemoryBackend::resident_bytes_in`]. Fails with `NotFound` if the
+    /// channel does not exist.
+    pub fn channel_resident_bytes(&self, channel_id: u32) -> io::Result<usize> {
+        let entry = ((channel_id as usize) < self.max_channels)
+            .then(|| self.sound_entry(channel_id as usize))
+            .flatten()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Channel {} does not exist", channel_id),
+                )
+            })?;
+        self.shm
+            .resident_bytes_in(entry.band_offset as usize, entry.band_size as usize)
+    }
+
+    /// Number of live producers attached to a channel, in any process; 0 if
+    /// the channel does not exist. Producers of processes that exited without
+    /// dropping them are no longer counted.
+    pub fn producer_count(&self, channel_id: u32) -> usize {
+        self.get_channel(channel_id)
+            .map_or(0, |channel| channel.buffer.attached_count(false))
+    }
+
+    /// Number of live consumers (including broadcast subscribers) attached to
+    /// a channel, in any process; 0 if the channel does not exist.
+    pub fn consumer_count(&self, channel_id: u32) -> usize {
+        self.get_channel(channel_id)
+            .map_or(0, |channel| channel.buffer.attached_count(true))
+    }
+
+    /// Whether a corrupted slot was detected in a channel (see
+    /// [`RingBuffer::is_poisoned`]); false if the channel does not exist.
+    /// Removing and recreating the channel is the only way to use it again.
+    pub fn is_poisoned(&self, channel_id: u32) -> bool {
+        self.get_channel(channel_id)
+            .is_some_and(|channel| channel.buffer.is_poisoned())
     }
 
-    // Get the number of active channels
     pub fn channel_count(&self) -> u32 {
         unsafe { (*self.header).channel_count }
     }
@@ -370,6 +1529,27 @@ impl ChannelPartition {
     pub fn buffer(&self) -> &RingBuffer {
         &self.buffer
     }
+
+    /// Get the address and length in bytes of the channel's slots, `capacity`
+    /// slots of `buffer().stride()` bytes each, laid out as described by
+    /// `buffer().slot_layout()`
+    ///
+    /// Meant for foreign runtimes that wrap the slots in their own buffer type.
+    /// The priority and overflow bands are not included.
+    ///
+    /// # Safety
+    /// The pointer is only valid while the allocator this partition came from
+    /// is alive, and only until the channel is removed or the segment is
+    /// compacted or reset. The slots are written concurrently by producers and
+    /// consumers in other processes: read a slot only after acquiring its
+    /// sequence word as the publish protocol describes, and never write one
+    /// that has not been claimed.
+    pub fn data_raw_parts(&self) -> (*mut u8, usize) {
+        (
+            self.buffer.slots_ptr(),
+            self.capacity * self.buffer.stride(),
+        )
+    }
 }
 
 // Implement Send + Sync since we manage synchronization internally
@@ -23,6 +23,44 @@ pub fn futex_wait(atomic: &AtomicU32, expected: u32) {
     }
 }
 
+/// Wait on `atomic` while it equals `expected`, for at most `timeout`.
+///
+/// Returns `true` if the wait timed out, and `false` if it ended early: the
+/// value already differed, a `futex_wake` woke it, or it was interrupted.
+/// Spurious early returns are possible, so callers re-check their condition.
+#[cfg(target_os = "linux")]
+pub fn futex_wait_timeout(atomic: &AtomicU32, expected: u32, timeout: std::time::Duration) -> bool {
+    use std::sync::atomic::Ordering;
+
+    if atomic.load(Ordering::Relaxed) != expected {
+        return false;
+    }
+
+    // FUTEX_WAIT takes a relative timeout
+    let ts = libc::timespec {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            atomic as *const AtomicU32 as *const u32,
+            libc::FUTEX_WAIT,
+            expected,
+            &ts as *const libc::timespec,
+            std::ptr::null::<u32>(),
+            0u32,
+        )
+    };
+    rc == -1 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ETIMEDOUT)
+}
+
+/// Park on `atomic` while it equals `expected`, for at most `timeout`.
+/// Used for short backoff parks where a missed wakeup only costs the timeout.
+pub(crate) fn futex_park(atomic: &AtomicU32, expected: u32, timeout: std::time::Duration) {
+    futex_wait_timeout(atomic, expected, timeout);
+}
+
 #[cfg(target_os = "linux")]
 pub fn futex_wake(atomic: &AtomicU32) {
     unsafe {
@@ -38,13 +76,43 @@ pub fn futex_wake(atomic: &AtomicU32) {
     }
 }
 
+/// Wake every thread waiting on `atomic`, for shutdown and for channels with
+/// several consumers. Use `futex_wake` when one waiter is enough, to avoid
+/// waking a herd that goes straight back to sleep.
+#[cfg(target_os = "linux")]
+pub fn futex_wake_all(atomic: &AtomicU32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            atomic as *const AtomicU32 as *const u32,
+            libc::FUTEX_WAKE,
+            i32::MAX, // Wake all waiters
+            std::ptr::null::<libc::timespec>(),
+            std::ptr::null::<u32>(),
+            0u32,
+        );
+    }
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn futex_wait(_atomic: &AtomicU32, _expected: u32) {
     // Fallback for non-Linux: busy wait with yield
     std::thread::yield_now();
 }
 
+#[cfg(not(target_os = "linux"))]
+pub fn futex_wait_timeout(atomic: &AtomicU32, expected: u32, timeout: std::time::Duration) -> bool {
+    // Fallback for non-Linux: sleep out the wait
+    std::thread::sleep(timeout);
+    atomic.load(std::sync::atomic::Ordering::Relaxed) == expected
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn futex_wake(_atomic: &AtomicU32) {
     // No-op on non-Linux
 }
+
+#[cfg(not(target_os = "linux"))]
+pub fn futex_wake_all(_atomic: &AtomicU32) {
+    // No-op on non-Linux
+}
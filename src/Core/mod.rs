@@ -1,7 +1,9 @@
 pub mod SharedMemory;
 pub mod alloc;
 pub mod futex;
+pub(crate) mod process;
 
 pub use SharedMemory::{
-    attach_shared_memory, create_shared_memory, RawHandle, SharedMemoryBackend,
+    attach_shared_memory, attach_shared_memory_fd, create_shared_memory, RawHandle,
+    SharedMemoryBackend,
 };
@@ -0,0 +1,13 @@
+// Process liveness checks for state other processes left in shared memory.
+
+/// Whether a process with `pid` exists. A process we may not signal still counts.
+#[cfg(unix)]
+pub(crate) fn process_exists(pid: u32) -> bool {
+    let rc = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn process_exists(_pid: u32) -> bool {
+    true
+}
@@ -1,7 +1,9 @@
 use crate::Core::alloc::ChannelPartition;
 use crate::Core::alloc::SharedMemoryAllocator;
 use crate::MPMC::Buffer::RingBuffer;
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_COMPRESSED, FLAG_SPILLED};
 use std::fmt;
+use std::sync::atomic::Ordering;
 
 /// Debug function for SharedMemoryAllocator
 ///
@@ -26,31 +28,156 @@ pub fn debug_shared_memory_allocator(
 ///
 /// Shows:
 /// - Channel ID
-/// - Underlying RingBuffer details
+/// - Underlying RingBuffer details (see `debug_ring_buffer`)
 pub fn debug_channel_partition(
     partition: &ChannelPartition,
     f: &mut fmt::Formatter<'_>,
 ) -> fmt::Result {
     f.debug_struct("ChannelPartition")
         .field("channel_id", &partition.channel_id)
-        .field(
-            "buffer",
-            &format_args!("RingBuffer(0x{:x})", partition.buffer.buffer_base as usize),
-        )
+        .field("buffer", &partition.buffer)
         .finish()
 }
 
 /// Debug function for RingBuffer
 ///
-/// Safely displays the buffer's memory location without dereferencing
+/// Shows the cursors and occupancy, read atomically from the channel entry:
+/// - Buffer base address and capacity
+/// - Current head and tail
+/// - `len` and whether the ring is empty or full
+/// - The priority band, if any, in the same form
+///
+/// Slots and payloads are never dereferenced. The values are a snapshot and
+/// may be mutually inconsistent while producers and consumers are active.
 pub fn debug_ring_buffer(buffer: &RingBuffer, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.debug_struct("RingBuffer")
+    let head = unsafe { &*buffer.head }.load(Ordering::Acquire);
+    let tail = unsafe { &*buffer.tail }.load(Ordering::Acquire);
+    let len = buffer.len();
+    let mut out = f.debug_struct("RingBuffer");
+    out.field(
+        "buffer_base",
+        &format_args!("0x{:x}", buffer.buffer_base as usize),
+    )
+    .field("capacity", &buffer.capacity)
+    .field("head", &head)
+    .field("tail", &tail)
+    .field("len", &len)
+    .field("empty", &(len == 0))
+    .field("full", &(len >= buffer.capacity));
+    if buffer.is_broadcast() {
+        out.field("subscriber", &buffer.subscriber);
+    }
+    if let Some(priority) = buffer.priority_band() {
+        out.field("priority", priority);
+    }
+    out.finish()
+}
+
+/// Debug function for MessageMeta
+///
+/// Human-readable form used by `MessageMeta`'s `Display`:
+/// - Timestamp as an ISO-8601 UTC instant
+/// - Known `message_type` and `sender_runtime` values by name
+/// - Transport flags by name, application bits in hex
+///
+/// `{:?}` on `MessageMeta` keeps the derived, raw-integer form.
+pub fn debug_message_meta(meta: &MessageMeta, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let message_type = match meta.message_type {
+        1 => " (default)",
+        _ => "",
+    };
+    let runtime = match meta.sender_runtime {
+        1 => " (rust)",
+        _ => "",
+    };
+    f.debug_struct("MessageMeta")
+        .field("message_id", &meta.message_id)
+        .field(
+            "timestamp",
+            &format_args!("{}", Timestamp(meta.timestamp_ns)),
+        )
+        .field("channel_id", &meta.channel_id)
+        .field(
+            "message_type",
+            &format_args!("{}{}", meta.message_type, message_type),
+        )
+        .field("sender_pid", &meta.sender_pid)
         .field(
-            "buffer_base",
-            &format_args!("0x{:x}", buffer.buffer_base as usize),
+            "sender_runtime",
+            &format_args!("{}{}", meta.sender_runtime, runtime),
+        )
+        .field("flags", &format_args!("{}", Flags(meta.flags)))
+        .field("payload_len", &meta.payload_len)
+        .field("checksum", &format_args!("{:#010x}", meta.checksum))
+        .field("schema_version", &meta.schema_version)
+        .field("ttl_ms", &meta.ttl_ms)
+        .finish()
+}
+
+/// Nanoseconds since the Unix epoch, shown as e.g. `2024-05-01T12:00:00.000000001Z`.
+struct Timestamp(u64);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("unset");
+        }
+        let secs = self.0 / 1_000_000_000;
+        let nanos = self.0 % 1_000_000_000;
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let rem = secs % 86_400;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+            year,
+            month,
+            day,
+            rem / 3600,
+            rem % 3600 / 60,
+            rem % 60,
+            nanos
         )
-        .field("capacity", &buffer.capacity)
-        .finish_non_exhaustive()
+    }
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+/// (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
-// Getter functions
+/// `MessageMeta::flags` with the transport bits named, e.g. `SPILLED | 0x0003`.
+struct Flags(u16);
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+        let mut first = true;
+        for (bit, name) in [(FLAG_SPILLED, "SPILLED"), (FLAG_COMPRESSED, "COMPRESSED")] {
+            if rest & bit != 0 {
+                if !first {
+                    f.write_str(" | ")?;
+                }
+                f.write_str(name)?;
+                rest &= !bit;
+                first = false;
+            }
+        }
+        if rest != 0 || first {
+            if !first {
+                f.write_str(" | ")?;
+            }
+            write!(f, "{:#06x}", rest)?;
+        }
+        Ok(())
+    }
+}
@@ -1,19 +1,49 @@
 // This is the shared round buffer for MPMC - divided by the channels
 
-use super::layout::ChannelEntry;
+use super::layout::{ChannelEntry, EventLog};
+use super::overflow::OverflowBand;
+use crate::MPMC::backoff::Backoff;
 use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
 
 use std::sync::atomic::AtomicU64;
 
-/// The size of the inline payload per slot.
-/// This should be tuned per deployment.
+/// The default size of the inline payload per slot, for channels created
+/// without `ChannelSpec::with_inline_size`.
 pub const MSG_INLINE: usize = 1024;
 
-/// A single slot in the ring buffer.
+/// Alignment of every slot, and granularity of the slot stride.
+pub const SLOT_ALIGN: usize = 64;
+
+/// Byte offset of `Slot::sequence` (a little-endian `u64`) within a slot.
+///
+/// A producer that finds `sequence == tail` may claim the slot by advancing
+/// `ChannelEntry::tail` from `tail` to `tail + 1` with a compare-and-swap. It
+/// then writes the metadata and payload and publishes them by storing
+/// `tail + 1` into `sequence` with release ordering. A consumer reads the slot
+/// once `sequence == head + 1` (loaded with acquire ordering) and it has
+/// advanced `head`, and hands it back by storing `head + capacity`.
+pub const SLOT_SEQUENCE_OFFSET: usize = 0;
+
+/// Byte offset of the `MessageMeta` within a slot.
+pub const SLOT_META_OFFSET: usize = 8;
+
+/// Byte offset of the inline payload within a slot. The payload holds
+/// `MessageMeta::payload_len` bytes, at most the channel's inline size.
+pub const SLOT_PAYLOAD_OFFSET: usize = 56;
+
+const _: () = assert!(std::mem::offset_of!(Slot, sequence) == SLOT_SEQUENCE_OFFSET);
+const _: () = assert!(std::mem::offset_of!(Slot, meta) == SLOT_META_OFFSET);
+const _: () = assert!(Slot::PAYLOAD_OFFSET == SLOT_PAYLOAD_OFFSET);
+
+/// The fixed header at the start of every slot in the ring buffer.
 ///
-/// This struct represents the actual data layout in shared memory.
+/// In shared memory each slot is this header, then the channel's inline
+/// payload (`MSG_INLINE` bytes unless the channel chose another size), then
+/// the lease word, padded to a multiple of `SLOT_ALIGN`. The ring computes the
+/// payload and lease positions from the channel's inline size; see
+/// [`Slot::PAYLOAD_OFFSET`], [`Slot::lease_offset`] and [`Slot::stride`].
 /// It is marked `#[repr(C)]` to ensure a defined and stable memory layout.
-#[repr(C, align(64))]
+#[repr(C)]
 pub struct Slot {
     /// The sequence number of the slot. This is the core of the synchronization.
     /// - A producer claims a `tail` sequence and waits for the `sequence` in
@@ -25,9 +55,124 @@ pub struct Slot {
 
     /// Transport-only metadata (message ID, timestamp, etc.).
     pub meta: MessageMeta,
+}
+
+impl Slot {
+    /// Offset of the inline payload from the start of a slot.
+    pub const PAYLOAD_OFFSET: usize = std::mem::size_of::<Slot>();
+
+    /// Offset of the lease word in a slot holding `inline_size` payload bytes.
+    /// The lease is the redelivery deadline (unix ns) of a message claimed with
+    /// an ack lease, or 0; only meaningful while the slot is claimed and
+    /// unreleased.
+    pub const fn lease_offset(inline_size: usize) -> usize {
+        Self::PAYLOAD_OFFSET + inline_size
+    }
+
+    /// Bytes from one slot to the next for an inline payload of `inline_size`
+    /// bytes (a multiple of 8).
+    pub const fn stride(inline_size: usize) -> usize {
+        (Self::lease_offset(inline_size) + 8).next_multiple_of(SLOT_ALIGN)
+    }
+
+    /// Whether `inline_size` is usable as a channel's inline payload size:
+    /// non-zero, a multiple of 8 (so the lease word stays aligned) and small
+    /// enough to record in `ChannelEntry::inline_size`.
+    pub const fn is_valid_inline_size(inline_size: usize) -> bool {
+        inline_size != 0 && inline_size.is_multiple_of(8) && inline_size <= u32::MAX as usize
+    }
+}
+
+/// Why [`RingBuffer::try_enqueue`] did not enqueue a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnqueueError {
+    /// Every slot holds an unread message (`len() == capacity`), or the
+    /// overflow band has no block left for a spilled payload. Retrying only
+    /// helps once a consumer makes room.
+    Full,
+    /// The ring has room, but the slot at the tail is still in someone else's
+    /// hands: another producer claimed or is writing it, or a consumer has
+    /// taken the message in it and not yet released the slot. Worth retrying
+    /// right away.
+    Contended,
+    /// The payload is larger than `max_payload`.
+    TooLarge,
+    /// The channel was poisoned after a slot was found corrupted (see
+    /// [`RingBuffer::is_poisoned`]). Retrying never helps.
+    Poisoned,
+    /// The channel was resized or moved since this view was made (see
+    /// [`RingBuffer::is_stale`]). Fetch the channel again.
+    Stale,
+}
+
+impl std::fmt::Display for EnqueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EnqueueError::Full => "ring buffer is full",
+            EnqueueError::Contended => "ring buffer slot is contended",
+            EnqueueError::TooLarge => "payload is larger than the slot and overflow band allow",
+            EnqueueError::Poisoned => "channel poisoned",
+            EnqueueError::Stale => "channel view is stale",
+        })
+    }
+}
+
+impl std::error::Error for EnqueueError {}
+
+/// Why [`RingBuffer::try_dequeue`] could not tell whether the ring holds a
+/// message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DequeueError {
+    /// The channel was poisoned after a slot was found corrupted (see
+    /// [`RingBuffer::is_poisoned`]). Retrying never helps.
+    Poisoned,
+    /// The channel was resized or moved since this view was made (see
+    /// [`RingBuffer::is_stale`]). Fetch the channel again.
+    Stale,
+}
+
+impl std::fmt::Display for DequeueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DequeueError::Poisoned => "channel poisoned",
+            DequeueError::Stale => "channel view is stale",
+        })
+    }
+}
+
+impl std::error::Error for DequeueError {}
 
-    /// Opaque byte array payload.
-    pub payload: [u8; MSG_INLINE],
+/// Where the parts of a slot sit in one channel's ring, as returned by
+/// [`RingBuffer::slot_layout`]. All offsets are relative to the start of a
+/// slot; slot `i` starts `i * stride` bytes into the band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLayout {
+    /// Offset of the sequence word ([`SLOT_SEQUENCE_OFFSET`])
+    pub sequence_offset: usize,
+    /// Offset of the `MessageMeta` ([`SLOT_META_OFFSET`])
+    pub meta_offset: usize,
+    /// Offset of the inline payload ([`SLOT_PAYLOAD_OFFSET`])
+    pub payload_offset: usize,
+    /// Inline payload bytes per slot on this channel
+    pub inline_size: usize,
+    /// Offset of the lease word (see [`Slot::lease_offset`])
+    pub lease_offset: usize,
+    /// Bytes from one slot to the next (see [`Slot::stride`])
+    pub stride: usize,
+}
+
+impl SlotLayout {
+    /// The layout of slots holding `inline_size` payload bytes.
+    pub const fn new(inline_size: usize) -> Self {
+        Self {
+            sequence_offset: SLOT_SEQUENCE_OFFSET,
+            meta_offset: SLOT_META_OFFSET,
+            payload_offset: SLOT_PAYLOAD_OFFSET,
+            inline_size,
+            lease_offset: Slot::lease_offset(inline_size),
+            stride: Slot::stride(inline_size),
+        }
+    }
 }
 
 /// A high-performance, lock-free, multi-producer, multi-consumer (MPMC) ring buffer view.
@@ -41,6 +186,28 @@ pub struct Slot {
 ///   be written.
 /// - **Consumers (Dequeue)**: Consumers claim a message by atomically incrementing `head`.
 ///   They use the `sequence` field to know when a message has been fully written by a producer.
+///
+/// ### Slot indexing:
+/// Position `p` lives in slot `p & mask` when the capacity is a power of two, and
+/// in slot `p % capacity` on channels created with an exact capacity. Cursors and
+/// sequence numbers are absolute positions either way (a slot's `sequence` is
+/// `p` when free for position `p`, `p + 1` once written, and `p + capacity` once
+/// consumed), so wrap detection never depends on the mask.
+///
+/// ### Priority band:
+/// A channel may carry a second, smaller ring whose messages are always dequeued
+/// before the normal ring's. FIFO order then only holds within each band: a
+/// priority message overtakes normal messages that were enqueued before it.
+///
+/// ### Acknowledged reads:
+/// A claimed slot that was never released stays pinned: producers cannot reuse
+/// it. Claims taken with a lease record a deadline in the slot's `lease`; once it
+/// passes, another consumer may take over the claim and deliver the message again.
+///
+/// ### Broadcast channels:
+/// Each subscriber owns a read cursor and copies a message out before advancing
+/// it, without touching the slot's `sequence`. A producer may reuse a slot only
+/// once every registered cursor is past it, i.e. `tail < min(cursors) + capacity`.
 pub struct RingBuffer {
     /// Pointer to the channel metadata in the control area.
     pub(crate) metadata: *const ChannelEntry,
@@ -52,8 +219,49 @@ pub struct RingBuffer {
     pub(crate) capacity: usize,
 
     /// A bitmask used to wrap sequence numbers around the buffer.
-    /// Calculated as `capacity - 1`.
+    /// Calculated as `capacity - 1`; only meaningful for power-of-two capacities.
     pub(crate) mask: usize,
+
+    /// Whether slots are indexed with `position % capacity` instead of the mask,
+    /// for channels created with an exact (non-power-of-two) capacity.
+    pub(crate) modulo: bool,
+
+    /// Inline payload bytes per slot (`ChannelEntry::inline_size`).
+    pub(crate) inline_size: usize,
+
+    /// Bytes from one slot to the next, `Slot::stride(inline_size)`.
+    pub(crate) stride: usize,
+
+    /// Spill area for payloads larger than the inline size, if the channel has one.
+    pub(crate) overflow: Option<OverflowBand>,
+
+    /// Producer cursor of this ring (`tail` or `priority_tail` in the channel entry).
+    pub(crate) tail: *const AtomicU64,
+
+    /// Consumer cursor of this ring (`head` or `priority_head` in the channel entry).
+    pub(crate) head: *const AtomicU64,
+
+    /// The high-priority ring sharing this channel's entry, if configured.
+    pub(crate) priority: Option<Box<RingBuffer>>,
+
+    /// How enqueue/dequeue wait between retries on a contended slot.
+    pub(crate) backoff: Backoff,
+
+    /// Retries a dequeue spends on a slot whose producer claimed it but has not
+    /// published it yet, before reporting the ring empty for now.
+    pub(crate) stall_spins: u32,
+
+    /// Index of the broadcast subscriber cursor this view reads with, if subscribed.
+    /// `head` then points at that cursor instead of the shared one.
+    pub(crate) subscriber: Option<usize>,
+
+    /// The segment's event log, where poisoning the channel is recorded; null
+    /// for views not built by the allocator.
+    pub(crate) events: *const EventLog,
+
+    /// `ChannelEntry::layout_generation` when this view was made; the view is
+    /// stale once the entry's value moves on.
+    pub(crate) generation: u64,
 }
 
 unsafe impl Send for RingBuffer {}
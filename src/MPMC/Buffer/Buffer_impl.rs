@@ -1,83 +1,794 @@
-use std::mem::size_of;
+use std::io;
 use std::ptr;
-use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+use std::sync::atomic::{fence, AtomicU32, AtomicU64};
 
-use super::layout::ChannelEntry;
-use super::Buffer::{RingBuffer, Slot, MSG_INLINE};
-use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+use super::layout::{
+    ChannelEntry, EventLog, ATTACHED_CONSUMER, CHANNEL_FLAG_BROADCAST, CHANNEL_FLAG_CHECKSUM,
+    CHANNEL_FLAG_CONTROL, CHANNEL_FLAG_OVERWRITE, CHANNEL_FLAG_POISONED, CHANNEL_FLAG_SEALED,
+    CHANNEL_FLAG_SINGLE_CONSUMER, CHANNEL_FLAG_SINGLE_PRODUCER, MAX_SUBSCRIBERS,
+};
+use super::message_ref::MessageRef;
+use super::overflow::OverflowBand;
+use super::Buffer::{DequeueError, EnqueueError, RingBuffer, Slot, SlotLayout, MSG_INLINE};
+use crate::Core::alloc::AllocatorEventKind;
+use crate::MPMC::backoff::{Backoff, Snooze, DEFAULT_STALL_SPINS};
+use crate::MPMC::clock::ClockSource;
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_DISCARDED, FLAG_SPILLED};
+
+/// Why a producer could not claim the slot at the tail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stall {
+    /// Another producer claimed it or is still writing it.
+    Busy,
+    /// It holds a message from the previous lap that has not been released.
+    Occupied,
+    /// The channel is poisoned, or this view is stale.
+    Poisoned,
+}
 
 impl RingBuffer {
     /// Create a ring buffer view over an existing memory region.
     ///
     /// # Safety
     /// Caller must ensure `metadata` and `buffer_base` are valid pointers to shared memory.
+    ///
+    /// # Panics
+    /// If `buffer_base` (or the priority band within it) is not aligned for `Slot`.
     pub unsafe fn new(metadata: *const ChannelEntry, buffer_base: *mut u8) -> Self {
-        let capacity = (*metadata).capacity as usize;
-        Self {
+        let entry = &*metadata;
+        let mut ring = Self::lane(
             metadata,
             buffer_base,
+            0,
+            entry.capacity as usize,
+            &*entry.tail,
+            &*entry.head,
+        );
+
+        let priority_capacity = entry.priority_capacity as usize;
+        if priority_capacity != 0 {
+            ring.priority = Some(Box::new(Self::lane(
+                metadata,
+                buffer_base,
+                entry.priority_offset as usize,
+                priority_capacity,
+                &*entry.priority_tail,
+                &*entry.priority_head,
+            )));
+        }
+        ring
+    }
+
+    /// View of one band of a channel: its slots (at `slots_offset` into the data
+    /// band) and the cursor pair driving them. Both bands share the channel's
+    /// flags, signal word and overflow band.
+    unsafe fn lane(
+        metadata: *const ChannelEntry,
+        buffer_base: *mut u8,
+        slots_offset: usize,
+        capacity: usize,
+        tail: *const AtomicU64,
+        head: *const AtomicU64,
+    ) -> Self {
+        let slots = buffer_base.add(slots_offset);
+        assert!(
+            slots.cast::<Slot>().is_aligned(),
+            "slot band at {slots:p} is not aligned to {} bytes",
+            std::mem::align_of::<Slot>()
+        );
+        let inline_size = match (*metadata).inline_size {
+            0 => MSG_INLINE,
+            size => size as usize,
+        };
+        Self {
+            metadata,
+            buffer_base: slots,
             capacity,
             mask: capacity - 1,
+            modulo: !capacity.is_power_of_two(),
+            inline_size,
+            stride: Slot::stride(inline_size),
+            overflow: OverflowBand::from_entry(metadata, buffer_base),
+            tail,
+            head,
+            priority: None,
+            backoff: Backoff::Spin,
+            stall_spins: DEFAULT_STALL_SPINS,
+            subscriber: None,
+            events: std::ptr::null(),
+            generation: (*metadata).layout_generation.load(Acquire),
+        }
+    }
+
+    /// Record poisoning of this channel in the segment's event log.
+    pub(crate) fn with_event_log(mut self, events: *const EventLog) -> Self {
+        self.events = events;
+        if let Some(priority) = &mut self.priority {
+            priority.events = events;
+        }
+        self
+    }
+
+    /// Choose how this view waits between retries when slots are contended.
+    /// Applies to the priority band as well.
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+        if let Some(priority) = &mut self.priority {
+            priority.set_backoff(backoff);
+        }
+    }
+
+    /// The backoff policy used by this view.
+    #[inline]
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Bound how long a dequeue waits for a producer that claimed the next slot
+    /// but has not published it (preempted, or killed mid-write): after `spins`
+    /// retries, paced by the backoff policy, it returns None as if the ring were
+    /// empty, and a later dequeue tries again. Applies to the priority band as
+    /// well. Defaults to `DEFAULT_STALL_SPINS`.
+    pub fn set_stall_spins(&mut self, spins: u32) {
+        self.stall_spins = spins;
+        if let Some(priority) = &mut self.priority {
+            priority.set_stall_spins(spins);
         }
     }
 
-    /// Size in bytes of one slot stride in memory.
+    /// The stall budget used by this view.
+    #[inline]
+    pub fn stall_spins(&self) -> u32 {
+        self.stall_spins
+    }
+
+    /// Size in bytes of one slot stride in memory for slots holding
+    /// `inline_size` payload bytes (`MSG_INLINE` by default).
+    #[inline]
+    pub fn slot_stride(inline_size: usize) -> usize {
+        Slot::stride(inline_size)
+    }
+
+    /// Inline payload bytes per slot on this channel.
+    #[inline]
+    pub fn inline_size(&self) -> usize {
+        self.inline_size
+    }
+
+    /// Bytes each slot of this band occupies in shared memory.
+    #[inline]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// First slot of this band in this process's mapping.
     #[inline]
-    pub fn slot_stride() -> usize {
-        size_of::<Slot>()
+    pub(crate) fn slots_ptr(&self) -> *mut u8 {
+        self.buffer_base
+    }
+
+    /// Where the sequence word, metadata, payload and lease sit in this
+    /// channel's slots, for code that reads or writes them directly.
+    pub fn slot_layout(&self) -> SlotLayout {
+        SlotLayout::new(self.inline_size)
+    }
+
+    /// Largest payload `enqueue` accepts: the inline size, or the overflow
+    /// block size if the channel can spill larger payloads.
+    #[inline]
+    pub fn max_payload(&self) -> usize {
+        self.overflow.as_ref().map_or(self.inline_size, |overflow| {
+            overflow.block_size().max(self.inline_size)
+        })
     }
 
     /// Initialize per-slot sequence numbers to k for k in 0..capacity.
     /// This should ONLY be called by the creator process.
     ///
+    /// Running it again is harmless while nothing has been sent, but once a
+    /// cursor of either band has moved the slots hold messages that a reset
+    /// would corrupt, so it fails with `AlreadyExists` and leaves them alone;
+    /// see [`reinit_slots`](Self::reinit_slots) to empty the channel on purpose.
+    ///
     /// # Safety
     /// Caller guarantees the underlying memory is allocated and writable.
-    pub unsafe fn init_slots(&self) {
+    pub unsafe fn init_slots(&self) -> io::Result<()> {
+        if self.cursors_moved() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "Channel {} already carries messages (head {}, tail {}); use reinit_slots to reset it",
+                    (*self.metadata).channel_id,
+                    self.head_position(),
+                    self.tail_position()
+                ),
+            ));
+        }
+        self.write_initial_slots();
+        Ok(())
+    }
+
+    /// Empty the channel: move every cursor (both bands' heads and tails and
+    /// the broadcast subscribers') back to 0 and initialize the slots as for a
+    /// new channel. Unread messages are lost and overflow blocks freed.
+    ///
+    /// # Safety
+    /// Like [`init_slots`](Self::init_slots), and no producer or consumer in
+    /// any process may be using the channel meanwhile.
+    pub unsafe fn reinit_slots(&self) {
+        let entry = &*self.metadata;
+        for cursor in [
+            &entry.head,
+            &entry.tail,
+            &entry.priority_head,
+            &entry.priority_tail,
+        ] {
+            cursor.store(0, Release);
+        }
+        for cursor in &entry.subscribers {
+            cursor.store(0, Release);
+        }
+        self.write_initial_slots();
+    }
+
+    /// Whether anything was ever claimed or read in this band or the priority band.
+    fn cursors_moved(&self) -> bool {
+        self.head_position() != 0
+            || self.tail_position() != 0
+            || self.priority.as_ref().is_some_and(|p| p.cursors_moved())
+    }
+
+    /// The slot contents of a new channel, in this band and the priority band.
+    unsafe fn write_initial_slots(&self) {
         for k in 0..self.capacity {
             let slot = self.slot_mut(k);
             (*slot).sequence.store(k as u64, Relaxed);
+            self.lease(slot).store(0, Relaxed);
+        }
+        if let Some(overflow) = &self.overflow {
+            overflow.reset();
+        }
+        if let Some(priority) = &self.priority {
+            priority.write_initial_slots();
+        }
+    }
+
+    /// Index of the slot that sequence position `position` maps to.
+    #[inline]
+    fn slot_index(&self, position: u64) -> usize {
+        if self.modulo {
+            (position % self.capacity as u64) as usize
+        } else {
+            position as usize & self.mask
         }
     }
 
     #[inline]
     unsafe fn slot_mut(&self, index: usize) -> *mut Slot {
-        let base = self.buffer_base.add(index * Self::slot_stride());
+        let base = self.buffer_base.add(index * self.stride);
         base as *mut Slot
     }
 
+    /// The inline payload area of `slot`, `inline_size` bytes long.
+    #[inline]
+    pub(crate) unsafe fn payload_ptr(slot: *mut Slot) -> *mut u8 {
+        slot.cast::<u8>().add(Slot::PAYLOAD_OFFSET)
+    }
+
+    /// The lease word of `slot`, right after its inline payload.
+    #[inline]
+    unsafe fn lease(&self, slot: *mut Slot) -> &AtomicU64 {
+        &*slot
+            .cast::<u8>()
+            .add(Slot::lease_offset(self.inline_size))
+            .cast::<AtomicU64>()
+    }
+
+    /// Whether producers on this channel stamp a CRC32C of each payload.
+    #[inline]
+    pub fn checksums_enabled(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_CHECKSUM != 0
+    }
+
+    /// Check a received payload against the checksum recorded by its producer.
+    /// Always succeeds on channels without checksums.
+    pub fn verify_checksum(&self, meta: &MessageMeta, payload: &[u8]) -> bool {
+        !self.checksums_enabled() || crc32c::crc32c(payload) == meta.checksum
+    }
+
+    /// Whether every subscriber of this channel receives every message.
+    #[inline]
+    pub fn is_broadcast(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_BROADCAST != 0
+    }
+
+    /// Whether a full enqueue drops the oldest unread message instead of failing.
+    #[inline]
+    pub fn is_overwrite(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_OVERWRITE != 0
+    }
+
+    /// Whether the channel was created for a single producer, which claims
+    /// slots without a CAS.
+    #[inline]
+    pub fn is_single_producer(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_SINGLE_PRODUCER != 0
+    }
+
+    /// Whether the channel was created to allow only one consumer at a time.
+    #[inline]
+    pub fn is_single_consumer(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_SINGLE_CONSUMER != 0
+    }
+
+    /// Whether this is the segment's control channel.
+    #[inline]
+    pub fn is_control(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Relaxed) & CHANNEL_FLAG_CONTROL != 0
+    }
+
+    /// Whether the channel was sealed against further sends.
+    #[inline]
+    pub fn is_sealed(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Acquire) & CHANNEL_FLAG_SEALED != 0
+    }
+
+    /// Whether a corrupted slot was detected in the channel. A poisoned
+    /// channel fails every enqueue with [`EnqueueError::Poisoned`] and reads
+    /// as empty; `Producer` and `Consumer` report it as `InvalidData`.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        unsafe { &(*self.metadata).flags }.load(Acquire) & CHANNEL_FLAG_POISONED != 0
+    }
+
+    /// Whether the channel was resized, or its band moved by `compact`, since
+    /// this view was made. A stale view fails every enqueue with
+    /// [`EnqueueError::Stale`] and every [`try_dequeue`](Self::try_dequeue)
+    /// with [`DequeueError::Stale`], since its capacity and slot addresses no
+    /// longer match the ring; `dequeue` and `peek` just return None. Fetch the
+    /// channel again instead.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        unsafe { &(*self.metadata).layout_generation }.load(Acquire) != self.generation
+    }
+
+    /// Whether slots must be left alone: the channel is poisoned or this view
+    /// is stale.
+    #[inline]
+    fn is_unusable(&self) -> bool {
+        self.is_poisoned() || self.is_stale()
+    }
+
+    /// Mark the channel poisoned and wake everyone blocked on it, so they see
+    /// the flag instead of waiting for a slot that will never change.
+    fn poison(&self) {
+        let flags = unsafe { &(*self.metadata).flags }.fetch_or(CHANNEL_FLAG_POISONED, Release);
+        if flags & CHANNEL_FLAG_POISONED == 0 {
+            if let Some(events) = unsafe { self.events.as_ref() } {
+                events.append(AllocatorEventKind::Poisoned, unsafe {
+                    (*self.metadata).channel_id
+                });
+            }
+        }
+        self.signal_consumer();
+        self.signal_producer();
+    }
+
+    /// Whether the slot for `position` may legitimately hold `seq`. Slots only
+    /// ever hold a position (free, in some lap) or a position + 1 (published),
+    /// and never run more than a lap ahead of the tail; anything else means the
+    /// ring was corrupted, and the channel is poisoned.
+    fn check_sequence(&self, seq: u64, position: u64) -> bool {
+        let capacity = self.capacity as u64;
+        let lap_offset = (seq % capacity + capacity - position % capacity) % capacity;
+        let tail = unsafe { &*self.tail }.load(Acquire);
+        let possible =
+            (lap_offset == 0 || lap_offset == 1 % capacity) && seq <= tail.saturating_add(capacity);
+        if !possible {
+            self.poison();
+        }
+        possible
+    }
+
+    /// Seal the channel: producers checking [`is_sealed`](Self::is_sealed)
+    /// refuse to send from now on. Cannot be undone.
+    pub fn seal(&self) {
+        unsafe { &(*self.metadata).flags }.fetch_or(CHANNEL_FLAG_SEALED, Release);
+    }
+
+    /// First sequence a broadcast producer may not claim yet: one lap past the
+    /// slowest registered subscriber, or unbounded when nobody is subscribed.
+    fn broadcast_limit(&self) -> u64 {
+        let entry = unsafe { &*self.metadata };
+        let mut mask = entry.subscriber_mask.load(Acquire);
+        let mut limit = u64::MAX;
+        while mask != 0 {
+            let index = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            let cursor = entry.subscribers[index].load(Acquire);
+            limit = limit.min(cursor + self.capacity as u64);
+        }
+        limit
+    }
+
+    /// Number of messages in this band that have not been dequeued yet,
+    /// counting ones a producer is still writing. On a broadcast channel it is
+    /// the backlog of the slowest subscriber, and 0 without subscribers.
+    ///
+    /// The value is a snapshot and may be stale as soon as it is returned.
+    pub fn len(&self) -> usize {
+        let capacity = self.capacity as u64;
+        // Head first, so a racing consumer can only make the result too large
+        let head = if self.is_broadcast() {
+            match self.broadcast_limit() {
+                u64::MAX => u64::MAX,
+                limit => limit - capacity,
+            }
+        } else {
+            unsafe { &*self.head }.load(Acquire)
+        };
+        let tail = unsafe { &*self.tail }.load(Acquire);
+        tail.saturating_sub(head).min(capacity) as usize
+    }
+
+    /// Whether [`len`](Self::len) is 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Classify the slot at `tail` (holding `seq`) for a producer:
+    /// 0 = free to claim, negative = ring full, positive = retry (stale `tail`,
+    /// or the previous writer of this slot has not published yet).
+    #[inline]
+    fn producer_dif(&self, seq: u64, tail: u64) -> i64 {
+        if !self.is_broadcast() {
+            return seq as i64 - tail as i64;
+        }
+        if tail >= self.broadcast_limit() {
+            return -1;
+        }
+        // Subscribers leave `sequence` alone, so the slot is free once the write
+        // from the previous lap has been published (or on the first lap)
+        let capacity = self.capacity as u64;
+        if (tail < capacity && seq == tail) || seq + capacity == tail + 1 {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Register this view as a subscriber of its broadcast channel. It receives
+    /// messages enqueued from now on.
+    pub(crate) fn subscribe(&mut self) -> io::Result<()> {
+        if !self.is_broadcast() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Channel is not a broadcast channel",
+            ));
+        }
+        let entry = unsafe { &*self.metadata };
+
+        let index = loop {
+            let mask = entry.subscriber_mask.load(Acquire);
+            let index = (!mask).trailing_zeros() as usize;
+            if index >= MAX_SUBSCRIBERS {
+                return Err(io::Error::new(
+                    io::ErrorKind::OutOfMemory,
+                    format!(
+                        "All {} broadcast subscriber slots are taken",
+                        MAX_SUBSCRIBERS
+                    ),
+                ));
+            }
+            // Position the cursor before publishing it to producers
+            entry.subscribers[index].store(entry.tail.load(Acquire), Release);
+            if entry
+                .subscriber_mask
+                .compare_exchange(mask, mask | (1 << index), AcqRel, Acquire)
+                .is_ok()
+            {
+                break index;
+            }
+        };
+
+        // Producers that did not see the new bit may have lapped the cursor;
+        // start from the current tail instead of reading overwritten slots
+        let cursor = &entry.subscribers[index];
+        let tail = entry.tail.load(Acquire);
+        if tail > cursor.load(Acquire) + self.capacity as u64 {
+            cursor.store(tail, Release);
+        }
+
+        self.head = &*entry.subscribers[index];
+        self.subscriber = Some(index);
+        Ok(())
+    }
+
+    /// Release this view's subscriber slot so producers stop waiting for it.
+    pub(crate) fn unsubscribe(&mut self) {
+        if let Some(index) = self.subscriber.take() {
+            let entry = unsafe { &*self.metadata };
+            entry.subscriber_mask.fetch_and(!(1u32 << index), AcqRel);
+            self.head = &*entry.head;
+            self.signal_producer();
+        }
+    }
+
+    /// Sequence of the next message this broadcast subscriber will read, or
+    /// `None` if this view is not a subscriber.
+    pub fn subscriber_position(&self) -> Option<u64> {
+        self.subscriber?;
+        Some(unsafe { &*self.head }.load(Acquire))
+    }
+
+    /// Move this broadcast subscriber's cursor to `position`, so the next
+    /// read returns the message with that sequence. Returns the position
+    /// actually taken: `position` itself, or the oldest message still in the
+    /// ring if producers have already reused the slot of `position`.
+    ///
+    /// Fails with `InvalidInput` if this view is not a subscriber or
+    /// `position` is past the last claimed sequence.
+    pub fn seek_subscriber(&self, position: u64) -> io::Result<u64> {
+        if self.subscriber.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Only broadcast subscribers have a private cursor to seek",
+            ));
+        }
+        let tail = unsafe { &*self.tail };
+        let cursor = unsafe { &*self.head };
+        let mut target = position;
+        loop {
+            let claimed = tail.load(Acquire);
+            if target > claimed {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Sequence {} has not been sent yet (next is {})",
+                        target, claimed
+                    ),
+                ));
+            }
+            // The slot of `claimed - capacity` may be taken by a producer that
+            // checked the old cursor, so only the newer `capacity - 1` are safe
+            let oldest = claimed.saturating_sub(self.capacity as u64 - 1);
+            target = target.max(oldest);
+            cursor.store(target, Release);
+            // Producers that did not see the new cursor may have lapped it
+            if tail.load(Acquire) < target + self.capacity as u64 {
+                self.signal_producer();
+                return Ok(target);
+            }
+        }
+    }
+
+    /// Take the next message for this broadcast subscriber, stopping once its
+    /// cursor reaches `limit`.
+    ///
+    /// The payload is copied before the cursor advances; producers cannot reuse
+    /// the slot until it does, so the copy never races a writer.
+    fn take_broadcast(&self, limit: u64) -> Option<(MessageMeta, Vec<u8>)> {
+        let cursor = unsafe { &*self.head };
+        let mut snooze = Snooze::new(self.backoff);
+
+        loop {
+            let position = cursor.load(Acquire);
+            if position >= limit || self.is_unusable() {
+                return None;
+            }
+            let slot_ptr = unsafe { self.slot_mut(self.slot_index(position)) };
+            let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
+            if seq != position + 1 && seq != position && !self.check_sequence(seq, position) {
+                return None;
+            }
+            if seq < position + 1 {
+                // empty
+                return None;
+            }
+            if seq == position + 1 {
+                let message = unsafe { ((*slot_ptr).meta, self.payload_of(slot_ptr).to_vec()) };
+                if cursor
+                    .compare_exchange(position, position + 1, AcqRel, Relaxed)
+                    .is_ok()
+                {
+                    self.signal_producer();
+                    if self.drop_unread(&message.0) {
+                        continue;
+                    }
+                    unsafe { &(*self.metadata).dequeue_count }.fetch_add(1, Relaxed);
+                    return Some(message);
+                }
+            }
+            // another reader of this subscription took it; retry
+            snooze.snooze(self);
+        }
+    }
+
+    /// Copy a payload, given as `fragments` totalling `len` bytes, that does not
+    /// fit inline into a freshly claimed overflow block.
+    ///
+    /// Returns `Ok(None)` when the payload fits inline (or the channel has no
+    /// overflow band), `Ok(Some(block))` when it was spilled, and `Err(())` when it
+    /// cannot be stored right now (too large for a block, or every block in use).
+    fn spill(&self, fragments: &[&[u8]], len: usize) -> Result<Option<u32>, ()> {
+        let overflow = match &self.overflow {
+            Some(overflow) if len > self.inline_size => overflow,
+            _ => return Ok(None),
+        };
+        if len > overflow.block_size() {
+            return Err(());
+        }
+        let block = overflow.claim().ok_or(())?;
+        unsafe {
+            Self::copy_fragments(overflow.block_ptr(block), fragments);
+        }
+        Ok(Some(block))
+    }
+
+    /// Copy `fragments` back to back starting at `dst`.
+    ///
+    /// # Safety
+    /// `dst` must be valid for writes of the fragments' combined length.
+    #[inline]
+    unsafe fn copy_fragments(mut dst: *mut u8, fragments: &[&[u8]]) {
+        for fragment in fragments {
+            ptr::copy_nonoverlapping(fragment.as_ptr(), dst, fragment.len());
+            dst = dst.add(fragment.len());
+        }
+    }
+
+    /// Give back an overflow block claimed by `spill` for a message that was never published.
+    fn unspill(&self, block: Option<u32>) {
+        if let (Some(overflow), Some(block)) = (&self.overflow, block) {
+            overflow.release(block);
+        }
+    }
+
+    /// Write metadata and a payload made of `fragments` totalling `len` bytes
+    /// into an owned slot. Does not publish it.
+    ///
+    /// # Safety
+    /// The caller must own `slot_ptr` (its sequence claimed but not yet published),
+    /// and `len` must not exceed the inline size unless the payload was spilled.
+    unsafe fn write_slot(
+        &self,
+        slot_ptr: *mut Slot,
+        meta: &MessageMeta,
+        fragments: &[&[u8]],
+        len: usize,
+        spilled: Option<u32>,
+    ) {
+        // Write metadata
+        (*slot_ptr).meta = *meta;
+        (*slot_ptr).meta.payload_len = len as u32;
+        if self.checksums_enabled() {
+            (*slot_ptr).meta.checksum = fragments
+                .iter()
+                .fold(0, |crc, fragment| crc32c::crc32c_append(crc, fragment));
+        }
+
+        match spilled {
+            Some(block) => {
+                // The payload lives in the overflow band; the slot only records where
+                (*slot_ptr).meta.flags |= FLAG_SPILLED;
+                ptr::copy_nonoverlapping(
+                    block.to_le_bytes().as_ptr(),
+                    Self::payload_ptr(slot_ptr),
+                    4,
+                );
+            }
+            None => {
+                (*slot_ptr).meta.flags &= !FLAG_SPILLED;
+                // Write payload
+                Self::copy_fragments(Self::payload_ptr(slot_ptr), fragments);
+            }
+        }
+    }
+
+    /// Overflow block index recorded in a spilled slot.
+    #[inline]
+    unsafe fn spilled_block(slot_ptr: *const Slot) -> u32 {
+        let mut bytes = [0u8; 4];
+        ptr::copy_nonoverlapping(
+            Self::payload_ptr(slot_ptr.cast_mut()),
+            bytes.as_mut_ptr(),
+            4,
+        );
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Borrow the payload of a claimed slot, following the overflow reference if spilled.
+    ///
+    /// # Safety
+    /// The slot must be claimed for reading and not yet released.
+    pub(crate) unsafe fn payload_of(&self, slot_ptr: *const Slot) -> &[u8] {
+        self.payload_with(&(*slot_ptr).meta, Self::spilled_block(slot_ptr), slot_ptr)
+    }
+
+    /// The payload described by `meta` in `slot_ptr`, with `block` as its overflow
+    /// block if spilled. Lets a reader that copied the metadata use its copy.
+    ///
+    /// # Safety
+    /// `meta` and `block` must be a consistent snapshot of the slot's contents.
+    unsafe fn payload_with(&self, meta: &MessageMeta, block: u32, slot_ptr: *const Slot) -> &[u8] {
+        let len = meta.payload_len as usize;
+        match &self.overflow {
+            Some(overflow) if meta.flags & FLAG_SPILLED != 0 => std::slice::from_raw_parts(
+                overflow.block_ptr(block),
+                len.min(overflow.block_size()),
+            ),
+            _ => std::slice::from_raw_parts(
+                Self::payload_ptr(slot_ptr.cast_mut()),
+                len.min(self.inline_size),
+            ),
+        }
+    }
+
     /// Enqueue a batch of messages.
-    /// Returns the starting index on success, or None if the ring DOES NOT have enough contiguous space.
+    /// Returns the starting index on success, or None if the ring DOES NOT have enough contiguous space
+    /// or any payload is larger than `max_payload`.
     /// Note: This implementation is "all or nothing" for the batch.
     pub fn enqueue_batch(&self, messages: &[(&MessageMeta, &[u8])]) -> Option<usize> {
         let batch_size = messages.len();
         if batch_size == 0 {
             return Some(0); // Nothing to do
         }
+        if messages.iter().any(|(_, p)| p.len() > self.max_payload()) {
+            return None; // Would be truncated
+        }
+        if self.is_unusable() {
+            return None;
+        }
         if batch_size > self.capacity {
+            self.count_full();
             return None; // Impossible to fit
         }
 
-        let meta_ptr = self.metadata;
-        let tail_atomic = unsafe { &(*meta_ptr).tail };
+        // Spill oversized payloads up front so a failed spill never leaves a claimed slot
+        // unpublished. The Vec is only allocated when something actually spills.
+        let mut spills: Vec<Option<u32>> = Vec::new();
+        if self.overflow.is_some() && messages.iter().any(|(_, p)| p.len() > self.inline_size) {
+            spills.reserve_exact(batch_size);
+            for (_, payload) in messages {
+                match self.spill(&[payload], payload.len()) {
+                    Ok(block) => spills.push(block),
+                    Err(()) => {
+                        spills.into_iter().for_each(|b| self.unspill(b));
+                        self.count_full();
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let tail_atomic = unsafe { &*self.tail };
+        let mut snooze = Snooze::new(self.backoff);
 
         loop {
             let tail = tail_atomic.load(Relaxed);
 
             // 1. Check if all slots in the batch range are available
             let mut all_available = true;
+            let mut evicted = false;
             for i in 0..batch_size {
                 let target_seq = tail + i as u64;
-                let idx = (target_seq as usize) & self.mask;
+                let idx = self.slot_index(target_seq);
                 let slot_ptr = unsafe { self.slot_mut(idx) };
                 let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
 
-                let dif = seq as i64 - target_seq as i64;
+                let dif = self.producer_dif(seq, target_seq);
                 if dif < 0 {
                     // Start of batch is full? or middle is full?
                     // If any slot is full, we can't proceed with this batch at this tail position.
+                    evicted = self.evict(target_seq);
                     all_available = false;
                     break;
                 } else if dif > 0 {
+                    if !self.check_sequence(seq, target_seq) {
+                        spills.into_iter().for_each(|b| self.unspill(b));
+                        return None;
+                    }
                     // Should theoretically not happen if we read tail correctly,
                     // unless tail moved massively forward and we read an old tail?
                     // Or someone else claimed it.
@@ -86,6 +797,10 @@ impl RingBuffer {
                 }
             }
 
+            if evicted {
+                // made room on an overwrite channel; look at the range again
+                continue;
+            }
             if !all_available {
                 // Check if it's full. If head is far behind, return None.
                 // Actually standard enqueue returns None if diff < 0.
@@ -94,6 +809,8 @@ impl RingBuffer {
                 // To differentiate, we'd check head. But for simplicity, let's just return None and let caller decide (spin or drop).
                 // Actually, standard enqueue spins on "producer not finished" (dif > 0) but returns None on "full" (dif < 0).
                 // We will return None if any slot is not ready. Caller (Producer) usually retries.
+                spills.into_iter().for_each(|b| self.unspill(b));
+                self.count_full();
                 return None;
             }
 
@@ -105,145 +822,970 @@ impl RingBuffer {
                 // We own the range [tail, tail + batch_size)
                 for (i, (meta, payload)) in messages.iter().enumerate() {
                     let target_seq = tail + i as u64;
-                    let idx = (target_seq as usize) & self.mask;
+                    let idx = self.slot_index(target_seq);
                     let slot_ptr = unsafe { self.slot_mut(idx) };
 
+                    let spilled = spills.get(i).copied().flatten();
+
                     unsafe {
-                        // Write metadata
-                        (*slot_ptr).meta = **meta; // Clone meta
-                        (*slot_ptr).meta.payload_len = payload.len() as u32;
-
-                        // Write payload
-                        let len = payload.len().min(MSG_INLINE);
-                        ptr::copy_nonoverlapping(
-                            payload.as_ptr(),
-                            (*slot_ptr).payload.as_mut_ptr(),
-                            len,
-                        );
+                        self.write_slot(slot_ptr, meta, &[payload], payload.len(), spilled);
 
                         // Publish
                         (&(*slot_ptr).sequence).store(target_seq + 1, Release);
                     }
                 }
-                return Some((tail as usize) & self.mask);
+                self.count_enqueued(batch_size as u64);
+                return Some(self.slot_index(tail));
             }
             // logical contention, retry loop
-            std::hint::spin_loop();
+            snooze.snooze(self);
         }
     }
 
     /// Enqueue reserves a slot and publishes the message.
-    /// Returns the index on success, or None if the ring appears full.
+    /// Returns the index on success, or None if the ring appears full or the
+    /// payload is larger than `max_payload`.
     pub fn enqueue(&self, meta: MessageMeta, payload: &[u8]) -> Option<usize> {
-        let meta_ptr = self.metadata;
-        // Safety: We assume metadata pointer is valid for the lifetime of the RingBuffer view
-        let tail_atomic = unsafe { &(*meta_ptr).tail };
+        self.enqueue_vectored(meta, &[payload])
+    }
+
+    /// Like [`enqueue`](Self::enqueue), for a payload made of `fragments` that
+    /// are written back to back into the slot in one pass, without first
+    /// concatenating them. `payload_len` becomes their combined length.
+    pub fn enqueue_vectored(&self, meta: MessageMeta, fragments: &[&[u8]]) -> Option<usize> {
+        let len = fragments.iter().map(|fragment| fragment.len()).sum();
+        if len > self.max_payload() {
+            return None; // Would be truncated
+        }
+        if self.is_stale() {
+            return None; // Its overflow band may be gone too
+        }
+        let Ok(spilled) = self.spill(fragments, len) else {
+            self.count_full();
+            return None;
+        };
 
+        let mut snooze = Snooze::new(self.backoff);
         loop {
-            let tail = tail_atomic.load(Relaxed);
-            let idx = (tail as usize) & self.mask;
-            let slot_ptr = unsafe { self.slot_mut(idx) };
-            let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
-            let dif = seq as i64 - tail as i64;
+            match self.claim_write(&meta, fragments, len, spilled) {
+                Ok(position) => return Some(self.slot_index(position)),
+                Err(Stall::Busy) => snooze.snooze(self),
+                Err(Stall::Occupied) => {
+                    self.unspill(spilled);
+                    self.count_full();
+                    return None;
+                }
+                Err(Stall::Poisoned) => {
+                    self.unspill(spilled);
+                    return None;
+                }
+            }
+        }
+    }
 
-            if dif == 0 {
-                if tail_atomic
-                    .compare_exchange_weak(tail, tail + 1, AcqRel, Relaxed)
-                    .is_ok()
-                {
-                    // We own this slot now
-                    unsafe {
-                        // Write metadata
-                        (*slot_ptr).meta = meta;
-                        (*slot_ptr).meta.payload_len = payload.len() as u32;
-
-                        // Write payload
-                        let len = payload.len().min(MSG_INLINE);
-                        ptr::copy_nonoverlapping(
-                            payload.as_ptr(),
-                            (*slot_ptr).payload.as_mut_ptr(),
-                            len,
-                        );
+    /// A single attempt at [`enqueue`](Self::enqueue) that says why it failed:
+    /// [`EnqueueError::Full`] when the ring has no room, so the caller should
+    /// back off or block, and [`EnqueueError::Contended`] when it lost a race
+    /// for the slot and may retry immediately.
+    pub fn try_enqueue(&self, meta: MessageMeta, payload: &[u8]) -> Result<usize, EnqueueError> {
+        self.try_enqueue_vectored(meta, &[payload])
+    }
 
-                        // Publish
-                        (&(*slot_ptr).sequence).store(tail + 1, Release);
-                    }
-                    return Some(idx);
+    /// [`try_enqueue`](Self::try_enqueue) for a payload made of `fragments`,
+    /// as in [`enqueue_vectored`](Self::enqueue_vectored).
+    pub fn try_enqueue_vectored(
+        &self,
+        meta: MessageMeta,
+        fragments: &[&[u8]],
+    ) -> Result<usize, EnqueueError> {
+        self.try_enqueue_seq(meta, fragments)
+            .map(|position| self.slot_index(position))
+    }
+
+    /// Like [`try_enqueue_vectored`](Self::try_enqueue_vectored), but returns
+    /// the message's sequence in this band: the `tail` value it was enqueued
+    /// at. Unlike the slot index it is never reused, so it identifies the
+    /// message for as long as the channel exists; every later message on the
+    /// band gets a larger one.
+    pub fn try_enqueue_seq(
+        &self,
+        meta: MessageMeta,
+        fragments: &[&[u8]],
+    ) -> Result<u64, EnqueueError> {
+        let len = fragments.iter().map(|fragment| fragment.len()).sum();
+        if len > self.max_payload() {
+            return Err(EnqueueError::TooLarge);
+        }
+        if self.is_stale() {
+            return Err(EnqueueError::Stale);
+        }
+        let Ok(spilled) = self.spill(fragments, len) else {
+            self.count_full();
+            return Err(EnqueueError::Full);
+        };
+
+        let stall = match self.claim_write(&meta, fragments, len, spilled) {
+            Ok(position) => return Ok(position),
+            Err(stall) => stall,
+        };
+        self.unspill(spilled);
+        Err(self.enqueue_error(stall))
+    }
+
+    /// Claim the slot at the tail for a message written in place, returning
+    /// its position and the slot. The errors are those of
+    /// [`try_enqueue_seq`](Self::try_enqueue_seq), except `TooLarge`.
+    ///
+    /// Consumers stop at the slot until [`publish_in_place`](Self::publish_in_place)
+    /// is called with what this returned, which must happen exactly once.
+    pub(crate) fn try_claim(&self) -> Result<(u64, *mut Slot), EnqueueError> {
+        self.claim_slot().map_err(|stall| self.enqueue_error(stall))
+    }
+
+    /// Publish a slot taken by [`try_claim`](Self::try_claim) whose first `len`
+    /// payload bytes were written in place, with `meta` as its metadata
+    /// (`payload_len` and, on checksummed channels, `checksum` are filled in).
+    /// With `discard` set the slot is published with `FLAG_DISCARDED` instead,
+    /// and consumers skip it.
+    ///
+    /// # Safety
+    /// `position` and `slot` must come from a single `try_claim` on this ring,
+    /// and `len` must not exceed the inline size.
+    pub(crate) unsafe fn publish_in_place(
+        &self,
+        position: u64,
+        slot: *mut Slot,
+        meta: &MessageMeta,
+        len: usize,
+        discard: bool,
+    ) {
+        let payload = std::slice::from_raw_parts(Self::payload_ptr(slot), len);
+        (*slot).meta = *meta;
+        (*slot).meta.payload_len = len as u32;
+        (*slot).meta.flags &= !(FLAG_SPILLED | FLAG_DISCARDED);
+        if discard {
+            (*slot).meta.flags |= FLAG_DISCARDED;
+        } else if self.checksums_enabled() {
+            (*slot).meta.checksum = crc32c::crc32c(payload);
+        }
+        (*slot).sequence.store(position + 1, Release);
+        if !discard {
+            self.count_enqueued(1);
+        }
+    }
+
+    /// What a failed claim means for the sender.
+    fn enqueue_error(&self, stall: Stall) -> EnqueueError {
+        if stall == Stall::Poisoned {
+            return if self.is_stale() {
+                EnqueueError::Stale
+            } else {
+                EnqueueError::Poisoned
+            };
+        }
+        // An occupied slot means a full ring only if nothing was dequeued from
+        // it; otherwise a consumer is still releasing (or holding a lease on) it
+        if stall == Stall::Occupied && self.len() >= self.capacity {
+            self.count_full();
+            return EnqueueError::Full;
+        }
+        EnqueueError::Contended
+    }
+
+    /// Claim the slot at the current tail and write the message into it,
+    /// returning the tail position it took.
+    fn claim_write(
+        &self,
+        meta: &MessageMeta,
+        fragments: &[&[u8]],
+        len: usize,
+        spilled: Option<u32>,
+    ) -> Result<u64, Stall> {
+        let (tail, slot_ptr) = self.claim_slot()?;
+
+        // We own this slot now
+        unsafe {
+            self.write_slot(slot_ptr, meta, fragments, len, spilled);
+
+            // Publish
+            (&(*slot_ptr).sequence).store(tail + 1, Release);
+        }
+        self.count_enqueued(1);
+        Ok(tail)
+    }
+
+    /// Claim the slot at the current tail, returning the tail position it took
+    /// and the slot, which the caller must then write and publish.
+    ///
+    /// `Busy` means another producer got there first; `Occupied` that the slot
+    /// still holds a message from the previous lap that could not be evicted;
+    /// `Poisoned` that the channel is, that this view is stale, or that the
+    /// slot turned out to be corrupted.
+    ///
+    /// On a single-producer channel nobody else moves `tail`, so the claim is a
+    /// plain store. Debug builds assert that no second producer is attached.
+    fn claim_slot(&self) -> Result<(u64, *mut Slot), Stall> {
+        if self.is_unusable() {
+            return Err(Stall::Poisoned);
+        }
+        // Safety: We assume the cursor pointers are valid for the lifetime of the RingBuffer view
+        let tail_atomic = unsafe { &*self.tail };
+        let tail = tail_atomic.load(Relaxed);
+        let idx = self.slot_index(tail);
+        let slot_ptr = unsafe { self.slot_mut(idx) };
+        let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
+        let dif = self.producer_dif(seq, tail);
+        if dif != 0 && !self.check_sequence(seq, tail) {
+            return Err(Stall::Poisoned);
+        }
+
+        if dif < 0 {
+            // the slot frees up once an evicted message is released
+            return if self.evict(tail) {
+                Err(Stall::Busy)
+            } else {
+                Err(Stall::Occupied)
+            };
+        }
+        if dif > 0 {
+            // someone else is producing
+            return Err(Stall::Busy);
+        }
+        if self.is_single_producer() {
+            debug_assert!(
+                self.registered_count(false) <= 1,
+                "second producer attached to single-producer channel {}",
+                unsafe { (*self.metadata).channel_id }
+            );
+            tail_atomic.store(tail + 1, Relaxed);
+        } else if tail_atomic
+            .compare_exchange_weak(tail, tail + 1, AcqRel, Relaxed)
+            .is_err()
+        {
+            // we lost the race for this slot
+            return Err(Stall::Busy);
+        }
+        Ok((tail, slot_ptr))
+    }
+
+    /// On an overwrite channel, drop the unread message a producer wants to
+    /// replace at `position` (the one a lap earlier) to free its slot.
+    ///
+    /// The message is claimed through `head` exactly like a dequeue, so a
+    /// consumer racing for it either wins and reads it whole or moves on.
+    /// Returns false, leaving the ring alone, when the channel does not
+    /// overwrite or that message is no longer the oldest unread one: it is
+    /// then held by a consumer and the ring is genuinely full.
+    fn evict(&self, position: u64) -> bool {
+        if !self.is_overwrite() {
+            return false;
+        }
+        let Some(oldest) = position.checked_sub(self.capacity as u64) else {
+            return false;
+        };
+        let slot_ptr = unsafe { self.slot_mut(self.slot_index(oldest)) };
+        if unsafe { &(*slot_ptr).sequence }.load(Acquire) != oldest + 1
+            || unsafe { &*self.head }
+                .compare_exchange(oldest, oldest + 1, AcqRel, Relaxed)
+                .is_err()
+        {
+            return false;
+        }
+        unsafe { self.release_read(oldest, slot_ptr) };
+        true
+    }
+
+    /// Dequeue acquires a ready slot and returns its content, taking from the
+    /// priority band first if the channel has one.
+    /// Returns None if the ring appears empty.
+    pub fn dequeue(&self) -> Option<(MessageMeta, Vec<u8>)> {
+        if self.subscriber.is_some() {
+            let message = self.take_broadcast(u64::MAX);
+            if message.is_none() {
+                self.count_empty();
+            }
+            return message;
+        }
+        let Some((ring, head, slot_ptr)) = self.claim_next() else {
+            self.count_empty();
+            return None;
+        };
+
+        let (meta, payload) = unsafe { ((*slot_ptr).meta, ring.payload_of(slot_ptr).to_vec()) };
+
+        // free slot for future producers
+        unsafe {
+            ring.release_read(head, slot_ptr);
+        }
+        Some((meta, payload))
+    }
+
+    /// Like [`dequeue`](Self::dequeue), but an empty answer is only `Ok(None)`
+    /// when the ring is empty: a poisoned channel or a stale view, which
+    /// `dequeue` also reports as None, fail with [`DequeueError`].
+    pub fn try_dequeue(&self) -> Result<Option<(MessageMeta, Vec<u8>)>, DequeueError> {
+        let message = self.dequeue();
+        if message.is_none() {
+            if self.is_stale() {
+                return Err(DequeueError::Stale);
+            }
+            if self.is_poisoned() {
+                return Err(DequeueError::Poisoned);
+            }
+        }
+        Ok(message)
+    }
+
+    /// Copy the next message without dequeuing it, looking at the priority
+    /// band first. Returns None if the ring appears empty.
+    ///
+    /// Nothing is reserved: another consumer may dequeue the message right
+    /// after, so a later `dequeue` can return a different one. An expired
+    /// message is still shown here; it is only dropped once dequeued.
+    /// Discarded slots are looked past, as `dequeue` would skip them.
+    pub fn peek(&self) -> Option<(MessageMeta, Vec<u8>)> {
+        if self.subscriber.is_none() {
+            if let Some(message) = self.priority.as_ref().and_then(|p| p.peek()) {
+                return Some(message);
+            }
+        }
+
+        let head_atomic = unsafe { &*self.head };
+        let mut snooze = Snooze::new(self.backoff);
+        loop {
+            if self.is_unusable() {
+                return None;
+            }
+            let head = head_atomic.load(Acquire);
+            // Slots past the head stay published until the head passes them
+            for position in head..head + self.capacity as u64 {
+                let slot_ptr = unsafe { self.slot_mut(self.slot_index(position)) };
+                let sequence = unsafe { &(*slot_ptr).sequence };
+                let seq = sequence.load(Acquire);
+                let dif = seq as i64 - (position as i64 + 1);
+                // -1 is the common empty slot, not worth checking on every poll
+                if dif != 0 && dif != -1 && !self.check_sequence(seq, position) {
+                    return None;
+                }
+                if dif < 0 {
+                    // empty
+                    return None;
+                }
+                if dif > 0 {
+                    break;
                 }
+                // A producer only rewrites the slot after a consumer released it,
+                // which moves the sequence on; if it did not move, the copy is intact.
+                // The metadata is checked first so a torn block index is never followed.
+                let (meta, block) = unsafe { ((*slot_ptr).meta, Self::spilled_block(slot_ptr)) };
+                fence(Acquire);
+                if sequence.load(Relaxed) != position + 1 {
+                    break;
+                }
+                if meta.flags & FLAG_DISCARDED != 0 {
+                    continue;
+                }
+                let payload = unsafe { self.payload_with(&meta, block, slot_ptr).to_vec() };
+                fence(Acquire);
+                if sequence.load(Relaxed) != position + 1 {
+                    break;
+                }
+                return Some((meta, payload));
+            }
+            // taken by another consumer meanwhile; look at the new head
+            snooze.snooze(self);
+        }
+    }
+
+    /// Copy the message at `position` without claiming it, for an observer
+    /// with a private cursor that must neither move `head` nor hold up
+    /// producers. A slot released by a consumer keeps its contents until a
+    /// producer claims it for the next lap, so taken messages can still be read.
+    ///
+    /// Returns `Ok(None)` if nothing was published at `position` yet, and
+    /// `Err(())` if the message is gone: a producer claimed its slot for a
+    /// later lap, or it was spilled and its overflow block was released.
+    pub(crate) fn observe(&self, position: u64) -> Result<Option<(MessageMeta, Vec<u8>)>, ()> {
+        if self.is_stale() {
+            return Ok(None);
+        }
+        let capacity = self.capacity as u64;
+        let tail = unsafe { &*self.tail };
+        let slot_ptr = unsafe { self.slot_mut(self.slot_index(position)) };
+        let sequence = unsafe { &(*slot_ptr).sequence };
+        let seq = sequence.load(Acquire);
+        if seq < position + 1 {
+            return Ok(None);
+        }
+        // Broadcast slots keep `position + 1` until rewritten; others move to
+        // `position + capacity` once a consumer releases them
+        let released = !self.is_broadcast() && seq == position + capacity;
+        if seq != position + 1 && !released {
+            return Err(());
+        }
+
+        // Like `peek`, but the copy is intact as long as no producer claimed
+        // the slot's next lap. The metadata is checked first so a torn block
+        // index is never followed, and a spilled payload also needs its block,
+        // which any producer may reuse once a consumer released the slot.
+        let (meta, block) = unsafe { ((*slot_ptr).meta, Self::spilled_block(slot_ptr)) };
+        let spilled = self.overflow.is_some() && meta.flags & FLAG_SPILLED != 0;
+        let intact = || {
+            fence(Acquire);
+            tail.load(Relaxed) <= position + capacity
+                && (!spilled || sequence.load(Relaxed) == position + 1)
+        };
+        if (spilled && released) || !intact() {
+            return Err(());
+        }
+        let payload = unsafe { self.payload_with(&meta, block, slot_ptr).to_vec() };
+        if !intact() {
+            return Err(());
+        }
+        Ok(Some((meta, payload)))
+    }
+
+    /// Current value of this band's consumer cursor.
+    #[inline]
+    pub(crate) fn head_position(&self) -> u64 {
+        unsafe { &*self.head }.load(Acquire)
+    }
+
+    /// Current value of this band's producer cursor: the next sequence a
+    /// producer will claim.
+    #[inline]
+    pub(crate) fn tail_position(&self) -> u64 {
+        unsafe { &*self.tail }.load(Acquire)
+    }
+
+    /// Dequeue every message that was already enqueued when the call started,
+    /// appending them to `out` in ring order. Returns how many were drained.
+    ///
+    /// `tail` is snapshotted on entry and draining stops once `head` reaches it,
+    /// so a producer that keeps enqueueing cannot keep this loop alive forever.
+    /// It also stops early at the first slot that is not yet published. The
+    /// priority band, if any, is drained first.
+    pub fn drain_into(&self, out: &mut Vec<(MessageMeta, Vec<u8>)>) -> usize {
+        let mut drained = 0;
+        self.drain_while(&mut |meta, payload| {
+            out.push((meta, payload));
+            drained += 1;
+            true
+        });
+        drained
+    }
+
+    /// [`drain_into`](Self::drain_into), handing each message to `take`
+    /// instead. Stops right after a message for which `take` returns false,
+    /// leaving the rest in the ring; returns false if it did.
+    pub(crate) fn drain_while(&self, take: &mut impl FnMut(MessageMeta, Vec<u8>) -> bool) -> bool {
+        if let Some(priority) = &self.priority {
+            if !priority.drain_while(take) {
+                return false;
+            }
+        }
+        let stop = unsafe { &*self.tail }.load(Acquire);
+        if self.subscriber.is_some() {
+            while let Some((meta, payload)) = self.take_broadcast(stop) {
+                if !take(meta, payload) {
+                    return false;
+                }
+            }
+            return true;
+        }
+
+        while let Some((head, slot_ptr)) = self.claim_read_before(stop) {
+            let (meta, payload) = unsafe { ((*slot_ptr).meta, self.payload_of(slot_ptr).to_vec()) };
+            unsafe { self.release_read(head, slot_ptr) };
+            if !take(meta, payload) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Dequeue the next ready slot without copying its payload.
+    ///
+    /// The returned guard borrows the payload directly from shared memory and
+    /// hands the slot back to producers when dropped. See [`MessageRef`] for the
+    /// aliasing and lifetime rules.
+    /// Returns None if the ring appears empty, and always on a broadcast
+    /// subscription; use [`dequeue`](Self::dequeue) there.
+    pub fn dequeue_ref(&self) -> Option<MessageRef<'_>> {
+        if self.subscriber.is_some() {
+            // Subscribers copy before advancing their cursor; a borrowed slot
+            // would have to pin it, so broadcast reads always copy
+            return None;
+        }
+        let Some((ring, head, slot_ptr)) = self.claim_next() else {
+            self.count_empty();
+            return None;
+        };
+        Some(MessageRef::new(ring, head, slot_ptr))
+    }
+
+    /// The high-priority ring of this channel, if it has one.
+    ///
+    /// Enqueueing on it makes the message visible ahead of everything waiting in
+    /// the normal ring; dequeueing through the channel's main view already
+    /// checks it first.
+    pub fn priority_band(&self) -> Option<&RingBuffer> {
+        self.priority.as_deref()
+    }
+
+    /// Claim the next message, trying the priority band before the normal ring.
+    /// Returns the band the slot belongs to so it can be read and released there.
+    fn claim_next(&self) -> Option<(&RingBuffer, u64, *mut Slot)> {
+        if let Some(priority) = &self.priority {
+            if let Some((head, slot_ptr)) = priority.claim_read() {
+                return Some((priority, head, slot_ptr));
+            }
+        }
+        let (head, slot_ptr) = self.claim_read()?;
+        Some((self, head, slot_ptr))
+    }
+
+    /// Claim the next message under an ack lease that lapses at `deadline_ns`.
+    ///
+    /// Claims whose lease expired before `now_ns` are taken over first, oldest
+    /// first and priority band first; otherwise the next message is claimed as in
+    /// `claim_next`. The slot stays pinned until [`ack_leased`](Self::ack_leased)
+    /// is called with `deadline_ns`. The flag is true for a redelivery.
+    pub(crate) fn claim_leased(
+        &self,
+        now_ns: u64,
+        deadline_ns: u64,
+    ) -> Option<(&RingBuffer, u64, *mut Slot, bool)> {
+        if let Some(priority) = &self.priority {
+            if let Some((head, slot_ptr)) = priority.reclaim_expired(now_ns, deadline_ns) {
+                return Some((priority, head, slot_ptr, true));
+            }
+        }
+        if let Some((head, slot_ptr)) = self.reclaim_expired(now_ns, deadline_ns) {
+            return Some((self, head, slot_ptr, true));
+        }
+
+        let Some((ring, head, slot_ptr)) = self.claim_next() else {
+            self.count_empty();
+            return None;
+        };
+        // Until the deadline is stored the lease reads as 0 and nobody can take it over
+        unsafe { ring.lease(slot_ptr) }.store(deadline_ns, Release);
+        Some((ring, head, slot_ptr, false))
+    }
+
+    /// Take over a leased claim in this band whose deadline passed before `now_ns`.
+    ///
+    /// Only the last `capacity` positions behind `head` can still be pinned, so
+    /// that is the window scanned. Claims without a lease (plain dequeues,
+    /// `MessageRef`s) are never taken over.
+    fn reclaim_expired(&self, now_ns: u64, deadline_ns: u64) -> Option<(u64, *mut Slot)> {
+        let head = unsafe { &*self.head }.load(Acquire);
+        for position in head.saturating_sub(self.capacity as u64)..head {
+            let slot_ptr = unsafe { self.slot_mut(self.slot_index(position)) };
+            let slot = unsafe { &*slot_ptr };
+            if slot.sequence.load(Acquire) != position + 1 {
+                // released, or already rewritten by a producer
                 continue;
-            } else if dif < 0 {
-                // full
-                return None;
-            } else {
-                // someone else is producing; backoff and retry
-                std::hint::spin_loop();
+            }
+            let lease_word = unsafe { self.lease(slot_ptr) };
+            let lease = lease_word.load(Acquire);
+            if lease == 0 || lease > now_ns {
                 continue;
             }
+            if lease_word
+                .compare_exchange(lease, deadline_ns, AcqRel, Relaxed)
+                .is_ok()
+            {
+                return Some((position, slot_ptr));
+            }
         }
+        None
     }
 
-    /// Dequeue acquires a ready slot and returns its content.
-    /// Returns None if the ring appears empty.
-    pub fn dequeue(&self) -> Option<(MessageMeta, Vec<u8>)> {
-        let meta_ptr = self.metadata;
-        let head_atomic = unsafe { &(*meta_ptr).head };
+    /// Release a claim taken by [`claim_leased`](Self::claim_leased), provided
+    /// it still holds the lease `deadline_ns`.
+    ///
+    /// Returns false, leaving the slot alone, when the lease expired and another
+    /// consumer took the message over; that consumer now owns the release.
+    ///
+    /// # Safety
+    /// `head` and `slot` must come from a `claim_leased` on this ring.
+    pub(crate) unsafe fn ack_leased(&self, head: u64, slot: *mut Slot, deadline_ns: u64) -> bool {
+        if self
+            .lease(slot)
+            .compare_exchange(deadline_ns, 0, AcqRel, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        self.release_read(head, slot);
+        true
+    }
+
+    /// Claim the slot at `head` for reading.
+    ///
+    /// On success `head` has been advanced past the slot, so other consumers move
+    /// on, but the slot's sequence is left at `head + 1`. Producers therefore
+    /// cannot reuse it until [`release_read`](Self::release_read) is called.
+    pub(crate) fn claim_read(&self) -> Option<(u64, *mut Slot)> {
+        self.claim_read_before(u64::MAX)
+    }
+
+    /// Like [`claim_read`](Self::claim_read), but treats the ring as empty once
+    /// `head` reaches `limit`.
+    ///
+    /// Messages whose TTL has passed are released and counted as expired on the
+    /// way, and discarded ones released, so every read path skips them.
+    ///
+    /// A slot is only claimed after an `Acquire` load of its sequence returned
+    /// `head + 1`, the value the producer stored with `Release` after writing
+    /// the metadata and payload. That pair orders the writes before every read
+    /// of the claimed slot, in this process or another mapping the segment, so
+    /// callers need no further fence.
+    fn claim_read_before(&self, limit: u64) -> Option<(u64, *mut Slot)> {
+        let head_atomic = unsafe { &*self.head };
+        let mut snooze = Snooze::new(self.backoff);
+        let mut stalled = 0;
 
         loop {
             let head = head_atomic.load(Relaxed);
-            let idx = (head as usize) & self.mask;
+            if head >= limit || self.is_unusable() {
+                return None;
+            }
+            let idx = self.slot_index(head);
             let slot_ptr = unsafe { self.slot_mut(idx) };
             let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
             let dif = seq as i64 - (head as i64 + 1);
+            if dif != 0 && dif != -1 && !self.check_sequence(seq, head) {
+                return None;
+            }
 
             if dif == 0 {
                 if head_atomic
                     .compare_exchange_weak(head, head + 1, AcqRel, Relaxed)
                     .is_ok()
                 {
-                    let (meta, payload) = unsafe {
-                        let meta = (*slot_ptr).meta;
-                        let len = meta.payload_len as usize;
-                        let mut payload = vec![0u8; len];
-                        ptr::copy_nonoverlapping(
-                            (*slot_ptr).payload.as_ptr(),
-                            payload.as_mut_ptr(),
-                            len,
-                        );
-                        (meta, payload)
-                    };
-
-                    // free slot for future producers
-                    unsafe {
-                        (&(*slot_ptr).sequence).store(head + self.capacity as u64, Release);
+                    if self.drop_unread(unsafe { &(*slot_ptr).meta }) {
+                        // dropped in place; nobody else can see it any more
+                        unsafe { self.release_read(head, slot_ptr) };
+                        continue;
                     }
-                    return Some((meta, payload));
+                    unsafe { &(*self.metadata).dequeue_count }.fetch_add(1, Relaxed);
+                    return Some((head, slot_ptr));
                 }
+                // lost the race to another consumer
+                snooze.snooze(self);
                 continue;
             } else if dif < 0 {
                 // empty
                 return None;
             } else {
-                // producer not finished; retry
-                std::hint::spin_loop();
+                // producer not finished; retry, but not forever
+                if stalled >= self.stall_spins {
+                    return None;
+                }
+                stalled += 1;
+                snooze.snooze(self);
                 continue;
             }
         }
     }
-    /// Signal consumers that new data is available
+
+    /// Hand a slot previously returned by [`claim_read`](Self::claim_read) back
+    /// to producers.
+    ///
+    /// # Safety
+    /// `head` and `slot` must come from a single successful `claim_read` on this
+    /// ring, and each claim must be released exactly once.
+    pub(crate) unsafe fn release_read(&self, head: u64, slot: *mut Slot) {
+        if (*slot).meta.flags & FLAG_SPILLED != 0 {
+            self.unspill(Some(Self::spilled_block(slot)));
+        }
+        (*slot).sequence.store(head + self.capacity as u64, Release);
+        self.signal_producer();
+    }
+
+    /// Count `count` published messages and raise the channel's high-water
+    /// mark to the band's current depth if it is a new peak.
+    #[inline]
+    fn count_enqueued(&self, count: u64) {
+        let entry = unsafe { &*self.metadata };
+        entry.enqueue_count.fetch_add(count, Relaxed);
+        let depth = self.len() as u64;
+        let mut peak = entry.high_water.load(Relaxed);
+        while depth > peak {
+            match entry
+                .high_water
+                .compare_exchange_weak(peak, depth, Relaxed, Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => peak = current,
+            }
+        }
+    }
+
+    /// Count an enqueue rejected because the channel was full.
+    #[inline]
+    fn count_full(&self) {
+        unsafe { &(*self.metadata).enqueue_full_failures }.fetch_add(1, Relaxed);
+    }
+
+    /// Whether `meta` carries a TTL that has passed. The clock is only read for
+    /// messages that have one.
+    #[inline]
+    fn expired(meta: &MessageMeta) -> bool {
+        meta.ttl_ms != 0 && meta.is_expired(ClockSource::of(meta).now_ns())
+    }
+
+    /// Whether a claimed message is dropped rather than delivered: its producer
+    /// discarded it, or its TTL has passed, which is counted as expired.
+    #[inline]
+    fn drop_unread(&self, meta: &MessageMeta) -> bool {
+        if meta.flags & FLAG_DISCARDED != 0 {
+            return true;
+        }
+        let expired = Self::expired(meta);
+        if expired {
+            self.count_expired();
+        }
+        expired
+    }
+
+    /// Count a message dropped because its TTL had passed.
+    #[inline]
+    fn count_expired(&self) {
+        unsafe { &(*self.metadata).expired_count }.fetch_add(1, Relaxed);
+    }
+
+    /// Count a receive that found the channel empty.
+    #[inline]
+    fn count_empty(&self) {
+        unsafe { &(*self.metadata).dequeue_empty }.fetch_add(1, Relaxed);
+    }
+
+    /// The channel's futex word, shared by both bands.
+    #[inline]
+    pub(crate) fn signal(&self) -> &AtomicU32 {
+        unsafe { &(*self.metadata).signal }
+    }
+
+    /// Consumers parked on [`signal`](Self::signal). Anything that parks there
+    /// must be counted here, or `signal_consumer` may not wake it.
+    #[inline]
+    pub(crate) fn data_waiters(&self) -> &AtomicU32 {
+        unsafe { &(*self.metadata).data_waiters }
+    }
+
+    /// Signal consumers that new data is available.
+    /// Only issues a wake syscall when a consumer is actually parked.
     pub fn signal_consumer(&self) {
-        unsafe {
-            let signal = &(*self.metadata).signal;
+        let entry = unsafe { &*self.metadata };
+        entry.signal.fetch_add(1, SeqCst);
+        if entry.data_waiters.load(SeqCst) != 0 {
+            entry.consumer_wakes.fetch_add(1, Relaxed);
+            crate::Core::futex::futex_wake(&entry.signal);
+        }
+    }
+
+    /// Wake every consumer parked on the channel, whether or not a send has
+    /// already signalled it. Does nothing, not even a shared write, when no
+    /// consumer is parked.
+    pub fn wake_parked_consumers(&self) {
+        let entry = unsafe { &*self.metadata };
+        if entry.data_waiters.load(SeqCst) != 0 {
+            entry.signal.fetch_add(1, SeqCst);
+            entry.consumer_wakes.fetch_add(1, Relaxed);
+            crate::Core::futex::futex_wake_all(&entry.signal);
+        }
+    }
+
+    /// Signal producers blocked on a full ring that a slot was freed.
+    /// Only issues a wake syscall when a producer is actually parked.
+    pub fn signal_producer(&self) {
+        let entry = unsafe { &*self.metadata };
+        entry.space_signal.fetch_add(1, SeqCst);
+        if entry.space_waiters.load(SeqCst) != 0 {
+            crate::Core::futex::futex_wake(&entry.space_signal);
+        }
+    }
+
+    /// Current value of the space-available counter. Sample it before trying to
+    /// enqueue and pass it to [`wait_for_space`](Self::wait_for_space) if the ring is full.
+    #[inline]
+    pub fn space_signal(&self) -> u32 {
+        unsafe { &(*self.metadata).space_signal }.load(SeqCst)
+    }
+
+    /// Park until a consumer frees a slot after `seen` was sampled, or `timeout` elapses.
+    ///
+    /// Returns immediately if a slot was already freed since `seen`, so a
+    /// consumer that races with a failed enqueue cannot leave the producer asleep.
+    /// Spurious returns are possible; callers retry their enqueue in a loop.
+    pub fn wait_for_space(&self, seen: u32, timeout: std::time::Duration) {
+        let entry = unsafe { &*self.metadata };
+        entry.space_waiters.fetch_add(1, SeqCst);
+        crate::Core::futex::futex_park(&entry.space_signal, seen, timeout);
+        entry.space_waiters.fetch_sub(1, SeqCst);
+    }
+
+    /// Reserve `count` consecutive message ids from the channel's shared
+    /// counter and return the first. Ids of failed sends are not reused.
+    #[inline]
+    pub fn reserve_message_ids(&self, count: u64) -> u64 {
+        unsafe { &(*self.metadata).next_message_id }.fetch_add(count, Relaxed)
+    }
+
+    /// Record `pid` as the channel's producer process.
+    pub fn register_producer(&self, pid: u32) {
+        unsafe { &(*self.metadata).producer_pid }.store(pid, Release);
+    }
+
+    /// Pid of the most recently registered producer, or 0 if none registered.
+    #[inline]
+    pub fn producer_pid(&self) -> u32 {
+        unsafe { &(*self.metadata).producer_pid }.load(Acquire)
+    }
+
+    /// Record the calling process in the channel's registry of attached
+    /// handles, as a consumer or a producer. Returns the entry to hand to
+    /// `unregister_attached`, or None if every entry is taken by a live process.
+    pub fn register_attached(&self, consumer: bool) -> Option<usize> {
+        let value = std::process::id() | if consumer { ATTACHED_CONSUMER } else { 0 };
+        let attached = unsafe { &(*self.metadata).attached };
+        let claim = || {
+            attached
+                .iter()
+                .position(|pid| pid.compare_exchange(0, value, AcqRel, Relaxed).is_ok())
+        };
+        claim().or_else(|| {
+            self.sweep_attached();
+            claim()
+        })
+    }
+
+    /// Clear an entry taken by `register_attached`.
+    pub fn unregister_attached(&self, index: usize) {
+        unsafe { &(*self.metadata).attached[index] }.store(0, Release);
+    }
+
+    /// Number of live producer (or consumer) handles attached to the channel,
+    /// after clearing the entries of processes that no longer exist.
+    pub fn attached_count(&self, consumer: bool) -> usize {
+        self.sweep_attached();
+        self.registered_count(consumer)
+    }
+
+    /// Number of producer (or consumer) entries in the registry, dead
+    /// processes included. Only loads, so it is cheap enough for a send.
+    fn registered_count(&self, consumer: bool) -> usize {
+        let role = if consumer { ATTACHED_CONSUMER } else { 0 };
+        unsafe { &(*self.metadata).attached }
+            .iter()
+            .map(|pid| pid.load(Acquire))
+            .filter(|&value| value != 0 && value & ATTACHED_CONSUMER == role)
+            .count()
+    }
+
+    /// Clear registry entries whose process has exited without clearing them.
+    fn sweep_attached(&self) {
+        for pid in unsafe { &(*self.metadata).attached } {
+            let value = pid.load(Acquire);
+            if value != 0 && !crate::Core::process::process_exists(value & !ATTACHED_CONSUMER) {
+                // Lost races mean another process already cleared or reused it
+                let _ = pid.compare_exchange(value, 0, AcqRel, Relaxed);
+            }
+        }
+    }
+
+    /// Count a producer as attached, reopening the channel if it was closed.
+    pub fn attach_producer(&self) {
+        let entry = unsafe { &*self.metadata };
+        entry.open_producers.fetch_add(1, AcqRel);
+        entry.closed.store(0, Release);
+    }
+
+    /// Count an attached producer as gone. When it was the last one the channel
+    /// is marked closed and every blocked consumer is woken; every message the
+    /// producers sent is published by then.
+    pub fn detach_producer(&self) {
+        let entry = unsafe { &*self.metadata };
+        if entry.open_producers.fetch_sub(1, AcqRel) == 1 {
+            entry.closed.store(1, Release);
+            let signal = self.signal();
             signal.fetch_add(1, Release);
-            crate::Core::futex::futex_wake(signal);
+            crate::Core::futex::futex_wake_all(signal);
         }
     }
 
+    /// Whether the last producer of this channel has closed it.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        unsafe { &(*self.metadata).closed }.load(Acquire) != 0
+    }
+
+    /// Advance the producer heartbeat. Called by producers after each send.
+    #[inline]
+    pub fn beat(&self) {
+        unsafe { &(*self.metadata).heartbeat }.fetch_add(1, Relaxed);
+    }
+
+    /// Current producer heartbeat. Only changes are meaningful, not the value.
+    #[inline]
+    pub fn heartbeat(&self) -> u64 {
+        unsafe { &(*self.metadata).heartbeat }.load(Relaxed)
+    }
+
+    /// Like [`wait_for_data`](Self::wait_for_data), but gives up after `timeout`
+    /// so the caller can re-check conditions nobody will signal, such as a dead producer.
+    pub fn wait_for_data_timeout(&self, timeout: std::time::Duration) {
+        let val = self.signal().load(Acquire);
+        if self.has_data() {
+            return;
+        }
+        self.park(val, timeout);
+    }
+
+    /// Park on [`signal`](Self::signal) while it still reads `seen`, for at most
+    /// `timeout`, counted in `data_waiters` so producers wake the caller.
+    pub(crate) fn park(&self, seen: u32, timeout: std::time::Duration) {
+        self.data_waiters().fetch_add(1, SeqCst);
+        crate::Core::futex::futex_park(self.signal(), seen, timeout);
+        self.data_waiters().fetch_sub(1, SeqCst);
+    }
+
     /// Wait for new data to be available
+    ///
+    /// Producers publish a slot before bumping `signal`, so the ring is re-checked
+    /// after sampling the signal word. A send that lands between the caller's
+    /// failed dequeue and this call either shows up in that re-check or changes
+    /// `signal` so the futex wait returns immediately; no wakeup is lost. The
+    /// wait is counted in `data_waiters` before the futex compares `signal`, so
+    /// a producer that skips the wake syscall has already moved `signal` on.
     pub fn wait_for_data(&self) {
-        unsafe {
-            let signal = &(*self.metadata).signal;
-            let val = signal.load(Acquire);
-            crate::Core::futex::futex_wait(signal, val);
+        let signal = self.signal();
+        let val = signal.load(Acquire);
+        if self.has_data() {
+            return;
         }
+        self.data_waiters().fetch_add(1, SeqCst);
+        crate::Core::futex::futex_wait(signal, val);
+        self.data_waiters().fetch_sub(1, SeqCst);
+    }
+
+    /// Whether the next slot of either band has been published, without
+    /// dequeuing it or touching the channel's counters.
+    #[inline]
+    pub(crate) fn has_data(&self) -> bool {
+        self.has_ready_slot() || self.priority.as_ref().is_some_and(|p| p.has_ready_slot())
+    }
+
+    /// Check whether the slot at `head` has been published by a producer.
+    #[inline]
+    fn has_ready_slot(&self) -> bool {
+        let head = unsafe { &*self.head }.load(Acquire);
+        let slot_ptr = unsafe { self.slot_mut(self.slot_index(head)) };
+        let seq = unsafe { &(*slot_ptr).sequence }.load(Acquire);
+        seq as i64 - (head as i64 + 1) >= 0
     }
 }
@@ -0,0 +1,64 @@
+use std::io;
+
+use super::Buffer::{RingBuffer, Slot};
+
+/// Proof of a message received with `Consumer::receive_ack`, used to acknowledge it.
+///
+/// ### Delivery rules:
+/// - The slot stays claimed until [`ack`](Self::ack) succeeds; producers cannot
+///   reuse it, so an unacknowledged message is never lost.
+/// - Other consumers keep receiving newer messages meanwhile. Producers reuse
+///   slots in ring order, so an old unacked message eventually stalls them.
+/// - Dropping the token without acking leaves the message claimed. Once its
+///   deadline passes, the next `receive_ack` on the channel (in any process)
+///   delivers it again, which is also what happens when the consumer crashes.
+pub struct AckToken<'a> {
+    ring: &'a RingBuffer,
+    head: u64,
+    slot: *mut Slot,
+    deadline_ns: u64,
+    redelivered: bool,
+}
+
+impl<'a> AckToken<'a> {
+    pub(crate) fn new(
+        ring: &'a RingBuffer,
+        head: u64,
+        slot: *mut Slot,
+        deadline_ns: u64,
+        redelivered: bool,
+    ) -> Self {
+        Self {
+            ring,
+            head,
+            slot,
+            deadline_ns,
+            redelivered,
+        }
+    }
+
+    /// Acknowledge the message, handing its slot back to producers.
+    ///
+    /// Fails with `TimedOut` if the deadline passed and another consumer has
+    /// already taken the message over; it is then that consumer's to ack.
+    pub fn ack(self) -> io::Result<()> {
+        // Safety: the token holds the only lease on this claim
+        if unsafe { self.ring.ack_leased(self.head, self.slot, self.deadline_ns) } {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Ack lease expired and the message was redelivered",
+        ))
+    }
+
+    /// Whether this delivery took over a claim whose lease had expired.
+    pub fn redelivered(&self) -> bool {
+        self.redelivered
+    }
+
+    /// Unix time in nanoseconds after which the message may be redelivered.
+    pub fn deadline_ns(&self) -> u64 {
+        self.deadline_ns
+    }
+}
@@ -1,9 +1,66 @@
 use crossbeam_utils::CachePadded;
 use std::sync::atomic::AtomicU64;
 
-/// The maximum number of channels that can be configured in the shared memory region.
-/// This must be a constant to allow for a fixed-size array in the GlobalHeader.
-pub const MAX_CHANNELS: usize = 256;
+/// Length of the channel table when the creator of a region does not choose one.
+/// The length actually in use is stored in `GlobalHeader::max_channels`.
+pub const DEFAULT_MAX_CHANNELS: usize = 256;
+
+/// Name of the shared memory segment under `/dev/shm` that
+/// `SharedMemoryAllocator::new` and `attach` use; `new_named` and
+/// `attach_named` take another to keep a separate segment.
+pub const DEFAULT_SEGMENT_NAME: &str = "dmxp_alloc";
+
+/// `ChannelEntry::flags` bit: producers store a CRC32C of each payload in
+/// `MessageMeta::checksum` and consumers verify it on receive.
+pub const CHANNEL_FLAG_CHECKSUM: u32 = 1 << 0;
+
+/// `ChannelEntry::flags` bit: every registered subscriber receives every message,
+/// and a slot is only reused once all subscriber cursors have passed it.
+pub const CHANNEL_FLAG_BROADCAST: u32 = 1 << 1;
+
+/// `ChannelEntry::flags` bit: the channel was sealed with
+/// `SharedMemoryAllocator::seal_channel`; producers may no longer send, but
+/// what is already in the ring can still be received.
+pub const CHANNEL_FLAG_SEALED: u32 = 1 << 2;
+
+/// `ChannelEntry::flags` bit: the ring and priority band may have any non-zero
+/// capacity, and slots are indexed with `position % capacity` instead of a mask.
+pub const CHANNEL_FLAG_EXACT_CAPACITY: u32 = 1 << 3;
+
+/// `ChannelEntry::flags` bit: an enqueue that finds the ring full drops the
+/// oldest unread message to make room instead of failing.
+pub const CHANNEL_FLAG_OVERWRITE: u32 = 1 << 4;
+
+/// `ChannelEntry::flags` bit: only one producer ever sends at a time, so an
+/// enqueue takes its slot with a plain store to `tail` instead of a CAS.
+pub const CHANNEL_FLAG_SINGLE_PRODUCER: u32 = 1 << 5;
+
+/// `ChannelEntry::flags` bit: a slot was found holding a sequence no ring
+/// operation can produce, so the channel's contents can no longer be trusted.
+/// Enqueues and dequeues refuse to touch it from then on.
+pub const CHANNEL_FLAG_POISONED: u32 = 1 << 6;
+
+/// `ChannelEntry::flags` bit: at most one consumer may be attached at a time;
+/// attaching a second fails instead of letting the two compete for messages.
+pub const CHANNEL_FLAG_SINGLE_CONSUMER: u32 = 1 << 7;
+
+/// `ChannelEntry::flags` bit: the segment's control channel, carrying
+/// `ControlMessage`s rather than application data.
+pub const CHANNEL_FLAG_CONTROL: u32 = 1 << 8;
+
+/// Records kept in the segment's `EventLog`; once it is full each new event
+/// overwrites the oldest.
+pub const EVENT_LOG_LEN: usize = 64;
+
+/// Maximum number of subscribers registered on one broadcast channel.
+pub const MAX_SUBSCRIBERS: usize = 8;
+
+/// Number of producer and consumer handles `ChannelEntry::attached` tracks.
+pub const MAX_ATTACHED: usize = 25;
+
+/// Set in a `ChannelEntry::attached` entry that belongs to a consumer; the
+/// other bits hold the pid.
+pub const ATTACHED_CONSUMER: u32 = 1 << 31;
 
 /// Defines the metadata for a single MPMC channel within the global header.
 ///
@@ -11,15 +68,18 @@ pub const MAX_CHANNELS: usize = 256;
 /// to manage one channel's ring buffer. By centralizing these here, we keep
 /// the control plane separate from the data plane and optimize memory layout.
 #[repr(C, align(128))]
+#[derive(Default)]
 pub struct ChannelEntry {
     /// Logical identifier (0xFFFF_FFFF if unused).
     pub channel_id: u32,
 
-    /// Per-channel flags (e.g., drop-on-full, notify-on-write).
-    pub flags: u32,
+    /// Per-channel flags (`CHANNEL_FLAG_*`). Fixed at creation except for
+    /// `CHANNEL_FLAG_SEALED` and `CHANNEL_FLAG_POISONED`, which can be set
+    /// while the channel is in use.
+    pub flags: std::sync::atomic::AtomicU32,
 
     /// The capacity of this channel's ring buffer in number of slots.
-    /// Recommended power-of-two.
+    /// A power of two unless `CHANNEL_FLAG_EXACT_CAPACITY` is set.
     pub capacity: u64,
 
     /// The byte offset from the start of the shared memory region to the
@@ -30,6 +90,61 @@ pub struct ChannelEntry {
     /// Producers write to this (and wake), consumers wait on this.
     pub signal: std::sync::atomic::AtomicU32,
 
+    /// Size in bytes of each overflow block (0 if the channel has no overflow band).
+    pub overflow_block_size: u32,
+
+    /// Offset of the overflow band relative to `band_offset`.
+    pub overflow_offset: u64,
+
+    /// Number of overflow blocks available for payloads larger than the inline size.
+    pub overflow_blocks: u64,
+
+    /// Total size in bytes of this channel's data band (slots, priority slots and overflow band).
+    pub band_size: u64,
+
+    /// Number of slots in the high-priority ring (0 if the channel has no priority band).
+    pub priority_capacity: u64,
+
+    /// Offset of the high-priority slots relative to `band_offset`.
+    pub priority_offset: u64,
+
+    /// Futex word for producer wakeups. Consumers bump it (and wake) when they
+    /// free a slot; producers blocked on a full ring wait on it.
+    pub space_signal: std::sync::atomic::AtomicU32,
+
+    /// Number of producers currently parked on `space_signal`, so consumers only
+    /// pay for a wake syscall when someone is waiting.
+    pub space_waiters: std::sync::atomic::AtomicU32,
+
+    /// Pid of the most recently attached producer (0 if none has attached).
+    pub producer_pid: std::sync::atomic::AtomicU32,
+
+    /// Bit `i` is set while broadcast subscriber `i` is registered.
+    pub subscriber_mask: std::sync::atomic::AtomicU32,
+
+    /// Bumped by producers on every send so consumers can tell an idle producer
+    /// from one whose process has died.
+    pub heartbeat: AtomicU64,
+
+    /// Number of producers attached and not yet closed.
+    pub open_producers: std::sync::atomic::AtomicU32,
+
+    /// Set to 1 when the last open producer closes the channel, cleared when a
+    /// producer attaches again. Consumers drain what is left, then stop.
+    pub closed: std::sync::atomic::AtomicU32,
+
+    /// Next `message_id` to hand out. Every producer on the channel, in any
+    /// process, draws ids from here, so ids are unique and increasing per channel.
+    pub next_message_id: AtomicU64,
+
+    /// Messages enqueued on either band. Like the other metrics counters it is
+    /// updated with `Relaxed` ordering and only meant for monitoring.
+    pub enqueue_count: AtomicU64,
+
+    /// Enqueue attempts (single or batch) rejected because the ring or the
+    /// overflow band was full.
+    pub enqueue_full_failures: AtomicU64,
+
     /// The "tail" cursor for producers. Atomically incremented to claim a slot for writing.
     /// Padded to prevent false sharing with adjacent channel metadata.
     pub tail: CachePadded<AtomicU64>,
@@ -38,6 +153,53 @@ pub struct ChannelEntry {
     /// Padded to prevent false sharing with adjacent channel metadata.
     pub head: CachePadded<AtomicU64>,
 
+    /// Producer cursor of the high-priority ring.
+    pub priority_tail: CachePadded<AtomicU64>,
+
+    /// Consumer cursor of the high-priority ring.
+    pub priority_head: CachePadded<AtomicU64>,
+
+    /// Read cursors of broadcast subscribers; only those set in `subscriber_mask` are live.
+    pub subscribers: [CachePadded<AtomicU64>; MAX_SUBSCRIBERS],
+
+    /// Messages dequeued, counting each delivery to a broadcast subscriber.
+    /// Kept away from the producer-side counters, which share a cache line with
+    /// `next_message_id`.
+    pub dequeue_count: AtomicU64,
+
+    /// Receive attempts that found the channel empty.
+    pub dequeue_empty: AtomicU64,
+
+    /// Pids of the producer and consumer handles attached to the channel, with
+    /// `ATTACHED_CONSUMER` set for consumers; 0 marks a free entry. Handles
+    /// clear their entry when dropped, and entries of processes that died
+    /// without doing so are cleared when the registry is counted.
+    pub attached: [std::sync::atomic::AtomicU32; MAX_ATTACHED],
+
+    /// Inline payload bytes per slot in both rings, fixed at creation; 0 means
+    /// `MSG_INLINE`. Determines the slot stride (see `Slot::stride`).
+    pub inline_size: u32,
+
+    /// Messages dropped on receive because their TTL had passed.
+    pub expired_count: AtomicU64,
+
+    /// Number of consumers currently parked on `signal`, so producers only pay
+    /// for a wake syscall when someone is waiting.
+    pub data_waiters: std::sync::atomic::AtomicU32,
+
+    /// Wake syscalls producers issued on `signal`.
+    pub consumer_wakes: AtomicU64,
+
+    /// Most messages ever waiting in either band at once, as seen by producers
+    /// right after an enqueue. Reset with `SharedMemoryAllocator::reset_high_water`.
+    pub high_water: AtomicU64,
+
+    /// Bumped by `SharedMemoryAllocator::resize_channel` and `compact` before
+    /// and after they rewrite the ring's geometry or move its band. Views
+    /// remember the value they were made with and stop touching slots once it
+    /// changes.
+    pub layout_generation: AtomicU64,
+
     /// Padding to ensure the struct size is aligned to 128 bytes (or 64 bytes).
     /// We use explicit padding if necessary, but `align(128)` handles the stride.
     /// Note: The design asks for 64B alignment, but 128B is safer for modern CPUs (prefetchers).
@@ -45,10 +207,51 @@ pub struct ChannelEntry {
     pub _pad: [u64; 0],
 }
 
+/// One channel lifecycle event in the `EventLog`, written under a sequence
+/// lock: `sequence` is 0 while a writer fills the record in and `position + 1`
+/// once it is complete, so readers can tell a finished record from a torn one.
+#[repr(C)]
+#[derive(Default)]
+pub struct EventRecord {
+    /// `position + 1` of the event stored here, or 0 while it is being written.
+    pub sequence: AtomicU64,
+
+    /// `CLOCK_REALTIME` nanoseconds at which the event happened.
+    pub timestamp_ns: AtomicU64,
+
+    /// The channel the event is about.
+    pub channel_id: std::sync::atomic::AtomicU32,
+
+    /// What happened (`AllocatorEventKind` code).
+    pub kind: std::sync::atomic::AtomicU32,
+
+    /// Pid of the process that caused the event.
+    pub pid: std::sync::atomic::AtomicU32,
+
+    pub _pad: u32,
+}
+
+/// Ring of the most recent channel lifecycle events (created, removed, sealed,
+/// poisoned, resized), after the channel ID bitmap. Writers from any process
+/// claim a position with a `fetch_add` on `next` and store it at
+/// `records[position % EVENT_LOG_LEN]`.
+#[repr(C, align(128))]
+pub struct EventLog {
+    /// Position of the next event to record; also the number recorded so far.
+    pub next: AtomicU64,
+
+    pub records: [EventRecord; EVENT_LOG_LEN],
+}
+
 /// The global header located at the very beginning of the shared memory region.
 ///
 /// It acts as the entry point for any process, containing versioning info
-/// and the table of channel entries.
+/// and the length of the channel table. The table itself, `max_channels`
+/// `ChannelEntry`s, follows directly after the header, and after the table
+/// comes the channel ID bitmap: `max_channels` bits in little-endian `u64`
+/// words, bit `i` set while channel ID `i` is taken, padded to a multiple of
+/// 128 bytes like the header and the entries. The `EventLog` follows the
+/// bitmap.
 #[repr(C, align(128))]
 pub struct GlobalHeader {
     /// A "magic number" to identify the memory region as a DMXP-KVCache buffer.
@@ -57,15 +260,43 @@ pub struct GlobalHeader {
     /// The version of the memory layout.
     pub version: u32,
 
-    /// Compiled/allocated maximum channel entries.
+    /// Number of entries in the channel table following the header, chosen by
+    /// the creating process.
     pub max_channels: u32,
 
     /// The number of channels currently active and configured.
     pub channel_count: u32,
 
-    /// Reserved/padding (align to 16 or 64 bytes).
-    pub reserved: u32,
+    /// `BYTE_ORDER_MARKER` as stored by the creating process, so a process
+    /// of the other byte order sees it byte-swapped.
+    pub byte_order: u32,
+}
+
+/// Value of `GlobalHeader::byte_order`. No byte swap maps it onto itself, so
+/// an attaching process of the opposite endianness reads
+/// `BYTE_ORDER_MARKER.swap_bytes()` instead.
+pub const BYTE_ORDER_MARKER: u32 = 0x0102_0304;
+
+impl GlobalHeader {
+    /// Bytes taken by the header together with a channel table of
+    /// `max_channels` entries, its ID bitmap and the event log, where the
+    /// first channel band may start. None if that overflows `usize`.
+    pub fn control_size(max_channels: usize) -> Option<usize> {
+        Self::event_log_offset(max_channels)?.checked_add(std::mem::size_of::<EventLog>())
+    }
+
+    /// Offset of the channel ID bitmap, right after a channel table of
+    /// `max_channels` entries. None if that overflows `usize`.
+    pub fn id_bitmap_offset(max_channels: usize) -> Option<usize> {
+        std::mem::size_of::<ChannelEntry>()
+            .checked_mul(max_channels)?
+            .checked_add(std::mem::size_of::<GlobalHeader>())
+    }
 
-    /// The table of metadata for each channel.
-    pub channels: [ChannelEntry; MAX_CHANNELS],
+    /// Offset of the event log, right after the ID bitmap of a table of
+    /// `max_channels` entries. None if that overflows `usize`.
+    pub fn event_log_offset(max_channels: usize) -> Option<usize> {
+        // 1024 bits fill one 128-byte block
+        Self::id_bitmap_offset(max_channels)?.checked_add(max_channels.div_ceil(1024) * 128)
+    }
 }
@@ -0,0 +1,64 @@
+use std::ops::Deref;
+
+use super::Buffer::{RingBuffer, Slot};
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+
+/// A borrowed, zero-copy view of a dequeued message.
+///
+/// The payload slice points directly into the slot in shared memory; nothing is
+/// copied onto the heap. The slot stays claimed for as long as the guard lives
+/// and is handed back to producers when it is dropped.
+///
+/// ### Lifetime and aliasing rules:
+/// - The consumer `head` cursor is advanced when the guard is created, so other
+///   consumers keep making progress past this slot while it is held.
+/// - The slot's `sequence` is only released on drop. Until then no producer can
+///   write to it, which is what makes handing out `&[u8]` into shared memory sound.
+/// - Because producers reuse slots in ring order, holding a guard for a long time
+///   stalls producers once the ring wraps back around to this slot. Keep guards
+///   short-lived and never `mem::forget` one, or the channel will wedge as full.
+/// - The guard borrows the [`RingBuffer`] (and, through `Consumer::receive_ref`,
+///   the consumer), so the mapping cannot be unmapped while it is alive.
+pub struct MessageRef<'a> {
+    ring: &'a RingBuffer,
+    head: u64,
+    slot: *mut Slot,
+}
+
+impl<'a> MessageRef<'a> {
+    pub(crate) fn new(ring: &'a RingBuffer, head: u64, slot: *mut Slot) -> Self {
+        Self { ring, head, slot }
+    }
+
+    /// Metadata written by the producer for this message.
+    pub fn meta(&self) -> &MessageMeta {
+        unsafe { &(*self.slot).meta }
+    }
+
+    /// The payload bytes, borrowed from the slot.
+    pub fn payload(&self) -> &[u8] {
+        // Safety: the slot stays claimed until this guard is dropped
+        unsafe { self.ring.payload_of(self.slot) }
+    }
+}
+
+impl Deref for MessageRef<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.payload()
+    }
+}
+
+impl AsRef<[u8]> for MessageRef<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.payload()
+    }
+}
+
+impl Drop for MessageRef<'_> {
+    fn drop(&mut self) {
+        // Safety: the guard owns exactly one successful claim on this slot
+        unsafe { self.ring.release_read(self.head, self.slot) }
+    }
+}
@@ -0,0 +1,124 @@
+// Overflow band for payloads that do not fit in a slot's inline area.
+//
+// Layout (relative to the start of the band):
+//   [ occupancy bitmap: one bit per block, padded to 128 bytes ][ block 0 ][ block 1 ] ...
+//
+// A producer claims a free block by setting its bit, copies the payload in, and
+// stores the block index in the slot. The consumer copies (or borrows) the bytes
+// and clears the bit once it has released the slot.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+
+use super::layout::ChannelEntry;
+
+const BITS_PER_WORD: usize = 64;
+
+/// A transient view of a channel's overflow band.
+pub(crate) struct OverflowBand {
+    bitmap: *const AtomicU64,
+    words: usize,
+    blocks: usize,
+    block_size: usize,
+    data: *mut u8,
+}
+
+impl OverflowBand {
+    /// Build a view from the channel metadata, or `None` if the channel has no overflow band.
+    ///
+    /// # Safety
+    /// `metadata` and `buffer_base` must describe a valid, mapped channel.
+    pub(crate) unsafe fn from_entry(
+        metadata: *const ChannelEntry,
+        buffer_base: *mut u8,
+    ) -> Option<Self> {
+        let blocks = (*metadata).overflow_blocks as usize;
+        let block_size = (*metadata).overflow_block_size as usize;
+        if blocks == 0 || block_size == 0 {
+            return None;
+        }
+
+        let base = buffer_base.add((*metadata).overflow_offset as usize);
+        let words = Self::bitmap_words(blocks);
+        Some(Self {
+            bitmap: base as *const AtomicU64,
+            words,
+            blocks,
+            block_size,
+            data: base.add(Self::bitmap_bytes(blocks)),
+        })
+    }
+
+    /// Bytes needed for an overflow band of `blocks` blocks of `block_size` bytes,
+    /// or None if that does not fit in a `usize`.
+    pub(crate) fn required_size(blocks: usize, block_size: usize) -> Option<usize> {
+        if blocks == 0 {
+            return Some(0);
+        }
+        blocks
+            .checked_mul(block_size)?
+            .checked_add(Self::bitmap_bytes(blocks))
+    }
+
+    fn bitmap_words(blocks: usize) -> usize {
+        blocks.div_ceil(BITS_PER_WORD)
+    }
+
+    fn bitmap_bytes(blocks: usize) -> usize {
+        (Self::bitmap_words(blocks) * 8 + 127) & !127
+    }
+
+    /// Largest payload a single block can hold.
+    #[inline]
+    pub(crate) fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Mark every block free. Bits past the last block are kept permanently set
+    /// so `claim` never hands them out.
+    ///
+    /// # Safety
+    /// Must only be called while no producer or consumer is using the band.
+    pub(crate) unsafe fn reset(&self) {
+        for w in 0..self.words {
+            let first = w * BITS_PER_WORD;
+            let valid = (self.blocks - first).min(BITS_PER_WORD);
+            let unusable = if valid == BITS_PER_WORD {
+                0
+            } else {
+                !0u64 << valid
+            };
+            (*self.bitmap.add(w)).store(unusable, Release);
+        }
+    }
+
+    /// Claim a free block, returning its index, or `None` if every block is in use.
+    pub(crate) fn claim(&self) -> Option<u32> {
+        for w in 0..self.words {
+            let word = unsafe { &*self.bitmap.add(w) };
+            let mut current = word.load(Acquire);
+            while current != !0u64 {
+                let bit = (!current).trailing_zeros() as usize;
+                match word.compare_exchange_weak(current, current | (1 << bit), AcqRel, Acquire) {
+                    Ok(_) => return Some((w * BITS_PER_WORD + bit) as u32),
+                    Err(actual) => current = actual,
+                }
+            }
+        }
+        None
+    }
+
+    /// Return a block to the free pool.
+    pub(crate) fn release(&self, block: u32) {
+        let block = block as usize;
+        debug_assert!(block < self.blocks, "overflow block out of range");
+        let word = unsafe { &*self.bitmap.add(block / BITS_PER_WORD) };
+        word.fetch_and(!(1u64 << (block % BITS_PER_WORD)), Release);
+    }
+
+    /// Pointer to the first byte of `block`.
+    #[inline]
+    pub(crate) fn block_ptr(&self, block: u32) -> *mut u8 {
+        unsafe { self.data.add(block as usize * self.block_size) }
+    }
+}
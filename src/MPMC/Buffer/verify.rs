@@ -0,0 +1,184 @@
+// Self-checking payloads for stress tests.
+//
+// Throughput tests only count what comes out of a ring; a slot whose metadata
+// and payload come from different writes still counts as one message. Here
+// every payload is derived from its message id and carries a CRC32C of itself,
+// so a consumer can recompute it and catch torn or misattributed slots.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use super::Buffer::RingBuffer;
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+
+/// Longest payload `seq_payload` produces.
+pub const SEQ_PAYLOAD_MAX: usize = 64;
+
+/// The payload for sequence `seq`: `seq` as a little-endian `u64`, filler bytes
+/// drawn from `seq`, and a CRC32C of all of that. Its length (12 to
+/// [`SEQ_PAYLOAD_MAX`] bytes) depends on `seq` too, so a torn `payload_len`
+/// is caught as well.
+pub fn seq_payload(seq: u64) -> Vec<u8> {
+    let filler = (seq % (SEQ_PAYLOAD_MAX as u64 - 11)) as usize;
+    let mut payload = Vec::with_capacity(12 + filler);
+    payload.extend_from_slice(&seq.to_le_bytes());
+    // splitmix64, seeded with the sequence
+    let mut state = seq;
+    while payload.len() < 8 + filler {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let take = (8 + filler - payload.len()).min(8);
+        payload.extend_from_slice(&z.to_le_bytes()[..take]);
+    }
+    let crc = crc32c::crc32c(&payload);
+    payload.extend_from_slice(&crc.to_le_bytes());
+    payload
+}
+
+/// Check that `payload` is exactly [`seq_payload(seq)`](seq_payload). Fails
+/// with `InvalidData` saying what differs.
+pub fn check_seq_payload(seq: u64, payload: &[u8]) -> io::Result<()> {
+    let invalid = |what: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Payload of message {}: {}", seq, what),
+        ))
+    };
+    if payload.len() < 12 {
+        return invalid(format!("{} bytes is too short", payload.len()));
+    }
+    let (body, crc) = payload.split_at(payload.len() - 4);
+    if crc32c::crc32c(body).to_le_bytes() != crc {
+        return invalid("checksum mismatch".to_string());
+    }
+    let written = u64::from_le_bytes(body[..8].try_into().unwrap());
+    if written != seq {
+        return invalid(format!("written for message {}", written));
+    }
+    if payload != seq_payload(seq) {
+        return invalid("contents differ".to_string());
+    }
+    Ok(())
+}
+
+impl RingBuffer {
+    /// Enqueue [`seq_payload(seq)`](seq_payload) with `seq` as its
+    /// `message_id`, for [`dequeue_verify`](Self::dequeue_verify) to check.
+    /// Returns the slot index, or None if the ring appears full.
+    pub fn enqueue_seq_payload(&self, seq: u64) -> Option<usize> {
+        let meta = MessageMeta {
+            message_id: seq,
+            ..Default::default()
+        };
+        self.enqueue(meta, &seq_payload(seq))
+    }
+
+    /// Dequeue a message sent with
+    /// [`enqueue_seq_payload`](Self::enqueue_seq_payload) and return its
+    /// `message_id`, or None if the ring appears empty.
+    ///
+    /// # Panics
+    /// If the payload is not the one derived from the message's `message_id`.
+    pub fn dequeue_verify(&self) -> Option<u64> {
+        let (meta, payload) = self.dequeue()?;
+        if let Err(e) = check_seq_payload(meta.message_id, &payload) {
+            panic!("{} (payload_len {})", e, meta.payload_len);
+        }
+        Some(meta.message_id)
+    }
+}
+
+/// Run `producers` threads sending `per_producer` sequence payloads each
+/// through `ring` while `consumers` threads dequeue and verify them, and
+/// return every `message_id` received, sorted.
+///
+/// Producer `p` sends ids `p * per_producer ..` in order. Each consumer
+/// checks every payload with [`RingBuffer::dequeue_verify`] and that the ids
+/// it sees from each producer only increase, so a lost, repeated, reordered or
+/// corrupted message panics the run.
+pub fn stress_verified(
+    ring: &RingBuffer,
+    producers: usize,
+    consumers: usize,
+    per_producer: u64,
+) -> Vec<u64> {
+    let total = producers as u64 * per_producer;
+    let finished = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut ids: Vec<u64> = thread::scope(|scope| {
+        for p in 0..producers as u64 {
+            let (finished, failed) = (&finished, &failed);
+            scope.spawn(move || {
+                for seq in p * per_producer..(p + 1) * per_producer {
+                    while ring.enqueue_seq_payload(seq).is_none() {
+                        // Nobody is left to make room
+                        if failed.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        thread::yield_now();
+                    }
+                }
+                finished.fetch_add(1, Ordering::Release);
+            });
+        }
+        let readers: Vec<_> = (0..consumers)
+            .map(|_| {
+                let (finished, failed) = (&finished, &failed);
+                scope.spawn(move || {
+                    let _abort = AbortOnPanic(failed);
+                    let mut seen = Vec::new();
+                    let mut last = vec![None; producers];
+                    loop {
+                        // Checked before dequeuing: once every producer has
+                        // published, an empty ring stays empty
+                        let done = finished.load(Ordering::Acquire) == producers;
+                        let Some(seq) = ring.dequeue_verify() else {
+                            if done || failed.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            thread::yield_now();
+                            continue;
+                        };
+                        let from = (seq / per_producer) as usize;
+                        assert!(from < producers, "message {} was never sent", seq);
+                        assert!(
+                            last[from] < Some(seq),
+                            "message {} after {:?} from producer {}",
+                            seq,
+                            last[from],
+                            from
+                        );
+                        last[from] = Some(seq);
+                        seen.push(seq);
+                    }
+                    seen
+                })
+            })
+            .collect();
+        readers
+            .into_iter()
+            .flat_map(|reader| reader.join().unwrap())
+            .collect()
+    });
+    ids.sort_unstable();
+    if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+        panic!("message {} received twice", pair[0]);
+    }
+    assert_eq!(ids.len() as u64, total, "messages lost");
+    ids
+}
+
+/// Tells the other threads of a stress run to stop when a consumer panics.
+struct AbortOnPanic<'a>(&'a AtomicBool);
+
+impl Drop for AbortOnPanic<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -2,10 +2,32 @@
 
 // no atomics in MessageMeta; keep as plain integral types for ABI
 
+/// The payload did not fit inline and lives in the channel's overflow band.
+/// The slot's inline payload holds the overflow block index instead.
+///
+/// The upper byte of `MessageMeta::flags` is reserved for transport-level bits;
+/// the lower byte is free for applications.
+pub const FLAG_SPILLED: u16 = 1 << 15;
+
+/// The stored payload is LZ4-compressed (`Producer::send_compressed`).
+/// Consumers built with the `lz4` feature decompress it on receive and report
+/// the decompressed length in `payload_len`, leaving this bit set.
+pub const FLAG_COMPRESSED: u16 = 1 << 14;
+
+/// `timestamp_ns` was read from the monotonic clock (`ClockSource::Monotonic`,
+/// nanoseconds since boot) rather than the wall clock.
+pub const FLAG_MONOTONIC: u16 = 1 << 13;
+
+/// The producer gave up on the message after claiming its slot (a
+/// `SlotWriter` that overran the slot); consumers skip it unread.
+pub const FLAG_DISCARDED: u16 = 1 << 12;
+
 /// Transport-only metadata that precedes each payload in a Slot.
-/// ABI-stable across languages; all fields are little-endian.
+/// ABI-stable across languages; all fields are little-endian (segments of
+/// the other byte order are refused on attach via `GlobalHeader::byte_order`).
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MessageMeta {
     pub message_id: u64,
     pub timestamp_ns: u64,
@@ -15,4 +37,43 @@ pub struct MessageMeta {
     pub sender_runtime: u16,
     pub flags: u16,
     pub payload_len: u32,
+    /// CRC32C of the payload, filled in on enqueue when the channel has
+    /// checksums enabled (0 otherwise). Occupies what used to be trailing padding.
+    pub checksum: u32,
+    /// Version of the payload layout for this `message_type`, so consumers can
+    /// tell old and new formats apart. 0 unless the producer set it.
+    pub schema_version: u16,
+    /// Time to live in milliseconds, counted from `timestamp_ns`; 0 means the
+    /// message never expires. Consumers drop expired messages instead of
+    /// returning them. Occupies what used to be trailing padding.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ttl_ms: u32,
+}
+
+impl MessageMeta {
+    /// The time after which the message is dropped on receive, on the clock
+    /// `timestamp_ns` was read from (the wall clock unless `FLAG_MONOTONIC` is
+    /// set). None if it has no TTL.
+    pub fn expiry_ns(&self) -> Option<u64> {
+        if self.ttl_ms == 0 {
+            return None;
+        }
+        Some(
+            self.timestamp_ns
+                .saturating_add(u64::from(self.ttl_ms) * 1_000_000),
+        )
+    }
+
+    /// Whether the message's TTL had passed at `now_ns`.
+    pub fn is_expired(&self, now_ns: u64) -> bool {
+        self.expiry_ns().is_some_and(|expiry| expiry <= now_ns)
+    }
+}
+
+/// Human-readable form with a decoded timestamp and flags; see
+/// `Debug::StructDebug::debug_message_meta`. `{:?}` prints the raw fields.
+impl std::fmt::Display for MessageMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::Debug::StructDebug::debug_message_meta(self, f)
+    }
 }
@@ -0,0 +1,144 @@
+// Tokio integration for Consumer, enabled with the `tokio` feature.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+
+use super::consumer::{Consumer, LIVENESS_INTERVAL};
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+
+/// The channel's futex word or its waiter count, handed to a blocking-pool thread.
+struct SignalPtr(*const AtomicU32);
+
+unsafe impl Send for SignalPtr {}
+
+impl SignalPtr {
+    /// # Safety
+    /// The mapping holding the word must still be alive.
+    unsafe fn get(&self) -> &AtomicU32 {
+        &*self.0
+    }
+}
+
+/// Marks a park in flight on the blocking pool. The consumer does not unmap
+/// the channel until every guard is gone.
+struct ParkGuard(Arc<AtomicUsize>);
+
+impl ParkGuard {
+    fn new(waiters: &Arc<AtomicUsize>) -> Self {
+        waiters.fetch_add(1, Ordering::AcqRel);
+        Self(waiters.clone())
+    }
+}
+
+impl Drop for ParkGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl Consumer {
+    /// Receives a message and metadata without blocking the executor.
+    ///
+    /// While the channel is empty the task waits on the channel's futex from a
+    /// Tokio blocking-pool thread, so it wakes as soon as a producer signals
+    /// instead of polling. Like `receive_blocking_with_meta`, it fails with
+    /// `BrokenPipe` once the producer has terminated.
+    ///
+    /// Cancelling the future is safe; a park already in flight finishes on its
+    /// own within `LIVENESS_INTERVAL`.
+    pub async fn recv_async(&self) -> io::Result<(MessageMeta, Vec<u8>)> {
+        let buffer = self.channel.buffer();
+        loop {
+            // Sampled before the attempt, so a send racing with it makes the park return at once
+            let seen = buffer.signal().load(Ordering::Acquire);
+            if let Some(message) = self.receive_with_meta()? {
+                return Ok(message);
+            }
+
+            let guard = ParkGuard::new(&self.async_waiters);
+            let signal = SignalPtr(buffer.signal());
+            let waiters = SignalPtr(buffer.data_waiters());
+            let _ = tokio::task::spawn_blocking(move || {
+                let _guard = guard;
+                // Safety: dropping the consumer waits for `_guard` before unmapping
+                let (signal, waiters) = unsafe { (signal.get(), waiters.get()) };
+                // Counted so producers wake this park (see `RingBuffer::park`)
+                waiters.fetch_add(1, Ordering::SeqCst);
+                crate::Core::futex::futex_park(signal, seen, LIVENESS_INTERVAL);
+                waiters.fetch_sub(1, Ordering::SeqCst);
+            })
+            .await;
+        }
+    }
+
+    /// Turns the consumer into a [`Stream`] of received messages.
+    pub fn into_stream(self) -> ConsumerStream {
+        ConsumerStream {
+            consumer: Arc::new(self),
+            pending: None,
+            done: false,
+        }
+    }
+
+    /// Waits out blocking-pool parks left behind by cancelled `recv_async`
+    /// calls, which still reference the mapping.
+    pub(super) fn finish_async_parks(&self) {
+        let signal = self.channel.buffer().signal();
+        while self.async_waiters.load(Ordering::Acquire) != 0 {
+            crate::Core::futex::futex_wake_all(signal);
+            std::thread::yield_now();
+        }
+    }
+}
+
+type Receive = Pin<Box<dyn Future<Output = io::Result<(MessageMeta, Vec<u8>)>> + Send>>;
+
+/// A [`Consumer`] as a [`Stream`], created by [`Consumer::into_stream`].
+///
+/// Each item is the result of one `recv_async`: failed receives (such as a
+/// checksum mismatch) are yielded as `Err`, and the stream ends once the
+/// producer has terminated.
+pub struct ConsumerStream {
+    consumer: Arc<Consumer>,
+    pending: Option<Receive>,
+    done: bool,
+}
+
+impl ConsumerStream {
+    /// The consumer this stream receives from.
+    pub fn consumer(&self) -> &Consumer {
+        &self.consumer
+    }
+}
+
+impl Stream for ConsumerStream {
+    type Item = io::Result<(MessageMeta, Vec<u8>)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        let pending = this.pending.get_or_insert_with(|| {
+            let consumer = this.consumer.clone();
+            Box::pin(async move { consumer.recv_async().await })
+        });
+        let result = ready!(pending.as_mut().poll(cx));
+        this.pending = None;
+
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                this.done = true;
+                Poll::Ready(None)
+            }
+            result => Poll::Ready(Some(result)),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// Backoff policies for the retry loops inside the ring buffer.
+//
+// Producers and consumers retry when a slot is owned by someone else (another
+// producer mid-write, or a CAS lost to a competing thread). How they wait between
+// attempts is a trade-off between latency and CPU burned while contended.
+
+use crate::MPMC::Buffer::RingBuffer;
+use std::sync::atomic::Ordering::Acquire;
+use std::time::Duration;
+
+/// How a producer or consumer waits before retrying a contended slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backoff {
+    /// Issue a CPU spin hint and retry immediately. Lowest latency, highest CPU use.
+    #[default]
+    Spin,
+    /// Yield the thread to the scheduler between attempts.
+    Yield,
+    /// Spin with exponentially growing bursts, then yield, then park briefly
+    /// on the channel's futex. Suited to heavily oversubscribed machines.
+    ExponentialThenPark,
+}
+
+/// Steps of exponential spinning before switching to `yield_now`.
+const SPIN_STEPS: u32 = 6;
+
+/// Steps (including spinning ones) before switching to parking.
+const YIELD_STEPS: u32 = 10;
+
+/// Upper bound for one park; a producer publishing wakes it sooner.
+const PARK_TIMEOUT: Duration = Duration::from_micros(50);
+
+/// Retries a dequeue makes by default while the producer of the next message
+/// is still writing it (see `RingBuffer::set_stall_spins`).
+pub const DEFAULT_STALL_SPINS: u32 = 4096;
+
+/// Per-retry-loop state for a [`Backoff`] policy.
+pub(crate) struct Snooze {
+    policy: Backoff,
+    step: u32,
+}
+
+impl Snooze {
+    pub(crate) fn new(policy: Backoff) -> Self {
+        Self { policy, step: 0 }
+    }
+
+    /// Wait once before the next retry. `ExponentialThenPark` parks on `ring`'s
+    /// futex word once spinning and yielding are exhausted.
+    pub(crate) fn snooze(&mut self, ring: &RingBuffer) {
+        match self.policy {
+            Backoff::Spin => std::hint::spin_loop(),
+            Backoff::Yield => std::thread::yield_now(),
+            Backoff::ExponentialThenPark => {
+                if self.step < SPIN_STEPS {
+                    for _ in 0..1u32 << self.step {
+                        std::hint::spin_loop();
+                    }
+                } else if self.step < YIELD_STEPS {
+                    std::thread::yield_now();
+                } else {
+                    ring.park(ring.signal().load(Acquire), PARK_TIMEOUT);
+                }
+                self.step = (self.step + 1).min(YIELD_STEPS);
+            }
+        }
+    }
+}
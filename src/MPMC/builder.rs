@@ -1,18 +1,80 @@
-use super::{Consumer, Producer};
-use crate::Core::alloc::SharedMemoryAllocator;
+use super::consumer::DEFAULT_ACK_TIMEOUT;
+use super::{
+    Backoff, ClockSource, Consumer, MultiConsumer, Producer, ShardedProducer, TailConsumer,
+    Timestamps, DEFAULT_STALL_SPINS,
+};
+use crate::Core::alloc::{ChannelPartition, ChannelSpec, SharedMemoryAllocator};
+use crate::MPMC::Buffer::layout::{DEFAULT_MAX_CHANNELS, DEFAULT_SEGMENT_NAME};
+use crate::MPMC::Buffer::MSG_INLINE;
+use std::time::Duration;
+
+/// Whether building a producer or consumer may create the channel it opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Use an existing channel; fail with `NotFound` if it or the shared
+    /// memory segment does not exist.
+    AttachOnly,
+    /// Create the channel, and the segment if there is none yet; fail with
+    /// `AlreadyExists` if the channel exists.
+    CreateOnly,
+    /// Use the channel if it exists, create it otherwise.
+    CreateOrAttach,
+}
 
 pub struct ChannelBuilder {
+    allocator_name: String,
     buffer_size: usize,
+    max_channels: usize,
     channel_id: u32,
     capacity: usize,
+    inline_size: usize,
+    overflow_blocks: usize,
+    overflow_block_size: usize,
+    checksum: bool,
+    priority_capacity: usize,
+    backoff: Backoff,
+    stall_spins: u32,
+    broadcast: bool,
+    exact_capacity: bool,
+    overwrite: bool,
+    single_producer: bool,
+    single_consumer: bool,
+    ack_timeout: Duration,
+    create_policy: Option<Policy>,
+    default_timeout: Option<Duration>,
+    timestamps: Timestamps,
+    clock_source: ClockSource,
+    unlink_on_drop: bool,
+    segment: Option<&'static SharedMemoryAllocator>,
 }
 
 impl Default for ChannelBuilder {
     fn default() -> Self {
         Self {
+            allocator_name: DEFAULT_SEGMENT_NAME.to_string(),
             buffer_size: 128 * 1024 * 1024, // 128MB default
-            channel_id: 0,                  // Default channel ID
-            capacity: 1024,                 // Default capacity
+            max_channels: DEFAULT_MAX_CHANNELS,
+            channel_id: 0,  // Default channel ID
+            capacity: 1024, // Default capacity
+            inline_size: MSG_INLINE,
+            overflow_blocks: 0, // No overflow band
+            overflow_block_size: 0,
+            checksum: false,      // Checksums off
+            priority_capacity: 0, // No priority band
+            backoff: Backoff::Spin,
+            stall_spins: DEFAULT_STALL_SPINS,
+            broadcast: false,
+            exact_capacity: false,
+            overwrite: false,
+            single_producer: false,
+            single_consumer: false,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            create_policy: None, // Producers create, consumers attach
+            default_timeout: None,
+            timestamps: Timestamps::Precise,
+            clock_source: ClockSource::Realtime,
+            unlink_on_drop: false,
+            segment: None, // Each handle maps the segment itself
         }
     }
 }
@@ -22,57 +84,396 @@ impl ChannelBuilder {
         Self::default()
     }
 
+    /// Open channels in the allocator whose segment is `/dev/shm/<name>`
+    /// (see `SharedMemoryAllocator::new_named`) instead of the default one.
+    /// Channel IDs are per allocator, so the same ID names unrelated channels
+    /// in two of them.
+    pub fn with_allocator_name(mut self, name: impl Into<String>) -> Self {
+        self.allocator_name = name.into();
+        self
+    }
+
     pub fn with_buffer_size(mut self, size: usize) -> Self {
         self.buffer_size = size;
         self
     }
 
+    /// Length of the channel table when the shared memory segment is created,
+    /// bounding channel IDs. Has no effect when the segment already exists;
+    /// its creator's limit applies. Defaults to `DEFAULT_MAX_CHANNELS`.
+    pub fn with_max_channels(mut self, max_channels: usize) -> Self {
+        self.max_channels = max_channels;
+        self
+    }
+
     pub fn with_channel_id(mut self, channel_id: u32) -> Self {
         self.channel_id = channel_id;
         self
     }
 
     pub fn build_producer(self) -> std::io::Result<Producer> {
-        // Try to attach to existing shared memory first, create if it doesn't exist
-        let allocator = match SharedMemoryAllocator::attach(self.buffer_size) {
-            Ok(alloc) => {
-                // Shared memory exists, attach to it
-                alloc
-            }
-            Err(_) => {
-                // Shared memory doesn't exist, create it
-                SharedMemoryAllocator::new(self.buffer_size)?
-            }
+        self.open_producer()
+    }
+
+    /// Build a producer that spreads messages over every channel in
+    /// `channel_ids`, each opened as `build_producer` would open it.
+    /// The channel ID set with `with_channel_id` is ignored.
+    pub fn build_sharded_producer(
+        mut self,
+        channel_ids: &[u32],
+    ) -> std::io::Result<ShardedProducer> {
+        let mut producers = Vec::with_capacity(channel_ids.len());
+        for &channel_id in channel_ids {
+            self.channel_id = channel_id;
+            producers.push(self.open_producer()?);
+        }
+        ShardedProducer::new(producers)
+    }
+
+    fn open_producer(&self) -> std::io::Result<Producer> {
+        let policy = self.create_policy.unwrap_or(Policy::CreateOrAttach);
+        let (allocator, mut channel) = self.open_channel(policy)?;
+        channel.buffer.set_backoff(self.backoff);
+
+        Ok(Producer::new(allocator, channel, self.channel_id)
+            .with_timestamps(self.timestamps)
+            .with_clock_source(self.clock_source))
+    }
+
+    /// If the built producer or consumer creates the shared memory segment,
+    /// remove its `/dev/shm` file once it is dropped (see
+    /// `SharedMemoryAllocator::with_unlink_on_drop`). Off by default.
+    pub fn with_unlink_on_drop(mut self, enabled: bool) -> Self {
+        self.unlink_on_drop = enabled;
+        self
+    }
+
+    /// Whether `build_producer`, `build_consumer` and `build_subscriber` may
+    /// create the channel. Producers default to `Policy::CreateOrAttach`,
+    /// consumers to `Policy::AttachOnly`.
+    pub fn with_create_policy(mut self, policy: Policy) -> Self {
+        self.create_policy = Some(policy);
+        self
+    }
+
+    /// Open the shared memory segment and this builder's channel as `policy` allows.
+    /// Open handles in `allocator` instead of mapping the segment again for
+    /// each one. Used by the C API, whose allocator lives for the whole
+    /// process; the segment name, size and channel limit are ignored.
+    pub(crate) fn in_segment(mut self, allocator: &'static SharedMemoryAllocator) -> Self {
+        self.segment = Some(allocator);
+        self
+    }
+
+    /// The allocator handles are opened in: a fresh mapping of the segment,
+    /// or the one passed to `in_segment`.
+    fn open_segment(&self, create: bool) -> std::io::Result<Option<SharedMemoryAllocator>> {
+        if self.segment.is_some() {
+            return Ok(None);
+        }
+        match SharedMemoryAllocator::attach_named(&self.allocator_name, self.buffer_size) {
+            Ok(allocator) => Ok(Some(allocator)),
+            // Only a missing segment is created. Any other failure is reported
+            // instead of truncating a segment other processes may be using.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => Ok(Some(
+                SharedMemoryAllocator::new_named(
+                    &self.allocator_name,
+                    self.buffer_size,
+                    self.max_channels,
+                )?
+                .with_unlink_on_drop(self.unlink_on_drop),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn open_channel(
+        &self,
+        policy: Policy,
+    ) -> std::io::Result<(Option<SharedMemoryAllocator>, ChannelPartition)> {
+        // Reject a bad channel shape before touching shared memory
+        let spec = match policy {
+            Policy::AttachOnly => None,
+            Policy::CreateOnly | Policy::CreateOrAttach => Some(self.channel_spec()?),
         };
 
-        // Check if channel already exists, if not create it
-        let channel = match allocator.get_channel(self.channel_id) {
-            Some(existing_channel) => {
-                // Channel exists, reuse it
-                existing_channel
+        let owned = self.open_segment(spec.is_some())?;
+        let allocator = owned.as_ref().or(self.segment).expect("a segment is open");
+
+        let channel = match (allocator.get_channel(self.channel_id), spec) {
+            (Some(_), _) if policy == Policy::CreateOnly => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("Channel {} already exists", self.channel_id),
+                ));
             }
-            None => {
-                // Channel doesn't exist, create a new one
-                allocator.create_channel(self.capacity, Some(self.channel_id))?
+            (Some(existing_channel), _) => existing_channel,
+            (None, Some(spec)) => allocator.create_channel_with(spec, Some(self.channel_id))?,
+            (None, None) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Channel {} not found", self.channel_id),
+                ));
             }
         };
-
-        Ok(Producer::new(allocator, channel, self.channel_id))
+        Ok((owned, channel))
     }
 
+    /// Number of slots in a newly created channel; must be a power of two
+    /// unless `with_exact_capacity` is set.
     pub fn with_capacity(mut self, capacity: usize) -> Self {
         self.capacity = capacity;
         self
     }
 
-    pub fn build_consumer(self) -> std::io::Result<Consumer> {
-        let allocator = SharedMemoryAllocator::attach(self.buffer_size)?;
-        let channel = allocator.get_channel(self.channel_id).ok_or_else(|| {
+    /// Payload bytes each slot of a newly created channel holds inline,
+    /// `MSG_INLINE` by default (see `ChannelSpec::with_inline_size`).
+    /// Has no effect when the producer attaches to an existing channel.
+    pub fn with_inline_size(mut self, bytes: usize) -> Self {
+        self.inline_size = bytes;
+        self
+    }
+
+    /// Payload bytes each slot of a newly created channel holds inline, so
+    /// every slot can carry a message of up to `bytes` at once. The same
+    /// setting as `with_inline_size`. Has no effect when the producer attaches
+    /// to an existing channel.
+    pub fn with_slot_size(self, bytes: usize) -> Self {
+        self.with_inline_size(bytes)
+    }
+
+    /// The shape of the channel `build_producer` creates, validated.
+    fn channel_spec(&self) -> std::io::Result<ChannelSpec> {
+        let spec = ChannelSpec::new(self.capacity)
+            .with_inline_size(self.inline_size)
+            .with_overflow(self.overflow_blocks, self.overflow_block_size)
+            .with_checksum(self.checksum)
+            .with_priority(self.priority_capacity)
+            .with_broadcast(self.broadcast)
+            .with_exact_capacity(self.exact_capacity)
+            .with_overwrite(self.overwrite)
+            .with_single_producer(self.single_producer)
+            .with_single_consumer(self.single_consumer);
+        spec.validate().map_err(|e| {
             std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("Channel {} not found", self.channel_id),
+                e.kind(),
+                format!("Invalid channel {}: {}", self.channel_id, e),
             )
         })?;
-        Ok(Consumer::new(allocator, channel, self.channel_id))
+        Ok(spec)
+    }
+
+    /// Give a newly created channel an overflow band of `blocks` blocks of
+    /// `block_size` bytes, so payloads larger than the inline size can be sent.
+    /// Has no effect when the producer attaches to an existing channel.
+    pub fn with_overflow(mut self, blocks: usize, block_size: usize) -> Self {
+        self.overflow_blocks = blocks;
+        self.overflow_block_size = block_size;
+        self
+    }
+
+    /// Stamp every payload on a newly created channel with a CRC32C that consumers
+    /// verify, failing the receive with `InvalidData` on mismatch.
+    /// Has no effect when the producer attaches to an existing channel.
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Give a newly created channel a high-priority ring of `capacity` slots
+    /// for `Producer::send_priority`. Has no effect when the producer attaches
+    /// to an existing channel.
+    pub fn with_priority_capacity(mut self, capacity: usize) -> Self {
+        self.priority_capacity = capacity;
+        self
+    }
+
+    /// Let a newly created channel have any non-zero capacity instead of a power
+    /// of two, at the cost of a modulo per slot access. Has no effect when the
+    /// producer attaches to an existing channel.
+    pub fn with_exact_capacity(mut self, enabled: bool) -> Self {
+        self.exact_capacity = enabled;
+        self
+    }
+
+    /// Make a newly created channel drop its oldest unread message when a send
+    /// finds it full, instead of failing with `WouldBlock`; see
+    /// `ChannelSpec::with_overwrite`. Has no effect when the producer attaches
+    /// to an existing channel.
+    pub fn with_overwrite(mut self, enabled: bool) -> Self {
+        self.overwrite = enabled;
+        self
+    }
+
+    /// Create the channel for a single producer, whose sends skip the
+    /// compare-and-swap on the tail; see `ChannelSpec::with_single_producer`.
+    /// Has no effect when the producer attaches to an existing channel.
+    pub fn with_single_producer(mut self, enabled: bool) -> Self {
+        self.single_producer = enabled;
+        self
+    }
+
+    /// Create the channel for a single consumer: `build_consumer` fails with
+    /// `AlreadyExists` while another consumer is attached; see
+    /// `ChannelSpec::with_single_consumer`. Has no effect when attaching to an
+    /// existing channel.
+    pub fn with_single_consumer(mut self, enabled: bool) -> Self {
+        self.single_consumer = enabled;
+        self
+    }
+
+    /// How the built producer or consumer waits when a slot is contended by
+    /// another thread or process. Defaults to `Backoff::Spin`.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// How many retries the built consumer spends on a message whose producer
+    /// has not finished writing it before reporting the channel empty for now
+    /// (see `RingBuffer::set_stall_spins`). Defaults to `DEFAULT_STALL_SPINS`.
+    pub fn with_stall_spins(mut self, spins: u32) -> Self {
+        self.stall_spins = spins;
+        self
+    }
+
+    /// Make a newly created channel a broadcast channel, where every subscriber
+    /// receives every message. Has no effect when the producer attaches to an
+    /// existing channel.
+    pub fn with_broadcast(mut self, enabled: bool) -> Self {
+        self.broadcast = enabled;
+        self
+    }
+
+    /// How long a message received with `Consumer::receive_ack` may go
+    /// unacknowledged before it is redelivered. Defaults to 30 seconds.
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// How long `Consumer::receive_default` waits for a message on the built
+    /// consumer. Without it `receive_default` does not wait at all.
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// How the built producer stamps `MessageMeta::timestamp_ns`. Defaults to
+    /// `Timestamps::Precise`, one clock read per message.
+    pub fn with_timestamps(mut self, timestamps: Timestamps) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Which clock the built producer stamps `MessageMeta::timestamp_ns` from.
+    /// Defaults to `ClockSource::Realtime`; `ClockSource::Monotonic` keeps
+    /// latency measurements sane across wall-clock steps.
+    pub fn with_clock_source(mut self, clock: ClockSource) -> Self {
+        self.clock_source = clock;
+        self
+    }
+
+    pub fn build_consumer(self) -> std::io::Result<Consumer> {
+        let policy = self.create_policy.unwrap_or(Policy::AttachOnly);
+        let (allocator, mut channel) = self.open_channel(policy)?;
+        if channel.buffer().is_broadcast() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Channel {} is a broadcast channel; use build_subscriber",
+                    self.channel_id
+                ),
+            ));
+        }
+        channel.buffer.set_backoff(self.backoff);
+        channel.buffer.set_stall_spins(self.stall_spins);
+        let consumer = Consumer::new(allocator, channel, self.channel_id)
+            .with_ack_timeout(self.ack_timeout)
+            .with_default_timeout(self.default_timeout);
+        // Register first and count after, so two racing attaches cannot both
+        // see zero; at worst both fail and the caller retries.
+        let buffer = consumer.channel.buffer();
+        if buffer.is_single_consumer() && buffer.attached_count(true) > 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "Channel {} is a single-consumer channel and already has a consumer",
+                    self.channel_id
+                ),
+            ));
+        }
+        Ok(consumer)
+    }
+
+    /// Build a consumer subscribed to a broadcast channel. It gets its own read
+    /// cursor and receives every message sent after it subscribed; the slot is
+    /// given back when the consumer is dropped.
+    pub fn build_subscriber(self) -> std::io::Result<Consumer> {
+        let policy = self.create_policy.unwrap_or(Policy::AttachOnly);
+        let (allocator, mut channel) = self.open_channel(policy)?;
+        channel.buffer.subscribe()?;
+        channel.buffer.set_backoff(self.backoff);
+        Ok(Consumer::new(allocator, channel, self.channel_id)
+            .with_default_timeout(self.default_timeout))
+    }
+
+    /// Build an observer that shows the channel's messages as they are
+    /// published, without taking them from its consumers or holding up its
+    /// producers; see [`TailConsumer`].
+    pub fn build_tail_consumer(self) -> std::io::Result<TailConsumer> {
+        let policy = self.create_policy.unwrap_or(Policy::AttachOnly);
+        let (allocator, channel) = self.open_channel(policy)?;
+        Ok(TailConsumer::new(allocator, channel, self.channel_id))
+    }
+
+    /// Build a consumer that receives from every channel in `channel_ids`.
+    /// The channel ID set with `with_channel_id` is ignored.
+    pub fn build_multi_consumer(self, channel_ids: &[u32]) -> std::io::Result<MultiConsumer> {
+        if channel_ids.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "MultiConsumer needs at least one channel",
+            ));
+        }
+
+        let owned = self.open_segment(false)?;
+        let allocator = owned.as_ref().or(self.segment).expect("a segment is open");
+        let mut channels = Vec::with_capacity(channel_ids.len());
+        for &channel_id in channel_ids {
+            let mut channel = allocator.get_channel(channel_id).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Channel {} not found", channel_id),
+                )
+            })?;
+            if channel.buffer().is_broadcast() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Channel {} is a broadcast channel; use build_subscriber",
+                        channel_id
+                    ),
+                ));
+            }
+            channel.buffer.set_backoff(self.backoff);
+            channel.buffer.set_stall_spins(self.stall_spins);
+            channels.push(channel);
+        }
+        // Registered like a consumer on each channel, then counted as in
+        // build_consumer
+        let multi = MultiConsumer::new(owned, channels);
+        if let Some(channel) = multi.channels().iter().find(|channel| {
+            channel.buffer().is_single_consumer() && channel.buffer().attached_count(true) > 1
+        }) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "Channel {} is a single-consumer channel and already has a consumer",
+                    channel.id()
+                ),
+            ));
+        }
+        Ok(multi)
     }
 }
@@ -0,0 +1,128 @@
+// Clocks for stamping `MessageMeta::timestamp_ns`.
+//
+// Reading the wall clock costs a vDSO call (or a real syscall on some VMs) per
+// message. Producers that only need a rough send time can use a coarse clock that
+// a background thread refreshes every millisecond, or skip timestamps entirely.
+//
+// The wall clock jumps when NTP steps it. Producers whose consumers measure
+// latency can stamp from the monotonic clock instead, which every process on
+// the machine reads with the same epoch (boot).
+
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_MONOTONIC};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the coarse clock is refreshed.
+const COARSE_TICK: Duration = Duration::from_millis(1);
+
+/// How a producer fills in `MessageMeta::timestamp_ns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timestamps {
+    /// Read the wall clock for every message.
+    #[default]
+    Precise,
+    /// Read a cached wall clock refreshed every millisecond by a background
+    /// thread; cheap, but up to about 1ms behind.
+    Coarse,
+    /// Leave `timestamp_ns` at 0.
+    Off,
+}
+
+impl Timestamps {
+    /// The timestamp for a message sent now, in nanoseconds on `clock`.
+    pub(crate) fn now_ns(self, clock: ClockSource) -> u64 {
+        match (self, clock) {
+            (Timestamps::Precise, clock) => clock.now_ns(),
+            (Timestamps::Coarse, ClockSource::Realtime) => coarse_now_ns(&COARSE_NOW),
+            (Timestamps::Coarse, ClockSource::Monotonic) => coarse_now_ns(&COARSE_MONOTONIC),
+            (Timestamps::Off, _) => 0,
+        }
+    }
+}
+
+/// Which clock a producer reads for `MessageMeta::timestamp_ns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockSource {
+    /// The wall clock, in nanoseconds since the Unix epoch. Comparable with
+    /// timestamps from other machines, but jumps when the clock is stepped.
+    #[default]
+    Realtime,
+    /// `CLOCK_MONOTONIC`, in nanoseconds since boot. Never goes backwards and
+    /// is shared by every process on the machine, so consumers can subtract it
+    /// from their own reading. Messages stamped from it carry `FLAG_MONOTONIC`.
+    Monotonic,
+}
+
+impl ClockSource {
+    /// The clock `meta.timestamp_ns` was read from.
+    pub fn of(meta: &MessageMeta) -> Self {
+        if meta.flags & FLAG_MONOTONIC != 0 {
+            ClockSource::Monotonic
+        } else {
+            ClockSource::Realtime
+        }
+    }
+
+    /// The current time on this clock, in nanoseconds.
+    pub fn now_ns(self) -> u64 {
+        match self {
+            ClockSource::Realtime => precise_now_ns(),
+            ClockSource::Monotonic => monotonic_now_ns(),
+        }
+    }
+}
+
+pub(crate) fn precise_now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+#[cfg(unix)]
+fn monotonic_now_ns() -> u64 {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Cannot fail for CLOCK_MONOTONIC with a valid pointer
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    now.tv_sec as u64 * 1_000_000_000 + now.tv_nsec as u64
+}
+
+/// Without `CLOCK_MONOTONIC` the epoch is this process's first reading, so
+/// timestamps are only comparable within the process.
+#[cfg(not(unix))]
+fn monotonic_now_ns() -> u64 {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    EPOCH
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_nanos() as u64
+}
+
+static COARSE_NOW: AtomicU64 = AtomicU64::new(0);
+static COARSE_MONOTONIC: AtomicU64 = AtomicU64::new(0);
+static COARSE_START: Once = Once::new();
+
+/// A cached clock (`COARSE_NOW` or `COARSE_MONOTONIC`), starting the refresh
+/// thread on first use.
+fn coarse_now_ns(cached: &AtomicU64) -> u64 {
+    COARSE_START.call_once(|| {
+        refresh_coarse();
+        std::thread::Builder::new()
+            .name("dmxp-coarse-clock".into())
+            .spawn(|| loop {
+                std::thread::sleep(COARSE_TICK);
+                refresh_coarse();
+            })
+            .expect("failed to spawn the coarse clock thread");
+    });
+    cached.load(Ordering::Relaxed)
+}
+
+fn refresh_coarse() {
+    COARSE_NOW.store(precise_now_ns(), Ordering::Relaxed);
+    COARSE_MONOTONIC.store(monotonic_now_ns(), Ordering::Relaxed);
+}
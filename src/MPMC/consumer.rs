@@ -1,44 +1,214 @@
 // src/MPMC/consumer.rs
 
-use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
-use std::sync::atomic::{AtomicBool, Ordering};
+use super::clock::ClockSource;
+use super::latency::{LatencyHistogram, LatencySnapshot};
+use super::producer::Producer;
+use super::slot_io::SlotReader;
+use crate::Core::process::process_exists;
+use crate::MPMC::Buffer::{AckToken, MessageRef};
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_COMPRESSED};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often a blocked receive re-checks that the producer process still exists.
+pub(crate) const LIVENESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Spin iterations `receive_adaptive` makes between reads of the clock.
+const SPINS_PER_CLOCK_READ: u32 = 64;
+
+/// How long a message received with `receive_ack` may stay unacknowledged
+/// before it is redelivered, unless the builder sets another timeout.
+pub(crate) const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most an LZ4 block can expand: each length byte of a match adds at most 255
+/// bytes of output.
+#[cfg(feature = "lz4")]
+const LZ4_MAX_RATIO: usize = 255;
+
+/// Undo `Producer::send_compressed`: LZ4 block prefixed with the original size.
+/// The prefix is checked against what the block could possibly hold before
+/// anything is allocated for it, so a corrupt one cannot ask for gigabytes.
+#[cfg(feature = "lz4")]
+fn decompress(meta: &MessageMeta, payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let invalid = |what: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Failed to decompress message_id {}: {}",
+                meta.message_id, what
+            ),
+        )
+    };
+    let Some((prefix, block)) = payload.split_first_chunk::<4>() else {
+        return Err(invalid("missing size prefix".to_string()));
+    };
+    let size = u32::from_le_bytes(*prefix) as usize;
+    if size > block.len().saturating_mul(LZ4_MAX_RATIO) {
+        return Err(invalid(format!(
+            "size prefix of {} bytes for a {}-byte block",
+            size,
+            block.len()
+        )));
+    }
+    lz4_flex::decompress_size_prepended(payload).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(not(feature = "lz4"))]
+fn decompress(meta: &MessageMeta, _payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!(
+            "message_id {} is LZ4-compressed; enable the `lz4` feature to receive it",
+            meta.message_id
+        ),
+    ))
+}
 
 /// A consumer for receiving messages from a shared memory channel.
 /// The consumer is responsible for reading messages from the ring buffer
 /// and managing the lifecycle of the shared memory region.
 pub struct Consumer {
-    _allocator: crate::Core::alloc::SharedMemoryAllocator,
-    channel: crate::Core::alloc::ChannelPartition,
+    // None when built in an allocator that lives for the whole process
+    _allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
+    pub(super) channel: crate::Core::alloc::ChannelPartition,
     channel_id: u32,
     producer_alive: Arc<AtomicBool>,
-    last_message_time: std::sync::atomic::AtomicI64,
+    last_heartbeat: AtomicU64,
+    created: Instant,
+    next_probe_ms: AtomicU64,
+    ack_timeout: Duration,
+    default_timeout: Option<Duration>,
+    last_message_id: Mutex<Option<u64>>,
+    latency: Option<Box<LatencyHistogram>>,
+    /// This consumer's entry in the channel's registry of attached handles
+    attached: Option<usize>,
+    /// Messages `receive_filtered` dropped for not matching
+    filtered: AtomicU64,
+    /// Why the last `receive_batch_blocking` batch or `drain` ended early,
+    /// returned by the next call to either
+    batch_error: Mutex<Option<std::io::Error>>,
+    #[cfg(feature = "tokio")]
+    pub(super) async_waiters: Arc<std::sync::atomic::AtomicUsize>,
+    /// The eventfd behind `readiness_fd`, created on first use
+    #[cfg(target_os = "linux")]
+    pub(super) readiness: Mutex<Option<super::readiness::Readiness>>,
 }
 
 impl Consumer {
     pub(crate) fn new(
-        allocator: crate::Core::alloc::SharedMemoryAllocator,
+        allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
         channel: crate::Core::alloc::ChannelPartition,
         channel_id: u32,
     ) -> Self {
+        let last_heartbeat = AtomicU64::new(channel.buffer().heartbeat());
+        let attached = channel.buffer().register_attached(true);
         Self {
             _allocator: allocator,
             channel,
             channel_id,
             producer_alive: Arc::new(AtomicBool::new(true)),
-            last_message_time: std::sync::atomic::AtomicI64::new(0),
+            last_heartbeat,
+            created: Instant::now(),
+            next_probe_ms: AtomicU64::new(0),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            default_timeout: None,
+            last_message_id: Mutex::new(None),
+            latency: None,
+            attached,
+            filtered: AtomicU64::new(0),
+            batch_error: Mutex::new(None),
+            #[cfg(feature = "tokio")]
+            async_waiters: Arc::default(),
+            #[cfg(target_os = "linux")]
+            readiness: Mutex::new(None),
         }
     }
 
-    /// Updates the last message timestamp to now
-    fn update_last_message_time(&self) {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs() as i64;
-        self.last_message_time.store(now, Ordering::Release);
+    pub(crate) fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    pub(crate) fn with_default_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// Measure the end-to-end latency of every message this consumer receives,
+    /// from the producer's `timestamp_ns` to the receive, for
+    /// [`latency_snapshot`](Self::latency_snapshot).
+    ///
+    /// Costs a clock read per message. Messages sent without a timestamp
+    /// (see `ChannelBuilder::with_timestamps`) are not measured.
+    pub fn with_latency_tracking(mut self) -> Self {
+        self.latency = Some(Box::new(LatencyHistogram::new()));
+        self
+    }
+
+    /// Latency percentiles of the messages received so far. All zero unless
+    /// the consumer was set up `with_latency_tracking`.
+    pub fn latency_snapshot(&self) -> LatencySnapshot {
+        self.latency
+            .as_ref()
+            .map_or_else(LatencySnapshot::default, |h| h.snapshot())
+    }
+
+    /// Feed a received message to the latency histogram, if tracking is on.
+    fn track_latency(&self, meta: &MessageMeta) {
+        if let Some(histogram) = &self.latency {
+            histogram.record(meta.timestamp_ns, ClockSource::of(meta).now_ns());
+        }
+    }
+
+    /// Rejects a payload whose checksum does not match the one its producer recorded.
+    /// The message has already been consumed; it is reported, not redelivered.
+    fn verify_payload(&self, meta: &MessageMeta, payload: &[u8]) -> std::io::Result<()> {
+        if self.channel.buffer().verify_checksum(meta, payload) {
+            return Ok(());
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Payload checksum mismatch on channel {} (message_id {})",
+                self.channel_id, meta.message_id
+            ),
+        ))
+    }
+
+    /// Turns a received slot's contents into what the producer sent: verifies the
+    /// checksum, then decompresses the payload if it was sent compressed.
+    fn decode(
+        &self,
+        meta: MessageMeta,
+        payload: Vec<u8>,
+    ) -> std::io::Result<(MessageMeta, Vec<u8>)> {
+        self.track_latency(&meta);
+        self.verify_payload(&meta, &payload)?;
+        Self::decompressed(meta, payload)
+    }
+
+    /// `meta` and `payload` with a `FLAG_COMPRESSED` payload decompressed and
+    /// `payload_len` set to its original length; anything else is returned as is.
+    fn decompressed(
+        mut meta: MessageMeta,
+        payload: Vec<u8>,
+    ) -> std::io::Result<(MessageMeta, Vec<u8>)> {
+        if meta.flags & FLAG_COMPRESSED == 0 {
+            return Ok((meta, payload));
+        }
+        let payload = decompress(&meta, &payload)?;
+        meta.payload_len = payload.len() as u32;
+        Ok((meta, payload))
+    }
+
+    /// Subscribes to the broadcast channel `channel_id` in the default shared
+    /// memory region. Shorthand for `ChannelBuilder::build_subscriber`.
+    pub fn subscribe_broadcast(channel_id: u32) -> std::io::Result<Consumer> {
+        crate::MPMC::ChannelBuilder::new()
+            .with_channel_id(channel_id)
+            .build_subscriber()
     }
 
     /// Receives a message from the channel if one is available.
@@ -55,25 +225,361 @@ impl Consumer {
     /// Receives a message and metadata from the channel if one is available.
     pub fn receive_with_meta(&self) -> std::io::Result<Option<(MessageMeta, Vec<u8>)>> {
         let buffer = self.channel.buffer();
+        // Sampled first: once closed, every message ever sent is already visible
+        let closed = buffer.is_closed();
 
         match buffer.dequeue() {
-            Some((meta, payload)) => {
-                self.update_last_message_time();
-                Ok(Some((meta, payload)))
+            Some((meta, payload)) => self.decode(meta, payload).map(Some),
+            None => {
+                self.check_producer(closed)?;
+                Ok(None)
             }
+        }
+    }
+
+    /// Returns the next message and its metadata without consuming it.
+    ///
+    /// The read cursor does not move, so a later receive returns the same
+    /// message unless another consumer on the channel takes it first. The
+    /// checksum is verified when the message is actually received.
+    ///
+    /// # Returns
+    /// * `Ok(Some((meta, data)))` if a message is waiting
+    /// * `Ok(None)` if no message is available
+    /// * `Err(io::Error)` if the producer has terminated
+    pub fn peek_with_meta(&self) -> std::io::Result<Option<(MessageMeta, Vec<u8>)>> {
+        let buffer = self.channel.buffer();
+        let closed = buffer.is_closed();
+
+        match buffer.peek() {
+            Some((meta, payload)) => Self::decompressed(meta, payload).map(Some),
             None => {
-                // Check if producer is still alive
-                if !self.is_producer_alive() {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::BrokenPipe,
-                        "Producer has terminated",
-                    ));
+                self.check_producer(closed)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Receives a message along with how many message ids were skipped since the
+    /// previous `receive_checked` on this consumer.
+    ///
+    /// Producers number messages consecutively from a counter shared by the
+    /// channel, but an id is burned whenever a send or batch fails, so a nonzero
+    /// count means messages were dropped or never made it into the ring. The
+    /// first message reports 0, ids wrap around at `u64::MAX`, and an id at or
+    /// behind the last one reports 0 and restarts tracking from there.
+    ///
+    /// Concurrent producers may enqueue slightly out of id order, so the count is
+    /// only exact on channels with a single producer and no priority band, and
+    /// only when every message is received through this method.
+    ///
+    /// # Returns
+    /// * `Ok(Some((meta, data, missing)))` if a message was received
+    /// * `Ok(None)` if no message is available
+    /// * `Err(io::Error)` if the producer has terminated or an error occurred
+    pub fn receive_checked(&self) -> std::io::Result<Option<(MessageMeta, Vec<u8>, u64)>> {
+        // Held across the receive so concurrent callers see ids in ring order
+        let mut last = self.last_message_id.lock();
+        let Some((meta, payload)) = self.receive_with_meta()? else {
+            return Ok(None);
+        };
+
+        let missing = match *last {
+            Some(prev) => {
+                let delta = meta.message_id.wrapping_sub(prev) as i64;
+                if delta > 0 {
+                    delta as u64 - 1
+                } else {
+                    0
                 }
+            }
+            None => 0,
+        };
+        *last = Some(meta.message_id);
+        Ok(Some((meta, payload, missing)))
+    }
+
+    /// Receives a message without copying its payload out of shared memory.
+    ///
+    /// The returned [`MessageRef`] derefs to the payload bytes inside the slot and
+    /// releases the slot back to producers when dropped. Holding it for long
+    /// periods will eventually stall producers; see [`MessageRef`] for details.
+    /// Compressed payloads are returned as stored (`FLAG_COMPRESSED` set).
+    ///
+    /// # Returns
+    /// * `Ok(Some(message))` if a message was received
+    /// * `Ok(None)` if no message is available
+    /// * `Err(io::Error)` if the producer has terminated or an error occurred,
+    ///   or `Unsupported` on a broadcast subscription
+    pub fn receive_ref(&self) -> std::io::Result<Option<MessageRef<'_>>> {
+        if self.channel.buffer().subscriber.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Broadcast subscriptions cannot borrow slots; use receive",
+            ));
+        }
+        let closed = self.channel.buffer().is_closed();
+        match self.channel.buffer().dequeue_ref() {
+            Some(message) => {
+                self.track_latency(message.meta());
+                self.verify_payload(message.meta(), &message)?;
+                Ok(Some(message))
+            }
+            None => {
+                self.check_producer(closed)?;
                 Ok(None)
             }
         }
     }
 
+    /// Receives a message as an `io::Read` over its slot, for code that parses
+    /// from a reader. Nothing is copied until it is read.
+    ///
+    /// Like [`receive_ref`](Self::receive_ref), which it builds on, the slot is
+    /// only released when the reader is dropped, compressed payloads are read
+    /// as stored, and broadcast subscriptions fail with `Unsupported`.
+    ///
+    /// # Returns
+    /// * `Ok(Some(reader))` if a message was received
+    /// * `Ok(None)` if no message is available
+    /// * `Err(io::Error)` as `receive_ref` fails
+    pub fn reader(&self) -> std::io::Result<Option<SlotReader<'_>>> {
+        Ok(self.receive_ref()?.map(SlotReader::new))
+    }
+
+    /// Receives a message into `buf` instead of a new `Vec`, for consumers that
+    /// reuse one buffer for every message.
+    ///
+    /// The payload is copied straight out of the slot, decompressed first if it
+    /// was sent compressed. Broadcast subscriptions and compressed payloads go
+    /// through a temporary copy.
+    ///
+    /// # Returns
+    /// * `Ok(Some((meta, len)))` with the payload in `buf[..len]`
+    /// * `Ok(None)` if no message is available
+    /// * `Err` with `InvalidInput` naming the required length if `buf` is too
+    ///   small; like `dmxp_consumer_receive_ext`, the message is dropped
+    /// * Otherwise the errors of `receive_with_meta`
+    pub fn receive_into(&self, buf: &mut [u8]) -> std::io::Result<Option<(MessageMeta, usize)>> {
+        if self.channel.buffer().subscriber.is_some() {
+            return match self.receive_with_meta()? {
+                Some((meta, payload)) => Self::copy_into(meta, &payload, buf).map(Some),
+                None => Ok(None),
+            };
+        }
+        let Some(message) = self.receive_ref()? else {
+            return Ok(None);
+        };
+        let mut meta = *message.meta();
+        if meta.flags & FLAG_COMPRESSED == 0 {
+            return Self::copy_into(meta, &message, buf).map(Some);
+        }
+        let payload = decompress(&meta, &message)?;
+        drop(message);
+        meta.payload_len = payload.len() as u32;
+        Self::copy_into(meta, &payload, buf).map(Some)
+    }
+
+    fn copy_into(
+        meta: MessageMeta,
+        payload: &[u8],
+        buf: &mut [u8],
+    ) -> std::io::Result<(MessageMeta, usize)> {
+        let Some(out) = buf.get_mut(..payload.len()) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Buffer too small ({} < {}); the message was dropped",
+                    buf.len(),
+                    payload.len()
+                ),
+            ));
+        };
+        out.copy_from_slice(payload);
+        Ok((meta, payload.len()))
+    }
+
+    /// Receives the next message whose metadata matches `predicate`, for a
+    /// consumer that only handles some `message_type`s of a shared channel.
+    ///
+    /// Messages that do not match are consumed and dropped, not requeued:
+    /// putting them back would reorder the channel, and two consumers filtering
+    /// for each other's messages could pass them back and forth forever. Each
+    /// one dropped is counted in [`filtered_count`](Self::filtered_count). The
+    /// predicate sees the metadata as stored, so a compressed message has
+    /// `FLAG_COMPRESSED` set and its compressed `payload_len`; non-matching
+    /// payloads are never copied out of their slot (except on broadcast
+    /// subscriptions) nor decompressed.
+    ///
+    /// # Returns
+    /// * `Ok(Some((meta, data)))` with the first matching message
+    /// * `Ok(None)` if the channel ran out of messages before one matched
+    /// * Otherwise the errors of `receive_with_meta`, including a checksum
+    ///   mismatch on a message that would have been dropped
+    pub fn receive_filtered(
+        &self,
+        predicate: impl Fn(&MessageMeta) -> bool,
+    ) -> std::io::Result<Option<(MessageMeta, Vec<u8>)>> {
+        if self.channel.buffer().subscriber.is_some() {
+            while let Some((meta, payload)) = self.receive_with_meta()? {
+                if predicate(&meta) {
+                    return Ok(Some((meta, payload)));
+                }
+                self.filtered.fetch_add(1, Ordering::Relaxed);
+            }
+            return Ok(None);
+        }
+        while let Some(message) = self.receive_ref()? {
+            if predicate(message.meta()) {
+                return Self::decompressed(*message.meta(), message.to_vec()).map(Some);
+            }
+            self.filtered.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(None)
+    }
+
+    /// Messages [`receive_filtered`](Self::receive_filtered) has dropped on
+    /// this consumer for not matching.
+    pub fn filtered_count(&self) -> u64 {
+        self.filtered.load(Ordering::Relaxed)
+    }
+
+    /// Moves the next message of this channel to `producer`'s channel, copying
+    /// the payload from one slot straight into the other without a heap buffer.
+    ///
+    /// The metadata travels with the message (type, flags, timestamp, sender,
+    /// TTL); only `message_id` and `channel_id` are assigned by the destination.
+    /// Compressed payloads are forwarded still compressed.
+    ///
+    /// The destination is checked before a message is taken. If it fills up
+    /// between the check and the enqueue, the call waits for space like
+    /// `send_blocking` rather than lose the message. Only when another
+    /// consumer of this channel takes the checked message first can the one
+    /// taken instead still be refused.
+    ///
+    /// # Returns
+    /// * `Ok(true)` if a message was forwarded
+    /// * `Ok(false)` if this channel had no message
+    /// * `Err` with `WouldBlock` if the destination is full, or the error its
+    ///   `send` would fail with (sealed, poisoned, consumer gone, payload too
+    ///   large); no message is taken
+    /// * Otherwise the errors of `receive_ref`
+    pub fn forward_to(&self, producer: &Producer) -> std::io::Result<bool> {
+        if !producer.has_space() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                format!("Channel {} is full", producer.channel_id()),
+            ));
+        }
+        // Anything up to this channel's largest payload fits the destination
+        // unless its limit is lower; only then is the next message's size needed
+        let buffer = self.channel.buffer();
+        let len = if buffer.max_payload() > producer.max_message_size() {
+            match buffer.peek() {
+                Some((meta, _)) => meta.payload_len as usize,
+                None => 0,
+            }
+        } else {
+            0
+        };
+        producer.check_forward(len)?;
+        match self.receive_ref()? {
+            Some(message) => {
+                producer.forward(*message.meta(), &message)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Receives a message that stays in the channel until it is acknowledged.
+    ///
+    /// The slot is only handed back to producers by [`AckToken::ack`]. If the
+    /// token is not acked within the consumer's ack timeout (see
+    /// `ChannelBuilder::with_ack_timeout`), for instance because this process
+    /// crashed, a later `receive_ack` by any consumer of the channel returns the
+    /// message again with [`AckToken::redelivered`] set. Expired messages are
+    /// redelivered before new ones are received.
+    ///
+    /// A message that fails checksum verification or decompression is released
+    /// rather than redelivered, and the receive fails.
+    ///
+    /// # Returns
+    /// * `Ok(Some((meta, data, token)))` if a message was received
+    /// * `Ok(None)` if no message is available
+    /// * `Err(io::Error)` if the producer has terminated or an error occurred,
+    ///   or `Unsupported` on a broadcast subscription
+    pub fn receive_ack(&self) -> std::io::Result<Option<(MessageMeta, Vec<u8>, AckToken<'_>)>> {
+        let buffer = self.channel.buffer();
+        if buffer.subscriber.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Broadcast subscriptions cannot hold messages for acknowledgment",
+            ));
+        }
+
+        let now_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let deadline_ns = now_ns + self.ack_timeout.as_nanos() as u64;
+
+        let closed = buffer.is_closed();
+        match buffer.claim_leased(now_ns, deadline_ns) {
+            Some((ring, head, slot_ptr, redelivered)) => {
+                let (meta, payload) =
+                    unsafe { ((*slot_ptr).meta, ring.payload_of(slot_ptr).to_vec()) };
+                let token = AckToken::new(ring, head, slot_ptr, deadline_ns, redelivered);
+                match self.decode(meta, payload) {
+                    Ok((meta, payload)) => Ok(Some((meta, payload, token))),
+                    Err(e) => {
+                        let _ = token.ack();
+                        Err(e)
+                    }
+                }
+            }
+            None => {
+                self.check_producer(closed)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Consumes everything that was in the channel when the call started.
+    ///
+    /// Messages enqueued after the call begins are left for later receives, so
+    /// this terminates even while producers keep sending. Compressed payloads
+    /// are decompressed as by `receive_with_meta`; checksums are not verified
+    /// here.
+    ///
+    /// A message that fails decompression is dropped and ends the drain
+    /// early, like a batch of `receive_batch_blocking`: the messages before it
+    /// are returned, the ones after it stay in the channel, and the next call
+    /// to either fails with its error.
+    pub fn drain(&self) -> std::io::Result<Vec<(MessageMeta, Vec<u8>)>> {
+        if let Some(e) = self.batch_error.lock().take() {
+            return Err(e);
+        }
+        let mut messages = Vec::new();
+        let mut failed = None;
+        self.channel.buffer().drain_while(&mut |meta, payload| {
+            self.track_latency(&meta);
+            match Self::decompressed(meta, payload) {
+                Ok(message) => messages.push(message),
+                Err(e) => failed = Some(e),
+            }
+            failed.is_none()
+        });
+        match failed {
+            Some(e) if messages.is_empty() => Err(e),
+            Some(e) => {
+                *self.batch_error.lock() = Some(e);
+                Ok(messages)
+            }
+            None => Ok(messages),
+        }
+    }
+
     /// Receives a message, blocking until one is available or the producer terminates.
     pub fn receive_blocking(&self) -> std::io::Result<Vec<u8>> {
         self.receive_blocking_with_meta()
@@ -81,47 +587,166 @@ impl Consumer {
     }
 
     /// Receives a message and metadata, blocking until one is available.
+    ///
+    /// After the producers close the channel, the messages still buffered are
+    /// returned first; the receive after the last one fails with `BrokenPipe`.
     pub fn receive_blocking_with_meta(&self) -> std::io::Result<(MessageMeta, Vec<u8>)> {
         let buffer = self.channel.buffer();
         loop {
+            let closed = buffer.is_closed();
             match buffer.dequeue() {
-                Some((meta, payload)) => {
-                    self.update_last_message_time();
-                    return Ok((meta, payload));
-                }
+                Some((meta, payload)) => return self.decode(meta, payload),
                 None => {
-                    if !self.is_producer_alive() {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::BrokenPipe,
-                            "Producer has terminated",
-                        ));
-                    }
-                    // Wait for signal
-                    buffer.wait_for_data();
+                    self.check_producer(closed)?;
+                    // Wait for signal, waking periodically to notice a dead producer
+                    buffer.wait_for_data_timeout(LIVENESS_INTERVAL);
+                }
+            }
+        }
+    }
+
+    /// Receives a message, busy-spinning for up to `spin` before parking like
+    /// `receive_blocking`.
+    ///
+    /// Under steady traffic the next message usually arrives within the spin
+    /// window and is picked up without a futex round trip; an idle channel
+    /// costs one core for `spin` and then nothing. The spin only watches the
+    /// ring, reading the clock every few dozen iterations.
+    pub fn receive_adaptive(&self, spin: Duration) -> std::io::Result<Vec<u8>> {
+        self.receive_adaptive_with_meta(spin)
+            .map(|(_, payload)| payload)
+    }
+
+    /// [`receive_adaptive`](Self::receive_adaptive) with the message's metadata.
+    /// Fails like `receive_blocking_with_meta`.
+    pub fn receive_adaptive_with_meta(
+        &self,
+        spin: Duration,
+    ) -> std::io::Result<(MessageMeta, Vec<u8>)> {
+        let buffer = self.channel.buffer();
+        let started = Instant::now();
+        'spin: while started.elapsed() < spin {
+            for _ in 0..SPINS_PER_CLOCK_READ {
+                if buffer.has_data() || buffer.is_closed() {
+                    break 'spin;
+                }
+                std::hint::spin_loop();
+            }
+        }
+        self.receive_blocking_with_meta()
+    }
+
+    /// Receives up to `max` messages and their metadata, blocking only until
+    /// the first one is available.
+    ///
+    /// Once awake it takes the messages that are already ready, up to `max`,
+    /// and returns without waiting for more, so a burst costs one wakeup
+    /// rather than one per message. Returns an empty batch only if `max` is 0.
+    ///
+    /// Fails like `receive_blocking_with_meta` while waiting for the first
+    /// message. A later message that fails checksum verification or
+    /// decompression is dropped and ends the batch early: the messages before
+    /// it are returned, and the next call (or `drain`) fails with its error.
+    pub fn receive_batch_blocking(
+        &self,
+        max: usize,
+    ) -> std::io::Result<Vec<(MessageMeta, Vec<u8>)>> {
+        if let Some(e) = self.batch_error.lock().take() {
+            return Err(e);
+        }
+        let mut batch = Vec::new();
+        if max == 0 {
+            return Ok(batch);
+        }
+        batch.reserve(max.min(self.channel.capacity));
+        batch.push(self.receive_blocking_with_meta()?);
+
+        let buffer = self.channel.buffer();
+        while batch.len() < max {
+            let Some((meta, payload)) = buffer.dequeue() else {
+                break;
+            };
+            match self.decode(meta, payload) {
+                Ok(message) => batch.push(message),
+                Err(e) => {
+                    *self.batch_error.lock() = Some(e);
+                    break;
                 }
             }
         }
+        Ok(batch)
+    }
+
+    /// Called after a receive found the channel empty. Fails with `BrokenPipe`
+    /// when no more messages can arrive: the channel was already closed when
+    /// the receive started (`closed`), or the producer died. Fails with
+    /// `InvalidData` if the channel was poisoned, and with `NotConnected` if
+    /// it was resized or moved under this consumer; both also read as empty.
+    fn check_producer(&self, closed: bool) -> std::io::Result<()> {
+        if self.channel.buffer().is_poisoned() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Channel {} poisoned", self.channel_id),
+            ));
+        }
+        if self.channel.buffer().is_stale() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                format!(
+                    "Channel {} was resized or moved; attach again",
+                    self.channel_id
+                ),
+            ));
+        }
+        if closed {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                format!("Channel {} was closed by its producer", self.channel_id),
+            ));
+        }
+        if !self.is_producer_alive() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Producer has terminated",
+            ));
+        }
+        Ok(())
     }
 
     /// Checks if the producer is still alive
+    ///
+    /// A heartbeat that moved since the last check proves the producer is sending.
+    /// Otherwise the producer may just be idle, so the recorded pid is probed
+    /// with `kill(pid, 0)`, at most once per `LIVENESS_INTERVAL`. A dead producer
+    /// clears the `producer_alive` flag for good. With several producers on one
+    /// channel only the most recently attached one is probed.
     fn is_producer_alive(&self) -> bool {
-        // If we've received a message recently, assume the producer is still alive
-        let last_msg_time = self.last_message_time.load(Ordering::Acquire);
-        if last_msg_time > 0 {
-            use std::time::{SystemTime, UNIX_EPOCH};
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs() as i64;
+        let buffer = self.channel.buffer();
+        let beat = buffer.heartbeat();
+        if self.last_heartbeat.swap(beat, Ordering::AcqRel) != beat {
+            return self.producer_alive.load(Ordering::Acquire);
+        }
+        if !self.producer_alive.load(Ordering::Acquire) {
+            return false;
+        }
 
-            // If we've received a message in the last 5 seconds, assume producer is alive
-            if now - last_msg_time < 5 {
-                return true;
-            }
+        let now_ms = self.created.elapsed().as_millis() as u64;
+        if now_ms < self.next_probe_ms.load(Ordering::Relaxed) {
+            return true;
         }
+        self.next_probe_ms.store(
+            now_ms + LIVENESS_INTERVAL.as_millis() as u64,
+            Ordering::Relaxed,
+        );
 
-        // Fall back to the keep-alive flag
-        self.producer_alive.load(Ordering::Acquire)
+        match buffer.producer_pid() {
+            0 => true, // No producer has registered yet
+            pid if process_exists(pid) => true,
+            _ => {
+                self.producer_alive.store(false, Ordering::Release);
+                false
+            }
+        }
     }
 
     /// Receives a message from the channel, waiting up to the specified timeout.
@@ -138,7 +763,25 @@ impl Consumer {
             .map(|opt| opt.map(|(_, payload)| payload))
     }
 
+    /// Receives a message, waiting up to the default timeout set with
+    /// `ChannelBuilder::with_default_timeout`. Without one it returns at once,
+    /// like [`receive`](Self::receive).
+    pub fn receive_default(&self) -> std::io::Result<Option<Vec<u8>>> {
+        match self.default_timeout {
+            Some(timeout) => self.receive_timeout(timeout),
+            None => self.receive(),
+        }
+    }
+
+    /// The timeout `receive_default` waits for, if one was configured.
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
     /// Receives a message and metadata with timeout.
+    ///
+    /// While the channel is empty the thread parks on the channel's futex for
+    /// the remaining time, so a send wakes it straight away.
     pub fn receive_timeout_with_meta(
         &self,
         timeout: Duration,
@@ -146,37 +789,131 @@ impl Consumer {
         let start = Instant::now();
 
         loop {
-            match self.receive_with_meta() {
-                Ok(Some(data)) => return Ok(Some(data)),
-                Ok(None) => {
-                    if start.elapsed() >= timeout {
-                        return Ok(None);
-                    }
-                    // Use exponential backoff to reduce CPU usage
-                    let elapsed = start.elapsed();
-                    let remaining = timeout.saturating_sub(elapsed);
-                    let sleep_time = std::cmp::min(remaining, Duration::from_millis(10));
-                    std::thread::sleep(sleep_time);
-                }
-                Err(e) => return Err(e),
+            if let Some(message) = self.receive_with_meta()? {
+                return Ok(Some(message));
             }
-
-            if start.elapsed() >= timeout {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
                 return Ok(None);
             }
+            // Park until a producer signals or the time is up
+            self.channel.buffer().wait_for_data_timeout(remaining);
         }
     }
 
+    /// Sequence of the next message this broadcast subscriber will receive
+    /// (see `Producer::send_seq`): the offset to save to resume after the
+    /// messages received so far. `None` on a consumer that is not a broadcast
+    /// subscriber, whose read position is shared with the other consumers.
+    pub fn offset(&self) -> Option<u64> {
+        self.channel.buffer().subscriber_position()
+    }
+
+    /// Moves this broadcast subscriber so the next receive returns the message
+    /// with sequence `seq`, replaying messages received before or skipping
+    /// ahead.
+    ///
+    /// # Returns
+    /// * `Ok(0)` if the message is still in the ring
+    /// * `Ok(gap)` if producers have reused its slot: the subscriber resumes at
+    ///   the oldest message left instead, `gap` messages later
+    /// * `Err` with `InvalidInput` on a consumer that is not a broadcast
+    ///   subscriber, or if `seq` is past the last message sent
+    pub fn seek_to(&self, seq: u64) -> std::io::Result<u64> {
+        let position = self.channel.buffer().seek_subscriber(seq)?;
+        Ok(position - seq)
+    }
+
+    /// Saves [`offset`](Self::offset) to `path`, replacing any previous offset,
+    /// so a subscriber created after a restart can resume with `load_offset`.
+    ///
+    /// The file holds the offset as decimal text. It is written next to `path`
+    /// and renamed over it, so a crash never leaves a partial offset behind.
+    /// Fails with `InvalidInput` on a consumer that is not a broadcast
+    /// subscriber.
+    pub fn save_offset(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let offset = self.offset().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Only broadcast subscribers have an offset to save",
+            )
+        })?;
+        let path = path.as_ref();
+        let mut staging = path.as_os_str().to_owned();
+        staging.push(".tmp");
+        std::fs::write(&staging, format!("{}\n", offset))?;
+        std::fs::rename(&staging, path)
+    }
+
+    /// Reads an offset written by [`save_offset`](Self::save_offset) and
+    /// [`seek_to`](Self::seek_to)s it, returning the number of messages lost
+    /// in between (0 when none were).
+    ///
+    /// Fails with `InvalidData` if the file does not hold an offset, and
+    /// otherwise like `seek_to` and reading the file.
+    pub fn load_offset(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<u64> {
+        let text = std::fs::read_to_string(path)?;
+        let offset = text.trim().parse().map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid offset file: {}", e),
+            )
+        })?;
+        self.seek_to(offset)
+    }
+
     /// Returns the channel ID for this consumer
     pub fn channel_id(&self) -> u32 {
         self.channel_id
     }
 
+    /// Returns the backoff policy used when the channel is contended
+    pub fn backoff(&self) -> crate::MPMC::Backoff {
+        self.channel.buffer().backoff()
+    }
+
+    /// Returns the number of slots in the channel's ring
+    pub fn capacity(&self) -> usize {
+        self.channel.capacity
+    }
+
+    /// Returns the payload bytes each slot holds inline, as set by
+    /// `ChannelBuilder::with_slot_size`
+    pub fn slot_size(&self) -> usize {
+        self.channel.buffer().inline_size()
+    }
+
+    /// Returns the bytes each slot occupies in shared memory, header and
+    /// padding included
+    pub fn stride(&self) -> usize {
+        self.channel.buffer().stride()
+    }
+
+    /// Returns the largest message the channel can carry
+    pub fn max_message_size(&self) -> usize {
+        self.channel.buffer().max_payload()
+    }
+
     /// Returns a reference to the producer alive flag
     ///
     /// This can be used to check if the producer is still alive.
-    /// When the producer drops, this flag will be set to false.
+    /// It is cleared once a receive finds the producer's process gone; clearing
+    /// it manually makes receives on an empty channel fail with `BrokenPipe`.
     pub fn producer_alive(&self) -> &Arc<AtomicBool> {
         &self.producer_alive
     }
 }
+
+impl Drop for Consumer {
+    fn drop(&mut self) {
+        // Free the broadcast subscriber slot so producers stop waiting on it
+        self.channel.buffer.unsubscribe();
+        if let Some(index) = self.attached {
+            self.channel.buffer.unregister_attached(index);
+        }
+        #[cfg(feature = "tokio")]
+        self.finish_async_parks();
+        #[cfg(target_os = "linux")]
+        self.finish_readiness();
+    }
+}
@@ -0,0 +1,79 @@
+// src/MPMC/iter.rs
+//
+// Iterators over the messages of a Consumer, for `for` loops instead of
+// hand-written receive loops.
+
+use std::io;
+
+use super::Consumer;
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+
+/// Iterator over the messages waiting in a channel, created by
+/// [`Consumer::iter`]. Never blocks.
+///
+/// Each item is the result of one `receive_with_meta`: failed receives (such
+/// as a checksum mismatch) are yielded as `Err`. It returns `None` once the
+/// channel is empty or the producer has terminated; calling `next` again
+/// later picks up messages sent since.
+pub struct Iter<'a> {
+    consumer: &'a Consumer,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = io::Result<(MessageMeta, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.consumer.receive_with_meta() {
+            Ok(message) => message.map(Ok),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Iterator that waits for each message, created by
+/// [`Consumer::blocking_iter`].
+///
+/// Each item is the result of one `receive_blocking_with_meta`: failed
+/// receives are yielded as `Err`. It ends once the producer has terminated
+/// and every message it sent has been received.
+pub struct BlockingIter<'a> {
+    consumer: &'a Consumer,
+    done: bool,
+}
+
+impl Iterator for BlockingIter<'_> {
+    type Item = io::Result<(MessageMeta, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.consumer.receive_blocking_with_meta() {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.done = true;
+                None
+            }
+            result => Some(result),
+        }
+    }
+}
+
+impl std::iter::FusedIterator for BlockingIter<'_> {}
+
+impl Consumer {
+    /// Iterates over the messages already in the channel without blocking;
+    /// see [`Iter`].
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { consumer: self }
+    }
+
+    /// Iterates over messages as they arrive, blocking for each one, until
+    /// the producer closes the channel; see [`BlockingIter`].
+    pub fn blocking_iter(&self) -> BlockingIter<'_> {
+        BlockingIter {
+            consumer: self,
+            done: false,
+        }
+    }
+}
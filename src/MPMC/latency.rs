@@ -0,0 +1,108 @@
+// End-to-end latency tracking for consumers.
+//
+// Every message carries the wall-clock time it was sent (`MessageMeta::timestamp_ns`),
+// so a consumer can measure send-to-receive latency without any cooperation from
+// the producer. Samples go into a log-linear histogram in the style of HDR
+// histograms: exact below 16ns, then 16 buckets per power of two, which bounds
+// the relative error of a reported percentile to about 6%.
+
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::time::Duration;
+
+/// Buckets per power of two, as a power of two.
+const SUB_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+
+/// Enough buckets for any `u64` nanosecond value.
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB_BUCKETS;
+
+/// Latency percentiles over every message received since tracking was enabled,
+/// as returned by `Consumer::latency_snapshot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencySnapshot {
+    /// Median latency
+    pub p50: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Largest latency seen
+    pub max: Duration,
+    /// Number of messages measured
+    pub count: u64,
+}
+
+/// Lock-free histogram of latencies in nanoseconds.
+pub(crate) struct LatencyHistogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    max: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a message sent at `sent_ns` and received at `now_ns`. Messages
+    /// without a timestamp are skipped; a send time ahead of ours (clock skew
+    /// between cores or hosts) counts as zero latency.
+    pub(crate) fn record(&self, sent_ns: u64, now_ns: u64) {
+        if sent_ns == 0 {
+            return;
+        }
+        let latency = now_ns.saturating_sub(sent_ns);
+        self.buckets[bucket_of(latency)].fetch_add(1, Relaxed);
+        self.count.fetch_add(1, Relaxed);
+        self.max.fetch_max(latency, Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LatencySnapshot {
+        let counts: Vec<u64> = self.buckets.iter().map(|b| b.load(Relaxed)).collect();
+        let count: u64 = counts.iter().sum();
+        let max = self.max.load(Relaxed);
+        let percentile = |q: f64| {
+            if count == 0 {
+                return Duration::ZERO;
+            }
+            let rank = ((count as f64 * q).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (index, n) in counts.iter().enumerate() {
+                seen += n;
+                if seen >= rank {
+                    return Duration::from_nanos(bucket_high(index).min(max));
+                }
+            }
+            Duration::from_nanos(max)
+        };
+
+        LatencySnapshot {
+            p50: percentile(0.50),
+            p99: percentile(0.99),
+            max: Duration::from_nanos(max),
+            count,
+        }
+    }
+}
+
+/// Index of the bucket holding `value`.
+fn bucket_of(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let exponent = 63 - value.leading_zeros();
+    let sub = (value >> (exponent - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    (exponent - SUB_BITS + 1) as usize * SUB_BUCKETS + sub
+}
+
+/// Largest value that falls into bucket `index`.
+fn bucket_high(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let low = ((SUB_BUCKETS + index % SUB_BUCKETS) as u64) << shift;
+    low + ((1u64 << shift) - 1)
+}
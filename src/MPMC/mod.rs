@@ -1,16 +1,52 @@
+#[cfg(feature = "tokio")]
+mod async_consumer;
+pub(crate) mod backoff;
 mod builder;
+mod clock;
 mod consumer;
+mod iter;
+mod latency;
+mod multi_consumer;
 mod producer;
+mod rate_limit;
+#[cfg(target_os = "linux")]
+mod readiness;
+mod sharded_producer;
+mod slot_io;
+mod tail;
+#[cfg(feature = "bytemuck")]
+mod typed;
 
-pub use builder::ChannelBuilder;
+#[cfg(feature = "tokio")]
+pub use async_consumer::ConsumerStream;
+pub use backoff::{Backoff, DEFAULT_STALL_SPINS};
+pub use builder::{ChannelBuilder, Policy};
+pub use clock::{ClockSource, Timestamps};
 pub use consumer::Consumer;
+pub use iter::{BlockingIter, Iter};
+pub use latency::LatencySnapshot;
+pub use multi_consumer::MultiConsumer;
 pub use producer::Producer;
+pub use sharded_producer::ShardedProducer;
+pub use slot_io::{SlotReader, SlotWriter};
+pub use tail::{TailConsumer, TailEvent};
+#[cfg(feature = "bytemuck")]
+pub use typed::{TypedConsumer, TypedProducer};
 
 pub mod Buffer {
     pub mod Buffer;
     pub mod Buffer_impl;
+    pub mod ack_token;
     pub mod layout;
-    pub use Buffer::{RingBuffer, Slot, MSG_INLINE}; // re-export for stable path
+    pub mod message_ref;
+    pub(crate) mod overflow;
+    pub mod verify;
+    pub use ack_token::AckToken;
+    pub use message_ref::MessageRef;
+    pub use Buffer::{
+        DequeueError, EnqueueError, RingBuffer, Slot, SlotLayout, MSG_INLINE, SLOT_ALIGN,
+        SLOT_META_OFFSET, SLOT_PAYLOAD_OFFSET, SLOT_SEQUENCE_OFFSET,
+    }; // re-export for stable path
 }
 
 pub mod Structs {
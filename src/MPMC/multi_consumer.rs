@@ -0,0 +1,109 @@
+// src/MPMC/multi_consumer.rs
+
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// How long a blocked `receive_any_blocking` parks on one channel's futex before
+/// rotating to the next. Bounds the extra latency for messages on other channels.
+const WAIT_SLICE: Duration = Duration::from_millis(1);
+
+/// A consumer that receives from several channels at once.
+///
+/// Channels are scanned in a rotating order: each receive starts just past the
+/// channel that delivered the previous message, so a busy channel cannot starve
+/// a quiet one.
+pub struct MultiConsumer {
+    // None when built in an allocator that lives for the whole process
+    _allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
+    channels: Vec<crate::Core::alloc::ChannelPartition>,
+    next: AtomicUsize,
+    // This consumer's entry in each channel's attached registry, if one was free
+    attached: Vec<Option<usize>>,
+}
+
+impl MultiConsumer {
+    pub(crate) fn new(
+        allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
+        channels: Vec<crate::Core::alloc::ChannelPartition>,
+    ) -> Self {
+        let attached = channels
+            .iter()
+            .map(|channel| channel.buffer().register_attached(true))
+            .collect();
+        Self {
+            _allocator: allocator,
+            channels,
+            next: AtomicUsize::new(0),
+            attached,
+        }
+    }
+
+    /// Receives one message from whichever channel has one ready.
+    ///
+    /// # Returns
+    /// * `Ok(Some((channel_id, meta, data)))` if a message was received
+    /// * `Ok(None)` if every channel is empty
+    /// * `Err(io::Error)` if a payload failed its checksum
+    pub fn receive_any(&self) -> std::io::Result<Option<(u32, MessageMeta, Vec<u8>)>> {
+        let count = self.channels.len();
+        let start = self.next.load(Ordering::Relaxed);
+
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            let channel = &self.channels[index];
+            if let Some((meta, payload)) = channel.buffer().dequeue() {
+                self.next.store((index + 1) % count, Ordering::Relaxed);
+                if !channel.buffer().verify_checksum(&meta, &payload) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "Payload checksum mismatch on channel {} (message_id {})",
+                            channel.id(),
+                            meta.message_id
+                        ),
+                    ));
+                }
+                return Ok(Some((channel.id(), meta, payload)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Receives one message, blocking until any channel has one.
+    ///
+    /// The thread parks on each channel's futex in turn for a short slice, so it
+    /// sleeps while everything is idle but never waits long on a quiet channel
+    /// while another one fills up.
+    pub fn receive_any_blocking(&self) -> std::io::Result<(u32, MessageMeta, Vec<u8>)> {
+        let mut park_on = 0;
+        loop {
+            if let Some(message) = self.receive_any()? {
+                return Ok(message);
+            }
+            self.channels[park_on]
+                .buffer()
+                .wait_for_data_timeout(WAIT_SLICE);
+            park_on = (park_on + 1) % self.channels.len();
+        }
+    }
+
+    pub(crate) fn channels(&self) -> &[crate::Core::alloc::ChannelPartition] {
+        &self.channels
+    }
+
+    /// Returns the IDs of the channels this consumer reads from, in scan order
+    pub fn channel_ids(&self) -> Vec<u32> {
+        self.channels.iter().map(|channel| channel.id()).collect()
+    }
+}
+
+impl Drop for MultiConsumer {
+    fn drop(&mut self) {
+        for (channel, attached) in self.channels.iter().zip(&self.attached) {
+            if let Some(index) = *attached {
+                channel.buffer().unregister_attached(index);
+            }
+        }
+    }
+}
@@ -1,30 +1,54 @@
 // In src/MPMC/producer.rs
-use crate::MPMC::Buffer::MSG_INLINE;
-use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use super::rate_limit::RateLimit;
+use super::slot_io::SlotWriter;
+use super::{ClockSource, Timestamps};
+use crate::MPMC::backoff::Snooze;
+use crate::MPMC::Buffer::{EnqueueError, RingBuffer};
+#[cfg(feature = "lz4")]
+use crate::MPMC::Structs::Buffer_Structs::FLAG_COMPRESSED;
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_MONOTONIC};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
+
+/// `message_type` of messages sent without one.
+const DEFAULT_MESSAGE_TYPE: u32 = 1;
+
+/// Upper byte of `MessageMeta::flags`, reserved for the transport (see `FLAG_SPILLED`).
+const TRANSPORT_FLAGS: u16 = 0xFF00;
+
+/// Attempts a send makes while the ring reports `EnqueueError::Contended`
+/// before giving up with `WouldBlock`.
+const CONTENDED_RETRIES: u32 = 1024;
 
 /// A producer for sending messages through a shared memory channel.
 /// The producer is responsible for writing messages to the ring buffer
 /// and managing the lifecycle of the shared memory region.
 pub struct Producer {
-    _allocator: crate::Core::alloc::SharedMemoryAllocator,
+    // None when built in an allocator that lives for the whole process
+    _allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
     channel: crate::Core::alloc::ChannelPartition,
     channel_id: u32,
     keep_alive: Arc<AtomicBool>,
     max_message_size: usize,
-    sequence_counter: AtomicU64,
+    timestamps: Timestamps,
+    clock: ClockSource,
+    /// This producer's entry in the channel's registry of attached handles
+    attached: Option<usize>,
+    rate_limit: Option<RateLimit>,
 }
 
 impl Producer {
     pub(crate) fn new(
-        allocator: crate::Core::alloc::SharedMemoryAllocator,
+        allocator: Option<crate::Core::alloc::SharedMemoryAllocator>,
         channel: crate::Core::alloc::ChannelPartition,
         channel_id: u32,
     ) -> Self {
-        // Max message size is fixed by the inline payload size
-        let max_message_size = MSG_INLINE;
+        let max_message_size = channel.buffer().max_payload();
+
+        channel.buffer().register_producer(std::process::id());
+        channel.buffer().attach_producer();
+        let attached = channel.buffer().register_attached(false);
 
         Self {
             _allocator: allocator,
@@ -32,7 +56,93 @@ impl Producer {
             channel_id,
             keep_alive: Arc::new(AtomicBool::new(true)),
             max_message_size,
-            sequence_counter: AtomicU64::new(0),
+            timestamps: Timestamps::Precise,
+            clock: ClockSource::Realtime,
+            attached,
+            rate_limit: None,
+        }
+    }
+
+    pub(crate) fn with_timestamps(mut self, timestamps: Timestamps) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    pub(crate) fn with_clock_source(mut self, clock: ClockSource) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Cap this producer at `msgs_per_sec` messages a second, with a token
+    /// bucket that holds a tenth of a second's worth; 0 removes the cap.
+    ///
+    /// Sends that find the bucket empty fail with `WouldBlock`, like sends into
+    /// a full ring, while `send_blocking` sleeps until a token is due. The
+    /// bucket belongs to this handle: it does not coordinate with other
+    /// producers, in this process or others, sending on the same channel.
+    pub fn with_rate_limit(mut self, msgs_per_sec: u32) -> Self {
+        self.rate_limit = (msgs_per_sec != 0).then(|| RateLimit::new(msgs_per_sec));
+        self
+    }
+
+    /// Take `count` send tokens from the rate limit, if there is one. Fails
+    /// with `WouldBlock` when the bucket is short, and with `InvalidInput` if
+    /// it can never hold `count`.
+    fn take_tokens(&self, count: usize) -> std::io::Result<()> {
+        let Some(limit) = &self.rate_limit else {
+            return Ok(());
+        };
+        if count > limit.burst() as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Batch of {} exceeds the rate limit's burst of {}",
+                    count,
+                    limit.burst()
+                ),
+            ));
+        }
+        if !limit.try_take(count as u32) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                format!(
+                    "Producer on channel {} is limited to {} messages per second",
+                    self.channel_id,
+                    limit.per_second()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return the tokens of a send that failed after `take_tokens`.
+    fn give_back_tokens(&self, count: usize) {
+        if let Some(limit) = &self.rate_limit {
+            limit.give_back(count as u32);
+        }
+    }
+
+    /// Wait after a send failed with `WouldBlock`: until the rate limit has a
+    /// token if that is what ran out, else until a consumer frees a slot (or
+    /// 100ms pass, to notice a cleared keep-alive flag).
+    fn wait_to_send(&self, buffer: &RingBuffer, seen: u32) {
+        let delay = self
+            .rate_limit
+            .as_ref()
+            .map_or(Duration::ZERO, RateLimit::delay);
+        if delay.is_zero() {
+            buffer.wait_for_space(seen, Duration::from_millis(100));
+        } else {
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// `FLAG_MONOTONIC` if a message stamped with `timestamp_ns` needs it.
+    fn clock_flag(&self, timestamp_ns: u64) -> u16 {
+        if self.clock == ClockSource::Monotonic && timestamp_ns != 0 {
+            FLAG_MONOTONIC
+        } else {
+            0
         }
     }
 
@@ -43,16 +153,14 @@ impl Producer {
             return Ok(());
         }
 
+        self.check_writable()?;
         let batch_size = messages.len();
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
+        self.take_tokens(batch_size)?;
+        let now = self.timestamps.now_ns(self.clock);
 
-        // Pre-allocate IDs (gaps on failure are acceptable for now)
-        let base_msg_id = self
-            .sequence_counter
-            .fetch_add(batch_size as u64, Ordering::Relaxed);
+        // Reserve a contiguous id range shared with every producer on the channel
+        // (gaps on failure are acceptable for now)
+        let base_msg_id = self.channel.buffer().reserve_message_ids(batch_size as u64);
 
         // Prepare metadata objects
         let mut meta_storage: Vec<MessageMeta> = Vec::with_capacity(batch_size);
@@ -62,11 +170,14 @@ impl Producer {
                 message_id: base_msg_id + i as u64,
                 timestamp_ns: now,
                 channel_id: self.channel_id,
-                message_type: 1, // Default type
+                message_type: DEFAULT_MESSAGE_TYPE,
                 sender_pid: std::process::id(),
                 sender_runtime: 1, // Rust
-                flags: 0,
+                flags: self.clock_flag(now),
                 payload_len: msg.len() as u32,
+                checksum: 0,
+                schema_version: 0,
+                ttl_ms: 0,
             });
         }
 
@@ -79,9 +190,17 @@ impl Producer {
 
         // Attempt enqueue
         if self.channel.buffer().enqueue_batch(&batch_args).is_some() {
+            self.channel.buffer().beat();
             self.channel.buffer().signal_consumer();
             Ok(())
+        } else if self.channel.buffer().is_poisoned() {
+            self.give_back_tokens(batch_size);
+            Err(self.poisoned())
+        } else if self.channel.buffer().is_stale() {
+            self.give_back_tokens(batch_size);
+            Err(self.stale())
         } else {
+            self.give_back_tokens(batch_size);
             Err(std::io::Error::new(
                 std::io::ErrorKind::WouldBlock,
                 "Channel full or contended",
@@ -96,47 +215,354 @@ impl Producer {
     ///
     /// # Returns
     /// * `Ok(())` if the message was sent successfully
-    /// * `Err(io::Error)` if the message is too large or the buffer is full
+    /// * `Err(io::Error)` if the message is too large or the buffer is full,
+    ///   or with `PermissionDenied` if the channel is sealed
     pub fn send<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<()> {
+        self.send_on(
+            self.channel.buffer(),
+            message.as_ref(),
+            DEFAULT_MESSAGE_TYPE,
+            0,
+            0,
+            self.timestamps,
+        )
+    }
+
+    /// Sends a message with `timestamp_ns` left at 0, skipping the clock read
+    /// whatever `ChannelBuilder::with_timestamps` chose. Fails like `send`.
+    pub fn send_no_timestamp<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<()> {
+        self.send_on(
+            self.channel.buffer(),
+            message.as_ref(),
+            DEFAULT_MESSAGE_TYPE,
+            0,
+            0,
+            Timestamps::Off,
+        )
+    }
+
+    /// Sends a message tagged with an application `message_type` and the
+    /// `schema_version` of its payload layout.
+    ///
+    /// Consumers read both back from the message's `MessageMeta`. Plain `send`
+    /// uses type 1 and version 0. Fails like `send`.
+    pub fn send_typed<T: AsRef<[u8]>>(
+        &self,
+        message: T,
+        message_type: u32,
+        schema_version: u16,
+    ) -> std::io::Result<()> {
+        self.send_on(
+            self.channel.buffer(),
+            message.as_ref(),
+            message_type,
+            schema_version,
+            0,
+            self.timestamps,
+        )
+    }
+
+    /// Sends a message LZ4-compressed, so compressible payloads take less room and
+    /// ones up to several times the maximum message size can fit.
+    ///
+    /// The message is stored with `FLAG_COMPRESSED` set, and receivers such as
+    /// `Consumer::receive_with_meta` decompress it transparently. If compression
+    /// does not shrink the payload it is sent as is, exactly like `send`.
+    ///
+    /// # Returns
+    /// * `Err` with `InvalidInput` if even the compressed payload exceeds
+    ///   `max_message_size`
+    /// * Otherwise the same results as `send`
+    #[cfg(feature = "lz4")]
+    pub fn send_compressed<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<()> {
         let message = message.as_ref();
+        let compressed = lz4_flex::compress_prepend_size(message);
+        if compressed.len() >= message.len() {
+            return self.send(message);
+        }
+        self.send_on(
+            self.channel.buffer(),
+            &compressed,
+            DEFAULT_MESSAGE_TYPE,
+            0,
+            FLAG_COMPRESSED,
+            self.timestamps,
+        )
+    }
 
-        // Check message size before attempting to enqueue
-        if message.len() > self.max_message_size {
+    /// Sends a message with an application `message_type` and `flags`, for
+    /// routing on the consumer side; the rest of the metadata (id, timestamp,
+    /// sender) is filled in as for `send`.
+    ///
+    /// Only the lower byte of `flags` belongs to applications; setting any bit
+    /// of the upper, transport-reserved byte fails with `InvalidInput`.
+    /// Otherwise fails like `send`.
+    pub fn send_with_meta<T: AsRef<[u8]>>(
+        &self,
+        message: T,
+        message_type: u32,
+        flags: u16,
+    ) -> std::io::Result<()> {
+        if flags & TRANSPORT_FLAGS != 0 {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Message too large ({} > {})",
-                    message.len(),
-                    self.max_message_size
-                ),
+                format!("Flags {:#06x} use the transport-reserved upper byte", flags),
             ));
         }
+        self.send_on(
+            self.channel.buffer(),
+            message.as_ref(),
+            message_type,
+            0,
+            flags,
+            self.timestamps,
+        )
+    }
+
+    /// Sends a message that consumers drop instead of receiving once `ttl` has
+    /// passed since it was sent.
+    ///
+    /// The TTL is stored in whole milliseconds, rounded up, and counted from
+    /// `timestamp_ns`; the message is therefore stamped even if timestamps are
+    /// turned off. Expired messages are skipped lazily when a consumer reaches
+    /// them and show up in `ChannelMetrics::expired_count`.
+    ///
+    /// # Returns
+    /// * `Err` with `InvalidInput` if `ttl` does not fit in `u32::MAX` milliseconds
+    /// * Otherwise the same results as `send`
+    pub fn send_ttl<T: AsRef<[u8]>>(&self, message: T, ttl: Duration) -> std::io::Result<()> {
+        let ttl_ms = u32::try_from(ttl.as_nanos().div_ceil(1_000_000))
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("TTL {:?} exceeds u32::MAX milliseconds", ttl),
+                )
+            })?
+            .max(1);
+        let timestamps = match self.timestamps {
+            Timestamps::Off => Timestamps::Precise,
+            timestamps => timestamps,
+        };
+        let meta = MessageMeta {
+            timestamp_ns: timestamps.now_ns(self.clock),
+            message_type: DEFAULT_MESSAGE_TYPE,
+            ttl_ms,
+            ..Default::default()
+        };
+        self.send_meta(self.channel.buffer(), &[message.as_ref()], meta)
+    }
 
+    /// Sends a message like `send` and returns its sequence on the channel: the
+    /// ring position it was written at, which grows with every message and is
+    /// never reused, unlike the slot index. Producers in every process draw
+    /// from the same sequence, so it identifies the message for correlating it
+    /// with a later result.
+    pub fn send_seq<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<u64> {
+        let meta = MessageMeta {
+            timestamp_ns: self.timestamps.now_ns(self.clock),
+            message_type: DEFAULT_MESSAGE_TYPE,
+            ..Default::default()
+        };
+        self.send_meta_seq(self.channel.buffer(), &[message.as_ref()], meta)
+    }
+
+    /// Sends a message made of several `fragments`, such as a header and a body,
+    /// as one payload of their combined length.
+    ///
+    /// The fragments are copied straight into the slot one after the other, so
+    /// there is no need to concatenate them first. Fails with `InvalidInput` if
+    /// their combined length exceeds `max_message_size`, otherwise like `send`.
+    pub fn send_vectored(&self, fragments: &[&[u8]]) -> std::io::Result<()> {
+        let meta = MessageMeta {
+            timestamp_ns: self.timestamps.now_ns(self.clock),
+            message_type: DEFAULT_MESSAGE_TYPE,
+            ..Default::default()
+        };
+        self.send_meta(self.channel.buffer(), fragments, meta)
+    }
+
+    /// Sends a message, retrying while the channel is full until `timeout` elapses.
+    ///
+    /// # Returns
+    /// * `Ok(())` once the message is enqueued
+    /// * `Err` with `TimedOut` if the channel stayed full for the whole timeout
+    /// * Any other error from `send` (message too large, consumer gone) immediately
+    pub fn send_timeout<T: AsRef<[u8]>>(
+        &self,
+        message: T,
+        timeout: Duration,
+    ) -> std::io::Result<()> {
+        let message = message.as_ref();
+        let start = Instant::now();
+        let mut backoff = Duration::from_micros(10);
+
+        loop {
+            match self.send(message) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    let remaining = timeout.saturating_sub(start.elapsed());
+                    if remaining.is_zero() {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            format!("Channel {} still full after {:?}", self.channel_id, timeout),
+                        ));
+                    }
+                    // Use exponential backoff to reduce CPU usage
+                    std::thread::sleep(backoff.min(remaining));
+                    backoff = (backoff * 2).min(Duration::from_millis(10));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends a message, parking the thread while the channel is full.
+    ///
+    /// The producer sleeps on the channel's space-available futex and is woken
+    /// when a consumer frees a slot, instead of spinning. Returns `BrokenPipe` if
+    /// the keep-alive flag is cleared while waiting.
+    pub fn send_blocking<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<()> {
+        let message = message.as_ref();
         let buffer = self.channel.buffer();
 
-        // Create metadata
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
+        loop {
+            let seen = buffer.space_signal();
+            match self.send_on(buffer, message, DEFAULT_MESSAGE_TYPE, 0, 0, self.timestamps) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    self.wait_to_send(buffer, seen);
+                    if !self.keep_alive.load(Ordering::Acquire) {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::BrokenPipe,
+                            "Consumer has terminated",
+                        ));
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
 
+    /// Sends a message through the channel's high-priority band.
+    ///
+    /// Consumers always take priority messages before normal ones, so this
+    /// message may overtake earlier `send`s; order is only preserved among
+    /// priority sends. Fails with `Unsupported` if the channel was created
+    /// without a priority band, and with `WouldBlock` when that band is full.
+    pub fn send_priority<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<()> {
+        let band = self.channel.buffer().priority_band().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Channel {} has no priority band", self.channel_id),
+            )
+        })?;
+        self.send_on(
+            band,
+            message.as_ref(),
+            DEFAULT_MESSAGE_TYPE,
+            0,
+            0,
+            self.timestamps,
+        )
+    }
+
+    /// Stamp `message` and enqueue it on `buffer` (the normal or the priority band).
+    fn send_on(
+        &self,
+        buffer: &RingBuffer,
+        message: &[u8],
+        message_type: u32,
+        schema_version: u16,
+        flags: u16,
+        timestamps: Timestamps,
+    ) -> std::io::Result<()> {
         let meta = MessageMeta {
-            message_id: self.sequence_counter.fetch_add(1, Ordering::Relaxed),
-            timestamp_ns: now,
-            channel_id: self.channel_id,
-            message_type: 1, // Default type
-            sender_pid: std::process::id(),
-            sender_runtime: 1, // Rust
-            flags: 0,
-            payload_len: message.len() as u32,
+            timestamp_ns: timestamps.now_ns(self.clock),
+            message_type,
+            schema_version,
+            flags,
+            ..Default::default()
         };
+        self.send_meta(buffer, &[message], meta)
+    }
+
+    /// Fill in the id, sender and length of `meta` and enqueue the message made
+    /// of `fragments` (written back to back) on `buffer`.
+    fn send_meta(
+        &self,
+        buffer: &RingBuffer,
+        fragments: &[&[u8]],
+        meta: MessageMeta,
+    ) -> std::io::Result<()> {
+        self.send_meta_seq(buffer, fragments, meta).map(drop)
+    }
+
+    /// [`send_meta`](Self::send_meta), returning the message's sequence on `buffer`.
+    fn send_meta_seq(
+        &self,
+        buffer: &RingBuffer,
+        fragments: &[&[u8]],
+        mut meta: MessageMeta,
+    ) -> std::io::Result<u64> {
+        meta.sender_pid = std::process::id();
+        meta.sender_runtime = 1; // Rust
+        meta.flags |= self.clock_flag(meta.timestamp_ns);
+        self.enqueue_meta(buffer, fragments, meta)
+    }
+
+    /// Fill in the id, channel and length of `meta`, leaving the rest as given,
+    /// and enqueue the message on `buffer`.
+    fn enqueue_meta(
+        &self,
+        buffer: &RingBuffer,
+        fragments: &[&[u8]],
+        mut meta: MessageMeta,
+    ) -> std::io::Result<u64> {
+        self.check_writable()?;
+        // Check message size before attempting to enqueue
+        let len: usize = fragments.iter().map(|fragment| fragment.len()).sum();
+        if len > self.max_message_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Message too large ({} > {})", len, self.max_message_size),
+            ));
+        }
 
-        match buffer.enqueue(meta, message) {
-            Some(_) => {
-                buffer.signal_consumer();
-                Ok(())
+        self.take_tokens(1)?;
+        meta.message_id = buffer.reserve_message_ids(1);
+        meta.channel_id = self.channel_id;
+        meta.payload_len = len as u32;
+
+        let sequence = self
+            .retry_contended(buffer, || buffer.try_enqueue_seq(meta, fragments))
+            .inspect_err(|_| self.give_back_tokens(1))?;
+        buffer.beat();
+        buffer.signal_consumer();
+        Ok(sequence)
+    }
+
+    /// Run `attempt`, an enqueue or claim on `buffer`, and map its failure to
+    /// the error a send reports.
+    fn retry_contended<T>(
+        &self,
+        buffer: &RingBuffer,
+        mut attempt: impl FnMut() -> Result<T, EnqueueError>,
+    ) -> std::io::Result<T> {
+        // A contended slot frees up within a few attempts; only a full ring
+        // (or one contended for too long) is reported as WouldBlock
+        let mut snooze = Snooze::new(buffer.backoff());
+        let mut result = attempt();
+        for _ in 0..CONTENDED_RETRIES {
+            if !matches!(result, Err(EnqueueError::Contended)) {
+                break;
             }
-            None => {
+            snooze.snooze(buffer);
+            result = attempt();
+        }
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(EnqueueError::Poisoned) => Err(self.poisoned()),
+            Err(EnqueueError::Stale) => Err(self.stale()),
+            Err(_) => {
                 if !self.keep_alive.load(Ordering::Acquire) {
                     return Err(std::io::Error::new(
                         std::io::ErrorKind::BrokenPipe,
@@ -152,6 +578,144 @@ impl Producer {
         }
     }
 
+    /// Claims the next slot of the channel and returns a writer that streams
+    /// the message straight into it, for code written against `io::Write`.
+    ///
+    /// The message is published when the writer is flushed or dropped, with
+    /// every byte written so far as its payload. It must fit inline in the slot
+    /// (`ChannelBuilder::with_inline_size`); it never spills to the overflow
+    /// band. A write that would overrun the slot fails with `InvalidInput`, and
+    /// the message is then discarded: consumers skip it.
+    ///
+    /// Consumers wait at the claimed slot until it is published, so write the
+    /// message promptly. Fails like `send` when the ring is full.
+    pub fn writer(&self) -> std::io::Result<SlotWriter<'_>> {
+        self.check_writable()?;
+        self.take_tokens(1)?;
+        let buffer = self.channel.buffer();
+        let timestamp_ns = self.timestamps.now_ns(self.clock);
+        let meta = MessageMeta {
+            message_id: buffer.reserve_message_ids(1),
+            timestamp_ns,
+            channel_id: self.channel_id,
+            message_type: DEFAULT_MESSAGE_TYPE,
+            sender_pid: std::process::id(),
+            sender_runtime: 1, // Rust
+            flags: self.clock_flag(timestamp_ns),
+            ..Default::default()
+        };
+        let (position, slot) = self
+            .retry_contended(buffer, || buffer.try_claim())
+            .inspect_err(|_| self.give_back_tokens(1))?;
+        Ok(SlotWriter::new(buffer, meta, position, slot))
+    }
+
+    /// Whether a send would find a free slot right now (always true on an
+    /// overwrite channel). The answer may be stale as soon as it is returned.
+    pub(super) fn has_space(&self) -> bool {
+        let buffer = self.channel.buffer();
+        buffer.is_overwrite() || buffer.len() < self.channel.capacity
+    }
+
+    /// Fails like `forward` would for a payload of `len` bytes, apart from a
+    /// full ring, so a message is only taken from its channel once it can be
+    /// sent on.
+    pub(super) fn check_forward(&self, len: usize) -> std::io::Result<()> {
+        self.check_writable()?;
+        if !self.keep_alive.load(Ordering::Acquire) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Consumer has terminated",
+            ));
+        }
+        if len > self.max_message_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Message too large ({} > {})", len, self.max_message_size),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Enqueues a message taken from another channel, keeping its metadata
+    /// apart from the id and channel, and waits like `send_blocking` while the
+    /// ring is full.
+    pub(super) fn forward(&self, meta: MessageMeta, payload: &[u8]) -> std::io::Result<()> {
+        let buffer = self.channel.buffer();
+
+        loop {
+            let seen = buffer.space_signal();
+            match self.enqueue_meta(buffer, &[payload], meta) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    self.wait_to_send(buffer, seen);
+                    if !self.keep_alive.load(Ordering::Acquire) {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::BrokenPipe,
+                            "Consumer has terminated",
+                        ));
+                    }
+                }
+                result => return result.map(drop),
+            }
+        }
+    }
+
+    /// Fails with `PermissionDenied` once the channel has been sealed,
+    /// with `InvalidData` once it has been poisoned, and with `NotConnected`
+    /// once it has been resized or moved under this producer.
+    fn check_writable(&self) -> std::io::Result<()> {
+        if self.channel.buffer().is_poisoned() {
+            return Err(self.poisoned());
+        }
+        if self.channel.buffer().is_stale() {
+            return Err(self.stale());
+        }
+        if self.channel.buffer().is_sealed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Channel {} is sealed", self.channel_id),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The error for a send on a poisoned channel.
+    fn poisoned(&self) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Channel {} poisoned", self.channel_id),
+        )
+    }
+
+    /// The error for a send on a channel resized or moved since the producer
+    /// attached.
+    fn stale(&self) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::NotConnected,
+            format!(
+                "Channel {} was resized or moved; attach again",
+                self.channel_id
+            ),
+        )
+    }
+
+    /// Wakes every consumer parked on the channel, so nothing already sent is
+    /// left waiting on a consumer's poll interval. Call it before the producer
+    /// goes idle or shuts down. Costs one shared load when no consumer is
+    /// parked.
+    pub fn flush(&self) {
+        self.channel.buffer().wake_parked_consumers();
+    }
+
+    /// Closes this producer's side of the channel; the same as dropping it.
+    ///
+    /// Once the last producer attached to the channel has closed, consumers
+    /// receive what is still buffered and then fail with `BrokenPipe` instead of
+    /// waiting for more.
+    pub fn close(self) {
+        drop(self)
+    }
+
     /// Returns the channel ID for this producer
     pub fn channel_id(&self) -> u32 {
         self.channel_id
@@ -165,8 +729,39 @@ impl Producer {
         &self.keep_alive
     }
 
+    /// Returns the backoff policy used when the channel is contended
+    pub fn backoff(&self) -> crate::MPMC::Backoff {
+        self.channel.buffer().backoff()
+    }
+
     /// Returns the maximum message size that can be sent
     pub fn max_message_size(&self) -> usize {
         self.max_message_size
     }
+
+    /// Returns the number of slots in the channel's ring
+    pub fn capacity(&self) -> usize {
+        self.channel.capacity
+    }
+
+    /// Returns the payload bytes each slot holds inline, as set by
+    /// `ChannelBuilder::with_slot_size`
+    pub fn slot_size(&self) -> usize {
+        self.channel.buffer().inline_size()
+    }
+
+    /// Returns the bytes each slot occupies in shared memory, header and
+    /// padding included
+    pub fn stride(&self) -> usize {
+        self.channel.buffer().stride()
+    }
+}
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        if let Some(index) = self.attached {
+            self.channel.buffer().unregister_attached(index);
+        }
+        self.channel.buffer().detach_producer();
+    }
 }
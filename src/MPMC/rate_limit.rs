@@ -0,0 +1,84 @@
+// Token-bucket rate limiting for producers.
+//
+// The bucket lives in the `Producer`, not in shared memory: it caps what one
+// producer handle sends, and producers in other processes (or other handles in
+// this one) have buckets of their own.
+
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tokens a full bucket holds, as a fraction of a second's worth, so a producer
+/// that was idle may send a burst of this much before being held to the rate.
+const BURST_SECONDS: f64 = 0.1;
+
+/// A bucket of send tokens refilled at a fixed rate.
+pub(crate) struct RateLimit {
+    per_second: u32,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimit {
+    /// A full bucket refilled with `per_second` tokens a second.
+    pub(crate) fn new(per_second: u32) -> Self {
+        let burst = (per_second as f64 * BURST_SECONDS).max(1.0);
+        Self {
+            per_second,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Messages per second this bucket allows.
+    pub(crate) fn per_second(&self) -> u32 {
+        self.per_second
+    }
+
+    /// Most tokens one `try_take` can ever get.
+    pub(crate) fn burst(&self) -> u32 {
+        self.burst as u32
+    }
+
+    /// Take `count` tokens if the bucket holds that many.
+    pub(crate) fn try_take(&self, count: u32) -> bool {
+        let mut bucket = self.bucket.lock();
+        self.refill(&mut bucket);
+        if bucket.tokens < count as f64 {
+            return false;
+        }
+        bucket.tokens -= count as f64;
+        true
+    }
+
+    /// Return tokens taken for a send that then failed.
+    pub(crate) fn give_back(&self, count: u32) {
+        let mut bucket = self.bucket.lock();
+        bucket.tokens = (bucket.tokens + count as f64).min(self.burst);
+    }
+
+    /// How long until the bucket holds a token; zero if it already does.
+    pub(crate) fn delay(&self) -> Duration {
+        let mut bucket = self.bucket.lock();
+        self.refill(&mut bucket);
+        let missing = 1.0 - bucket.tokens;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(missing / self.per_second as f64)
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second as f64).min(self.burst);
+        bucket.refilled = now;
+    }
+}
@@ -0,0 +1,135 @@
+// An eventfd that turns readable when a channel is signalled, for epoll-based
+// event loops. Linux only.
+//
+// Producers in other processes cannot write to this process's eventfd, so a
+// watcher thread parks on the channel's futex and forwards every signal to it.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use super::consumer::{Consumer, LIVENESS_INTERVAL};
+use crate::MPMC::Buffer::RingBuffer;
+
+/// The channel's futex word, handed to the watcher thread.
+struct SignalPtr(*const AtomicU32);
+
+unsafe impl Send for SignalPtr {}
+
+impl SignalPtr {
+    /// # Safety
+    /// The mapping holding the word must still be alive.
+    unsafe fn get(&self) -> &AtomicU32 {
+        &*self.0
+    }
+}
+
+/// A consumer's readiness eventfd and the thread that feeds it.
+pub(super) struct Readiness {
+    fd: OwnedFd,
+    stop: Arc<AtomicBool>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl Readiness {
+    fn new(ring: &RingBuffer) -> io::Result<Self> {
+        let raw = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // The watcher is parked nearly all the time, so it counts as a waiter
+        // for as long as it runs and every send wakes it
+        ring.data_waiters().fetch_add(1, Ordering::SeqCst);
+        let signal = ring.signal();
+
+        // Sampled before the first notification, so a send after it is not missed
+        let mut seen = signal.load(Ordering::Acquire);
+        // Messages sent before the fd existed may be waiting
+        notify(raw);
+
+        let signal = SignalPtr(signal);
+        let watcher = std::thread::Builder::new()
+            .name("dmxp-readiness".into())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    // Safety: the consumer joins this thread before unmapping
+                    let signal = unsafe { signal.get() };
+                    while !stop.load(Ordering::Acquire) {
+                        crate::Core::futex::futex_park(signal, seen, LIVENESS_INTERVAL);
+                        let now = signal.load(Ordering::Acquire);
+                        if now != seen {
+                            seen = now;
+                            notify(raw);
+                        }
+                    }
+                }
+            })
+            .inspect_err(|_| {
+                ring.data_waiters().fetch_sub(1, Ordering::SeqCst);
+            })?;
+
+        Ok(Self {
+            fd,
+            stop,
+            watcher: Some(watcher),
+        })
+    }
+
+    /// Stop the watcher thread, waking it until it has exited.
+    fn finish(&mut self, ring: &RingBuffer) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(watcher) = self.watcher.take() {
+            while !watcher.is_finished() {
+                crate::Core::futex::futex_wake_all(ring.signal());
+                std::thread::yield_now();
+            }
+            let _ = watcher.join();
+            ring.data_waiters().fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Add one to the eventfd counter, making it readable.
+fn notify(fd: RawFd) {
+    let one = 1u64;
+    // Only fails with EAGAIN once the counter is saturated, when it is readable anyway
+    unsafe { libc::write(fd, &one as *const u64 as *const libc::c_void, 8) };
+}
+
+impl Consumer {
+    /// Returns an eventfd that becomes readable when a producer signals the
+    /// channel, to register with an `epoll` (or `poll`) event loop alongside
+    /// sockets.
+    ///
+    /// The fd is created on first call and owned by the consumer; it stays
+    /// valid until the consumer is dropped. It is non-blocking, and readable
+    /// right after creation in case messages were already waiting. Once it
+    /// reports readable, read 8 bytes from it to reset it, then call `receive`
+    /// until it returns `None`: a send after the reset makes it readable again,
+    /// so no message is left behind. Like any readiness signal it may fire
+    /// without a message to receive.
+    pub fn readiness_fd(&self) -> io::Result<RawFd> {
+        let mut readiness = self.readiness.lock();
+        if let Some(readiness) = readiness.as_ref() {
+            return Ok(readiness.fd.as_raw_fd());
+        }
+        let created = Readiness::new(self.channel.buffer())?;
+        let fd = created.fd.as_raw_fd();
+        *readiness = Some(created);
+        Ok(fd)
+    }
+
+    /// Stops the thread feeding the readiness fd, which still references the
+    /// mapping.
+    pub(super) fn finish_readiness(&self) {
+        if let Some(readiness) = self.readiness.lock().as_mut() {
+            readiness.finish(self.channel.buffer());
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// src/MPMC/sharded_producer.rs
+
+use super::Producer;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A producer that spreads messages over several channels.
+///
+/// `send` hands each message to the next channel in rotation, skipping full
+/// ones, so load is shared evenly while every channel keeps up. `send_to`
+/// routes by key instead, so all messages with one key land on the same
+/// channel and stay in order.
+pub struct ShardedProducer {
+    producers: Vec<Producer>,
+    next: AtomicUsize,
+}
+
+impl ShardedProducer {
+    /// Combine `producers` into one; the rotation follows their order.
+    /// Fails with `InvalidInput` if there are none.
+    pub fn new(producers: Vec<Producer>) -> std::io::Result<Self> {
+        if producers.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "ShardedProducer needs at least one channel",
+            ));
+        }
+        Ok(Self {
+            producers,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Sends a message on the next channel in rotation that has room.
+    ///
+    /// # Returns
+    /// * `Ok(channel_id)` with the channel the message went to
+    /// * `Err(WouldBlock)` if every channel is full
+    /// * Any other error from the channel that was tried, e.g. `InvalidInput`
+    ///   for a message that is too large
+    pub fn send<T: AsRef<[u8]>>(&self, message: T) -> std::io::Result<u32> {
+        let message = message.as_ref();
+        let count = self.producers.len();
+        let start = self.next.load(Ordering::Relaxed);
+
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            let producer = &self.producers[index];
+            match producer.send(message) {
+                Ok(()) => {
+                    self.next.store((index + 1) % count, Ordering::Relaxed);
+                    return Ok(producer.channel_id());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::WouldBlock,
+            "Failed to enqueue message - all channels full",
+        ))
+    }
+
+    /// Sends a message on the channel `key` maps to, failing with `WouldBlock`
+    /// if that channel is full.
+    ///
+    /// The mapping is a CRC32C of `key` modulo the number of channels, so it
+    /// is the same in every process that lists the channels in the same order.
+    pub fn send_to<K: AsRef<[u8]>, T: AsRef<[u8]>>(
+        &self,
+        key: K,
+        message: T,
+    ) -> std::io::Result<u32> {
+        let producer = &self.producers[self.shard_of(key.as_ref())];
+        producer.send(message)?;
+        Ok(producer.channel_id())
+    }
+
+    /// Returns the ID of the channel `send_to` uses for `key`
+    pub fn channel_for<K: AsRef<[u8]>>(&self, key: K) -> u32 {
+        self.producers[self.shard_of(key.as_ref())].channel_id()
+    }
+
+    /// Returns the IDs of the channels this producer writes to, in rotation order
+    pub fn channel_ids(&self) -> Vec<u32> {
+        self.producers
+            .iter()
+            .map(|producer| producer.channel_id())
+            .collect()
+    }
+
+    fn shard_of(&self, key: &[u8]) -> usize {
+        crc32c::crc32c(key) as usize % self.producers.len()
+    }
+}
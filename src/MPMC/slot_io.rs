@@ -0,0 +1,172 @@
+// src/MPMC/slot_io.rs
+//
+// `io::Write` and `io::Read` over a single slot, for code that streams a
+// message instead of handing over a finished `&[u8]`.
+
+use crate::MPMC::Buffer::{MessageRef, RingBuffer, Slot};
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+use std::io;
+
+/// A message being written in place into a claimed slot, returned by
+/// `Producer::writer`.
+///
+/// The slot is published on `flush` or drop. Like a [`MessageRef`], a writer
+/// holds up the consumers of its channel while it is alive; keep it
+/// short-lived and never `mem::forget` one, or the channel will wedge.
+pub struct SlotWriter<'a> {
+    ring: &'a RingBuffer,
+    meta: MessageMeta,
+    position: u64,
+    slot: *mut Slot,
+    len: usize,
+    overrun: bool,
+    published: bool,
+}
+
+impl<'a> SlotWriter<'a> {
+    pub(crate) fn new(
+        ring: &'a RingBuffer,
+        meta: MessageMeta,
+        position: u64,
+        slot: *mut Slot,
+    ) -> Self {
+        Self {
+            ring,
+            meta,
+            position,
+            slot,
+            len: 0,
+            overrun: false,
+            published: false,
+        }
+    }
+
+    /// Bytes written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing has been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes the slot holds in total.
+    pub fn capacity(&self) -> usize {
+        self.ring.inline_size()
+    }
+
+    /// Publish the slot, discarded if a write overran it.
+    fn publish(&mut self) {
+        if self.published {
+            return;
+        }
+        self.published = true;
+        // Safety: the slot was claimed for this writer and is published once
+        unsafe {
+            self.ring
+                .publish_in_place(self.position, self.slot, &self.meta, self.len, self.overrun)
+        };
+        self.ring.beat();
+        if !self.overrun {
+            self.ring.signal_consumer();
+        }
+    }
+
+    fn overrun_error(&self, len: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Message too large ({} > {}); it was discarded",
+                len,
+                self.capacity()
+            ),
+        )
+    }
+}
+
+impl io::Write for SlotWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.published {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Message already published",
+            ));
+        }
+        let end = self.len + buf.len();
+        if self.overrun || end > self.capacity() {
+            self.overrun = true;
+            return Err(self.overrun_error(end));
+        }
+        // Safety: the slot is claimed for this writer and `end` is within its payload area
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                buf.as_ptr(),
+                RingBuffer::payload_ptr(self.slot).add(self.len),
+                buf.len(),
+            );
+        }
+        self.len = end;
+        Ok(buf.len())
+    }
+
+    /// Publishes the message; later writes fail. Fails with `InvalidInput` if
+    /// the message was discarded because a write overran the slot.
+    fn flush(&mut self) -> io::Result<()> {
+        let overrun = self.overrun && !self.published;
+        self.publish();
+        if overrun {
+            return Err(self.overrun_error(self.len));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SlotWriter<'_> {
+    fn drop(&mut self) {
+        self.publish();
+    }
+}
+
+/// A received message read through `io::Read`, returned by `Consumer::reader`.
+///
+/// Reads straight from the slot, which is handed back to producers when the
+/// reader is dropped; see [`MessageRef`] for how long it may be held.
+pub struct SlotReader<'a> {
+    message: MessageRef<'a>,
+    position: usize,
+}
+
+impl<'a> SlotReader<'a> {
+    pub(crate) fn new(message: MessageRef<'a>) -> Self {
+        Self {
+            message,
+            position: 0,
+        }
+    }
+
+    /// Metadata written by the producer for this message.
+    pub fn meta(&self) -> &MessageMeta {
+        self.message.meta()
+    }
+}
+
+impl io::Read for SlotReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let rest = &self.message[self.position..];
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+impl io::BufRead for SlotReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.message[self.position..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position = (self.position + amt).min(self.message.len());
+    }
+}
@@ -0,0 +1,127 @@
+// src/MPMC/tail.rs
+//
+// A read-only observer of a channel, for tailing production traffic while
+// debugging without taking messages from its real consumers.
+
+use crate::Core::alloc::{ChannelPartition, SharedMemoryAllocator};
+use crate::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_DISCARDED};
+
+/// What [`TailConsumer::receive`] found next.
+#[derive(Debug, Clone)]
+pub enum TailEvent {
+    /// A message as its producer stored it: checksums are not verified and
+    /// compressed payloads are not decompressed.
+    Message(MessageMeta, Vec<u8>),
+    /// This many messages were overwritten before the tail could read them.
+    Lagged(u64),
+}
+
+/// Watches the messages of a channel as they are published, built with
+/// `ChannelBuilder::build_tail_consumer`.
+///
+/// Like a broadcast subscriber it reads with a cursor of its own, but that
+/// cursor lives in this process: producers never wait for it and consumers
+/// never see it, and it does not count as an attached consumer. Every message
+/// is shown, whether or not a consumer has already taken it, until a producer
+/// reuses its slot; a tail that falls a whole ring behind skips ahead and
+/// reports the gap as [`TailEvent::Lagged`] instead of reading rewritten slots.
+/// Spilled messages can only be read until a consumer takes them, and the
+/// priority band is not watched.
+pub struct TailConsumer {
+    // None when built in an allocator that lives for the whole process
+    _allocator: Option<SharedMemoryAllocator>,
+    channel: ChannelPartition,
+    channel_id: u32,
+    position: u64,
+    lagged: u64,
+}
+
+impl TailConsumer {
+    /// Starts at the oldest message no consumer has taken yet, or on a
+    /// broadcast channel at the next one to be sent.
+    pub(crate) fn new(
+        allocator: Option<SharedMemoryAllocator>,
+        channel: ChannelPartition,
+        channel_id: u32,
+    ) -> Self {
+        let ring = channel.buffer();
+        let position = if ring.is_broadcast() {
+            ring.tail_position()
+        } else {
+            ring.head_position()
+        };
+        Self {
+            _allocator: allocator,
+            channel,
+            channel_id,
+            position,
+            lagged: 0,
+        }
+    }
+
+    /// The next message after the last one seen, without waiting.
+    ///
+    /// # Returns
+    /// * `Ok(Some(TailEvent::Message(meta, data)))` for the next message
+    /// * `Ok(Some(TailEvent::Lagged(n)))` if the next `n` messages were
+    ///   overwritten; the following call resumes after them
+    /// * `Ok(None)` if nothing newer has been published yet
+    /// * `Err` with `NotConnected` once the channel was resized or moved, as
+    ///   the tail's view of it no longer matches; build a new tail
+    pub fn receive(&mut self) -> std::io::Result<Option<TailEvent>> {
+        let ring = self.channel.buffer();
+        let capacity = ring.capacity as u64;
+        loop {
+            if ring.is_stale() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    format!(
+                        "Channel {} was resized or moved; attach again",
+                        self.channel_id
+                    ),
+                ));
+            }
+            // Positions a lap behind the tail may already be rewritten
+            let oldest = ring.tail_position().saturating_sub(capacity);
+            if self.position < oldest {
+                return Ok(Some(self.skip(oldest - self.position)));
+            }
+            match ring.observe(self.position) {
+                // Made stale meanwhile: the next pass reports it
+                Ok(None) if ring.is_stale() => {}
+                Ok(None) => return Ok(None),
+                Ok(Some((meta, payload))) => {
+                    self.position += 1;
+                    if meta.flags & FLAG_DISCARDED == 0 {
+                        return Ok(Some(TailEvent::Message(meta, payload)));
+                    }
+                }
+                // Lapped while copying: measure the gap from the new tail
+                Err(()) if ring.tail_position().saturating_sub(capacity) > self.position => {}
+                // A spilled message whose block was released
+                Err(()) => return Ok(Some(self.skip(1))),
+            }
+        }
+    }
+
+    fn skip(&mut self, count: u64) -> TailEvent {
+        self.position += count;
+        self.lagged += count;
+        TailEvent::Lagged(count)
+    }
+
+    /// Sequence of the next message this tail will read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Messages skipped so far because they were overwritten first.
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+
+    /// Returns the channel ID being watched
+    pub fn channel_id(&self) -> u32 {
+        self.channel_id
+    }
+}
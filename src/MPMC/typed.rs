@@ -0,0 +1,125 @@
+// Fixed-size typed messages, enabled with the `bytemuck` feature.
+//
+// A `bytemuck::Pod` value is its own wire format: sending borrows its bytes
+// without copying, and receiving copies the payload back into a `T` after
+// checking its length, so neither side needs `unsafe`.
+
+use std::io;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bytemuck::Pod;
+
+use super::{Consumer, Producer};
+
+/// A [`Producer`] that sends values of one `Pod` type.
+///
+/// Created with [`Producer::into_typed`].
+pub struct TypedProducer<T> {
+    producer: Producer,
+    _type: PhantomData<fn(&T)>,
+}
+
+/// A [`Consumer`] that receives values of one `Pod` type.
+///
+/// Created with [`Consumer::into_typed`]. Every payload must be exactly
+/// `size_of::<T>()` bytes; anything else fails the receive with `InvalidData`.
+pub struct TypedConsumer<T> {
+    consumer: Consumer,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl Producer {
+    /// Turns the producer into one that sends `T` records.
+    pub fn into_typed<T: Pod>(self) -> TypedProducer<T> {
+        TypedProducer {
+            producer: self,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl Consumer {
+    /// Turns the consumer into one that receives `T` records.
+    pub fn into_typed<T: Pod>(self) -> TypedConsumer<T> {
+        TypedConsumer {
+            consumer: self,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod> TypedProducer<T> {
+    /// Sends `value` as its `size_of::<T>()` bytes. Fails like `Producer::send`.
+    pub fn send(&self, value: &T) -> io::Result<()> {
+        self.producer.send(bytemuck::bytes_of(value))
+    }
+
+    /// Sends `value`, waiting for space like `Producer::send_blocking`.
+    pub fn send_blocking(&self, value: &T) -> io::Result<()> {
+        self.producer.send_blocking(bytemuck::bytes_of(value))
+    }
+
+    /// The underlying untyped producer.
+    pub fn inner(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Turns back into the untyped producer.
+    pub fn into_inner(self) -> Producer {
+        self.producer
+    }
+}
+
+impl<T: Pod> TypedConsumer<T> {
+    /// Receives a record if one is available.
+    ///
+    /// # Returns
+    /// * `Ok(Some(value))` if a record was received
+    /// * `Ok(None)` if no message is available
+    /// * `Err` with `InvalidData` if the payload is not `size_of::<T>()` bytes
+    ///   (the message is consumed either way)
+    /// * Otherwise the same errors as `Consumer::receive`
+    pub fn recv(&self) -> io::Result<Option<T>> {
+        self.consumer.receive()?.map(decode).transpose()
+    }
+
+    /// Receives a record, blocking until one arrives like
+    /// `Consumer::receive_blocking`.
+    pub fn recv_blocking(&self) -> io::Result<T> {
+        decode(self.consumer.receive_blocking()?)
+    }
+
+    /// Receives a record, waiting at most `timeout` for one to arrive.
+    pub fn recv_timeout(&self, timeout: Duration) -> io::Result<Option<T>> {
+        self.consumer
+            .receive_timeout(timeout)?
+            .map(decode)
+            .transpose()
+    }
+
+    /// The underlying untyped consumer.
+    pub fn inner(&self) -> &Consumer {
+        &self.consumer
+    }
+
+    /// Turns back into the untyped consumer.
+    pub fn into_inner(self) -> Consumer {
+        self.consumer
+    }
+}
+
+/// Copies a payload into a `T`; the payload need not be aligned for `T`.
+fn decode<T: Pod>(payload: Vec<u8>) -> io::Result<T> {
+    bytemuck::try_pod_read_unaligned(&payload).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Payload of {} bytes is not a {} ({} bytes)",
+                payload.len(),
+                std::any::type_name::<T>(),
+                std::mem::size_of::<T>()
+            ),
+        )
+    })
+}
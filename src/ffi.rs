@@ -1,17 +1,29 @@
 use crate::Core::alloc::SharedMemoryAllocator;
+use crate::MPMC::Buffer::layout::DEFAULT_MAX_CHANNELS;
 use crate::MPMC::ChannelBuilder;
 use crate::MPMC::Consumer;
 use crate::MPMC::Producer;
+use crate::MPMC::Structs::Buffer_Structs::MessageMeta;
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::ptr;
+use std::sync::OnceLock;
 
 // Error codes
-const DMXP_SUCCESS: i32 = 0;
-const DMXP_ERROR_NULL_POINTER: i32 = -1;
-const DMXP_ERROR_INVALID_ARG: i32 = -2;
-const DMXP_ERROR_CHANNEL_FULL: i32 = -4;
-const DMXP_ERROR_EMPTY: i32 = -5;
-const DMXP_ERROR_INTERNAL: i32 = -6;
-const DMXP_ERROR_TIMEOUT: i32 = -7;
+/// The call succeeded.
+pub const DMXP_SUCCESS: i32 = 0;
+/// A required pointer argument was null.
+pub const DMXP_ERROR_NULL_POINTER: i32 = -1;
+/// An argument was out of range, e.g. an output buffer too small for the message.
+pub const DMXP_ERROR_INVALID_ARG: i32 = -2;
+/// The channel is full; retry the send later.
+pub const DMXP_ERROR_CHANNEL_FULL: i32 = -4;
+/// No message was available.
+pub const DMXP_ERROR_EMPTY: i32 = -5;
+/// Any other failure.
+pub const DMXP_ERROR_INTERNAL: i32 = -6;
+/// No message arrived before the timeout.
+pub const DMXP_ERROR_TIMEOUT: i32 = -7;
 
 /// Handle to a producer instance (opaque pointer)
 pub struct ProducerHandle {
@@ -23,6 +35,11 @@ pub struct ConsumerHandle {
     inner: Consumer,
 }
 
+/// Handle to an attached shared memory segment (opaque pointer)
+pub struct AllocatorHandle {
+    inner: SharedMemoryAllocator,
+}
+
 /// Structure to return metadata to FFI caller
 /// Must match simple layout for C
 #[repr(C)]
@@ -35,19 +52,175 @@ pub struct FFIMessageMeta {
     pub sender_runtime: u16,
     pub flags: u16,
     pub payload_len: u32,
+    pub checksum: u32,
+}
+
+impl From<&MessageMeta> for FFIMessageMeta {
+    fn from(meta: &MessageMeta) -> Self {
+        Self {
+            message_id: meta.message_id,
+            timestamp_ns: meta.timestamp_ns,
+            channel_id: meta.channel_id,
+            message_type: meta.message_type,
+            sender_pid: meta.sender_pid,
+            sender_runtime: meta.sender_runtime,
+            flags: meta.flags,
+            payload_len: meta.payload_len,
+            checksum: meta.checksum,
+        }
+    }
+}
+
+/// One message of a `dmxp_producer_send_batch` call.
+#[repr(C)]
+pub struct DmxpMsg {
+    /// The payload; must not be null.
+    pub data: *const u8,
+    /// Payload length in bytes.
+    pub len: usize,
+}
+
+/// Capacity and occupancy of a channel, filled by `dmxp_get_channel_info`.
+#[repr(C)]
+pub struct DmxpChannelInfo {
+    /// The channel ID.
+    pub id: u32,
+    /// Number of slots in the ring.
+    pub capacity: u64,
+    /// Messages waiting to be received.
+    pub used_slots: u64,
+    /// Byte offset of the channel's region in the shared memory segment.
+    pub offset: u64,
+}
+
+// -----------------------------------------------------------------------------
+// Errors
+// -----------------------------------------------------------------------------
+
+thread_local! {
+    /// Message for the most recent error code returned on this thread.
+    static LAST_ERROR: RefCell<Cow<'static, str>> = const { RefCell::new(Cow::Borrowed("")) };
+}
+
+/// Record `message` as this thread's last error.
+fn set_last_error(message: impl Into<Cow<'static, str>>) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = message.into());
+}
+
+/// Record `message` as this thread's last error and return `code`.
+fn fail(code: i32, message: impl Into<Cow<'static, str>>) -> i32 {
+    set_last_error(message);
+    code
+}
+
+/// Copy the message for the last error returned on this thread into `buf`.
+///
+/// Every `dmxp_*` function that returns a negative code (or NULL) records why;
+/// successful calls leave the message untouched. The message is truncated to
+/// fit `len - 1` bytes and always null-terminated, unless `buf` is NULL or
+/// `len` is 0, in which case nothing is written.
+///
+/// # Returns
+/// * The full message length in bytes, excluding the terminator. A value
+///   `>= len` means the message was truncated. 0 if no error was recorded.
+#[no_mangle]
+pub extern "C" fn dmxp_last_error(buf: *mut std::ffi::c_char, len: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let message = last.borrow();
+        if !buf.is_null() && len > 0 {
+            // Cut on a char boundary so the C string stays valid UTF-8
+            let mut n = message.len().min(len - 1);
+            while !message.is_char_boundary(n) {
+                n -= 1;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(message.as_ptr(), buf.cast::<u8>(), n);
+                *buf.add(n) = 0;
+            }
+        }
+        message.len()
+    })
 }
 
 // -----------------------------------------------------------------------------
 // Allocator / Utils
 // -----------------------------------------------------------------------------
 
+/// The shared memory segment set up by `dmxp_init`, with its size in bytes.
+static ALLOCATOR: OnceLock<(usize, SharedMemoryAllocator)> = OnceLock::new();
+
+/// The allocator cached by `dmxp_init`, if it was called.
+fn allocator() -> Option<&'static (usize, SharedMemoryAllocator)> {
+    ALLOCATOR.get()
+}
+
+/// Attach to the shared memory segment, creating it if it does not exist yet.
+///
+/// Must be called once per process before any other `dmxp_*` function; they
+/// all use the segment set up here. `size_bytes` is the size of the whole
+/// segment (at least a few MB; 128MB is the Rust default). Calling it again
+/// with the same size is a no-op.
+///
+/// # Returns
+/// * 0 on success.
+/// * `DMXP_ERROR_INVALID_ARG` if `size_bytes` is 0, or the process was already
+///   initialized with a different size.
+/// * `DMXP_ERROR_INTERNAL` if the segment could not be created or attached,
+///   e.g. it exists with a smaller size.
+#[no_mangle]
+pub extern "C" fn dmxp_init(size_bytes: usize) -> i32 {
+    if size_bytes == 0 {
+        return fail(DMXP_ERROR_INVALID_ARG, "size_bytes must be non-zero");
+    }
+    if let Some((size, _)) = allocator() {
+        return init_size_matches(*size, size_bytes);
+    }
+
+    let opened = match SharedMemoryAllocator::attach(size_bytes) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            SharedMemoryAllocator::new(size_bytes, DEFAULT_MAX_CHANNELS)
+        }
+        result => result,
+    };
+    match opened {
+        Ok(allocator) => {
+            // Another thread may have won the race; its size decides
+            let (size, _) = ALLOCATOR.get_or_init(|| (size_bytes, allocator));
+            init_size_matches(*size, size_bytes)
+        }
+        Err(e) => fail(
+            DMXP_ERROR_INTERNAL,
+            format!("Failed to open shared memory: {}", e),
+        ),
+    }
+}
+
+/// Result of a `dmxp_init` call when the process is already initialized.
+fn init_size_matches(size: usize, size_bytes: usize) -> i32 {
+    if size == size_bytes {
+        return DMXP_SUCCESS;
+    }
+    fail(
+        DMXP_ERROR_INVALID_ARG,
+        format!(
+            "Already initialized with {} bytes, not {}",
+            size, size_bytes
+        ),
+    )
+}
+
+/// Error for calls made before `dmxp_init`.
+const NOT_INITIALIZED: &str = "dmxp_init must be called first";
+
 /// Get the number of active channels.
+///
+/// # Returns
+/// * The channel count, or `DMXP_ERROR_INVALID_ARG` if `dmxp_init` was not called.
 #[no_mangle]
 pub extern "C" fn dmxp_channel_count() -> i32 {
-    const SHM_SIZE: usize = 128 * 1024 * 1024;
-    match SharedMemoryAllocator::attach(SHM_SIZE) {
-        Ok(allocator) => allocator.channel_count() as i32,
-        Err(_) => -1, // Error attaching
+    match allocator() {
+        Some((_, allocator)) => allocator.channel_count() as i32,
+        None => fail(DMXP_ERROR_INVALID_ARG, NOT_INITIALIZED),
     }
 }
 
@@ -60,33 +233,153 @@ pub extern "C" fn dmxp_channel_count() -> i32 {
 ///
 /// # Returns
 /// * 0 on success.
+/// * `DMXP_ERROR_INVALID_ARG` if `dmxp_init` was not called.
 #[no_mangle]
 pub extern "C" fn dmxp_list_channels(
     out_buf: *mut u32,
     max_count: usize,
     out_count: *mut usize,
 ) -> i32 {
-    const SHM_SIZE: usize = 128 * 1024 * 1024;
+    match allocator() {
+        Some((_, allocator)) => list_channels(allocator, out_buf, max_count, out_count),
+        None => fail(DMXP_ERROR_INVALID_ARG, NOT_INITIALIZED),
+    }
+}
+
+/// Describe channel `channel_id`.
+///
+/// `used_slots` is a snapshot; producers and consumers may change it at any time.
+///
+/// # Returns
+/// * 0 on success.
+/// * `DMXP_ERROR_INVALID_ARG` if the channel does not exist or `dmxp_init` was
+///   not called.
+#[no_mangle]
+pub extern "C" fn dmxp_get_channel_info(channel_id: u32, out: *mut DmxpChannelInfo) -> i32 {
+    match allocator() {
+        Some((_, allocator)) => channel_info(allocator, channel_id, out),
+        None => fail(DMXP_ERROR_INVALID_ARG, NOT_INITIALIZED),
+    }
+}
 
+fn list_channels(
+    allocator: &SharedMemoryAllocator,
+    out_buf: *mut u32,
+    max_count: usize,
+    out_count: *mut usize,
+) -> i32 {
     if out_count.is_null() {
-        return DMXP_ERROR_NULL_POINTER;
+        return fail(DMXP_ERROR_NULL_POINTER, "out_count is null");
     }
 
-    match SharedMemoryAllocator::attach(SHM_SIZE) {
-        Ok(allocator) => {
-            let channels = allocator.get_channels();
-            let count = std::cmp::min(channels.len(), max_count);
+    let channels = allocator.get_channels();
+    let count = std::cmp::min(channels.len(), max_count);
 
-            unsafe { *out_count = count };
+    unsafe { *out_count = count };
 
-            if !out_buf.is_null() {
-                for i in 0..count {
-                    unsafe { *out_buf.add(i) = channels[i].id() };
-                }
-            }
-            DMXP_SUCCESS
+    if !out_buf.is_null() {
+        for i in 0..count {
+            unsafe { *out_buf.add(i) = channels[i].id() };
+        }
+    }
+    DMXP_SUCCESS
+}
+
+fn channel_info(
+    allocator: &SharedMemoryAllocator,
+    channel_id: u32,
+    out: *mut DmxpChannelInfo,
+) -> i32 {
+    if out.is_null() {
+        return fail(DMXP_ERROR_NULL_POINTER, "out is null");
+    }
+
+    let Some(info) = allocator
+        .channel_infos()
+        .into_iter()
+        .find(|info| info.id == channel_id)
+    else {
+        return fail(
+            DMXP_ERROR_INVALID_ARG,
+            format!("Channel {} not found", channel_id),
+        );
+    };
+
+    unsafe {
+        *out = DmxpChannelInfo {
+            id: info.id,
+            capacity: info.capacity as u64,
+            used_slots: info.used_slots as u64,
+            offset: info.offset as u64,
+        };
+    }
+    DMXP_SUCCESS
+}
+
+// -----------------------------------------------------------------------------
+// Allocator handle
+// -----------------------------------------------------------------------------
+
+/// Attach to the existing shared memory segment of `size_bytes` and keep it
+/// mapped until `dmxp_allocator_close`.
+///
+/// Meant for monitors: the handle needs no `dmxp_init`, never creates the
+/// segment, and queries through it reuse the one mapping. Returns null if the
+/// segment does not exist or is smaller than `size_bytes`.
+#[no_mangle]
+pub extern "C" fn dmxp_allocator_open(size_bytes: usize) -> *mut AllocatorHandle {
+    match SharedMemoryAllocator::attach(size_bytes) {
+        Ok(allocator) => Box::into_raw(Box::new(AllocatorHandle { inner: allocator })),
+        Err(e) => {
+            set_last_error(format!("Failed to open shared memory: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// `dmxp_channel_count` through an allocator handle.
+#[no_mangle]
+pub extern "C" fn dmxp_allocator_channel_count(handle: *mut AllocatorHandle) -> i32 {
+    if handle.is_null() {
+        return fail(DMXP_ERROR_NULL_POINTER, "handle is null");
+    }
+    unsafe { &(*handle).inner }.channel_count() as i32
+}
+
+/// `dmxp_list_channels` through an allocator handle.
+#[no_mangle]
+pub extern "C" fn dmxp_allocator_list_channels(
+    handle: *mut AllocatorHandle,
+    out_buf: *mut u32,
+    max_count: usize,
+    out_count: *mut usize,
+) -> i32 {
+    if handle.is_null() {
+        return fail(DMXP_ERROR_NULL_POINTER, "handle is null");
+    }
+    list_channels(unsafe { &(*handle).inner }, out_buf, max_count, out_count)
+}
+
+/// `dmxp_get_channel_info` through an allocator handle.
+#[no_mangle]
+pub extern "C" fn dmxp_allocator_get_channel_info(
+    handle: *mut AllocatorHandle,
+    channel_id: u32,
+    out: *mut DmxpChannelInfo,
+) -> i32 {
+    if handle.is_null() {
+        return fail(DMXP_ERROR_NULL_POINTER, "handle is null");
+    }
+    channel_info(unsafe { &(*handle).inner }, channel_id, out)
+}
+
+/// Unmap the segment opened by `dmxp_allocator_open`. Channels are unaffected.
+#[no_mangle]
+pub extern "C" fn dmxp_allocator_close(handle: *mut AllocatorHandle) {
+    if !handle.is_null() {
+        unsafe {
+            let _ = Box::from_raw(handle);
         }
-        Err(_) => DMXP_ERROR_INTERNAL,
     }
 }
 
@@ -94,15 +387,18 @@ pub extern "C" fn dmxp_list_channels(
 // Producer API
 // -----------------------------------------------------------------------------
 
+/// Create a producer on `channel_id`, creating the channel with `capacity`
+/// slots if it does not exist. Returns null on failure, including when
+/// `dmxp_init` was not called.
 #[no_mangle]
 pub extern "C" fn dmxp_producer_new(channel_id: u32, capacity: u32) -> *mut ProducerHandle {
-    const SHM_SIZE: usize = 128 * 1024 * 1024;
-
-    // Ensure allocator exists (attach or create)
-    let _ =
-        SharedMemoryAllocator::attach(SHM_SIZE).or_else(|_| SharedMemoryAllocator::new(SHM_SIZE));
+    let Some((_, segment)) = allocator() else {
+        set_last_error(NOT_INITIALIZED);
+        return ptr::null_mut();
+    };
 
     match ChannelBuilder::new()
+        .in_segment(segment)
         .with_channel_id(channel_id)
         .with_capacity(capacity as usize)
         .build_producer()
@@ -112,48 +408,91 @@ pub extern "C" fn dmxp_producer_new(channel_id: u32, capacity: u32) -> *mut Prod
             Box::into_raw(handle)
         }
         Err(e) => {
-            eprintln!("FFI Error: Failed to build producer: {}", e);
+            set_last_error(format!("Failed to build producer: {}", e));
             ptr::null_mut()
         }
     }
 }
 
+/// Send a batch of messages, in order, with one call.
+///
+/// `msgs` points to `count` messages; each `data` must be non-null. As many
+/// messages as fit are sent, always a prefix of the batch, and the number
+/// accepted is written to `out_sent` (also on error).
+///
+/// # Returns
+/// * 0 if at least one message was sent (check `out_sent` for the rest).
+/// * `DMXP_ERROR_CHANNEL_FULL` if none fit.
+/// * `DMXP_ERROR_NULL_POINTER` if `msgs`, `out_sent` or any `data` is null;
+///   nothing is sent.
+/// * `DMXP_ERROR_INVALID_ARG` if a message exceeds the channel's maximum
+///   message size; nothing is sent.
 #[no_mangle]
 pub extern "C" fn dmxp_producer_send_batch(
     handle: *mut ProducerHandle,
-    data_ptrs: *const *const u8,
-    data_lens: *const usize,
+    msgs: *const DmxpMsg,
     count: usize,
+    out_sent: *mut usize,
 ) -> i32 {
-    if handle.is_null() || data_ptrs.is_null() || data_lens.is_null() {
-        return DMXP_ERROR_NULL_POINTER;
+    if handle.is_null() || out_sent.is_null() || (msgs.is_null() && count > 0) {
+        return fail(DMXP_ERROR_NULL_POINTER, "handle, msgs or out_sent is null");
+    }
+    unsafe { *out_sent = 0 };
+    if count == 0 {
+        return DMXP_SUCCESS;
     }
 
     let producer = unsafe { &(*handle).inner };
+    let msgs = unsafe { std::slice::from_raw_parts(msgs, count) };
 
-    // Convert arrays of pointers to Vec<&[u8]>
-    // This involves a loop and slice creation, but it's much faster than individual FFI calls.
-    // We assume all pointers and lengths are valid for 'count'.
-
+    // Validate the whole batch before sending any of it
     let mut messages: Vec<&[u8]> = Vec::with_capacity(count);
+    for (i, msg) in msgs.iter().enumerate() {
+        if msg.data.is_null() {
+            return fail(DMXP_ERROR_NULL_POINTER, format!("msgs[{}].data is null", i));
+        }
+        if msg.len > producer.max_message_size() {
+            return fail(
+                DMXP_ERROR_INVALID_ARG,
+                format!(
+                    "msgs[{}] is too large ({} > {})",
+                    i,
+                    msg.len,
+                    producer.max_message_size()
+                ),
+            );
+        }
+        messages.push(unsafe { std::slice::from_raw_parts(msg.data, msg.len) });
+    }
 
-    unsafe {
-        for i in 0..count {
-            let ptr = *data_ptrs.add(i);
-            let len = *data_lens.add(i);
-            if ptr.is_null() {
-                return DMXP_ERROR_NULL_POINTER;
+    let sent = match producer.send_batch(&messages) {
+        Ok(_) => count,
+        // The batch is all-or-nothing; send what fits one message at a time
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+            let mut sent = 0;
+            for message in &messages {
+                match producer.send(message) {
+                    Ok(_) => sent += 1,
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        unsafe { *out_sent = sent };
+                        return fail(DMXP_ERROR_INTERNAL, e.to_string());
+                    }
+                }
             }
-            let slice = std::slice::from_raw_parts(ptr, len);
-            messages.push(slice);
+            sent
         }
-    }
+        Err(e) => return fail(DMXP_ERROR_INTERNAL, e.to_string()),
+    };
 
-    match producer.send_batch(&messages) {
-        Ok(_) => DMXP_SUCCESS,
-        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => DMXP_ERROR_CHANNEL_FULL,
-        Err(_) => DMXP_ERROR_INTERNAL,
+    unsafe { *out_sent = sent };
+    if sent == 0 {
+        return fail(
+            DMXP_ERROR_CHANNEL_FULL,
+            format!("Channel {} is full", producer.channel_id()),
+        );
     }
+    DMXP_SUCCESS
 }
 
 #[no_mangle]
@@ -163,7 +502,7 @@ pub extern "C" fn dmxp_producer_send(
     len: usize,
 ) -> i32 {
     if handle.is_null() || data.is_null() {
-        return DMXP_ERROR_NULL_POINTER;
+        return fail(DMXP_ERROR_NULL_POINTER, "handle or data is null");
     }
 
     let producer = unsafe { &(*handle).inner };
@@ -171,8 +510,11 @@ pub extern "C" fn dmxp_producer_send(
 
     match producer.send(slice) {
         Ok(_) => DMXP_SUCCESS,
-        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => DMXP_ERROR_CHANNEL_FULL,
-        Err(_) => DMXP_ERROR_INTERNAL,
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => fail(
+            DMXP_ERROR_CHANNEL_FULL,
+            format!("Channel {} is full", producer.channel_id()),
+        ),
+        Err(e) => fail(DMXP_ERROR_INTERNAL, e.to_string()),
     }
 }
 
@@ -189,9 +531,17 @@ pub extern "C" fn dmxp_producer_free(handle: *mut ProducerHandle) {
 // Consumer API
 // -----------------------------------------------------------------------------
 
+/// Create a consumer on the existing channel `channel_id`. Returns null on
+/// failure, including when `dmxp_init` was not called.
 #[no_mangle]
 pub extern "C" fn dmxp_consumer_new(channel_id: u32) -> *mut ConsumerHandle {
+    let Some((_, segment)) = allocator() else {
+        set_last_error(NOT_INITIALIZED);
+        return ptr::null_mut();
+    };
+
     match ChannelBuilder::new()
+        .in_segment(segment)
         .with_channel_id(channel_id)
         .build_consumer()
     {
@@ -200,7 +550,7 @@ pub extern "C" fn dmxp_consumer_new(channel_id: u32) -> *mut ConsumerHandle {
             Box::into_raw(handle)
         }
         Err(e) => {
-            eprintln!("FFI Error: Failed to build consumer: {}", e);
+            set_last_error(format!("Failed to build consumer: {}", e));
             ptr::null_mut()
         }
     }
@@ -223,7 +573,7 @@ pub extern "C" fn dmxp_consumer_receive_ext(
     out_meta: *mut FFIMessageMeta,
 ) -> i32 {
     if handle.is_null() || out_len.is_null() {
-        return DMXP_ERROR_NULL_POINTER;
+        return fail(DMXP_ERROR_NULL_POINTER, "handle or out_len is null");
     }
 
     let consumer = unsafe { &(*handle).inner };
@@ -233,14 +583,14 @@ pub extern "C" fn dmxp_consumer_receive_ext(
         // Blocking
         match consumer.receive_blocking_with_meta() {
             Ok(res) => Some(res),
-            Err(_) => return DMXP_ERROR_INTERNAL,
+            Err(e) => return fail(DMXP_ERROR_INTERNAL, e.to_string()),
         }
     } else if timeout_ms == 0 {
         // Non-blocking
         match consumer.receive_with_meta() {
             Ok(Some(res)) => Some(res),
-            Ok(None) => return DMXP_ERROR_EMPTY,
-            Err(_) => return DMXP_ERROR_INTERNAL,
+            Ok(None) => return fail(DMXP_ERROR_EMPTY, "No message available"),
+            Err(e) => return fail(DMXP_ERROR_INTERNAL, e.to_string()),
         }
     } else {
         // Timeout
@@ -248,15 +598,22 @@ pub extern "C" fn dmxp_consumer_receive_ext(
             .receive_timeout_with_meta(std::time::Duration::from_millis(timeout_ms as u64))
         {
             Ok(Some(res)) => Some(res),
-            Ok(None) => return DMXP_ERROR_TIMEOUT,
-            Err(_) => return DMXP_ERROR_INTERNAL,
+            Ok(None) => return fail(DMXP_ERROR_TIMEOUT, "Timed out waiting for a message"),
+            Err(e) => return fail(DMXP_ERROR_INTERNAL, e.to_string()),
         }
     };
 
     if let Some((meta, data)) = result {
         if data.len() > max_len {
             unsafe { *out_len = data.len() };
-            return DMXP_ERROR_INVALID_ARG;
+            return fail(
+                DMXP_ERROR_INVALID_ARG,
+                format!(
+                    "Output buffer too small ({} < {}); the message was dropped",
+                    max_len,
+                    data.len()
+                ),
+            );
         }
 
         unsafe {
@@ -266,19 +623,12 @@ pub extern "C" fn dmxp_consumer_receive_ext(
             *out_len = data.len();
 
             if !out_meta.is_null() {
-                (*out_meta).message_id = meta.message_id;
-                (*out_meta).timestamp_ns = meta.timestamp_ns;
-                (*out_meta).channel_id = meta.channel_id;
-                (*out_meta).message_type = meta.message_type;
-                (*out_meta).sender_pid = meta.sender_pid;
-                (*out_meta).sender_runtime = meta.sender_runtime;
-                (*out_meta).flags = meta.flags;
-                (*out_meta).payload_len = meta.payload_len;
+                *out_meta = FFIMessageMeta::from(&meta);
             }
         }
         DMXP_SUCCESS
     } else {
-        DMXP_ERROR_INTERNAL
+        fail(DMXP_ERROR_INTERNAL, "No message received")
     }
 }
 
@@ -294,6 +644,37 @@ pub extern "C" fn dmxp_consumer_receive(
     dmxp_consumer_receive_ext(handle, timeout, out_buf, out_len, ptr::null_mut())
 }
 
+/// Fill `out_meta` with the metadata of the next message without consuming it.
+///
+/// The payload is not copied; `payload_len` tells how large a buffer the
+/// following `dmxp_consumer_receive_ext` needs. Peeking reserves nothing:
+/// another consumer on the channel may take the message between the peek and
+/// the receive, which then returns the next message (or none).
+///
+/// # Returns
+/// * 0 if a message is waiting.
+/// * `DMXP_ERROR_EMPTY` if nothing is ready.
+/// * `DMXP_ERROR_INTERNAL` if the producer has terminated.
+#[no_mangle]
+pub extern "C" fn dmxp_consumer_peek(
+    handle: *mut ConsumerHandle,
+    out_meta: *mut FFIMessageMeta,
+) -> i32 {
+    if handle.is_null() || out_meta.is_null() {
+        return fail(DMXP_ERROR_NULL_POINTER, "handle or out_meta is null");
+    }
+
+    let consumer = unsafe { &(*handle).inner };
+    match consumer.peek_with_meta() {
+        Ok(Some((meta, _))) => {
+            unsafe { *out_meta = FFIMessageMeta::from(&meta) };
+            DMXP_SUCCESS
+        }
+        Ok(None) => fail(DMXP_ERROR_EMPTY, "No message available"),
+        Err(e) => fail(DMXP_ERROR_INTERNAL, e.to_string()),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn dmxp_consumer_free(handle: *mut ConsumerHandle) {
     if !handle.is_null() {
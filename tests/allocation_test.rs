@@ -6,31 +6,23 @@
 // Run memory-stats test:
 //   cargo test --test allocation_test track_allocations_with_memory_stats -- --nocapture
 
-use crossbeam_utils::CachePadded;
 use dmxp_kvcache::MPMC::Buffer::layout::ChannelEntry;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::MPMC::Buffer::{RingBuffer, MSG_INLINE};
 use dmxp_kvcache::MPMC::Structs::Buffer_Structs::MessageMeta;
 use std::alloc::{alloc, Layout};
-use std::sync::atomic::AtomicU64;
 
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
 
 fn create_dummy_channel_entry(capacity: u64) -> ChannelEntry {
     ChannelEntry {
-        channel_id: 0,
-        flags: 0,
         capacity,
-        band_offset: 0,
-        signal: std::sync::atomic::AtomicU32::new(0),
-        tail: crossbeam_utils::CachePadded::new(std::sync::atomic::AtomicU64::new(0)),
-        head: CachePadded::new(AtomicU64::new(0)),
-        _pad: [],
+        ..Default::default()
     }
 }
 
 fn make_aligned_backing(capacity: usize) -> (*mut u8, Layout) {
-    let size = capacity * RingBuffer::slot_stride();
+    let size = capacity * RingBuffer::slot_stride(MSG_INLINE);
     let layout = Layout::from_size_align(size, 128).unwrap();
     let ptr = unsafe { alloc(layout) };
     if ptr.is_null() {
@@ -51,7 +43,7 @@ fn track_allocations_with_dhat() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
@@ -87,7 +79,7 @@ fn track_allocations_with_memory_stats() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
@@ -121,7 +113,7 @@ fn verify_zero_allocation_enqueue_dequeue() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
@@ -143,3 +135,38 @@ fn verify_zero_allocation_enqueue_dequeue() {
         std::alloc::dealloc(ptr, layout);
     }
 }
+
+#[test]
+#[serial_test::serial]
+fn verify_zero_allocation_dequeue_ref() {
+    let _dhat = dhat::Profiler::new_heap();
+
+    let capacity = 1024;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+
+    let payload = vec![1u8; 100];
+    let meta = MessageMeta::default();
+
+    // Unlike dequeue, dequeue_ref borrows the payload in place, so the hot path
+    // must not touch the heap at all
+    let before = dhat::HeapStats::get();
+    for _ in 0..1000 {
+        rb.enqueue(meta, &payload);
+        if let Some(msg) = rb.dequeue_ref() {
+            assert_eq!(msg.len(), payload.len());
+        }
+    }
+    let after = dhat::HeapStats::get();
+
+    assert_eq!(after.total_blocks, before.total_blocks);
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
@@ -12,9 +12,8 @@
 // # Run all tests
 // cargo test -- --nocapture
 
-use crossbeam_utils::CachePadded;
 use dmxp_kvcache::MPMC::Buffer::layout::ChannelEntry;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::MPMC::Buffer::{RingBuffer, MSG_INLINE};
 use dmxp_kvcache::MPMC::Structs::Buffer_Structs::MessageMeta;
 use std::alloc::{alloc, Layout};
 use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
@@ -23,19 +22,13 @@ use std::thread;
 
 fn create_dummy_channel_entry(capacity: u64) -> ChannelEntry {
     ChannelEntry {
-        channel_id: 0,
-        flags: 0,
         capacity,
-        band_offset: 0,
-        signal: std::sync::atomic::AtomicU32::new(0),
-        tail: crossbeam_utils::CachePadded::new(std::sync::atomic::AtomicU64::new(0)),
-        head: CachePadded::new(AtomicU64::new(0)),
-        _pad: [],
+        ..Default::default()
     }
 }
 
 fn make_aligned_backing(capacity: usize) -> (*mut u8, Layout) {
-    let size = capacity * RingBuffer::slot_stride();
+    let size = capacity * RingBuffer::slot_stride(MSG_INLINE);
     let layout = Layout::from_size_align(size, 128).unwrap();
     let ptr = unsafe { alloc(layout) };
     if ptr.is_null() {
@@ -57,7 +50,7 @@ fn test_basic_ringbuffer_with_dhat() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
@@ -98,7 +91,7 @@ fn test_basic_ringbuffer_with_memory_stats() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
@@ -154,7 +147,7 @@ fn test_mpmc_stress_with_dhat() {
     // In this test, entry lives until the end of the function.
     let rb = Arc::new(unsafe { RingBuffer::new(entry_ptr, ptr) });
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let producers = 4;
@@ -257,7 +250,7 @@ fn test_mpmc_stress_with_memory_stats() {
 
     let send_rb = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
     unsafe {
-        send_rb.0.init_slots();
+        send_rb.0.init_slots().unwrap();
     }
 
     let producers = 4;
@@ -338,7 +331,7 @@ fn test_verify_zero_allocation() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let payload = vec![1u8; 100];
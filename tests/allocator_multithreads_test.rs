@@ -1,6 +1,6 @@
 use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
-use dmxp_kvcache::MPMC::Buffer::layout::GlobalHeader;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::MPMC::Buffer::layout::{GlobalHeader, DEFAULT_MAX_CHANNELS};
+use dmxp_kvcache::MPMC::Buffer::{RingBuffer, MSG_INLINE};
 use serial_test::serial;
 use std::fs;
 use std::io;
@@ -18,11 +18,11 @@ fn test_shared_memory_creation() -> io::Result<()> {
     cleanup_shared_memory();
 
     // Get the exact minimum required size (aligned)
-    let min_required = (std::mem::size_of::<GlobalHeader>() + 127) & !127;
+    let min_required = GlobalHeader::control_size(DEFAULT_MAX_CHANNELS).unwrap();
     let test_sizes = &[min_required, min_required * 2, 1024 * 1024]; // 1MB
 
     for &size in test_sizes {
-        let allocator = SharedMemoryAllocator::new(size)?;
+        let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
 
         //debugging
         println!("Available memory: {} bytes", allocator.available_memory());
@@ -36,9 +36,9 @@ fn test_shared_memory_creation() -> io::Result<()> {
 
     // Test with size smaller than header (should fail)
     // We need to ensure the size is small enough that even after alignment it's still too small
-    let too_small = std::mem::size_of::<GlobalHeader>() - 128; // Subtract a full alignment to be safe
+    let too_small = min_required - 128; // Subtract a full alignment to be safe
     assert!(
-        SharedMemoryAllocator::new(too_small).is_err(),
+        SharedMemoryAllocator::new(too_small, DEFAULT_MAX_CHANNELS).is_err(),
         "Should fail with size smaller than header"
     );
 
@@ -52,7 +52,7 @@ fn test_channel_management() -> io::Result<()> {
     cleanup_shared_memory();
 
     // Create allocator with enough space
-    let allocator = SharedMemoryAllocator::new(1024 * 1024 * 10)?; // 10MB
+    let allocator = SharedMemoryAllocator::new(1024 * 1024 * 10, DEFAULT_MAX_CHANNELS)?; // 10MB
 
     // Test creating specific channels
     let student_channel = allocator.create_channel(256, None)?;
@@ -106,7 +106,10 @@ fn test_concurrent_channel_operations() -> io::Result<()> {
 
     let total_threads = 4; // Reduced threads to reduce contention
     let ops_per_thread = 10; // Further reduced operations per thread
-    let allocator = Arc::new(SharedMemoryAllocator::new(64 * 1024 * 1024)?); // 64MB
+    let allocator = Arc::new(SharedMemoryAllocator::new(
+        64 * 1024 * 1024,
+        DEFAULT_MAX_CHANNELS,
+    )?); // 64MB
     let counter = Arc::new(AtomicU64::new(0));
 
     // Track created channels to avoid removing non-existent ones
@@ -271,8 +274,8 @@ fn test_memory_management() -> io::Result<()> {
     cleanup_shared_memory();
 
     // Calculate required sizes more accurately
-    let header_size = std::mem::size_of::<GlobalHeader>();
-    let slot_stride = RingBuffer::slot_stride();
+    let header_size = GlobalHeader::control_size(DEFAULT_MAX_CHANNELS).unwrap();
+    let slot_stride = RingBuffer::slot_stride(MSG_INLINE);
     println!("Slot stride: {} bytes", slot_stride);
     println!("Header size: {} bytes", header_size);
 
@@ -289,7 +292,7 @@ fn test_memory_management() -> io::Result<()> {
     let total_size = 100 * 1024 * 1024; // 100MB
 
     println!("Creating allocator with size: {} bytes", total_size);
-    let allocator = SharedMemoryAllocator::new(total_size)?;
+    let allocator = SharedMemoryAllocator::new(total_size, DEFAULT_MAX_CHANNELS)?;
 
     // First channel should fit
     println!(
@@ -348,7 +351,7 @@ fn test_persistence() -> io::Result<()> {
 
     // Create and populate shared memory
     {
-        let allocator = SharedMemoryAllocator::new(1024 * 1024 * 10)?; // 10MB
+        let allocator = SharedMemoryAllocator::new(1024 * 1024 * 10, DEFAULT_MAX_CHANNELS)?; // 10MB
         let channel = allocator.create_channel(256, None)?;
         // Simulate some data being written
         let _ = channel; // Use channel to prevent warning
@@ -372,7 +375,7 @@ fn test_error_conditions() -> io::Result<()> {
     cleanup_shared_memory();
 
     // 64MB should be enough for 256 channels of 16 slots
-    let allocator = SharedMemoryAllocator::new(64 * 1024 * 1024)?;
+    let allocator = SharedMemoryAllocator::new(64 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
 
     // Test invalid channel capacity (not power of two)
     assert!(allocator.create_channel(100, None).is_err());
@@ -380,9 +383,8 @@ fn test_error_conditions() -> io::Result<()> {
     // Test zero capacity
     assert!(allocator.create_channel(0, None).is_err());
 
-    // Test maximum channels
-    // MAX_CHANNELS is 256.
-    for i in 0..256 {
+    // Test maximum channels: every ID but the control channel's
+    for i in 0..DEFAULT_MAX_CHANNELS - 1 {
         if let Err(e) = allocator.create_channel(16, None) {
             panic!("Failed to create channel {}: {}", i, e);
         }
@@ -397,6 +399,96 @@ fn test_error_conditions() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn test_channel_ids_are_reused_after_removal() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(64 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    for round in 0..2 {
+        // 255 is the control channel's
+        let mut ids: Vec<u32> = (0..255)
+            .map(|_| allocator.create_channel(16, None).map(|c| c.id()))
+            .collect::<io::Result<_>>()?;
+        assert!(allocator.create_channel(16, None).is_err());
+        assert_eq!(allocator.channel_count(), 255, "round {}", round);
+
+        ids.sort_unstable();
+        assert_eq!(ids, (0..255).collect::<Vec<u32>>());
+        for id in ids {
+            allocator.remove_channel(id)?;
+        }
+        assert_eq!(allocator.channel_count(), 0);
+    }
+
+    // Automatic IDs are the lowest free ones, skipping taken ones
+    allocator.create_channel(16, Some(5))?;
+    assert_eq!(allocator.create_channel(16, None)?.id(), 0);
+    assert_eq!(allocator.create_channel(16, None)?.id(), 1);
+    allocator.remove_channel(0)?;
+    assert_eq!(allocator.next_channel_id(), 0);
+    assert_eq!(allocator.create_channel(16, None)?.id(), 0);
+    assert_eq!(allocator.create_channel(16, Some(3))?.id(), 3);
+    assert_eq!(
+        allocator.create_channel(16, Some(3)).unwrap_err().kind(),
+        io::ErrorKind::AlreadyExists
+    );
+    assert_eq!(allocator.create_channel(16, None)?.id(), 2);
+    assert_eq!(allocator.create_channel(16, None)?.id(), 4);
+    assert_eq!(allocator.create_channel(16, None)?.id(), 6);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_concurrent_create_remove_never_duplicates_ids() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    const MAX_CHANNELS: usize = 70;
+    const THREADS: usize = 8;
+    const HELD: usize = MAX_CHANNELS / THREADS;
+    let allocator = Arc::new(SharedMemoryAllocator::new(64 * 1024 * 1024, MAX_CHANNELS)?);
+    let live = Arc::new(Mutex::new(std::collections::HashSet::new()));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let allocator = allocator.clone();
+            let live = live.clone();
+            thread::spawn(move || -> io::Result<()> {
+                for _ in 0..200 {
+                    // Together the threads never hold more than the table, so
+                    // every create must succeed
+                    let mut held = Vec::with_capacity(HELD);
+                    for _ in 0..HELD {
+                        let id = allocator.create_channel(4, None)?.id();
+                        assert!(
+                            live.lock().unwrap().insert(id),
+                            "ID {} handed out twice",
+                            id
+                        );
+                        held.push(id);
+                    }
+                    for id in held {
+                        assert!(live.lock().unwrap().remove(&id));
+                        allocator.remove_channel(id)?;
+                    }
+                }
+                Ok(())
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap()?;
+    }
+
+    assert_eq!(allocator.channel_count(), 0);
+    assert_eq!(allocator.next_channel_id(), 0);
+    Ok(())
+}
+
 fn cleanup_shared_memory() {
     // Clean up any existing shared memory files
     if let Ok(entries) = fs::read_dir("/dev/shm") {
@@ -1,14 +1,22 @@
 // tests/allocator_test.rs
 
-use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
-use dmxp_kvcache::MPMC::Buffer::layout::GlobalHeader;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::Core::alloc::{
+    AllocatorEventKind, ChannelSpec, ControlMessage, FragmentationReport, SharedMemoryAllocator,
+    LAYOUT_VERSION,
+};
+use dmxp_kvcache::MPMC::Buffer::layout::{
+    GlobalHeader, ATTACHED_CONSUMER, BYTE_ORDER_MARKER, DEFAULT_MAX_CHANNELS,
+};
+use dmxp_kvcache::MPMC::Buffer::{DequeueError, EnqueueError, RingBuffer, Slot, MSG_INLINE};
+use dmxp_kvcache::MPMC::ChannelBuilder;
+use dmxp_kvcache::MPMC::Structs::Buffer_Structs::MessageMeta;
 use std::fs;
 use std::io;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // Test helper to ensure we're the only test using shared memory
 static TEST_LOCK: parking_lot::Mutex<()> = parking_lot::const_mutex(());
@@ -24,7 +32,7 @@ fn test_shared_memory_allocator() -> io::Result<()> {
     // Test 1: Create a new allocator
     // We need enough space for header + channels + alignment
     // 1024 slots * ~1KB = 1MB. Plus header. So 2MB is safer.
-    let allocator = SharedMemoryAllocator::new(4 * 1024 * 1024)?; // 4MB shared memory
+    let allocator = SharedMemoryAllocator::new(4 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?; // 4MB shared memory
     assert!(allocator.get_channel(0).is_none());
 
     // Test 2: Create a channel
@@ -55,16 +63,19 @@ fn test_concurrent_access() -> io::Result<()> {
 
     // Create shared memory in parent process with enough space for all channels
     let total_channels = 4;
-    let slot_stride = RingBuffer::slot_stride();
+    let slot_stride = RingBuffer::slot_stride(MSG_INLINE);
     let channel_slots = 256;
     let channel_size = channel_slots * slot_stride;
-    let header_size = std::mem::size_of::<GlobalHeader>();
+    let header_size = GlobalHeader::control_size(DEFAULT_MAX_CHANNELS).unwrap();
 
     // Add plenty of padding/alignment buffer
     let total_size = (header_size + (total_channels * channel_size) + 1024 * 1024) & !127;
 
     // Create the allocator in the main thread first
-    let allocator = Arc::new(SharedMemoryAllocator::new(total_size)?);
+    let allocator = Arc::new(SharedMemoryAllocator::new(
+        total_size,
+        DEFAULT_MAX_CHANNELS,
+    )?);
 
     // Create a barrier to synchronize thread starts
     let barrier = Arc::new(std::sync::Barrier::new(total_channels + 1));
@@ -235,7 +246,7 @@ fn test_get_channels_and_count() -> io::Result<()> {
     cleanup_shared_memory();
 
     // Create allocator with enough space
-    let allocator = SharedMemoryAllocator::new(10 * 1024 * 1024)?; // 10MB
+    let allocator = SharedMemoryAllocator::new(10 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?; // 10MB
 
     // Initially, no channels should exist
     assert_eq!(allocator.channel_count(), 0);
@@ -279,13 +290,361 @@ fn test_get_channels_and_count() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_channel_infos_report_occupancy() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(10 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    assert!(allocator.channel_infos().is_empty());
+
+    let first = allocator.create_channel(8, None)?;
+    let second = allocator.create_channel(4, None)?;
+    for _ in 0..3 {
+        first
+            .buffer()
+            .enqueue(MessageMeta::default(), b"x")
+            .expect("ring full");
+    }
+    first.buffer().dequeue().expect("ring empty");
+    for _ in 0..4 {
+        second
+            .buffer()
+            .enqueue(MessageMeta::default(), b"y")
+            .expect("ring full");
+    }
+
+    let infos = allocator.channel_infos();
+    assert_eq!(infos.len(), 2);
+    assert_eq!(
+        (infos[0].id, infos[0].capacity, infos[0].used_slots),
+        (0, 8, 2)
+    );
+    assert_eq!(
+        (infos[1].id, infos[1].capacity, infos[1].used_slots),
+        (1, 4, 4)
+    );
+    // Channels are laid out one after another past the header and channel table
+    assert!(infos[0].offset >= GlobalHeader::control_size(DEFAULT_MAX_CHANNELS).unwrap());
+    assert!(infos[1].offset > infos[0].offset);
+
+    Ok(())
+}
+
+#[test]
+fn test_diagnostics_cover_every_channel() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(10 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let channels = [
+        allocator.create_channel(8, None)?,
+        allocator.create_channel(16, None)?,
+        allocator.create_channel(4, None)?,
+    ];
+    for _ in 0..3 {
+        channels[1]
+            .buffer()
+            .enqueue(MessageMeta::default(), b"x")
+            .expect("ring full");
+    }
+    channels[1].buffer().dequeue().expect("ring empty");
+
+    let diagnostics = allocator.diagnostics();
+    assert!(allocator.is_initialized());
+    assert_eq!(diagnostics.magic, 0x444D58505F4D454D);
+    assert_eq!(diagnostics.channel_count, 3);
+    assert_eq!(diagnostics.channels.len(), 3);
+    assert_eq!(diagnostics.used_memory, allocator.used_memory());
+    let ids: Vec<u32> = diagnostics.channels.iter().map(|c| c.info.id).collect();
+    assert_eq!(ids, [0, 1, 2]);
+
+    let busy = &diagnostics.channels[1];
+    assert_eq!((busy.info.capacity, busy.info.used_slots), (16, 2));
+    assert_eq!(
+        (busy.metrics.enqueue_count, busy.metrics.dequeue_count),
+        (3, 1)
+    );
+
+    // One header line, one column header, one row per channel
+    let table = diagnostics.to_string();
+    assert_eq!(table.lines().count(), 2 + 3);
+    assert!(table.lines().nth(3).unwrap().trim_start().starts_with("1 "));
+
+    Ok(())
+}
+
+#[test]
+fn test_repair_clears_half_created_channels() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let size = 10 * 1024 * 1024;
+
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    for capacity in [8, 16, 4] {
+        allocator.create_channel(capacity, None)?;
+    }
+    allocator
+        .get_channel(2)
+        .unwrap()
+        .buffer()
+        .enqueue(MessageMeta::default(), b"survivor")
+        .expect("ring full");
+
+    // Simulate a creator that died after publishing the capacity but before
+    // describing the band, and one whose band points past the segment
+    unsafe {
+        let header = allocator.header_ptr() as *mut GlobalHeader;
+        let channels = allocator.channel_table_ptr();
+        (*channels.add(1)).band_size = 0;
+        (*channels.add(5)).channel_id = 5;
+        (*channels.add(5)).capacity = 8;
+        (*channels.add(5)).band_offset = (size * 2) as u64;
+        (*channels.add(5)).band_size = 1 << 20;
+        (*header).channel_count = 4;
+    }
+
+    let attached = SharedMemoryAllocator::attach(size)?;
+    assert_eq!(attached.damaged_channels(), [1, 5]);
+    assert!(attached.get_channel(1).is_none());
+    assert!(attached.get_channel(5).is_none());
+    let ids: Vec<u32> = attached.get_channels().iter().map(|c| c.id()).collect();
+    assert_eq!(ids, [0, 2]);
+
+    assert_eq!(attached.repair(), 2);
+    assert!(attached.damaged_channels().is_empty());
+    assert_eq!(attached.channel_count(), 2);
+
+    // The sound channels and their contents survive, and the IDs are reusable
+    let (_, payload) = attached
+        .get_channel(2)
+        .unwrap()
+        .buffer()
+        .dequeue()
+        .expect("message lost");
+    assert_eq!(payload, b"survivor");
+    assert_eq!(attached.get_channel(0).unwrap().capacity, 8);
+    assert_eq!(attached.create_channel(8, Some(1))?.id(), 1);
+    assert_eq!(attached.channel_count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_oversized_channel_is_rejected_cleanly() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(10 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let used = allocator.used_memory();
+
+    // capacity * slot_stride overflows u64
+    let capacity = 1usize << 60;
+    assert!(capacity
+        .checked_mul(RingBuffer::slot_stride(MSG_INLINE))
+        .is_none());
+    let err = allocator.create_channel(capacity, None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(ChannelSpec::new(capacity).band_size(), usize::MAX);
+
+    // So does the overflow band
+    let spec = ChannelSpec::new(8).with_overflow(1 << 40, 1 << 30);
+    let err = allocator.create_channel_with(spec, None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // Nothing was allocated, and a sane channel still fits
+    assert_eq!(allocator.channel_count(), 0);
+    assert_eq!(allocator.used_memory(), used);
+    assert_eq!(allocator.create_channel(8, None)?.id(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_channel_bands_are_slot_aligned() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(32 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let specs = [
+        ChannelSpec::new(1),
+        ChannelSpec::new(2).with_priority(1),
+        ChannelSpec::new(8).with_overflow(3, 1500),
+        ChannelSpec::new(64).with_priority(4),
+        ChannelSpec::new(1024),
+    ];
+    for spec in specs {
+        allocator.create_channel_with(spec, None)?;
+    }
+
+    let base = unsafe { allocator.shm().as_ptr() };
+    for channel in allocator.get_channels() {
+        let entry = unsafe { &*allocator.channel_table_ptr().add(channel.id() as usize) };
+        let band = unsafe { base.add(entry.band_offset as usize) };
+        assert!(band.cast::<Slot>().is_aligned(), "channel {}", channel.id());
+        let priority = unsafe { band.add(entry.priority_offset as usize) };
+        assert!(
+            priority.cast::<Slot>().is_aligned(),
+            "channel {}",
+            channel.id()
+        );
+
+        // The views work from the first slot on
+        let buffer = channel.buffer();
+        buffer
+            .enqueue(MessageMeta::default(), b"aligned")
+            .expect("ring full");
+        assert_eq!(buffer.dequeue().expect("message lost").1, b"aligned");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_exact_capacity_channels() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let size = 16 * 1024 * 1024;
+
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    let err = allocator.create_channel(3000, None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // Only the slots asked for are reserved
+    let spec = ChannelSpec::new(3000)
+        .with_priority(5)
+        .with_exact_capacity(true);
+    assert!(spec.band_size() < ChannelSpec::new(4096).band_size());
+    let channel = allocator.create_channel_with(spec, None)?;
+    assert_eq!(channel.capacity, 3000);
+
+    // Lap both bands a few times
+    let buffer = channel.buffer();
+    let priority = buffer.priority_band().expect("priority band");
+    for round in 0..3u32 {
+        for i in 0..3000u32 {
+            let payload = (round * 3000 + i).to_le_bytes();
+            buffer
+                .enqueue(MessageMeta::default(), &payload)
+                .expect("ring full");
+        }
+        assert!(buffer.enqueue(MessageMeta::default(), b"x").is_none());
+        for i in 0..5u32 {
+            priority
+                .enqueue(MessageMeta::default(), &i.to_le_bytes())
+                .expect("band full");
+        }
+        assert!(priority.enqueue(MessageMeta::default(), b"x").is_none());
+
+        // Priority messages come out first, then the ring in order
+        for i in 0..5u32 {
+            assert_eq!(buffer.dequeue().unwrap().1, i.to_le_bytes());
+        }
+        for i in 0..3000u32 {
+            assert_eq!(
+                buffer.dequeue().unwrap().1,
+                (round * 3000 + i).to_le_bytes()
+            );
+        }
+        assert!(buffer.dequeue().is_none());
+    }
+
+    // Other processes accept the channel as sound
+    let attached = SharedMemoryAllocator::attach(size)?;
+    assert!(attached.damaged_channels().is_empty());
+    assert_eq!(attached.get_channel(0).unwrap().capacity, 3000);
+
+    Ok(())
+}
+
+#[test]
+fn test_unlink_on_drop_removes_the_segment() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let size = 4 * 1024 * 1024;
+    let path = Path::new("/dev/shm/dmxp_alloc");
+
+    // Off by default: the segment outlives its creator
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    assert!(allocator.is_creator());
+    drop(allocator);
+    assert!(path.exists());
+
+    let allocator =
+        SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?.with_unlink_on_drop(true);
+    allocator.create_channel(8, None)?;
+    let attached = SharedMemoryAllocator::attach(size)?.with_unlink_on_drop(true);
+    assert!(!attached.is_creator());
+    drop(allocator);
+    assert!(!path.exists());
+
+    // The mapping outlives the file
+    let channel = attached.get_channel(0).expect("channel");
+    channel
+        .buffer()
+        .enqueue(MessageMeta::default(), b"still mapped")
+        .expect("ring full");
+    assert_eq!(channel.buffer().dequeue().unwrap().1, b"still mapped");
+    assert!(SharedMemoryAllocator::attach(size).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_reclaims_abandoned_channels() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let size = 8 * 1024 * 1024;
+
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    for _ in 0..4 {
+        allocator.create_channel(8, None)?;
+    }
+
+    // Channel 0 was used by a producer and a consumer whose process crashed
+    let mut exited = std::process::Command::new("true").spawn()?;
+    exited.wait()?;
+    let dead_pid = exited.id();
+    unsafe {
+        let attached = &(*allocator.channel_table_ptr()).attached;
+        attached[0].store(dead_pid, Ordering::Release);
+        attached[1].store(dead_pid | ATTACHED_CONSUMER, Ordering::Release);
+    }
+    // Channel 1 has a live producer, 2 still holds a message, 3 is sealed
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .with_channel_id(1)
+        .build_producer()?;
+    let channel = allocator.get_channel(2).unwrap();
+    channel
+        .buffer()
+        .enqueue(MessageMeta::default(), b"unread")
+        .expect("ring full");
+    allocator.seal_channel(3)?;
+
+    assert_eq!(allocator.gc_abandoned_channels(), 1);
+    assert!(allocator.get_channel(0).is_none());
+    let ids: Vec<u32> = allocator.get_channels().iter().map(|c| c.id()).collect();
+    assert_eq!(ids, [1, 2, 3]);
+    assert_eq!(allocator.channel_count(), 3);
+
+    drop(producer);
+    channel.buffer().dequeue().expect("message lost");
+    assert_eq!(allocator.gc_abandoned_channels(), 2);
+    let ids: Vec<u32> = allocator.get_channels().iter().map(|c| c.id()).collect();
+    assert_eq!(ids, [3]);
+    assert_eq!(allocator.gc_abandoned_channels(), 0);
+
+    Ok(())
+}
+
 #[test]
 fn test_memory_tracking() -> io::Result<()> {
     let _guard = TEST_LOCK.lock();
     cleanup_shared_memory();
 
     let total_size = 20 * 1024 * 1024; // 20MB
-    let allocator = SharedMemoryAllocator::new(total_size)?;
+    let allocator = SharedMemoryAllocator::new(total_size, DEFAULT_MAX_CHANNELS)?;
 
     // Check initial state
     let initial_used = allocator.used_memory();
@@ -343,3 +702,891 @@ fn test_memory_tracking() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_memory_of_removed_channels_is_reused() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let total_size = 20 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(total_size, DEFAULT_MAX_CHANNELS)?;
+    let check = |allocator: &SharedMemoryAllocator| {
+        assert_eq!(
+            allocator.used_memory() + allocator.available_memory(),
+            total_size
+        );
+    };
+    let band_offset =
+        |id: u32| unsafe { (*allocator.channel_table_ptr().add(id as usize)).band_offset };
+
+    let empty = allocator.used_memory();
+    for id in 0..3 {
+        allocator.create_channel(256, Some(id))?;
+    }
+    check(&allocator);
+    let full = allocator.used_memory();
+    let band = ChannelSpec::new(256).band_size();
+    assert_eq!(full, empty + 3 * band);
+
+    // Removing the middle channel hands its band back, even with one after it
+    let hole = band_offset(1);
+    allocator.remove_channel(1)?;
+    check(&allocator);
+    assert_eq!(allocator.used_memory(), full - band);
+
+    // A channel too big for the hole goes after the others
+    allocator.create_channel(512, Some(3))?;
+    assert!(band_offset(3) > band_offset(2));
+    check(&allocator);
+    let with_big = allocator.used_memory();
+
+    // One that fits takes the hole, and the accounting is back where it was
+    allocator.create_channel(128, Some(1))?;
+    assert_eq!(band_offset(1), hole);
+    check(&allocator);
+    assert_eq!(
+        allocator.used_memory(),
+        with_big + ChannelSpec::new(128).band_size()
+    );
+
+    for id in 0..4 {
+        allocator.remove_channel(id)?;
+        check(&allocator);
+    }
+    assert_eq!(allocator.used_memory(), empty);
+
+    Ok(())
+}
+
+#[test]
+fn test_channel_resident_bytes_grow_with_spilled_payloads() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(32 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let spec = ChannelSpec::new(8).with_overflow(16, 64 * 1024);
+    let channel = allocator.create_channel_with(spec, None)?;
+
+    // The slots are initialized on creation, the overflow blocks only on first use
+    let before = allocator.channel_resident_bytes(channel.id())?;
+    assert!(before > 0);
+    assert!(before < spec.band_size(), "{}", before);
+
+    let payload = vec![7u8; 60_000];
+    for _ in 0..8 {
+        channel
+            .buffer()
+            .enqueue(MessageMeta::default(), &payload)
+            .expect("ring full");
+    }
+    let after = allocator.channel_resident_bytes(channel.id())?;
+    assert!(
+        after >= before + 8 * payload.len(),
+        "{} -> {}",
+        before,
+        after
+    );
+    assert!(after <= spec.band_size());
+
+    let err = allocator.channel_resident_bytes(99).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    Ok(())
+}
+
+#[test]
+fn test_attach_rejects_other_layout_versions() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(8 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let header = allocator.header_ptr() as *mut GlobalHeader;
+    assert_eq!(unsafe { (*header).version }, LAYOUT_VERSION);
+    SharedMemoryAllocator::attach(8 * 1024 * 1024)?;
+
+    unsafe { (*header).version = LAYOUT_VERSION + 1 };
+    let err = SharedMemoryAllocator::attach(8 * 1024 * 1024)
+        .expect_err("attached to a segment with another layout version");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "layout version mismatch: segment={}, expected={}",
+            LAYOUT_VERSION + 1,
+            LAYOUT_VERSION
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_attach_rejects_segments_of_the_other_byte_order() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(8 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let header = allocator.header_ptr() as *mut GlobalHeader;
+    assert_eq!(unsafe { (*header).byte_order }, BYTE_ORDER_MARKER);
+
+    // What a big-endian creator's header looks like from here
+    unsafe {
+        (*header).magic = (*header).magic.swap_bytes();
+        (*header).version = (*header).version.swap_bytes();
+        (*header).max_channels = (*header).max_channels.swap_bytes();
+        (*header).byte_order = (*header).byte_order.swap_bytes();
+    }
+    let err = SharedMemoryAllocator::attach(8 * 1024 * 1024)
+        .expect_err("attached to a byte-swapped segment");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("opposite byte order"), "{}", err);
+
+    Ok(())
+}
+
+#[test]
+fn test_channel_table_length_is_configurable() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let Err(err) = SharedMemoryAllocator::new(8 * 1024 * 1024, 0) else {
+        panic!("created a segment without a channel table");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    // One entry would leave none besides the control channel's
+    let Err(err) = SharedMemoryAllocator::new(8 * 1024 * 1024, 1) else {
+        panic!("created a segment with only a control channel entry");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    for max_channels in [32, 1024] {
+        cleanup_shared_memory();
+        let allocator = SharedMemoryAllocator::new(16 * 1024 * 1024, max_channels)?;
+        assert_eq!(allocator.max_channels(), max_channels);
+        let header = allocator.header_ptr();
+        assert_eq!(unsafe { (*header).max_channels } as usize, max_channels);
+
+        // The last entry is the control channel's
+        let last = (max_channels - 2) as u32;
+        allocator.create_channel(8, Some(last))?;
+        // The first band starts right after the table
+        let entry = unsafe { &*allocator.channel_table_ptr().add(max_channels - 2) };
+        assert_eq!(
+            entry.band_offset as usize,
+            GlobalHeader::control_size(max_channels).unwrap()
+        );
+        for id in [max_channels - 1, max_channels] {
+            let err = allocator
+                .create_channel(8, Some(id as u32))
+                .expect_err("created a channel past the ordinary ones");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+
+        // Attaching processes take the length from the header
+        let attached = SharedMemoryAllocator::attach(16 * 1024 * 1024)?;
+        assert_eq!(attached.max_channels(), max_channels);
+        assert!(attached.get_channel(last).is_some());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_channels_with_different_inline_sizes() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(16 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    for (id, inline) in [(0u32, 256usize), (1, 1024), (2, 4096)] {
+        let spec = ChannelSpec::new(8).with_inline_size(inline);
+        assert_eq!(spec.band_size(), 8 * Slot::stride(inline));
+        let channel = allocator.create_channel_with(spec, Some(id))?;
+        let buffer = channel.buffer();
+        assert_eq!(buffer.inline_size(), inline);
+        assert_eq!(buffer.max_payload(), inline);
+
+        // A full-size payload lands at the computed offsets of its slot
+        let payload: Vec<u8> = (0..inline).map(|i| (i % 251) as u8).collect();
+        buffer.enqueue(MessageMeta::default(), b"first").unwrap();
+        buffer.enqueue(MessageMeta::default(), &payload).unwrap();
+        assert!(buffer
+            .enqueue(MessageMeta::default(), &vec![0; inline + 1])
+            .is_none());
+        let entry = unsafe { &*allocator.channel_table_ptr().add(id as usize) };
+        assert_eq!(entry.inline_size as usize, inline);
+        let slot = unsafe {
+            allocator
+                .shm()
+                .as_ptr()
+                .add(entry.band_offset as usize + Slot::stride(inline))
+        };
+        let in_memory =
+            unsafe { std::slice::from_raw_parts(slot.add(Slot::PAYLOAD_OFFSET), inline) };
+        assert_eq!(in_memory, &payload[..]);
+        assert_eq!(
+            unsafe { (*slot.cast::<Slot>()).meta.payload_len } as usize,
+            inline
+        );
+
+        // Attached views agree on the stride
+        let attached = SharedMemoryAllocator::attach(16 * 1024 * 1024)?;
+        let view = attached.get_channel(id).unwrap();
+        assert_eq!(view.buffer().dequeue().unwrap().1, b"first");
+        assert_eq!(view.buffer().dequeue().unwrap().1, payload);
+    }
+
+    for bad in [0, 100] {
+        let err = allocator
+            .create_channel_with(ChannelSpec::new(8).with_inline_size(bad), None)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_fragmentation_reports_gaps_left_by_removed_channels() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    // Room for four equal channels; three are created
+    let spec = ChannelSpec::new(64);
+    let size = SharedMemoryAllocator::required_size(8, &[spec; 4]);
+    let allocator = SharedMemoryAllocator::new(size, 8)?;
+    for _ in 0..3 {
+        allocator.create_channel_with(spec, None)?;
+    }
+    let offsets: Vec<usize> = allocator.channel_infos().iter().map(|c| c.offset).collect();
+    let band = offsets[1] - offsets[0];
+    let trailing = allocator.available_memory();
+    assert!(trailing >= band);
+    assert_eq!(
+        allocator.fragmentation(),
+        FragmentationReport {
+            largest_free_block: trailing,
+            total_free: trailing,
+            free_block_count: 1,
+        }
+    );
+
+    // Removing the middle channel leaves a gap of one band
+    allocator.remove_channel(1)?;
+    assert_eq!(
+        allocator.fragmentation(),
+        FragmentationReport {
+            largest_free_block: trailing,
+            total_free: trailing + band,
+            free_block_count: 2,
+        }
+    );
+
+    // Removing its neighbour merges the two into one gap of two bands
+    allocator.remove_channel(0)?;
+    let report = allocator.fragmentation();
+    assert_eq!(
+        report,
+        FragmentationReport {
+            largest_free_block: 2 * band,
+            total_free: trailing + 2 * band,
+            free_block_count: 2,
+        }
+    );
+    assert_eq!(report.total_free, allocator.available_memory());
+
+    Ok(())
+}
+
+#[test]
+fn test_compact_coalesces_free_space_for_a_large_channel() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let small = ChannelSpec::new(64);
+    let large = ChannelSpec::new(128);
+    let size = SharedMemoryAllocator::required_size(8, &[small; 4]);
+    assert!(SharedMemoryAllocator::required_size(8, &[small, small, large]) <= size);
+
+    let allocator = SharedMemoryAllocator::new(size, 8)?;
+    for _ in 0..4 {
+        allocator.create_channel_with(small, None)?;
+    }
+    for id in [1, 3] {
+        allocator
+            .get_channel(id)
+            .unwrap()
+            .buffer()
+            .enqueue(MessageMeta::default(), format!("kept {}", id).as_bytes())
+            .expect("ring full");
+    }
+
+    // Two single-band gaps: enough bytes in total, but no block large enough
+    allocator.remove_channel(0)?;
+    allocator.remove_channel(2)?;
+    assert_eq!(allocator.fragmentation().free_block_count, 2);
+    let err = allocator.create_channel_with(large, None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+
+    // A live producer blocks compaction
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .with_channel_id(1)
+        .build_producer()?;
+    let err = allocator.compact().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+    drop(producer);
+    // ...and so does a multi-consumer
+    let multi = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_multi_consumer(&[3])?;
+    let err = allocator.compact().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+    drop(multi);
+
+    // Views held across the move are stale rather than rebased, and say so
+    let held = allocator.get_channel(3).unwrap();
+    let mut tail = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .with_channel_id(3)
+        .build_tail_consumer()?;
+    let available = allocator.available_memory();
+    assert_eq!(allocator.compact()?, 2);
+    assert!(held.buffer().is_stale());
+    assert!(held.buffer().dequeue().is_none());
+    assert_eq!(
+        held.buffer().try_dequeue().unwrap_err(),
+        DequeueError::Stale
+    );
+    let err = tail.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+    let report = allocator.fragmentation();
+    assert_eq!(report.free_block_count, 1);
+    assert_eq!(report.largest_free_block, available);
+    assert_eq!(allocator.compact()?, 0);
+
+    // The moved channels kept their messages
+    for id in [1, 3] {
+        let (_, payload) = allocator
+            .get_channel(id)
+            .unwrap()
+            .buffer()
+            .dequeue()
+            .expect("message lost");
+        assert_eq!(payload, format!("kept {}", id).as_bytes());
+    }
+    allocator.create_channel_with(large, None)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_parts_cover_the_segment_and_a_channel_ring() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 4 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    let (base, len) = allocator.as_raw_parts();
+    assert_eq!(len, size);
+    assert_eq!(base.cast_const(), allocator.header_ptr().cast::<u8>());
+
+    allocator.create_channel(64, None)?;
+    let channel = allocator.create_channel_with(
+        ChannelSpec::new(16).with_inline_size(256).with_priority(4),
+        None,
+    )?;
+    let (data, data_len) = channel.data_raw_parts();
+    assert_eq!(data_len, channel.capacity * channel.buffer().stride());
+    assert_eq!(data_len, 16 * RingBuffer::slot_stride(256));
+    let offset = allocator.channel_infos()[1].offset;
+    assert_eq!(data, base.wrapping_add(offset));
+
+    // The slots seen through the raw parts are the ones the ring writes to
+    channel
+        .buffer()
+        .enqueue(MessageMeta::default(), b"raw")
+        .expect("ring full");
+    let layout = channel.buffer().slot_layout();
+    let payload = unsafe { std::slice::from_raw_parts(data.add(layout.payload_offset), 3) };
+    assert_eq!(payload, b"raw");
+
+    Ok(())
+}
+
+#[test]
+fn test_required_size_fits_a_channel_plan_exactly() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let plan = [
+        ChannelSpec::new(64),
+        ChannelSpec::new(16).with_inline_size(256),
+        ChannelSpec::new(8).with_overflow(8, 64 * 1024),
+        ChannelSpec::new(32).with_priority(8).with_inline_size(4096),
+        ChannelSpec::new(5)
+            .with_exact_capacity(true)
+            .with_inline_size(24),
+    ];
+    let max_channels = 8;
+    let size = SharedMemoryAllocator::required_size(max_channels, &plan);
+    assert_eq!(size % 128, 0);
+    assert!(size > GlobalHeader::control_size(max_channels).unwrap());
+
+    let allocator = SharedMemoryAllocator::new(size, max_channels)?;
+    for spec in plan {
+        allocator.create_channel_with(spec, None)?;
+    }
+    // Only the final rounding to 128 bytes may be left over
+    assert!(allocator.available_memory() < 128);
+
+    // One slot more than the plan does not fit
+    cleanup_shared_memory();
+    let mut bigger = plan;
+    bigger[0] = ChannelSpec::new(65).with_exact_capacity(true);
+    let allocator = SharedMemoryAllocator::new(size, max_channels)?;
+    let err = bigger
+        .into_iter()
+        .try_for_each(|spec| allocator.create_channel_with(spec, None).map(|_| ()))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+
+    assert_eq!(
+        SharedMemoryAllocator::required_size(max_channels, &[]),
+        GlobalHeader::control_size(max_channels).unwrap()
+    );
+    assert_eq!(
+        SharedMemoryAllocator::required_size(usize::MAX, &plan),
+        usize::MAX
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reset_clears_every_channel() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let allocator = SharedMemoryAllocator::new(16 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let empty = allocator.available_memory();
+    for _ in 0..4 {
+        allocator.create_channel(64, None)?;
+    }
+    allocator.create_channel(16, Some(200))?;
+    assert_eq!(allocator.channel_count(), 5);
+
+    allocator.reset();
+    assert_eq!(allocator.channel_count(), 0);
+    assert_eq!(allocator.available_memory(), empty);
+    assert!(allocator.get_channels().is_empty());
+    assert_eq!(allocator.next_channel_id(), 0);
+
+    // Attached views see the empty table, and IDs start over
+    let attached = SharedMemoryAllocator::attach(16 * 1024 * 1024)?;
+    assert!(attached.get_channel(200).is_none());
+    assert_eq!(attached.create_channel(64, None)?.id(), 0);
+    assert_eq!(allocator.create_channel(16, Some(200))?.id(), 200);
+    assert_eq!(allocator.channel_count(), 2);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_attach_through_a_passed_fd() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 4 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    let channel = allocator.create_channel(16, Some(7))?;
+    channel
+        .buffer()
+        .enqueue(MessageMeta::default(), b"over the fd")
+        .unwrap();
+
+    // What a supervisor would send a sandboxed worker over a Unix socket
+    let dmxp_kvcache::Core::RawHandle::Fd(fd) = (unsafe { allocator.shm() }).raw_handle();
+    let passed = unsafe { libc::dup(fd) };
+    assert!(passed >= 0);
+
+    // The worker never opens the path
+    cleanup_shared_memory();
+    let worker = SharedMemoryAllocator::attach_fd(passed, size)?;
+    assert!(!worker.is_creator());
+    assert_eq!(worker.max_channels(), DEFAULT_MAX_CHANNELS);
+    let view = worker.get_channel(7).unwrap();
+    assert_eq!(view.buffer().dequeue().unwrap().1, b"over the fd");
+
+    // A descriptor of something else is refused after the usual checks
+    let name = std::ffi::CString::new("not_dmxp").unwrap();
+    let other = unsafe { libc::memfd_create(name.as_ptr(), 0) };
+    assert_eq!(unsafe { libc::ftruncate(other, size as i64) }, 0);
+    let err = SharedMemoryAllocator::attach_fd(other, size)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    Ok(())
+}
+
+#[test]
+fn test_named_allocators_keep_independent_channel_ids() -> io::Result<()> {
+    let names = ["dmxp_test_arena_a", "dmxp_test_arena_b"];
+    for name in names {
+        let _ = fs::remove_file(format!("/dev/shm/{}", name));
+    }
+
+    let size = 4 * 1024 * 1024;
+    let a = SharedMemoryAllocator::new_named(names[0], size, 8)?.with_unlink_on_drop(true);
+    let b = SharedMemoryAllocator::new_named(names[1], size, DEFAULT_MAX_CHANNELS)?
+        .with_unlink_on_drop(true);
+
+    // Channel 0 exists in both, with its own shape
+    a.create_channel(16, Some(0))?;
+    b.create_channel(64, Some(0))?;
+    assert_eq!(a.get_channel(0).unwrap().capacity, 16);
+    assert_eq!(b.get_channel(0).unwrap().capacity, 64);
+    assert_eq!(a.max_channels(), 8);
+    assert_eq!(b.max_channels(), DEFAULT_MAX_CHANNELS);
+
+    // Automatic IDs are handed out per allocator
+    assert_eq!(a.create_channel(16, None)?.id(), 1);
+    assert_eq!(b.create_channel(16, None)?.id(), 1);
+    assert_eq!((a.channel_count(), b.channel_count()), (2, 2));
+
+    // Builders reach one arena by name, and its channel 0 only
+    let producer = ChannelBuilder::new()
+        .with_allocator_name(names[1])
+        .with_buffer_size(size)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_allocator_name(names[1])
+        .with_buffer_size(size)
+        .build_consumer()?;
+    producer.send(b"arena b")?;
+    assert_eq!(consumer.receive()?.unwrap(), b"arena b");
+    assert!(a.get_channel(0).unwrap().buffer().dequeue().is_none());
+
+    // Names that are not a plain file name are refused
+    for bad in ["", "../dmxp_alloc", "a/b"] {
+        let err = SharedMemoryAllocator::new_named(bad, size, 8)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    drop((producer, consumer));
+    drop((a, b));
+    for name in names {
+        assert!(!Path::new(&format!("/dev/shm/{}", name)).exists());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_create_channels_is_all_or_nothing() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 8 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+
+    // One spec larger than the whole segment: refused before anything is created
+    let err = allocator
+        .create_channels(&[ChannelSpec::new(64), ChannelSpec::new(1 << 16)])
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    assert_eq!(allocator.channel_count(), 0);
+    assert!(allocator.get_channel(0).is_none());
+
+    // A batch that would fit an empty segment but not what is left of this
+    // one: the channels created before the failure are rolled back
+    allocator.create_channel(4096, Some(0))?;
+    let free = allocator.available_memory();
+    let batch = [
+        ChannelSpec::new(1024),
+        ChannelSpec::new(1024),
+        ChannelSpec::new(2048),
+    ];
+    assert!(SharedMemoryAllocator::required_size(DEFAULT_MAX_CHANNELS, &batch) <= size);
+    assert!(batch[0].band_size() + batch[1].band_size() <= free);
+    assert!(batch.iter().map(ChannelSpec::band_size).sum::<usize>() > free);
+    let err = allocator.create_channels(&batch).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    assert_eq!(allocator.channel_count(), 1);
+    assert_eq!(allocator.available_memory(), free);
+    assert!(allocator.get_channel(1).is_none());
+
+    // The part that fits goes in whole, with the lowest free IDs
+    let channels = allocator.create_channels(&batch[..2])?;
+    let ids: Vec<u32> = channels.iter().map(|channel| channel.id()).collect();
+    assert_eq!(ids, [1, 2]);
+    assert_eq!(allocator.channel_count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_control_channel_pauses_a_worker() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 8 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    let control_id = allocator.control_channel_id();
+    assert_eq!(control_id, DEFAULT_MAX_CHANNELS as u32 - 1);
+
+    // Subscribe before anything is sent: earlier commands are not kept
+    let control = allocator.control_consumer()?;
+    let commands = allocator.control_producer()?;
+    assert!(allocator
+        .get_channel(control_id)
+        .unwrap()
+        .buffer()
+        .is_control());
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_consumer()?;
+
+    // A worker checks for commands between messages
+    let mut paused = false;
+    let mut received = Vec::new();
+    let mut step = |received: &mut Vec<Vec<u8>>| -> io::Result<()> {
+        while let Some(command) = control.poll()? {
+            match command {
+                ControlMessage::Pause => paused = true,
+                ControlMessage::Resume => paused = false,
+                _ => {}
+            }
+        }
+        if !paused {
+            while let Some(message) = consumer.receive()? {
+                received.push(message);
+            }
+        }
+        Ok(())
+    };
+
+    commands.send(ControlMessage::Pause)?;
+    producer.send(b"held")?;
+    step(&mut received)?;
+    assert!(received.is_empty());
+
+    commands.send(ControlMessage::Resume)?;
+    step(&mut received)?;
+    assert_eq!(received, [b"held".to_vec()]);
+
+    // Every command survives the trip through its payload
+    for command in [
+        ControlMessage::Pause,
+        ControlMessage::Resume,
+        ControlMessage::Drain,
+        ControlMessage::Stats,
+    ] {
+        assert_eq!(ControlMessage::from_bytes(&command.to_bytes())?, command);
+    }
+    let err = ControlMessage::from_bytes(&9u32.to_le_bytes()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Ordinary channels never get the control channel's ID
+    drop((control, commands));
+    allocator.remove_channel(control_id)?;
+    let err = allocator.create_channel(16, Some(control_id)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    drop((producer, consumer, allocator));
+    cleanup_shared_memory();
+    let allocator = SharedMemoryAllocator::new(size, 4)?;
+    for id in 0..3 {
+        assert_eq!(allocator.create_channel(16, None)?.id(), id);
+    }
+    let err = allocator.create_channel(16, None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    assert_eq!(allocator.next_channel_id(), 4);
+    allocator.control_producer()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_recent_events_record_channel_lifecycle() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 8 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    assert!(allocator.recent_events(10).is_empty());
+
+    let before = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    allocator.create_channel(16, Some(0))?;
+    allocator.create_channel(16, Some(1))?;
+    allocator.seal_channel(1)?;
+    allocator.seal_channel(1)?; // already sealed: not recorded again
+    allocator.remove_channel(0)?;
+
+    let events = allocator.recent_events(10);
+    let summary: Vec<(AllocatorEventKind, u32)> = events
+        .iter()
+        .map(|event| (event.kind, event.channel_id))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (AllocatorEventKind::Created, 0),
+            (AllocatorEventKind::Created, 1),
+            (AllocatorEventKind::Sealed, 1),
+            (AllocatorEventKind::Removed, 0),
+        ]
+    );
+    assert!(events.iter().all(|event| event.pid == std::process::id()));
+    assert!(events[0].timestamp_ns >= before);
+    assert!(events
+        .windows(2)
+        .all(|pair| pair[0].timestamp_ns <= pair[1].timestamp_ns));
+
+    // Other processes read the same log; `max` keeps the newest
+    let attached = SharedMemoryAllocator::attach(size)?;
+    assert_eq!(attached.recent_events(1), [events[3]]);
+
+    // Once full, the log keeps only the most recent events
+    for _ in 0..40 {
+        allocator.create_channel(16, Some(2))?;
+        allocator.remove_channel(2)?;
+    }
+    let events = attached.recent_events(usize::MAX);
+    assert_eq!(events.len(), 64);
+    assert_eq!(events[62].kind, AllocatorEventKind::Created);
+    assert_eq!(events[63].kind, AllocatorEventKind::Removed);
+    assert!(events.iter().all(|event| event.channel_id == 2));
+
+    Ok(())
+}
+
+#[test]
+fn test_resize_channel_only_when_idle() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 8 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    allocator.create_channel_with(ChannelSpec::new(1024).with_inline_size(64), Some(0))?;
+    allocator.create_channel(16, Some(1))?;
+    let free = allocator.available_memory();
+
+    // A live consumer, or a message waiting, keeps the channel as it is
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_consumer()?;
+    let err = allocator.resize_channel(0, 8).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+    producer.send(b"pending")?;
+    drop((producer, consumer));
+    let err = allocator.resize_channel(0, 8).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+    allocator
+        .get_channel(0)
+        .unwrap()
+        .buffer()
+        .dequeue()
+        .unwrap();
+
+    // Capacities the channel could not be created with, and missing channels
+    let err = allocator.resize_channel(0, 100).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = allocator.resize_channel(7, 8).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    // Shrinking hands the rest of the band back
+    let old_band = ChannelSpec::new(1024).with_inline_size(64).band_size();
+    let new_band = ChannelSpec::new(8).with_inline_size(64).band_size();
+    allocator.resize_channel(0, 8)?;
+    assert_eq!(allocator.available_memory(), free + old_band - new_band);
+    assert_eq!(
+        allocator.recent_events(1)[0].kind,
+        AllocatorEventKind::Resized
+    );
+
+    // ...and the ring holds 8 messages from now on
+    let channel = allocator.get_channel(0).unwrap();
+    assert_eq!(channel.capacity, 8);
+    let ring = channel.buffer();
+    for i in 0..8u8 {
+        let meta = MessageMeta::default();
+        assert!(ring.enqueue(meta, &[i]).is_some());
+    }
+    assert!(ring.enqueue(MessageMeta::default(), &[8]).is_none());
+    for i in 0..8u8 {
+        assert_eq!(ring.dequeue().unwrap().1, [i]);
+    }
+    assert!(ring.dequeue().is_none());
+    drop(channel);
+
+    // Growing needs the space after the band: taken by channel 1 until it
+    // is removed
+    let err = allocator.resize_channel(0, 2048).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    allocator.remove_channel(1)?;
+    allocator.resize_channel(0, 2048)?;
+    assert_eq!(allocator.get_channel(0).unwrap().capacity, 2048);
+
+    Ok(())
+}
+
+#[test]
+fn test_resize_channel_leaves_earlier_views_stale() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let size = 8 * 1024 * 1024;
+    let allocator = SharedMemoryAllocator::new(size, DEFAULT_MAX_CHANNELS)?;
+    allocator.create_channel(1024, Some(0))?;
+
+    // Views nobody registers: one from another mapping, and a tail
+    let other = SharedMemoryAllocator::attach(size)?;
+    let view = other.get_channel(0).unwrap();
+    let mut tail = ChannelBuilder::new()
+        .with_buffer_size(size)
+        .build_tail_consumer()?;
+    assert!(!view.buffer().is_stale());
+
+    allocator.resize_channel(0, 8)?;
+
+    let ring = view.buffer();
+    assert!(ring.is_stale());
+    assert!(ring.enqueue(MessageMeta::default(), b"lost").is_none());
+    assert_eq!(
+        ring.try_enqueue(MessageMeta::default(), b"lost"),
+        Err(EnqueueError::Stale)
+    );
+    assert!(ring
+        .enqueue_batch(&[(&MessageMeta::default(), &b"lost"[..])])
+        .is_none());
+
+    // A fresh view sees the new ring; the stale ones leave it alone
+    let fresh = other.get_channel(0).unwrap();
+    assert!(!fresh.buffer().is_stale());
+    assert_eq!(fresh.capacity, 8);
+    assert!(fresh
+        .buffer()
+        .enqueue(MessageMeta::default(), b"kept")
+        .is_some());
+    assert!(ring.peek().is_none());
+    assert!(ring.dequeue().is_none());
+    assert_eq!(ring.try_dequeue().unwrap_err(), DequeueError::Stale);
+    let err = tail.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+    assert_eq!(fresh.buffer().dequeue().unwrap().1, b"kept");
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+// Tokio integration tests; run with `cargo test --features tokio`.
+#![cfg(feature = "tokio")]
+
+use dmxp_kvcache::MPMC::{ChannelBuilder, Consumer, Producer};
+use futures::StreamExt;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+fn build_pair(channel_id: u32) -> io::Result<(Producer, Consumer)> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .with_capacity(16)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .build_consumer()?;
+    Ok((producer, consumer))
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn recv_async_wakes_on_send_without_blocking_the_executor() -> io::Result<()> {
+    let (producer, consumer) = build_pair(0)?;
+
+    // Nothing sent yet: the receive stays pending and can be cancelled
+    let idle = tokio::time::timeout(Duration::from_millis(20), consumer.recv_async()).await;
+    assert!(idle.is_err());
+
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        producer.send(b"wake up").unwrap();
+        producer
+    });
+
+    // The current-thread executor keeps running other tasks while the receive waits
+    let ticker = tokio::spawn(async {
+        for _ in 0..5 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    });
+    let (_meta, data) = tokio::time::timeout(Duration::from_secs(2), consumer.recv_async())
+        .await
+        .expect("recv_async missed the wakeup")?;
+    assert_eq!(data, b"wake up");
+    ticker.await.unwrap();
+
+    let _producer = sender.join().unwrap();
+    drop(consumer);
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn consumer_stream_yields_messages_in_order() -> io::Result<()> {
+    const COUNT: u32 = 200;
+    let (producer, consumer) = build_pair(1)?;
+
+    let sender = thread::spawn(move || {
+        for i in 0..COUNT {
+            while producer.send(i.to_le_bytes()).is_err() {
+                thread::yield_now();
+            }
+        }
+        producer
+    });
+
+    let mut stream = consumer.into_stream();
+    let mut expected = 0u32;
+    while let Some(message) = stream.next().await {
+        let (meta, data) = message?;
+        assert_eq!(data, expected.to_le_bytes());
+        assert_eq!(meta.channel_id, 1);
+        expected += 1;
+        if expected == COUNT {
+            break;
+        }
+    }
+    assert_eq!(expected, COUNT);
+    assert!(stream.consumer().receive()?.is_none());
+
+    let _producer = sender.join().unwrap();
+    Ok(())
+}
@@ -0,0 +1,137 @@
+// Contended MPMC runs under each Backoff policy.
+//
+// These assert that every policy delivers each message exactly once and in
+// per-producer order, and print wall/CPU time so the policies can be compared
+// with `cargo test --test backoff -- --nocapture`.
+
+use dmxp_kvcache::MPMC::{Backoff, ChannelBuilder};
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+const PRODUCERS: u64 = 4;
+const CONSUMERS: usize = 4;
+const PER_PRODUCER: u64 = 5_000;
+
+/// User + system CPU time consumed by this process so far.
+fn cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+    let to_duration = |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+    to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+}
+
+fn run_contended(backoff: Backoff) -> io::Result<()> {
+    cleanup_shared_memory();
+
+    let builder = || {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(0)
+            .with_capacity(64)
+            .with_backoff(backoff)
+    };
+    let producers = (0..PRODUCERS)
+        .map(|_| builder().build_producer())
+        .collect::<io::Result<Vec<_>>>()?;
+    let consumers = (0..CONSUMERS)
+        .map(|_| builder().build_consumer())
+        .collect::<io::Result<Vec<_>>>()?;
+    assert!(producers.iter().all(|p| p.backoff() == backoff));
+    assert!(consumers.iter().all(|c| c.backoff() == backoff));
+
+    let wall = Instant::now();
+    let cpu = cpu_time();
+    let received = Arc::new(AtomicUsize::new(0));
+    let total = (PRODUCERS * PER_PRODUCER) as usize;
+
+    let senders: Vec<_> = producers
+        .into_iter()
+        .enumerate()
+        .map(|(id, producer)| {
+            thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    let msg = ((id as u64) << 32 | i).to_le_bytes();
+                    while producer.send(msg).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let receivers: Vec<_> = consumers
+        .into_iter()
+        .map(|consumer| {
+            let received = received.clone();
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                while received.load(Ordering::Relaxed) < total {
+                    match consumer.receive().expect("receive failed") {
+                        Some(data) => {
+                            seen.push(u64::from_le_bytes(data.try_into().unwrap()));
+                            received.fetch_add(1, Ordering::Relaxed);
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+                seen
+            })
+        })
+        .collect();
+
+    for sender in senders {
+        sender.join().unwrap();
+    }
+    let mut all = Vec::with_capacity(total);
+    for receiver in receivers {
+        let seen = receiver.join().unwrap();
+
+        // Each consumer sees any one producer's messages in send order
+        let mut last: HashMap<u64, u64> = HashMap::new();
+        for &value in &seen {
+            let (id, i) = (value >> 32, value & 0xFFFF_FFFF);
+            if let Some(prev) = last.insert(id, i) {
+                assert!(prev < i, "{:?}: producer {} reordered", backoff, id);
+            }
+        }
+        all.extend(seen);
+    }
+
+    all.sort_unstable();
+    let mut expected: Vec<u64> = (0..PRODUCERS)
+        .flat_map(|id| (0..PER_PRODUCER).map(move |i| id << 32 | i))
+        .collect();
+    expected.sort_unstable();
+    assert_eq!(all, expected, "{:?}: lost or duplicated messages", backoff);
+
+    println!(
+        "{:?}: {} messages, wall {:?}, cpu {:?}",
+        backoff,
+        total,
+        wall.elapsed(),
+        cpu_time() - cpu
+    );
+    Ok(())
+}
+
+#[test]
+fn every_backoff_policy_delivers_exactly_once() -> io::Result<()> {
+    // One test so the policies never share the segment concurrently
+    for backoff in [Backoff::Spin, Backoff::Yield, Backoff::ExponentialThenPark] {
+        run_contended(backoff)?;
+    }
+    Ok(())
+}
+
+// Clean up shared memory
+fn cleanup_shared_memory() {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    }
+}
@@ -0,0 +1,161 @@
+// LZ4 payload compression; run with `cargo test --features lz4`.
+#![cfg(feature = "lz4")]
+
+use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
+use dmxp_kvcache::MPMC::Buffer::MSG_INLINE;
+use dmxp_kvcache::MPMC::ChannelBuilder;
+use dmxp_kvcache::MPMC::Structs::Buffer_Structs::{MessageMeta, FLAG_COMPRESSED};
+use std::io;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+fn json_like(len: usize) -> Vec<u8> {
+    let record = br#"{"user":"alice","action":"login","ok":true},"#;
+    record.iter().copied().cycle().take(len).collect()
+}
+
+#[test]
+#[serial_test::serial]
+fn compressed_messages_round_trip_transparently() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(8)
+        .with_checksum(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    // Three times the inline size still fits once compressed
+    let large = json_like(3 * MSG_INLINE);
+    producer.send_compressed(&large)?;
+    let (meta, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(data, large);
+    assert_eq!(meta.payload_len as usize, large.len());
+    assert_eq!(meta.flags & FLAG_COMPRESSED, FLAG_COMPRESSED);
+
+    // Payloads that do not shrink are stored exactly as `send` would
+    let noise: Vec<u8> = (0..512).map(|_| fastrand::u8(..)).collect();
+    producer.send_compressed(&noise)?;
+    let (meta, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(data, noise);
+    assert_eq!(meta.flags, 0);
+    assert_eq!(meta.payload_len, 512);
+
+    // Too large even after compression
+    let big_noise: Vec<u8> = (0..2 * MSG_INLINE).map(|_| fastrand::u8(..)).collect();
+    let err = producer.send_compressed(&big_noise).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+#[serial_test::serial]
+fn peek_and_blocking_receive_decompress() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(1)
+        .with_capacity(8)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(1)
+        .build_consumer()?;
+
+    let message = json_like(2000);
+    producer.send_compressed(&message)?;
+    assert_eq!(consumer.peek_with_meta()?.unwrap().1, message);
+    assert_eq!(consumer.receive_blocking()?, message);
+
+    Ok(())
+}
+
+#[test]
+#[serial_test::serial]
+fn implausible_size_prefix_is_rejected_before_decompressing() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .with_capacity(8)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .build_consumer()?;
+
+    // Written straight into the ring, as a buggy or hostile sender could
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    let channel = allocator.get_channel(2).unwrap();
+    let compressed = MessageMeta {
+        flags: FLAG_COMPRESSED,
+        ..Default::default()
+    };
+
+    // A 4 GiB size prefix in front of a 16-byte block
+    let mut payload = u32::MAX.to_le_bytes().to_vec();
+    payload.extend_from_slice(&[0x10; 16]);
+    channel.buffer().enqueue(compressed, &payload).unwrap();
+    let err = consumer.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("size prefix"), "{}", err);
+
+    // So is a payload too short to hold the prefix
+    channel.buffer().enqueue(compressed, &[0; 3]).unwrap();
+    let err = consumer.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // The stream carries on after them
+    let message = json_like(2000);
+    producer.send_compressed(&message)?;
+    assert_eq!(consumer.receive()?.unwrap(), message);
+
+    Ok(())
+}
+
+#[test]
+#[serial_test::serial]
+fn drain_decompresses_and_stops_at_a_corrupt_message() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(3)
+        .with_capacity(8)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(3)
+        .build_consumer()?;
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    let channel = allocator.get_channel(3).unwrap();
+    let corrupt = MessageMeta {
+        flags: FLAG_COMPRESSED,
+        ..Default::default()
+    };
+
+    let message = json_like(2000);
+    producer.send_compressed(&message)?;
+    channel.buffer().enqueue(corrupt, &[0; 3]).unwrap();
+    producer.send_compressed(&message)?;
+
+    // The same bytes as a receive would give, up to the corrupt message
+    let drained = consumer.drain()?;
+    assert_eq!(drained.len(), 1);
+    assert_eq!(drained[0].1, message);
+    assert_eq!(drained[0].0.payload_len as usize, message.len());
+
+    // Its error comes next, then the message after it
+    let err = consumer.drain().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let drained = consumer.drain()?;
+    assert_eq!(drained.len(), 1);
+    assert_eq!(drained[0].1, message);
+
+    Ok(())
+}
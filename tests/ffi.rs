@@ -0,0 +1,353 @@
+// Round trips through the C interface, driving the extern functions the way a
+// C caller would.
+
+use dmxp_kvcache::ffi::*;
+use std::ptr;
+use std::sync::Once;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+fn init() {
+    static FRESH: Once = Once::new();
+    FRESH.call_once(|| {
+        let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    });
+    assert_eq!(dmxp_init(SHM_SIZE), DMXP_SUCCESS);
+}
+
+fn receive(consumer: *mut ConsumerHandle) -> Result<Vec<u8>, i32> {
+    let mut buf = [0u8; 1024];
+    let mut len = buf.len();
+    match dmxp_consumer_receive_ext(consumer, 0, buf.as_mut_ptr(), &mut len, ptr::null_mut()) {
+        DMXP_SUCCESS => Ok(buf[..len].to_vec()),
+        code => Err(code),
+    }
+}
+
+#[test]
+#[serial_test::serial]
+fn send_batch_round_trip() {
+    init();
+    let producer = dmxp_producer_new(10, 16);
+    let consumer = dmxp_consumer_new(10);
+    assert!(!producer.is_null() && !consumer.is_null());
+
+    let payloads: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 8 + i as usize]).collect();
+    let msgs: Vec<DmxpMsg> = payloads
+        .iter()
+        .map(|p| DmxpMsg {
+            data: p.as_ptr(),
+            len: p.len(),
+        })
+        .collect();
+
+    let mut sent = usize::MAX;
+    assert_eq!(
+        dmxp_producer_send_batch(producer, msgs.as_ptr(), msgs.len(), &mut sent),
+        DMXP_SUCCESS
+    );
+    assert_eq!(sent, payloads.len());
+
+    for payload in &payloads {
+        assert_eq!(receive(consumer).as_ref(), Ok(payload));
+    }
+    assert_eq!(receive(consumer), Err(DMXP_ERROR_EMPTY));
+
+    dmxp_consumer_free(consumer);
+    dmxp_producer_free(producer);
+}
+
+#[test]
+#[serial_test::serial]
+fn send_batch_reports_partial_and_full() {
+    init();
+    let producer = dmxp_producer_new(11, 4);
+    let consumer = dmxp_consumer_new(11);
+    assert!(!producer.is_null() && !consumer.is_null());
+
+    let payloads: Vec<[u8; 4]> = (0..6u32).map(u32::to_le_bytes).collect();
+    let msgs: Vec<DmxpMsg> = payloads
+        .iter()
+        .map(|p| DmxpMsg {
+            data: p.as_ptr(),
+            len: p.len(),
+        })
+        .collect();
+
+    // Only the first four fit
+    let mut sent = 0;
+    assert_eq!(
+        dmxp_producer_send_batch(producer, msgs.as_ptr(), msgs.len(), &mut sent),
+        DMXP_SUCCESS
+    );
+    assert_eq!(sent, 4);
+
+    // Nothing fits now
+    assert_eq!(
+        dmxp_producer_send_batch(producer, msgs[4..].as_ptr(), 2, &mut sent),
+        DMXP_ERROR_CHANNEL_FULL
+    );
+    assert_eq!(sent, 0);
+
+    for payload in &payloads[..4] {
+        assert_eq!(receive(consumer).as_deref(), Ok(&payload[..]));
+    }
+
+    // Resuming from out_sent delivers the rest in order
+    assert_eq!(
+        dmxp_producer_send_batch(producer, msgs[4..].as_ptr(), 2, &mut sent),
+        DMXP_SUCCESS
+    );
+    assert_eq!(sent, 2);
+    for payload in &payloads[4..] {
+        assert_eq!(receive(consumer).as_deref(), Ok(&payload[..]));
+    }
+
+    dmxp_consumer_free(consumer);
+    dmxp_producer_free(producer);
+}
+
+#[test]
+#[serial_test::serial]
+fn send_batch_rejects_bad_messages_without_sending() {
+    init();
+    let producer = dmxp_producer_new(12, 16);
+    let consumer = dmxp_consumer_new(12);
+    assert!(!producer.is_null() && !consumer.is_null());
+
+    let good = *b"good";
+    let huge = vec![0u8; 64 * 1024];
+    let mut sent = usize::MAX;
+
+    let with_null = [
+        DmxpMsg {
+            data: good.as_ptr(),
+            len: good.len(),
+        },
+        DmxpMsg {
+            data: ptr::null(),
+            len: 4,
+        },
+    ];
+    assert_eq!(
+        dmxp_producer_send_batch(producer, with_null.as_ptr(), 2, &mut sent),
+        DMXP_ERROR_NULL_POINTER
+    );
+    assert_eq!(sent, 0);
+
+    let too_large = [
+        DmxpMsg {
+            data: good.as_ptr(),
+            len: good.len(),
+        },
+        DmxpMsg {
+            data: huge.as_ptr(),
+            len: huge.len(),
+        },
+    ];
+    assert_eq!(
+        dmxp_producer_send_batch(producer, too_large.as_ptr(), 2, &mut sent),
+        DMXP_ERROR_INVALID_ARG
+    );
+    assert_eq!(sent, 0);
+
+    assert_eq!(
+        dmxp_producer_send_batch(producer, ptr::null(), 1, &mut sent),
+        DMXP_ERROR_NULL_POINTER
+    );
+    assert_eq!(
+        dmxp_producer_send_batch(producer, too_large.as_ptr(), 2, ptr::null_mut()),
+        DMXP_ERROR_NULL_POINTER
+    );
+    assert_eq!(receive(consumer), Err(DMXP_ERROR_EMPTY));
+
+    dmxp_consumer_free(consumer);
+    dmxp_producer_free(producer);
+}
+
+fn last_error() -> String {
+    let mut buf = [0 as std::ffi::c_char; 256];
+    let len = dmxp_last_error(buf.as_mut_ptr(), buf.len());
+    let message = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    assert_eq!(message.to_bytes().len(), len);
+    message.to_str().unwrap().to_owned()
+}
+
+#[test]
+#[serial_test::serial]
+fn last_error_describes_the_failure() {
+    init();
+    assert!(dmxp_consumer_new(77).is_null());
+    assert_eq!(
+        last_error(),
+        "Failed to build consumer: Channel 77 not found"
+    );
+
+    let producer = dmxp_producer_new(13, 2);
+    assert!(!producer.is_null());
+    assert_eq!(dmxp_producer_send(producer, b"a".as_ptr(), 1), DMXP_SUCCESS);
+    assert_eq!(dmxp_producer_send(producer, b"b".as_ptr(), 1), DMXP_SUCCESS);
+    assert_eq!(
+        dmxp_producer_send(producer, b"c".as_ptr(), 1),
+        DMXP_ERROR_CHANNEL_FULL
+    );
+    assert_eq!(last_error(), "Channel 13 is full");
+
+    // Successful calls keep the previous message
+    assert_eq!(dmxp_init(SHM_SIZE), DMXP_SUCCESS);
+    assert_eq!(last_error(), "Channel 13 is full");
+
+    // Errors are per thread
+    std::thread::spawn(|| assert_eq!(last_error(), ""))
+        .join()
+        .unwrap();
+
+    dmxp_producer_free(producer);
+}
+
+#[test]
+#[serial_test::serial]
+fn last_error_truncates_and_terminates() {
+    init();
+    assert!(dmxp_consumer_new(78).is_null());
+    let message = b"Failed to build consumer: Channel 78 not found";
+
+    let mut buf = [0x7f as std::ffi::c_char; 10];
+    assert_eq!(dmxp_last_error(buf.as_mut_ptr(), 8), message.len());
+    let copied = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    assert_eq!(copied.to_bytes(), &message[..7]);
+    // Nothing past `len` is touched
+    assert_eq!(buf[8], 0x7f);
+
+    assert_eq!(dmxp_last_error(buf.as_mut_ptr(), 0), message.len());
+    assert_eq!(buf[0], b'F' as std::ffi::c_char);
+    assert_eq!(dmxp_last_error(ptr::null_mut(), 64), message.len());
+}
+
+#[test]
+#[serial_test::serial]
+fn peek_reports_meta_without_consuming() {
+    init();
+    let producer = dmxp_producer_new(14, 8);
+    let consumer = dmxp_consumer_new(14);
+    assert!(!producer.is_null() && !consumer.is_null());
+
+    let mut meta = std::mem::MaybeUninit::<FFIMessageMeta>::zeroed();
+    assert_eq!(
+        dmxp_consumer_peek(consumer, meta.as_mut_ptr()),
+        DMXP_ERROR_EMPTY
+    );
+    assert_eq!(
+        dmxp_consumer_peek(consumer, ptr::null_mut()),
+        DMXP_ERROR_NULL_POINTER
+    );
+
+    assert_eq!(
+        dmxp_producer_send(producer, b"peeked".as_ptr(), 6),
+        DMXP_SUCCESS
+    );
+    assert_eq!(
+        dmxp_consumer_peek(consumer, meta.as_mut_ptr()),
+        DMXP_SUCCESS
+    );
+    let peeked = unsafe { meta.assume_init_read() };
+    assert_eq!(peeked.channel_id, 14);
+    assert_eq!(peeked.payload_len, 6);
+
+    // The message is still there for the receive
+    let mut buf = [0u8; 16];
+    let mut len = buf.len();
+    let mut received = std::mem::MaybeUninit::<FFIMessageMeta>::zeroed();
+    assert_eq!(
+        dmxp_consumer_receive_ext(
+            consumer,
+            0,
+            buf.as_mut_ptr(),
+            &mut len,
+            received.as_mut_ptr()
+        ),
+        DMXP_SUCCESS
+    );
+    assert_eq!(&buf[..len], b"peeked");
+    let received = unsafe { received.assume_init() };
+    assert_eq!(received.message_id, peeked.message_id);
+    assert_eq!(
+        dmxp_consumer_peek(consumer, meta.as_mut_ptr()),
+        DMXP_ERROR_EMPTY
+    );
+
+    dmxp_consumer_free(consumer);
+    dmxp_producer_free(producer);
+}
+
+#[test]
+#[serial_test::serial]
+fn channel_info_reports_capacity_and_occupancy() {
+    init();
+    let producer = dmxp_producer_new(15, 8);
+    assert!(!producer.is_null());
+    for _ in 0..3 {
+        assert_eq!(dmxp_producer_send(producer, b"m".as_ptr(), 1), DMXP_SUCCESS);
+    }
+
+    let mut info = std::mem::MaybeUninit::<DmxpChannelInfo>::zeroed();
+    assert_eq!(dmxp_get_channel_info(15, info.as_mut_ptr()), DMXP_SUCCESS);
+    let info = unsafe { info.assume_init() };
+    assert_eq!(info.id, 15);
+    assert_eq!(info.capacity, 8);
+    assert_eq!(info.used_slots, 3);
+    assert!(info.offset > 0);
+
+    let mut missing = std::mem::MaybeUninit::<DmxpChannelInfo>::zeroed();
+    assert_eq!(
+        dmxp_get_channel_info(99, missing.as_mut_ptr()),
+        DMXP_ERROR_INVALID_ARG
+    );
+    assert_eq!(last_error(), "Channel 99 not found");
+    assert_eq!(
+        dmxp_get_channel_info(15, ptr::null_mut()),
+        DMXP_ERROR_NULL_POINTER
+    );
+
+    dmxp_producer_free(producer);
+}
+
+#[test]
+#[serial_test::serial]
+fn allocator_handle_queries_the_segment() {
+    init();
+    let producer = dmxp_producer_new(16, 4);
+    assert!(!producer.is_null());
+    assert_eq!(dmxp_producer_send(producer, b"m".as_ptr(), 1), DMXP_SUCCESS);
+
+    let handle = dmxp_allocator_open(SHM_SIZE);
+    assert!(!handle.is_null());
+    assert_eq!(dmxp_allocator_channel_count(handle), dmxp_channel_count());
+
+    let mut ids = [0u32; 64];
+    let mut count = 0;
+    assert_eq!(
+        dmxp_allocator_list_channels(handle, ids.as_mut_ptr(), ids.len(), &mut count),
+        DMXP_SUCCESS
+    );
+    assert!(ids[..count].contains(&16));
+
+    let mut info = std::mem::MaybeUninit::<DmxpChannelInfo>::zeroed();
+    assert_eq!(
+        dmxp_allocator_get_channel_info(handle, 16, info.as_mut_ptr()),
+        DMXP_SUCCESS
+    );
+    let info = unsafe { info.assume_init() };
+    assert_eq!((info.id, info.capacity, info.used_slots), (16, 4, 1));
+    dmxp_allocator_close(handle);
+
+    // A handle only attaches; it never grows or creates the segment
+    assert!(dmxp_allocator_open(2 * SHM_SIZE).is_null());
+    assert!(last_error().starts_with("Failed to open shared memory"));
+    assert_eq!(
+        dmxp_allocator_channel_count(ptr::null_mut()),
+        DMXP_ERROR_NULL_POINTER
+    );
+
+    dmxp_producer_free(producer);
+}
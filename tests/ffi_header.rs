@@ -0,0 +1,102 @@
+// Checks that include/dmxp.h matches src/ffi.rs and is valid C.
+
+use dmxp_kvcache::ffi::{DmxpChannelInfo, FFIMessageMeta};
+use std::mem::{offset_of, size_of};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn crate_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn generate_header() -> String {
+    let config = cbindgen::Config::from_root_or_default(crate_dir());
+    let mut out = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir())
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate bindings")
+        .write(&mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let committed = std::fs::read_to_string(crate_dir().join("include/dmxp.h")).unwrap();
+    assert!(
+        committed == generate_header(),
+        "include/dmxp.h is stale; run `cargo run --example gen_header`"
+    );
+}
+
+#[test]
+fn header_compiles_with_matching_struct_layouts() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler ({}) found", cc);
+        return;
+    }
+
+    // The C side must see the shared structs exactly as Rust lays them out
+    let mut source = String::from("#include <stddef.h>\n#include \"dmxp.h\"\n");
+    let mut check = |ty: &str, field: &str, offset: usize| {
+        source +=
+            &format!("_Static_assert(offsetof({ty}, {field}) == {offset}, \"{ty}.{field}\");\n");
+    };
+    let meta = "FFIMessageMeta";
+    check(meta, "message_id", offset_of!(FFIMessageMeta, message_id));
+    check(
+        meta,
+        "timestamp_ns",
+        offset_of!(FFIMessageMeta, timestamp_ns),
+    );
+    check(meta, "channel_id", offset_of!(FFIMessageMeta, channel_id));
+    check(
+        meta,
+        "message_type",
+        offset_of!(FFIMessageMeta, message_type),
+    );
+    check(meta, "sender_pid", offset_of!(FFIMessageMeta, sender_pid));
+    check(
+        meta,
+        "sender_runtime",
+        offset_of!(FFIMessageMeta, sender_runtime),
+    );
+    check(meta, "flags", offset_of!(FFIMessageMeta, flags));
+    check(meta, "payload_len", offset_of!(FFIMessageMeta, payload_len));
+    check(meta, "checksum", offset_of!(FFIMessageMeta, checksum));
+    let info = "DmxpChannelInfo";
+    check(info, "id", offset_of!(DmxpChannelInfo, id));
+    check(info, "capacity", offset_of!(DmxpChannelInfo, capacity));
+    check(info, "used_slots", offset_of!(DmxpChannelInfo, used_slots));
+    check(info, "offset", offset_of!(DmxpChannelInfo, offset));
+    for (ty, size) in [
+        (meta, size_of::<FFIMessageMeta>()),
+        (info, size_of::<DmxpChannelInfo>()),
+    ] {
+        source += &format!("_Static_assert(sizeof({ty}) == {size}, \"{ty} size\");\n");
+    }
+    source += "int use_api(ProducerHandle *p, ConsumerHandle *c) {\n\
+               \x20   FFIMessageMeta meta;\n\
+               \x20   uint8_t buf[MSG_INLINE];\n\
+               \x20   size_t len = sizeof buf;\n\
+               \x20   if (dmxp_producer_send(p, buf, 0) != DMXP_SUCCESS) return -1;\n\
+               \x20   return dmxp_consumer_receive_ext(c, 0, buf, &len, &meta);\n\
+               }\n";
+
+    let dir = std::env::temp_dir().join(format!("dmxp_header_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("check.c");
+    std::fs::write(&file, source).unwrap();
+
+    let include = crate_dir().join("include");
+    let status = Command::new(&cc)
+        .args(["-std=c11", "-Wall", "-Werror", "-fsyntax-only", "-I"])
+        .arg(Path::new(&include))
+        .arg(&file)
+        .status()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(status.success(), "generated header failed to compile");
+}
@@ -0,0 +1,52 @@
+// The one-time dmxp_init contract. Kept in its own test binary because the
+// initialized allocator is process-global and other FFI tests need it set.
+
+use dmxp_kvcache::ffi::*;
+use std::ptr;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+#[test]
+fn ffi_calls_require_dmxp_init() {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+
+    let mut count = 0usize;
+    assert_eq!(dmxp_channel_count(), DMXP_ERROR_INVALID_ARG);
+    assert_eq!(
+        dmxp_list_channels(ptr::null_mut(), 0, &mut count),
+        DMXP_ERROR_INVALID_ARG
+    );
+    let mut info = std::mem::MaybeUninit::<DmxpChannelInfo>::zeroed();
+    assert_eq!(
+        dmxp_get_channel_info(3, info.as_mut_ptr()),
+        DMXP_ERROR_INVALID_ARG
+    );
+    assert!(dmxp_producer_new(3, 16).is_null());
+    assert!(dmxp_consumer_new(3).is_null());
+    let mut message = [0 as std::ffi::c_char; 64];
+    dmxp_last_error(message.as_mut_ptr(), message.len());
+    let message = unsafe { std::ffi::CStr::from_ptr(message.as_ptr()) };
+    assert_eq!(message.to_str(), Ok("dmxp_init must be called first"));
+
+    assert_eq!(dmxp_init(0), DMXP_ERROR_INVALID_ARG);
+    assert_eq!(dmxp_init(SHM_SIZE), DMXP_SUCCESS);
+    assert_eq!(dmxp_init(SHM_SIZE), DMXP_SUCCESS);
+    // The segment is set up once; a different size is refused
+    assert_eq!(dmxp_init(2 * SHM_SIZE), DMXP_ERROR_INVALID_ARG);
+
+    let producer = dmxp_producer_new(3, 16);
+    assert!(!producer.is_null());
+    let consumer = dmxp_consumer_new(3);
+    assert!(!consumer.is_null());
+    assert_eq!(dmxp_channel_count(), 1);
+
+    let mut ids = [0u32; 4];
+    assert_eq!(
+        dmxp_list_channels(ids.as_mut_ptr(), ids.len(), &mut count),
+        DMXP_SUCCESS
+    );
+    assert_eq!(&ids[..count], &[3]);
+
+    dmxp_consumer_free(consumer);
+    dmxp_producer_free(producer);
+}
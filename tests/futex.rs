@@ -0,0 +1,112 @@
+// Futex wait/wake primitives used for parking producers and consumers.
+
+use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
+use dmxp_kvcache::Core::futex::{futex_wait_timeout, futex_wake, futex_wake_all};
+use dmxp_kvcache::MPMC::ChannelBuilder;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn timed_wait_returns_near_the_requested_duration() {
+    let word = AtomicU32::new(0);
+    let timeout = Duration::from_millis(50);
+
+    let start = Instant::now();
+    let timed_out = futex_wait_timeout(&word, 0, timeout);
+    let elapsed = start.elapsed();
+
+    assert!(timed_out);
+    assert!(elapsed >= timeout, "returned early after {:?}", elapsed);
+    // Loose enough for a loaded machine, far short of an unbounded wait
+    assert!(
+        elapsed < timeout + Duration::from_millis(500),
+        "overslept: {:?}",
+        elapsed
+    );
+}
+
+#[test]
+fn timed_wait_reports_wakeups_and_changed_values() {
+    // Value already changed: no wait at all
+    let word = AtomicU32::new(1);
+    assert!(!futex_wait_timeout(&word, 0, Duration::from_secs(5)));
+
+    let word = Arc::new(AtomicU32::new(0));
+    let waker = {
+        let word = word.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            word.store(1, Ordering::Release);
+            futex_wake(&word);
+        })
+    };
+    let start = Instant::now();
+    let timed_out = futex_wait_timeout(&word, 0, Duration::from_secs(5));
+    assert!(!timed_out);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    waker.join().unwrap();
+}
+
+#[test]
+fn wake_all_releases_every_waiter() {
+    const WAITERS: usize = 6;
+    let word = Arc::new(AtomicU32::new(0));
+
+    let waiters: Vec<_> = (0..WAITERS)
+        .map(|_| {
+            let word = word.clone();
+            // The word never changes, so only a wakeup ends the wait early
+            thread::spawn(move || futex_wait_timeout(&word, 0, Duration::from_secs(10)))
+        })
+        .collect();
+
+    // Let every thread park
+    thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    futex_wake_all(&word);
+
+    for waiter in waiters {
+        assert!(!waiter.join().unwrap(), "a waiter was not woken");
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn receive_timeout_parks_until_a_send_or_the_deadline() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(8 * 1024 * 1024)
+        .with_channel_id(0)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(8 * 1024 * 1024)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    let start = Instant::now();
+    assert!(consumer
+        .receive_timeout(Duration::from_millis(30))?
+        .is_none());
+    assert!(start.elapsed() >= Duration::from_millis(30));
+
+    let monitor = SharedMemoryAllocator::attach(8 * 1024 * 1024)?;
+    let sender = thread::spawn(move || {
+        // Send once the consumer is parked
+        let entry = unsafe { &*monitor.channel_table_ptr() };
+        while entry.data_waiters.load(Ordering::SeqCst) == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        producer.send(b"wake up").unwrap();
+        (producer, monitor)
+    });
+    let data = consumer.receive_timeout(Duration::from_secs(5))?;
+    let (_producer, monitor) = sender.join().unwrap();
+
+    assert_eq!(data.as_deref(), Some(&b"wake up"[..]));
+    // Woken by the send rather than a polling interval
+    assert_eq!(monitor.channel_metrics(0).unwrap().consumer_wakes, 1);
+    Ok(())
+}
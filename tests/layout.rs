@@ -1,8 +1,13 @@
 // Layout conformance tests for ABI stability across languages.
 // These tests assert sizes, alignments, and field offsets for
-// MessageMeta and SlotHeader. They also print the observed values
+// MessageMeta, Slot, ChannelEntry and the FFI structs. They also print the observed values
 // to aid debugging when a mismatch occurs on a given platform.
-// use dmxp_kvcache::MPMC::Buffer::SlotHeader; // Removed
+use dmxp_kvcache::ffi::DmxpChannelInfo;
+use dmxp_kvcache::MPMC::Buffer::layout::{ChannelEntry, EventLog, EventRecord, GlobalHeader};
+use dmxp_kvcache::MPMC::Buffer::{
+    Slot, SlotLayout, MSG_INLINE, SLOT_ALIGN, SLOT_META_OFFSET, SLOT_PAYLOAD_OFFSET,
+    SLOT_SEQUENCE_OFFSET,
+};
 use dmxp_kvcache::MPMC::Structs::MessageMeta;
 use memoffset::offset_of;
 use std::mem::{align_of, size_of};
@@ -10,8 +15,8 @@ use std::mem::{align_of, size_of};
 #[test]
 fn test_message_meta_layout() {
     // Calculate expected size with 8-byte alignment (due to u64 fields).
-    let raw = 8 + 8 + 4 + 4 + 4 + 2 + 2 + 4; // 36 bytes of fields
-    let aligned = (raw + 7) & !7; // round up to 8-byte multiple => 40
+    let raw = 8 + 8 + 4 + 4 + 4 + 2 + 2 + 4 + 4 + 2; // 42 bytes of fields
+    let aligned = (raw + 7) & !7; // round up to 8-byte multiple => 48
 
     let size = size_of::<MessageMeta>(); // get the size of the MessageMeta struct
     let align = align_of::<MessageMeta>(); // get the alignment of the MessageMeta struct
@@ -23,14 +28,18 @@ fn test_message_meta_layout() {
     let off_sender_runtime = offset_of!(MessageMeta, sender_runtime);
     let off_flags = offset_of!(MessageMeta, flags);
     let off_payload_len = offset_of!(MessageMeta, payload_len);
+    let off_checksum = offset_of!(MessageMeta, checksum);
+    let off_schema_version = offset_of!(MessageMeta, schema_version);
+    let off_ttl_ms = offset_of!(MessageMeta, ttl_ms);
 
     println!(
-        "MessageMeta => size: {size}, expected: {aligned}, align: {align} (u64 align: {}), offsets: [message_id:{off_message_id}, timestamp_ns:{off_timestamp_ns}, channel_id:{off_channel_id}, message_type:{off_message_type}, sender_pid:{off_sender_pid}, sender_runtime:{off_sender_runtime}, flags:{off_flags}, payload_len:{off_payload_len}]",
+        "MessageMeta => size: {size}, expected: {aligned}, align: {align} (u64 align: {}), offsets: [message_id:{off_message_id}, timestamp_ns:{off_timestamp_ns}, channel_id:{off_channel_id}, message_type:{off_message_type}, sender_pid:{off_sender_pid}, sender_runtime:{off_sender_runtime}, flags:{off_flags}, payload_len:{off_payload_len}, checksum:{off_checksum}, schema_version:{off_schema_version}, ttl_ms:{off_ttl_ms}]",
         align_of::<u64>()
     );
 
     // Check if the layout matches the expected values
     assert_eq!(size, aligned);
+    assert_eq!(size, 48);
     assert_eq!(align, align_of::<u64>());
     assert_eq!(off_message_id, 0);
     assert_eq!(off_timestamp_ns, 8);
@@ -40,7 +49,151 @@ fn test_message_meta_layout() {
     assert_eq!(off_sender_runtime, 28);
     assert_eq!(off_flags, 30);
     assert_eq!(off_payload_len, 32);
+    assert_eq!(off_checksum, 36);
+    assert_eq!(off_schema_version, 40);
+    assert_eq!(off_ttl_ms, 44);
 }
 
-// SlotHeader test removed as SlotHeader struct no longer exists.
-// See Buffer::Slot for the current slot layout.
+#[test]
+fn test_ffi_channel_info_layout() {
+    assert_eq!(size_of::<DmxpChannelInfo>(), 32);
+    assert_eq!(align_of::<DmxpChannelInfo>(), align_of::<u64>());
+    assert_eq!(offset_of!(DmxpChannelInfo, id), 0);
+    assert_eq!(offset_of!(DmxpChannelInfo, capacity), 8);
+    assert_eq!(offset_of!(DmxpChannelInfo, used_slots), 16);
+    assert_eq!(offset_of!(DmxpChannelInfo, offset), 24);
+}
+
+#[test]
+fn test_global_header_layout() {
+    assert_eq!(size_of::<GlobalHeader>(), 128);
+    assert_eq!(align_of::<GlobalHeader>(), 128);
+    assert_eq!(offset_of!(GlobalHeader, magic), 0);
+    assert_eq!(offset_of!(GlobalHeader, version), 8);
+    assert_eq!(offset_of!(GlobalHeader, max_channels), 12);
+    assert_eq!(offset_of!(GlobalHeader, channel_count), 16);
+    assert_eq!(offset_of!(GlobalHeader, byte_order), 20);
+
+    // The channel table follows the header, then the ID bitmap and the event
+    // log; channel bands follow the event log
+    assert_eq!(GlobalHeader::control_size(0), Some(128 + 2176));
+    assert_eq!(GlobalHeader::id_bitmap_offset(32), Some(128 + 32 * 1920));
+    assert_eq!(
+        GlobalHeader::event_log_offset(32),
+        Some(128 + 32 * 1920 + 128)
+    );
+    assert_eq!(
+        GlobalHeader::control_size(32),
+        Some(128 + 32 * 1920 + 128 + 2176)
+    );
+    assert_eq!(GlobalHeader::id_bitmap_offset(256), Some(128 + 256 * 1920));
+    assert_eq!(
+        GlobalHeader::control_size(256),
+        Some(128 + 256 * 1920 + 128 + 2176)
+    );
+    assert_eq!(
+        GlobalHeader::control_size(1025),
+        Some(128 + 1025 * 1920 + 256 + 2176)
+    );
+    assert_eq!(GlobalHeader::control_size(usize::MAX), None);
+}
+
+#[test]
+fn test_event_log_layout() {
+    assert_eq!(size_of::<EventRecord>(), 32);
+    assert_eq!(offset_of!(EventRecord, sequence), 0);
+    assert_eq!(offset_of!(EventRecord, timestamp_ns), 8);
+    assert_eq!(offset_of!(EventRecord, channel_id), 16);
+    assert_eq!(offset_of!(EventRecord, kind), 20);
+    assert_eq!(offset_of!(EventRecord, pid), 24);
+
+    assert_eq!(size_of::<EventLog>(), 2176);
+    assert_eq!(align_of::<EventLog>(), 128);
+    assert_eq!(offset_of!(EventLog, next), 0);
+    assert_eq!(offset_of!(EventLog, records), 8);
+}
+
+#[test]
+fn test_channel_entry_layout() {
+    assert_eq!(size_of::<ChannelEntry>(), 1920);
+    assert_eq!(align_of::<ChannelEntry>(), 128);
+    assert_eq!(offset_of!(ChannelEntry, band_size), 48);
+    assert_eq!(offset_of!(ChannelEntry, priority_capacity), 56);
+    assert_eq!(offset_of!(ChannelEntry, priority_offset), 64);
+    assert_eq!(offset_of!(ChannelEntry, space_signal), 72);
+    assert_eq!(offset_of!(ChannelEntry, space_waiters), 76);
+    assert_eq!(offset_of!(ChannelEntry, producer_pid), 80);
+    assert_eq!(offset_of!(ChannelEntry, subscriber_mask), 84);
+    assert_eq!(offset_of!(ChannelEntry, heartbeat), 88);
+    assert_eq!(offset_of!(ChannelEntry, open_producers), 96);
+    assert_eq!(offset_of!(ChannelEntry, closed), 100);
+    assert_eq!(offset_of!(ChannelEntry, next_message_id), 104);
+    assert_eq!(offset_of!(ChannelEntry, enqueue_count), 112);
+    assert_eq!(offset_of!(ChannelEntry, enqueue_full_failures), 120);
+    assert_eq!(offset_of!(ChannelEntry, tail), 128);
+    assert_eq!(offset_of!(ChannelEntry, head), 256);
+    assert_eq!(offset_of!(ChannelEntry, priority_tail), 384);
+    assert_eq!(offset_of!(ChannelEntry, priority_head), 512);
+    assert_eq!(offset_of!(ChannelEntry, subscribers), 640);
+    assert_eq!(offset_of!(ChannelEntry, dequeue_count), 1664);
+    assert_eq!(offset_of!(ChannelEntry, dequeue_empty), 1672);
+    assert_eq!(offset_of!(ChannelEntry, attached), 1680);
+    assert_eq!(offset_of!(ChannelEntry, inline_size), 1780);
+    assert_eq!(offset_of!(ChannelEntry, expired_count), 1784);
+    assert_eq!(offset_of!(ChannelEntry, data_waiters), 1792);
+    assert_eq!(offset_of!(ChannelEntry, consumer_wakes), 1800);
+    assert_eq!(offset_of!(ChannelEntry, high_water), 1808);
+    assert_eq!(offset_of!(ChannelEntry, layout_generation), 1816);
+}
+
+#[test]
+fn test_slot_layout() {
+    // The fixed header; payload and lease follow at offsets set by the inline size
+    assert_eq!(size_of::<Slot>(), 56);
+    assert_eq!(align_of::<Slot>(), 8);
+    assert_eq!(offset_of!(Slot, sequence), 0);
+    assert_eq!(offset_of!(Slot, meta), 8);
+    assert_eq!(Slot::PAYLOAD_OFFSET, 56);
+
+    // (inline size, lease offset, stride)
+    for (inline, lease, stride) in [(256, 312, 320), (1024, 1080, 1088), (4096, 4152, 4160)] {
+        assert_eq!(Slot::lease_offset(inline), lease, "inline {inline}");
+        assert_eq!(Slot::stride(inline), stride, "inline {inline}");
+        assert_eq!(stride % SLOT_ALIGN, 0);
+    }
+    // The default keeps the original 1088-byte slots
+    assert_eq!(Slot::stride(MSG_INLINE), 1088);
+    assert_eq!(Slot::stride(8), 128);
+
+    assert!(Slot::is_valid_inline_size(256));
+    assert!(!Slot::is_valid_inline_size(0));
+    assert!(!Slot::is_valid_inline_size(100));
+}
+
+#[test]
+fn test_slot_wire_format() {
+    // The offsets external producers write to; changing them breaks every one
+    assert_eq!(SLOT_SEQUENCE_OFFSET, 0);
+    assert_eq!(SLOT_META_OFFSET, 8);
+    assert_eq!(SLOT_PAYLOAD_OFFSET, 56);
+    assert_eq!(SLOT_SEQUENCE_OFFSET, offset_of!(Slot, sequence));
+    assert_eq!(SLOT_META_OFFSET, offset_of!(Slot, meta));
+    assert_eq!(SLOT_PAYLOAD_OFFSET, Slot::PAYLOAD_OFFSET);
+    assert_eq!(
+        SLOT_META_OFFSET + size_of::<MessageMeta>(),
+        SLOT_PAYLOAD_OFFSET
+    );
+
+    assert_eq!(
+        SlotLayout::new(MSG_INLINE),
+        SlotLayout {
+            sequence_offset: 0,
+            meta_offset: 8,
+            payload_offset: 56,
+            inline_size: 1024,
+            lease_offset: 1080,
+            stride: 1088,
+        }
+    );
+    assert_eq!(SlotLayout::new(256).stride, 320);
+}
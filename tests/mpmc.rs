@@ -1,6 +1,6 @@
-use crossbeam_utils::CachePadded;
 use dmxp_kvcache::MPMC::Buffer::layout::ChannelEntry;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::MPMC::Buffer::verify::{check_seq_payload, seq_payload, stress_verified};
+use dmxp_kvcache::MPMC::Buffer::{RingBuffer, MSG_INLINE};
 use dmxp_kvcache::MPMC::Structs::Buffer_Structs::MessageMeta;
 use std::alloc::{alloc, Layout};
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -9,19 +9,13 @@ use std::thread;
 
 fn create_dummy_channel_entry(capacity: u64) -> ChannelEntry {
     ChannelEntry {
-        channel_id: 0,
-        flags: 0,
         capacity,
-        band_offset: 0,
-        signal: std::sync::atomic::AtomicU32::new(0),
-        tail: CachePadded::new(AtomicU64::new(0)),
-        head: CachePadded::new(AtomicU64::new(0)),
-        _pad: [],
+        ..Default::default()
     }
 }
 
 fn make_aligned_backing(capacity: usize) -> (*mut u8, Layout) {
-    let size = capacity * RingBuffer::slot_stride();
+    let size = capacity * RingBuffer::slot_stride(MSG_INLINE);
     let layout = Layout::from_size_align(size, 128).unwrap();
     let ptr = unsafe { alloc(layout) };
     if ptr.is_null() {
@@ -45,7 +39,7 @@ fn mpmc_correctness_many_threads() {
 
     let buffer = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
     unsafe {
-        buffer.0.init_slots();
+        buffer.0.init_slots().unwrap();
     }
 
     let producers = 4;
@@ -114,7 +108,7 @@ fn mpmc_throughput_print() {
 
     let buffer = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
     unsafe {
-        buffer.0.init_slots();
+        buffer.0.init_slots().unwrap();
     }
 
     let start = std::time::Instant::now();
@@ -156,3 +150,143 @@ fn mpmc_throughput_print() {
         std::alloc::dealloc(ptr, layout);
     }
 }
+
+#[test]
+fn mpmc_exact_capacity_delivers_every_message_once() {
+    // Not a power of two: slots are indexed modulo the capacity, and the
+    // cursors lap the ring many times over
+    let capacity = 100;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let entry = Box::new(entry);
+    let entry_ptr: *const ChannelEntry = &*entry;
+
+    struct SendRingBuffer(RingBuffer);
+    unsafe impl Send for SendRingBuffer {}
+    unsafe impl Sync for SendRingBuffer {}
+
+    let buffer = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
+    unsafe {
+        buffer.0.init_slots().unwrap();
+    }
+
+    let producers = 4;
+    let consumers = 4;
+    let msgs_per_producer = 3000u32;
+    let total_msgs = producers * msgs_per_producer as usize;
+
+    let mut handles = vec![];
+    for p_id in 0..producers {
+        let buffer = buffer.clone();
+        handles.push(thread::spawn(move || {
+            let meta = MessageMeta {
+                message_id: p_id as u64,
+                ..Default::default()
+            };
+
+            // Alternate single sends and batches of three
+            let mut i = 0;
+            while i < msgs_per_producer {
+                if i % 2 == 0 || i + 3 > msgs_per_producer {
+                    let payload = i.to_le_bytes();
+                    while buffer.0.enqueue(meta, &payload).is_none() {
+                        thread::yield_now();
+                    }
+                    i += 1;
+                } else {
+                    let payloads: Vec<[u8; 4]> = (i..i + 3).map(u32::to_le_bytes).collect();
+                    let batch: Vec<(&MessageMeta, &[u8])> =
+                        payloads.iter().map(|p| (&meta, &p[..])).collect();
+                    while buffer.0.enqueue_batch(&batch).is_none() {
+                        thread::yield_now();
+                    }
+                    i += 3;
+                }
+            }
+        }));
+    }
+
+    let received_count = Arc::new(AtomicU64::new(0));
+    let mut consumer_handles = vec![];
+    for _ in 0..consumers {
+        let buffer = buffer.clone();
+        let received_count = received_count.clone();
+        consumer_handles.push(thread::spawn(move || {
+            let mut seen = vec![Vec::new(); producers];
+            loop {
+                if let Some((meta, data)) = buffer.0.dequeue() {
+                    let value = u32::from_le_bytes(data[..4].try_into().unwrap());
+                    seen[meta.message_id as usize].push(value);
+                    received_count.fetch_add(1, Ordering::Relaxed);
+                } else if received_count.load(Ordering::Relaxed) >= total_msgs as u64 {
+                    break seen;
+                } else {
+                    thread::yield_now();
+                }
+            }
+        }));
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let mut all = vec![Vec::new(); producers];
+    for h in consumer_handles {
+        for (p_id, values) in h.join().unwrap().into_iter().enumerate() {
+            // Each consumer sees a producer's messages in send order
+            assert!(values.windows(2).all(|w| w[0] < w[1]));
+            all[p_id].extend(values);
+        }
+    }
+    for mut values in all {
+        values.sort_unstable();
+        assert_eq!(values, (0..msgs_per_producer).collect::<Vec<_>>());
+    }
+    assert!(buffer.0.is_empty());
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn mpmc_payloads_match_their_message_ids_under_contention() {
+    // A small ring, so four producers and four consumers keep colliding on
+    // the same slots lap after lap
+    let capacity = 64;
+    let (ptr, layout) = make_aligned_backing(capacity);
+    let entry = Box::new(create_dummy_channel_entry(capacity as u64));
+    let ring = unsafe { RingBuffer::new(&*entry, ptr) };
+    unsafe {
+        ring.init_slots().unwrap();
+    }
+
+    let per_producer = 20_000;
+    let ids = stress_verified(&ring, 4, 4, per_producer);
+    assert!(ids.iter().copied().eq(0..4 * per_producer));
+    assert!(ring.is_empty());
+
+    // The check catches a payload swapped between messages, or altered
+    assert!(check_seq_payload(7, &seq_payload(7)).is_ok());
+    let err = check_seq_payload(7, &seq_payload(8)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let mut flipped = seq_payload(7);
+    flipped[9] ^= 1;
+    assert!(check_seq_payload(7, &flipped).is_err());
+
+    // ...and so does dequeue_verify, for a message whose id was changed
+    let meta = MessageMeta {
+        message_id: 3,
+        ..Default::default()
+    };
+    ring.enqueue(meta, &seq_payload(4)).unwrap();
+    let panic = std::panic::catch_unwind(|| ring.dequeue_verify()).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("message 3"), "{}", message);
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
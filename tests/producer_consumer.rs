@@ -0,0 +1,2590 @@
+// End-to-end tests driving Producer/Consumer pairs built through ChannelBuilder.
+//
+// Every test maps the same /dev/shm/dmxp_alloc segment, so they are serialized
+// with TEST_LOCK and start from a clean slate.
+
+use dmxp_kvcache::Core::alloc::{AllocatorEventKind, ChannelMetrics, SharedMemoryAllocator};
+use dmxp_kvcache::MPMC::Buffer::{RingBuffer, Slot, MSG_INLINE, SLOT_SEQUENCE_OFFSET};
+use dmxp_kvcache::MPMC::Structs::Buffer_Structs::{FLAG_MONOTONIC, FLAG_SPILLED};
+use dmxp_kvcache::MPMC::{
+    ChannelBuilder, ClockSource, Consumer, Policy, Producer, TailEvent, Timestamps,
+};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Test lock to prevent parallel test execution
+static TEST_LOCK: parking_lot::Mutex<()> = parking_lot::const_mutex(());
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+fn build_pair(channel_id: u32, capacity: usize) -> io::Result<(Producer, Consumer)> {
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .with_capacity(capacity)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .build_consumer()?;
+    Ok((producer, consumer))
+}
+
+#[test]
+fn blocking_receive_wakes_promptly_after_send() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 16)?;
+    let consumer = Arc::new(consumer);
+
+    let waiter = {
+        let consumer = consumer.clone();
+        thread::spawn(move || {
+            let data = consumer
+                .receive_blocking()
+                .expect("receive_blocking failed");
+            (data, Instant::now())
+        })
+    };
+
+    // Give the consumer time to park on the futex
+    thread::sleep(Duration::from_millis(200));
+
+    let sent_at = Instant::now();
+    producer.send(b"wake up")?;
+
+    let (data, received_at) = waiter.join().unwrap();
+    assert_eq!(data, b"wake up");
+    let latency = received_at.duration_since(sent_at);
+    assert!(
+        latency < Duration::from_millis(100),
+        "blocked consumer took {:?} to wake",
+        latency
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blocking_receive_does_not_miss_racing_sends() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 64)?;
+    let consumer = Arc::new(consumer);
+    const COUNT: usize = 2000;
+
+    let reader = {
+        let consumer = consumer.clone();
+        thread::spawn(move || {
+            for i in 0..COUNT {
+                let data = consumer
+                    .receive_blocking()
+                    .expect("receive_blocking failed");
+                assert_eq!(data, (i as u32).to_le_bytes());
+            }
+        })
+    };
+
+    for i in 0..COUNT {
+        let msg = (i as u32).to_le_bytes();
+        while producer.send(msg).is_err() {
+            thread::yield_now();
+        }
+        if i % 64 == 0 {
+            // Let the consumer drain and park so sends race with the futex wait
+            thread::sleep(Duration::from_micros(50));
+        }
+    }
+
+    reader.join().unwrap();
+    Ok(())
+}
+
+fn patterned(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed))
+        .collect()
+}
+
+#[test]
+fn spilled_and_inline_messages_preserve_order() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .with_overflow(4, 8192)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+    assert_eq!(producer.max_message_size(), 8192);
+
+    let sizes = [10, 8192, MSG_INLINE, MSG_INLINE + 1, 0, 4096, 100, 8000, 7];
+    // Several rounds so overflow blocks are recycled
+    for round in 0..5u8 {
+        for (i, &len) in sizes.iter().enumerate() {
+            producer.send(patterned(len, round.wrapping_add(i as u8)))?;
+        }
+        for (i, &len) in sizes.iter().enumerate() {
+            let (meta, data) = consumer.receive_with_meta()?.expect("message missing");
+            assert_eq!(data, patterned(len, round.wrapping_add(i as u8)));
+            assert_eq!(meta.payload_len as usize, len);
+            assert_eq!(meta.flags & FLAG_SPILLED != 0, len > MSG_INLINE);
+        }
+        assert!(consumer.receive()?.is_none());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn vectored_send_concatenates_fragments() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .with_overflow(4, 8192)
+        .with_checksum(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    producer.send_vectored(&[b"header", b"body"])?;
+    let (meta, data) = consumer.receive_with_meta()?.expect("message missing");
+    assert_eq!(data, b"headerbody");
+    assert_eq!(meta.payload_len, 10);
+
+    // Fragments that only fit together in an overflow block
+    let (head, tail) = (patterned(1000, 1), patterned(5000, 2));
+    producer.send_vectored(&[&head, &[], &tail])?;
+    let data = consumer.receive()?.expect("message missing");
+    assert_eq!(data, [head.as_slice(), tail.as_slice()].concat());
+
+    let err = producer
+        .send_vectored(&[&tail, &tail])
+        .expect_err("combined length exceeds the slot");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn overflow_exhaustion_behaves_like_full_ring() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .with_overflow(2, 4096)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    let big = patterned(3000, 1);
+    producer.send(&big)?;
+    producer.send(&big)?;
+
+    // Both overflow blocks are taken; inline messages still flow
+    let err = producer.send(&big).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    producer.send(b"small")?;
+
+    // Larger than a block can ever hold
+    let err = producer.send(patterned(5000, 0)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // A zero-copy read borrows straight from the overflow block and frees it on drop
+    {
+        let msg = consumer.receive_ref()?.expect("message missing");
+        assert_eq!(&*msg, &big[..]);
+        assert!(producer.send(&big).is_err());
+    }
+    producer.send(&big)?;
+
+    assert_eq!(consumer.receive()?.unwrap(), big);
+    assert_eq!(consumer.receive()?.unwrap(), b"small");
+    assert_eq!(consumer.receive()?.unwrap(), big);
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn checksum_mismatch_is_reported_as_invalid_data() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .with_capacity(8)
+        .with_checksum(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .build_consumer()?;
+
+    producer.send(b"first")?;
+    producer.send(b"second")?;
+    producer.send(b"third")?;
+
+    let (meta, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(data, b"first");
+    assert_ne!(meta.checksum, 0);
+
+    // Corrupt the second message directly in shared memory
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    unsafe {
+        let entry = &*allocator.channel_table_ptr().add(2);
+        let band = allocator.shm().as_ptr().add(entry.band_offset as usize);
+        let payload = band.add(RingBuffer::slot_stride(MSG_INLINE) + Slot::PAYLOAD_OFFSET);
+        *payload ^= 0xFF;
+    }
+
+    let err = consumer.receive_with_meta().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // The corrupt message is consumed; the stream carries on after it
+    assert_eq!(consumer.receive()?.unwrap(), b"third");
+
+    Ok(())
+}
+
+#[test]
+fn drain_terminates_while_producer_keeps_sending() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 256)?;
+    for i in 0..100u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let flood = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let _ = producer.send(b"more");
+            }
+        })
+    };
+
+    // Keep draining while the producer floods the channel; each call must return
+    let mut first = consumer.drain()?;
+    for _ in 0..10 {
+        consumer.drain()?;
+    }
+    stop.store(true, Ordering::Relaxed);
+    flood.join().unwrap();
+
+    assert!(first.len() >= 100);
+    first.truncate(100);
+    for (i, (_, data)) in first.iter().enumerate() {
+        assert_eq!(data[..], (i as u32).to_le_bytes());
+    }
+
+    // The flood thread dropped the producer, so the emptied channel reports closed
+    consumer.drain()?;
+    let err = consumer.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+    Ok(())
+}
+
+#[test]
+fn priority_messages_overtake_normal_ones() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .with_priority_capacity(4)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    producer.send(b"bulk-0")?;
+    producer.send_priority(b"ctrl-0")?;
+    producer.send(b"bulk-1")?;
+    producer.send(b"bulk-2")?;
+    producer.send_priority(b"ctrl-1")?;
+
+    // FIFO holds within each band, and the priority band always goes first
+    let order = ["ctrl-0", "ctrl-1", "bulk-0", "bulk-1", "bulk-2"];
+    for expected in order {
+        assert_eq!(consumer.receive()?.unwrap(), expected.as_bytes());
+    }
+    assert!(consumer.receive()?.is_none());
+
+    // The priority band fills independently of the normal ring
+    for i in 0..4u8 {
+        producer.send_priority([i])?;
+    }
+    let err = producer.send_priority(b"one too many").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    producer.send(b"bulk-3")?;
+
+    let drained = consumer.drain()?;
+    let payloads: Vec<&[u8]> = drained.iter().map(|(_, data)| &data[..]).collect();
+    assert_eq!(payloads, [&[0u8][..], &[1], &[2], &[3], b"bulk-3"]);
+
+    Ok(())
+}
+
+#[test]
+fn send_priority_requires_a_priority_band() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, _consumer) = build_pair(0, 8)?;
+    let err = producer.send_priority(b"ctrl").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    Ok(())
+}
+
+#[test]
+fn send_timeout_waits_for_space() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 8)?;
+    for i in 0..8u8 {
+        producer.send([i])?;
+    }
+
+    // Nobody drains: the send gives up at the deadline
+    let started = Instant::now();
+    let err = producer
+        .send_timeout(b"late", Duration::from_millis(20))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    let drainer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        consumer.receive().unwrap().unwrap();
+        consumer
+    });
+
+    producer.send_timeout(b"late", Duration::from_millis(100))?;
+
+    let consumer = drainer.join().unwrap();
+    let rest: Vec<Vec<u8>> = consumer.drain()?.into_iter().map(|(_, d)| d).collect();
+    assert_eq!(rest.len(), 8);
+    assert_eq!(rest.last().unwrap(), b"late");
+
+    Ok(())
+}
+
+#[test]
+fn send_blocking_parks_until_a_slot_is_freed() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 4)?;
+    for i in 0..4u8 {
+        producer.send([i])?;
+    }
+
+    let sent = Arc::new(AtomicBool::new(false));
+    let blocked = {
+        let sent = sent.clone();
+        thread::spawn(move || {
+            producer.send_blocking([4u8]).expect("send_blocking failed");
+            sent.store(true, Ordering::Release);
+            (producer, Instant::now())
+        })
+    };
+
+    // The ring is full, so the producer stays parked
+    thread::sleep(Duration::from_millis(100));
+    assert!(!sent.load(Ordering::Acquire));
+
+    let freed_at = Instant::now();
+    assert_eq!(consumer.receive()?.unwrap(), [0]);
+    let (producer, sent_at) = blocked.join().unwrap();
+    let latency = sent_at.duration_since(freed_at);
+    assert!(
+        latency < Duration::from_millis(50),
+        "parked producer took {:?} to wake",
+        latency
+    );
+
+    let rest: Vec<Vec<u8>> = consumer.drain()?.into_iter().map(|(_, d)| d).collect();
+    assert_eq!(rest, [[1], [2], [3], [4]]);
+
+    // A cleared keep-alive flag releases a producer blocked on a full ring
+    for i in 0..4u8 {
+        producer.send([i])?;
+    }
+    let keep_alive = producer.keep_alive().clone();
+    let blocked = thread::spawn(move || producer.send_blocking(b"never"));
+    thread::sleep(Duration::from_millis(20));
+    keep_alive.store(false, Ordering::Release);
+    let err = blocked.join().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+    Ok(())
+}
+
+/// Set in the environment of the child process spawned by
+/// `receive_blocking_reports_killed_producer`.
+const PRODUCER_CHILD_ENV: &str = "DMXP_TEST_PRODUCER_CHILD";
+
+/// Runs only inside the spawned child: sends one message, then idles until killed.
+#[test]
+fn producer_child_process() -> io::Result<()> {
+    if std::env::var_os(PRODUCER_CHILD_ENV).is_none() {
+        return Ok(());
+    }
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(5)
+        .with_capacity(8)
+        .build_producer()?;
+    producer.send(b"from child")?;
+    loop {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn receive_blocking_reports_killed_producer() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(["--exact", "producer_child_process", "--nocapture"])
+        .env(PRODUCER_CHILD_ENV, "1")
+        .stdout(std::process::Stdio::null())
+        .spawn()?;
+
+    // Wait for the child to create the channel and publish its message
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let consumer = loop {
+        let consumer = ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(5)
+            .build_consumer();
+        match consumer {
+            Ok(consumer) => break consumer,
+            Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Err(e) => {
+                child.kill()?;
+                return Err(e);
+            }
+        }
+    };
+    assert_eq!(consumer.receive_blocking()?, b"from child");
+
+    // The producer idles but is alive: a receive reports an empty channel, not an error
+    assert!(consumer.receive()?.is_none());
+
+    child.kill()?;
+    child.wait()?;
+
+    let started = Instant::now();
+    let err = consumer.receive_blocking().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(!consumer.producer_alive().load(Ordering::Acquire));
+
+    Ok(())
+}
+
+/// Set in the environment of the child process spawned by
+/// `attached_counts_track_processes`.
+const CONSUMER_CHILD_ENV: &str = "DMXP_TEST_CONSUMER_CHILD";
+
+/// Runs only inside the spawned child: attaches a consumer, then idles until killed.
+#[test]
+fn consumer_child_process() -> io::Result<()> {
+    if std::env::var_os(CONSUMER_CHILD_ENV).is_none() {
+        return Ok(());
+    }
+    let _consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(6)
+        .build_consumer()?;
+    loop {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn attached_counts_track_processes() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(6)
+        .with_capacity(8)
+        .build_producer()?;
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    assert_eq!(allocator.producer_count(6), 1);
+    assert_eq!(allocator.consumer_count(6), 0);
+
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(["--exact", "consumer_child_process", "--nocapture"])
+        .env(CONSUMER_CHILD_ENV, "1")
+        .stdout(std::process::Stdio::null())
+        .spawn()?;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while allocator.consumer_count(6) == 0 {
+        if Instant::now() > deadline {
+            child.kill()?;
+            panic!("child consumer never attached");
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let local = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(6)
+        .build_consumer()?;
+    assert_eq!(allocator.consumer_count(6), 2);
+    let info = allocator.channel_infos().pop().unwrap();
+    assert_eq!((info.producers, info.consumers), (1, 2));
+    drop(local);
+    assert_eq!(allocator.consumer_count(6), 1);
+
+    // The child dies without dropping its consumer; its entry is swept
+    child.kill()?;
+    child.wait()?;
+    assert_eq!(allocator.consumer_count(6), 0);
+
+    drop(producer);
+    assert_eq!(allocator.producer_count(6), 0);
+    assert_eq!(allocator.producer_count(7), 0);
+
+    Ok(())
+}
+
+/// Set in the environment of the child process spawned by
+/// `cross_process_reads_are_never_torn`.
+const STRESS_CHILD_ENV: &str = "DMXP_TEST_STRESS_CHILD";
+const STRESS_MESSAGES: u32 = 50_000;
+
+/// The payload of stress message `i`: its index, then a length that varies
+/// with `i`, filled with a byte derived from `i`.
+fn stress_payload(i: u32) -> Vec<u8> {
+    let len = 4 + (i as usize * 37) % (MSG_INLINE - 3);
+    let mut payload = vec![i as u8; len];
+    payload[..4].copy_from_slice(&i.to_le_bytes());
+    payload
+}
+
+/// Runs only inside the spawned child: sends every stress message as fast as
+/// the ring allows, then idles until killed.
+#[test]
+fn stress_producer_child_process() -> io::Result<()> {
+    if std::env::var_os(STRESS_CHILD_ENV).is_none() {
+        return Ok(());
+    }
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(9)
+        .with_capacity(64)
+        .build_producer()?;
+    for i in 0..STRESS_MESSAGES {
+        let payload = stress_payload(i);
+        loop {
+            match producer.send(&payload) {
+                Ok(()) => break,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::yield_now(),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    loop {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn cross_process_reads_are_never_torn() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(["--exact", "stress_producer_child_process", "--nocapture"])
+        .env(STRESS_CHILD_ENV, "1")
+        .stdout(std::process::Stdio::null())
+        .spawn()?;
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let consumer = loop {
+        let consumer = ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(9)
+            .build_consumer();
+        match consumer {
+            Ok(consumer) => break Arc::new(consumer),
+            Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Err(e) => {
+                child.kill()?;
+                return Err(e);
+            }
+        }
+    };
+
+    // Two readers race for every slot while the child keeps the ring full
+    let received = Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let consumer = consumer.clone();
+            let received = received.clone();
+            thread::spawn(move || -> io::Result<Vec<u32>> {
+                let mut seen = Vec::new();
+                while received.load(Ordering::Acquire) < STRESS_MESSAGES {
+                    if Instant::now() > deadline {
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "stress timed out"));
+                    }
+                    let Some((meta, payload)) = consumer.receive_with_meta()? else {
+                        thread::yield_now();
+                        continue;
+                    };
+                    assert_eq!(meta.channel_id, 9);
+                    assert_eq!(meta.payload_len as usize, payload.len());
+                    let i = u32::from_le_bytes(payload[..4].try_into().unwrap());
+                    assert_eq!(payload, stress_payload(i), "message {} torn", i);
+                    seen.push(i);
+                    received.fetch_add(1, Ordering::AcqRel);
+                }
+                Ok(seen)
+            })
+        })
+        .collect();
+    let results: Vec<_> = readers.into_iter().map(|r| r.join()).collect();
+    child.kill()?;
+    child.wait()?;
+
+    let mut all = Vec::new();
+    for result in results {
+        all.extend(result.expect("reader panicked")?);
+    }
+    all.sort_unstable();
+    assert_eq!(all, (0..STRESS_MESSAGES).collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn multi_consumer_drains_channels_at_different_rates() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    // (channel id, message count, pause between sends)
+    let plan = [
+        (0u32, 2000u32, Duration::ZERO),
+        (1, 200, Duration::from_micros(200)),
+        (2, 20, Duration::from_millis(5)),
+    ];
+    let producers = plan
+        .iter()
+        .map(|&(id, _, _)| {
+            ChannelBuilder::new()
+                .with_buffer_size(SHM_SIZE)
+                .with_channel_id(id)
+                .with_capacity(64)
+                .build_producer()
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let multi = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .build_multi_consumer(&[0, 1, 2])?;
+    assert_eq!(multi.channel_ids(), [0, 1, 2]);
+
+    // Fair rotation: a backlog on channel 0 does not hide a message on channel 2
+    const BACKLOG: u32 = 1_000_000;
+    for i in 0..32u32 {
+        producers[0].send((BACKLOG + i).to_le_bytes())?;
+    }
+    producers[2].send(u32::MAX.to_le_bytes())?;
+    let first: Vec<u32> = (0..2)
+        .map(|_| multi.receive_any().unwrap().unwrap().0)
+        .collect();
+    assert!(first.contains(&2), "channel 2 starved: {:?}", first);
+
+    let senders: Vec<_> = producers
+        .into_iter()
+        .zip(plan)
+        .map(|(producer, (_, count, pause))| {
+            thread::spawn(move || {
+                for i in 0..count {
+                    producer.send_blocking(i.to_le_bytes()).unwrap();
+                    thread::sleep(pause);
+                }
+            })
+        })
+        .collect();
+
+    let mut next = [0u32, 0, 0];
+    let mut backlog_left = 31;
+    let total: u32 = plan.iter().map(|&(_, count, _)| count).sum::<u32>() + backlog_left;
+    for _ in 0..total {
+        let (channel, _meta, data) = multi.receive_any_blocking()?;
+        let value = u32::from_le_bytes(data.try_into().unwrap());
+        if value >= BACKLOG {
+            // The rest of the preloaded backlog arrives before channel 0's new sends
+            assert_eq!((channel, next[0]), (0, 0));
+            assert_eq!(value, BACKLOG + 32 - backlog_left);
+            backlog_left -= 1;
+            continue;
+        }
+        let expected = &mut next[channel as usize];
+        assert_eq!(value, *expected, "channel {} out of order", channel);
+        *expected += 1;
+    }
+    for sender in senders {
+        sender.join().unwrap();
+    }
+
+    assert_eq!(next, [2000, 200, 20]);
+    assert!(multi.receive_any()?.is_none());
+
+    // The producers are gone and the channels drained, but the MultiConsumer
+    // is still attached to each of them, so none is reclaimed under it
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    assert_eq!(allocator.consumer_count(2), 1);
+    assert_eq!(allocator.gc_abandoned_channels(), 0);
+    drop(multi);
+    assert_eq!(allocator.gc_abandoned_channels(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn sharded_producer_spreads_messages_evenly() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let sharded = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_capacity(128)
+        .build_sharded_producer(&[0, 1, 2])?;
+    assert_eq!(sharded.channel_ids(), [0, 1, 2]);
+    let consumers = (0..3)
+        .map(|id| {
+            ChannelBuilder::new()
+                .with_buffer_size(SHM_SIZE)
+                .with_channel_id(id)
+                .build_consumer()
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Rotation while every channel has room
+    let mut sent_to = [0usize; 3];
+    for i in 0..300u32 {
+        sent_to[sharded.send(i.to_le_bytes())? as usize] += 1;
+    }
+    assert_eq!(sent_to, [100, 100, 100]);
+    for (id, consumer) in consumers.iter().enumerate() {
+        for expected in (id as u32..300).step_by(3) {
+            let data = consumer.receive()?.unwrap();
+            assert_eq!(u32::from_le_bytes(data.try_into().unwrap()), expected);
+        }
+        assert!(consumer.receive()?.is_none());
+    }
+
+    // Sticky routing: one key always maps to one channel
+    let channel = sharded.channel_for("sensor-7");
+    for _ in 0..10 {
+        assert_eq!(sharded.send_to("sensor-7", b"reading")?, channel);
+    }
+    assert_eq!(consumers[channel as usize].drain()?.len(), 10);
+
+    // Full channels are skipped until all of them are full
+    let filler = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(1)
+        .build_producer()?;
+    for _ in 0..128 {
+        filler.send(b"filler")?;
+    }
+    for _ in 0..256 {
+        assert_ne!(sharded.send(b"x")?, 1);
+    }
+    let err = sharded.send(b"x").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+    Ok(())
+}
+
+#[test]
+fn broadcast_subscribers_each_receive_the_full_stream() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    const COUNT: u32 = 1000;
+    // Default buffer size so Consumer::subscribe_broadcast can attach
+    let producer = ChannelBuilder::new()
+        .with_channel_id(6)
+        .with_capacity(16)
+        .with_broadcast(true)
+        .build_producer()?;
+    let subscribers = [
+        Consumer::subscribe_broadcast(6)?,
+        Consumer::subscribe_broadcast(6)?,
+    ];
+
+    // Shared-cursor consumers would steal messages from subscribers
+    let err = ChannelBuilder::new()
+        .with_channel_id(6)
+        .build_consumer()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = ChannelBuilder::new()
+        .build_multi_consumer(&[6])
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    let readers: Vec<_> = subscribers
+        .into_iter()
+        .map(|subscriber| {
+            thread::spawn(move || {
+                for i in 0..COUNT {
+                    let data = subscriber.receive_blocking().expect("receive failed");
+                    assert_eq!(data, i.to_le_bytes());
+                }
+                assert!(subscriber.receive().unwrap().is_none());
+                subscriber
+            })
+        })
+        .collect();
+
+    // The ring is much smaller than the stream, so the producer waits for the slower subscriber
+    for i in 0..COUNT {
+        producer.send_blocking(i.to_le_bytes())?;
+    }
+    let mut subscribers: Vec<Consumer> = readers.into_iter().map(|r| r.join().unwrap()).collect();
+
+    // An idle subscriber holds the producer back; dropping it releases the slot
+    let idle = subscribers.pop().unwrap();
+    let active = subscribers.pop().unwrap();
+    for i in 0..16u32 {
+        producer.send(i.to_le_bytes())?;
+        assert_eq!(active.receive()?.unwrap(), i.to_le_bytes());
+    }
+    let err = producer.send(b"blocked").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    drop(idle);
+    producer.send(b"released")?;
+    assert_eq!(active.receive()?.unwrap(), b"released");
+
+    Ok(())
+}
+
+#[test]
+fn subscriber_resumes_from_a_saved_offset() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let path = std::env::temp_dir().join(format!("dmxp-offset-{}", std::process::id()));
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(6)
+        .with_capacity(16)
+        .with_broadcast(true)
+        .build_producer()?;
+    let subscribe = || {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(6)
+            .build_subscriber()
+    };
+    let receive_all = |subscriber: &Consumer| -> io::Result<Vec<u32>> {
+        let mut values = Vec::new();
+        while let Some(data) = subscriber.receive()? {
+            values.push(u32::from_le_bytes(data[..4].try_into().unwrap()));
+        }
+        Ok(values)
+    };
+
+    let first = subscribe()?;
+    for i in 0..10u32 {
+        assert_eq!(producer.send_seq(i.to_le_bytes())?, i as u64);
+    }
+    for i in 0..4u32 {
+        assert_eq!(first.receive()?.unwrap(), i.to_le_bytes());
+    }
+    assert_eq!(first.offset(), Some(4));
+    first.save_offset(&path)?;
+    drop(first);
+
+    // Sent while nobody is subscribed, but still in the ring
+    for i in 10..12u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    let second = subscribe()?;
+    assert_eq!(second.offset(), Some(12));
+    assert_eq!(second.load_offset(&path)?, 0);
+    assert_eq!(receive_all(&second)?, (4..12).collect::<Vec<u32>>());
+    second.save_offset(&path)?;
+    drop(second);
+
+    // The ring laps while nobody is subscribed: resume at the oldest message left
+    for i in 12..42u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    let third = subscribe()?;
+    assert_eq!(third.load_offset(&path)?, 15);
+    assert_eq!(receive_all(&third)?, (27..42).collect::<Vec<u32>>());
+
+    let err = third.seek_to(43).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    std::fs::write(&path, "not an offset")?;
+    let err = third.load_offset(&path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path)?;
+
+    // Consumers sharing the channel cursor have no offset of their own
+    let (_, consumer) = build_pair(0, 16)?;
+    assert_eq!(consumer.offset(), None);
+    assert_eq!(
+        consumer.seek_to(0).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    Ok(())
+}
+
+fn build_ack_pair(capacity: usize, ack_timeout: Duration) -> io::Result<(Producer, Consumer)> {
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(7)
+        .with_capacity(capacity)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(7)
+        .with_ack_timeout(ack_timeout)
+        .build_consumer()?;
+    Ok((producer, consumer))
+}
+
+#[test]
+fn acked_message_frees_its_slot() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_ack_pair(2, Duration::from_secs(30))?;
+    producer.send(b"first")?;
+    producer.send(b"second")?;
+
+    let (meta, data, token) = consumer.receive_ack()?.unwrap();
+    assert_eq!(data, b"first");
+    assert_eq!(meta.payload_len, 5);
+    assert!(!token.redelivered());
+
+    // The unacked slot is still pinned, so the full ring stays full
+    let err = producer.send(b"third").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+    token.ack()?;
+    producer.send(b"third")?;
+    assert_eq!(consumer.receive()?.unwrap(), b"second");
+    assert_eq!(consumer.receive()?.unwrap(), b"third");
+    assert!(consumer.receive_ack()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn unacked_message_is_redelivered_after_timeout() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, crashed) = build_ack_pair(4, Duration::from_millis(50))?;
+    let survivor = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(7)
+        .with_ack_timeout(Duration::from_millis(50))
+        .build_consumer()?;
+    producer.send(b"work-0")?;
+    producer.send(b"work-1")?;
+
+    // Dropping the token without acking stands in for a crashed consumer
+    let lost_deadline = {
+        let (_, data, lost_token) = crashed.receive_ack()?.unwrap();
+        assert_eq!(data, b"work-0");
+        lost_token.deadline_ns()
+    };
+
+    // Before the deadline other consumers only see newer messages
+    let (_, data, token) = survivor.receive_ack()?.unwrap();
+    assert_eq!(data, b"work-1");
+    token.ack()?;
+    assert!(survivor.receive_ack()?.is_none());
+
+    thread::sleep(Duration::from_millis(80));
+    let (_, data, token) = survivor.receive_ack()?.unwrap();
+    assert_eq!(data, b"work-0");
+    assert!(token.redelivered());
+    assert!(token.deadline_ns() > lost_deadline);
+    token.ack()?;
+    assert!(survivor.receive_ack()?.is_none());
+
+    // A slow consumer whose message was taken over can no longer ack it
+    producer.send(b"work-2")?;
+    let (_, _, slow_token) = crashed.receive_ack()?.unwrap();
+    thread::sleep(Duration::from_millis(80));
+    let (_, data, token) = survivor.receive_ack()?.unwrap();
+    assert_eq!(data, b"work-2");
+    let err = slow_token.ack().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    token.ack()?;
+
+    Ok(())
+}
+
+#[test]
+fn acks_may_arrive_out_of_order() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_ack_pair(2, Duration::from_secs(30))?;
+    producer.send(b"a")?;
+    producer.send(b"b")?;
+    let (_, _, token_a) = consumer.receive_ack()?.unwrap();
+    let (_, _, token_b) = consumer.receive_ack()?.unwrap();
+
+    // Slots are reused in ring order: acking the newer message frees nothing yet
+    token_b.ack()?;
+    let err = producer.send(b"c").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+    token_a.ack()?;
+    producer.send(b"c")?;
+    producer.send(b"d")?;
+    assert_eq!(consumer.receive()?.unwrap(), b"c");
+    assert_eq!(consumer.receive()?.unwrap(), b"d");
+
+    Ok(())
+}
+
+#[test]
+fn receive_checked_reports_skipped_message_ids() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 2)?;
+    producer.send(b"id-0")?;
+    producer.send(b"id-1")?;
+    // A send on the full ring burns id 2
+    let err = producer.send(b"dropped").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+    let (meta, data, missing) = consumer.receive_checked()?.unwrap();
+    assert_eq!((meta.message_id, &data[..], missing), (0, &b"id-0"[..], 0));
+    let (meta, _, missing) = consumer.receive_checked()?.unwrap();
+    assert_eq!((meta.message_id, missing), (1, 0));
+
+    producer.send(b"id-3")?;
+    let (meta, data, missing) = consumer.receive_checked()?.unwrap();
+    assert_eq!((meta.message_id, &data[..], missing), (3, &b"id-3"[..], 1));
+    assert!(consumer.receive_checked()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn dropped_producer_closes_channel_after_buffered_messages() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 8)?;
+    let reader = thread::spawn(move || {
+        let mut received = Vec::new();
+        let err = loop {
+            match consumer.receive_blocking() {
+                Ok(data) => received.push(data),
+                Err(e) => break e,
+            }
+        };
+        (received, err)
+    });
+
+    producer.send(b"one")?;
+    producer.send(b"two")?;
+    producer.send(b"three")?;
+    drop(producer);
+
+    let start = Instant::now();
+    let (received, err) = reader.join().unwrap();
+    assert_eq!(received, [&b"one"[..], b"two", b"three"]);
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert!(err.to_string().contains("closed"));
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    Ok(())
+}
+
+#[test]
+fn channel_stays_open_until_the_last_producer_closes() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (first, consumer) = build_pair(0, 8)?;
+    let second = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_producer()?;
+
+    first.send(b"from first")?;
+    first.close();
+    assert_eq!(consumer.receive()?.unwrap(), b"from first");
+    assert!(consumer.receive()?.is_none());
+
+    second.send(b"from second")?;
+    second.close();
+    assert_eq!(consumer.receive()?.unwrap(), b"from second");
+    let err = consumer.receive().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+    // A producer attaching again reopens the channel
+    let _third = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_producer()?;
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn builder_rejects_invalid_capacity_before_touching_shared_memory() {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    for capacity in [0, 3, 1000] {
+        let err = ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_capacity(capacity)
+            .build_producer()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("power of two"), "{}", err);
+    }
+
+    #[cfg(target_os = "linux")]
+    assert!(!std::path::Path::new("/dev/shm/dmxp_alloc").exists());
+}
+
+#[test]
+fn slot_size_allows_full_size_message_in_every_slot() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(256)
+        .with_slot_size(4096)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+    assert_eq!(producer.max_message_size(), 4096);
+    assert_eq!(producer.slot_size(), 4096);
+    assert_eq!(consumer.slot_size(), 4096);
+
+    // Every slot holds a 4KB message at once
+    for i in 0..256 {
+        producer.send(patterned(4096, i as u8))?;
+    }
+    let err = producer.send(b"full").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    // Stored inline, without an overflow band
+    for i in 0..256 {
+        let (meta, payload) = consumer.receive_with_meta()?.unwrap();
+        assert_eq!(meta.flags & FLAG_SPILLED, 0);
+        assert_eq!(payload, patterned(4096, i as u8));
+    }
+
+    let err = producer.send(vec![0u8; 4097]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    Ok(())
+}
+
+#[test]
+fn create_policy_distinguishes_missing_and_existing_channels() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let builder = |channel_id, policy| {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(channel_id)
+            .with_capacity(8)
+            .with_create_policy(policy)
+    };
+
+    // No segment yet
+    let err = builder(0, Policy::AttachOnly)
+        .build_producer()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    let producer = builder(0, Policy::CreateOnly).build_producer()?;
+    let err = builder(0, Policy::CreateOnly)
+        .build_producer()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    // Segment exists, channel 1 does not
+    let err = builder(1, Policy::AttachOnly)
+        .build_consumer()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    // Either side may create the channel; the other one attaches to it
+    let early_consumer = builder(1, Policy::CreateOrAttach).build_consumer()?;
+    let late_producer = builder(1, Policy::CreateOrAttach).build_producer()?;
+    late_producer.send(b"ready")?;
+    assert_eq!(early_consumer.receive()?.unwrap(), b"ready");
+
+    // Creating more channels never truncates the segment under existing ones
+    producer.send(b"still here")?;
+    let consumer = builder(0, Policy::AttachOnly).build_consumer()?;
+    assert_eq!(consumer.receive()?.unwrap(), b"still here");
+
+    Ok(())
+}
+
+#[test]
+fn receive_default_waits_for_the_configured_timeout() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, immediate) = build_pair(0, 8)?;
+    let patient = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_default_timeout(Duration::from_millis(50))
+        .build_consumer()?;
+    assert_eq!(patient.default_timeout(), Some(Duration::from_millis(50)));
+    assert_eq!(immediate.default_timeout(), None);
+
+    let start = Instant::now();
+    assert!(patient.receive_default()?.is_none());
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // Without a default it does not wait
+    let start = Instant::now();
+    assert!(immediate.receive_default()?.is_none());
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    producer.send(b"in time")?;
+    assert_eq!(patient.receive_default()?.unwrap(), b"in time");
+
+    Ok(())
+}
+
+#[test]
+fn peek_does_not_consume() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(8)
+        .with_priority_capacity(2)
+        .with_overflow(2, 4096)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+    assert!(consumer.peek_with_meta()?.is_none());
+
+    let large = patterned(3000, 7);
+    producer.send(&large)?;
+    producer.send(b"small")?;
+
+    // Peeking twice sees the same message, spilled payload included
+    for _ in 0..2 {
+        let (meta, data) = consumer.peek_with_meta()?.expect("message missing");
+        assert_eq!(data, large);
+        assert_eq!(meta.flags & FLAG_SPILLED, FLAG_SPILLED);
+    }
+    let (peeked, _) = consumer.peek_with_meta()?.unwrap();
+    let (received, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(received.message_id, peeked.message_id);
+    assert_eq!(data, large);
+
+    // The priority band is looked at first, like a receive
+    producer.send_priority(b"urgent")?;
+    assert_eq!(consumer.peek_with_meta()?.unwrap().1, b"urgent");
+    assert_eq!(consumer.receive()?.unwrap(), b"urgent");
+    assert_eq!(consumer.peek_with_meta()?.unwrap().1, b"small");
+    assert_eq!(consumer.receive()?.unwrap(), b"small");
+    assert!(consumer.peek_with_meta()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn send_typed_tags_type_and_schema_version() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 8)?;
+    producer.send(b"plain")?;
+    producer.send_typed(b"v2 layout", 42, 2)?;
+
+    let (meta, _) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!((meta.message_type, meta.schema_version), (1, 0));
+    let (meta, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(data, b"v2 layout");
+    assert_eq!((meta.message_type, meta.schema_version), (42, 2));
+
+    Ok(())
+}
+
+#[test]
+fn send_with_meta_sets_type_and_application_flags() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 8)?;
+    producer.send_with_meta(b"routed", 7, 0x05)?;
+
+    let (meta, data) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(data, b"routed");
+    assert_eq!(meta.message_type, 7);
+    assert_eq!(meta.flags, 0x05);
+    assert_eq!(meta.channel_id, 0);
+    assert_eq!(meta.sender_pid, std::process::id());
+
+    // The upper byte belongs to the transport
+    let err = producer
+        .send_with_meta(b"spoofed", 7, FLAG_SPILLED)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn producers_on_a_channel_share_one_message_id_sequence() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const PER_PRODUCER: usize = 500;
+
+    let (batcher, consumer) = build_pair(0, 64)?;
+    let single = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_producer()?;
+
+    // One producer sends pairs as a batch, the other single messages
+    let batches = thread::spawn(move || {
+        for _ in 0..PER_PRODUCER {
+            while batcher.send_batch(&[b"a", b"b"]).is_err() {
+                thread::yield_now();
+            }
+        }
+        batcher
+    });
+    let singles = thread::spawn(move || {
+        for _ in 0..PER_PRODUCER {
+            while single.send(b"m").is_err() {
+                thread::yield_now();
+            }
+        }
+        single
+    });
+
+    let mut ids = Vec::with_capacity(3 * PER_PRODUCER);
+    let mut batch_start = None;
+    while ids.len() < 3 * PER_PRODUCER {
+        let Some((meta, data)) = consumer.receive_with_meta()? else {
+            thread::yield_now();
+            continue;
+        };
+        // A batch occupies a contiguous id range
+        if data == b"b" {
+            assert_eq!(batch_start.map(|id: u64| id + 1), Some(meta.message_id));
+        }
+        batch_start = (data == b"a").then_some(meta.message_id);
+        ids.push(meta.message_id);
+    }
+    let _producers = (batches.join().unwrap(), singles.join().unwrap());
+
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 3 * PER_PRODUCER, "duplicate message ids");
+
+    Ok(())
+}
+
+#[test]
+fn timestamps_follow_the_producer_mode() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    let now_ns = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64
+    };
+
+    let (precise, consumer) = build_pair(0, 64)?;
+    let build = |timestamps| {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(0)
+            .with_timestamps(timestamps)
+            .build_producer()
+    };
+    let coarse = build(Timestamps::Coarse)?;
+    let off = build(Timestamps::Off)?;
+
+    let before = now_ns();
+    precise.send(b"precise")?;
+    precise.send_no_timestamp(b"none")?;
+    coarse.send(b"coarse")?;
+    off.send(b"off")?;
+    off.send_batch(&[b"off batch"])?;
+    let after = now_ns();
+
+    let mut stamps = Vec::new();
+    while let Some((meta, data)) = consumer.receive_with_meta()? {
+        stamps.push((data, meta.timestamp_ns));
+    }
+    assert_eq!(stamps.len(), 5);
+    let [(_, precise_ns), (none, none_ns), (_, coarse_ns), (_, off_ns), (_, batch_ns)] =
+        &stamps[..]
+    else {
+        unreachable!()
+    };
+    assert!((before..=after).contains(precise_ns));
+    assert_eq!((none.as_slice(), *none_ns), (&b"none"[..], 0));
+    // The coarse clock lags the wall clock by at most a few ticks
+    assert!(*coarse_ns > 0 && *coarse_ns <= after);
+    assert!(before.saturating_sub(*coarse_ns) < Duration::from_millis(50).as_nanos() as u64);
+    assert_eq!((*off_ns, *batch_ns), (0, 0));
+
+    Ok(())
+}
+
+#[test]
+fn channel_metrics_count_traffic() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const CAPACITY: usize = 16;
+
+    let (producer, consumer) = build_pair(3, CAPACITY)?;
+    for i in 0..CAPACITY as u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    // Full: one rejected send and one rejected batch
+    assert!(producer.send(b"full").is_err());
+    assert!(producer.send_batch(&[b"a", b"b"]).is_err());
+
+    for _ in 0..CAPACITY {
+        assert!(consumer.receive()?.is_some());
+    }
+    assert!(consumer.receive()?.is_none());
+    producer.send_batch(&[b"a", b"b", b"c"])?;
+    assert_eq!(consumer.drain()?.len(), 3);
+
+    // Read from a separate mapping, as a monitoring process would
+    let monitor = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    assert_eq!(
+        monitor.channel_metrics(3),
+        Some(ChannelMetrics {
+            enqueue_count: CAPACITY as u64 + 3,
+            dequeue_count: CAPACITY as u64 + 3,
+            enqueue_full_failures: 2,
+            dequeue_empty: 1,
+            expired_count: 0,
+            consumer_wakes: 0,
+        })
+    );
+    assert_eq!(monitor.channel_metrics(4), None);
+
+    Ok(())
+}
+
+#[test]
+fn expired_messages_are_skipped_and_counted() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(3, 16)?;
+    producer.send_ttl(b"stale", Duration::from_millis(20))?;
+    producer.send_ttl(b"fresh", Duration::from_secs(60))?;
+    producer.send(b"forever")?;
+    thread::sleep(Duration::from_millis(50));
+
+    let (meta, payload) = consumer.receive_with_meta()?.expect("fresh message");
+    assert_eq!(payload, b"fresh");
+    assert_eq!(meta.ttl_ms, 60_000);
+    assert_eq!(meta.expiry_ns(), Some(meta.timestamp_ns + 60_000_000_000));
+    assert_eq!(consumer.receive()?.as_deref(), Some(&b"forever"[..]));
+    assert!(consumer.receive()?.is_none());
+
+    let metrics = SharedMemoryAllocator::attach(SHM_SIZE)?
+        .channel_metrics(3)
+        .unwrap();
+    assert_eq!(metrics.expired_count, 1);
+    assert_eq!(metrics.dequeue_count, 2);
+    // The expired slot was handed back to producers
+    for i in 0..16u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn overwrite_channel_keeps_the_newest_messages() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const CAPACITY: usize = 4;
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(8)
+        .with_capacity(CAPACITY)
+        .with_overwrite(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(8)
+        .build_consumer()?;
+
+    // Overrun the ring with nobody reading: every send succeeds
+    for i in 0..100u64 {
+        producer.send(i.to_le_bytes())?;
+    }
+    let newest: Vec<u64> = consumer
+        .drain()?
+        .iter()
+        .map(|(_, payload)| u64::from_le_bytes(payload[..8].try_into().unwrap()))
+        .collect();
+    assert_eq!(newest, vec![96, 97, 98, 99]);
+
+    // Overrun it again while a consumer reads; every message must arrive whole
+    // and in order, however many were dropped in between
+    const MESSAGES: u64 = 20_000;
+    let reader = thread::spawn(move || -> io::Result<u64> {
+        let mut last = None;
+        loop {
+            let Some(payload) = consumer.receive()? else {
+                thread::yield_now();
+                continue;
+            };
+            let value = u64::from_le_bytes(payload[..8].try_into().unwrap());
+            assert!(
+                payload.chunks(8).all(|chunk| chunk == value.to_le_bytes()),
+                "torn payload for message {}",
+                value
+            );
+            assert!(
+                last.is_none_or(|last| value > last),
+                "{} after {:?}",
+                value,
+                last
+            );
+            last = Some(value);
+            if value == MESSAGES - 1 {
+                return Ok(value);
+            }
+        }
+    });
+    for i in 0..MESSAGES {
+        let payload = i.to_le_bytes().repeat(64);
+        // Fails only while the consumer holds the oldest slot it just claimed
+        while producer.send(&payload).is_err() {
+            thread::yield_now();
+        }
+    }
+    assert_eq!(reader.join().unwrap()?, MESSAGES - 1);
+
+    Ok(())
+}
+
+#[test]
+fn latency_tracking_measures_send_to_receive_delay() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const DELAY: Duration = Duration::from_millis(20);
+
+    let (producer, consumer) = build_pair(0, 64)?;
+    let consumer = consumer.with_latency_tracking();
+    assert_eq!(consumer.latency_snapshot().count, 0);
+
+    for i in 0..10u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    // Untimestamped messages are not measured
+    producer.send_no_timestamp(b"no timestamp")?;
+    thread::sleep(DELAY);
+    while consumer.receive()?.is_some() {}
+
+    let snapshot = consumer.latency_snapshot();
+    assert_eq!(snapshot.count, 10);
+    assert!(
+        snapshot.p50 >= DELAY && snapshot.p50 < DELAY * 2,
+        "p50 {:?} for a {:?} delay",
+        snapshot.p50,
+        DELAY
+    );
+    assert!(snapshot.p50 <= snapshot.p99 && snapshot.p99 <= snapshot.max);
+
+    Ok(())
+}
+
+#[test]
+fn sealed_channel_rejects_sends_but_drains() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(2, 16)?;
+    producer.send(b"config v1")?;
+    producer.send(b"config v2")?;
+
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    assert!(!allocator.is_sealed(2));
+    allocator.seal_channel(2)?;
+    assert!(allocator.is_sealed(2));
+    assert!(!allocator.is_sealed(3));
+    assert_eq!(
+        allocator.seal_channel(3).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    for result in [
+        producer.send(b"late"),
+        producer.send_batch(&[b"late"]),
+        producer.send_timeout(b"late", Duration::from_millis(10)),
+    ] {
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+    }
+    // A producer attaching afterwards is refused too
+    let late = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .build_producer()?;
+    assert_eq!(
+        late.send(b"late").unwrap_err().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+
+    assert_eq!(consumer.receive()?.as_deref(), Some(&b"config v1"[..]));
+    assert_eq!(consumer.receive()?.as_deref(), Some(&b"config v2"[..]));
+    assert_eq!(consumer.receive()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn producer_and_consumer_report_channel_geometry() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(4, 32)?;
+    let stride = RingBuffer::slot_stride(MSG_INLINE);
+
+    assert_eq!(producer.capacity(), 32);
+    assert_eq!(consumer.capacity(), 32);
+    assert_eq!(producer.slot_size(), MSG_INLINE);
+    assert_eq!(consumer.slot_size(), MSG_INLINE);
+    assert_eq!(producer.stride(), stride);
+    assert_eq!(consumer.stride(), stride);
+    assert_eq!(consumer.max_message_size(), producer.max_message_size());
+
+    Ok(())
+}
+
+#[test]
+fn send_seq_returns_increasing_unique_sequences() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 16)?;
+    let second = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_producer()?;
+    assert_eq!(producer.send_seq(b"first")?, 0);
+    assert_eq!(consumer.receive()?.as_deref(), Some(&b"first"[..]));
+
+    // Far more sends than slots, from two producers at once
+    let stop = Arc::new(AtomicBool::new(false));
+    let drain = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Acquire) {
+                if consumer.receive().unwrap().is_none() {
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+    let sends = |producer: Producer| {
+        thread::spawn(move || -> io::Result<Vec<u64>> {
+            let mut sequences = Vec::new();
+            while sequences.len() < 1000 {
+                match producer.send_seq(b"payload") {
+                    Ok(sequence) => sequences.push(sequence),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::yield_now(),
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(sequences)
+        })
+    };
+    let (a, b) = (sends(producer), sends(second));
+    let (a, b) = (a.join().unwrap()?, b.join().unwrap()?);
+    stop.store(true, Ordering::Release);
+    drain.join().unwrap();
+
+    for sequences in [&a, &b] {
+        assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+    let mut all: Vec<u64> = a.into_iter().chain(b).collect();
+    all.sort_unstable();
+    assert_eq!(all, (1..=2000).collect::<Vec<u64>>());
+
+    Ok(())
+}
+
+#[test]
+fn forward_to_moves_messages_between_channels_in_order() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (source, inbox) = build_pair(0, 128)?;
+    let (outbox, sink) = build_pair(1, 64)?;
+
+    for i in 0..100u32 {
+        source.send_with_meta(format!("message {}", i), 7, 0x03)?;
+    }
+
+    let mut received = Vec::new();
+    let drain = |received: &mut Vec<_>| -> io::Result<()> {
+        while let Some((meta, payload)) = sink.receive_with_meta()? {
+            received.push((meta, payload));
+        }
+        Ok(())
+    };
+    loop {
+        match inbox.forward_to(&outbox) {
+            Ok(true) => {}
+            Ok(false) => break,
+            // A full destination leaves the message in the source
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => drain(&mut received)?,
+            Err(e) => return Err(e),
+        }
+    }
+    drain(&mut received)?;
+
+    assert!(inbox.receive()?.is_none());
+    assert_eq!(received.len(), 100);
+    for (i, (meta, payload)) in received.iter().enumerate() {
+        assert_eq!(payload, format!("message {}", i).as_bytes());
+        assert_eq!(meta.channel_id, 1);
+        assert_eq!(meta.message_type, 7);
+        assert_eq!(meta.flags, 0x03);
+        assert_eq!(meta.sender_pid, std::process::id());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn forward_to_leaves_refused_messages_in_the_source() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (source, inbox) = build_pair(0, 16)?;
+    let outbox = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(1)
+        .with_capacity(16)
+        .with_inline_size(64)
+        .build_producer()?;
+    assert!(outbox.max_message_size() < source.max_message_size());
+
+    // Too large for the destination
+    let large = vec![0xAB; outbox.max_message_size() + 1];
+    source.send(&large)?;
+    let err = inbox.forward_to(&outbox).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(inbox.receive()?.as_deref(), Some(&large[..]));
+
+    // Destination sealed
+    source.send(b"small")?;
+    SharedMemoryAllocator::attach(SHM_SIZE)?.seal_channel(1)?;
+    let err = inbox.forward_to(&outbox).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert_eq!(inbox.receive()?.as_deref(), Some(&b"small"[..]));
+
+    Ok(())
+}
+
+#[test]
+fn single_producer_channel_delivers_everything_in_order() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const MESSAGES: u64 = 200_000;
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .with_capacity(64)
+        .with_inline_size(8)
+        .with_single_producer(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .build_consumer()?;
+
+    let reader = thread::spawn(move || -> io::Result<()> {
+        for expected in 0..MESSAGES {
+            let payload = consumer.receive_blocking()?;
+            assert_eq!(payload, expected.to_le_bytes());
+        }
+        Ok(())
+    });
+    let start = Instant::now();
+    for i in 0..MESSAGES {
+        producer.send_blocking(i.to_le_bytes())?;
+    }
+    reader.join().unwrap()?;
+    println!(
+        "single producer: {} messages in {:?}",
+        MESSAGES,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn single_producer_channel_panics_on_a_second_producer_in_debug() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let builder = || {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(2)
+            .with_single_producer(true)
+    };
+    let producer = builder().build_producer()?;
+    producer.send(b"alone")?;
+
+    let second = builder().build_producer()?;
+    let sent = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| second.send(b"intruder")));
+    assert!(sent.is_err());
+
+    // Once the second producer is gone, the first one may send again
+    drop(second);
+    producer.send(b"alone again")?;
+
+    Ok(())
+}
+
+#[test]
+fn corrupted_sequence_poisons_the_channel_instead_of_hanging() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(3, 16)?;
+    let (other_producer, other_consumer) = build_pair(4, 16)?;
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    // Overwrite the sequence word of the slot for `position` on `channel_id`
+    let corrupt = |channel_id: u32, position: usize, sequence: u64| {
+        let channel = allocator.get_channel(channel_id).unwrap();
+        let (slots, _) = channel.data_raw_parts();
+        let stride = channel.buffer().stride();
+        unsafe {
+            let word = slots.add((position % 16) * stride + SLOT_SEQUENCE_OFFSET);
+            word.cast::<u64>().write_volatile(sequence);
+        }
+    };
+
+    // A consumer reaching the corrupted slot fails instead of spinning on it
+    producer.send(b"first")?;
+    producer.send(b"second")?;
+    assert_eq!(consumer.receive()?.unwrap(), b"first");
+    corrupt(3, 1, 12345);
+    assert!(!allocator.is_poisoned(3));
+    assert_eq!(
+        consumer.receive().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    assert!(allocator.is_poisoned(3));
+    assert_eq!(
+        consumer.receive_blocking().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+        producer.send_blocking(b"third").unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    // A producer finds it too, and other channels are unaffected
+    corrupt(4, 0, u64::MAX / 2);
+    assert_eq!(
+        other_producer.send(b"lost").unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    assert!(allocator.is_poisoned(4));
+    assert_eq!(
+        other_consumer.receive().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    assert!(!allocator.is_poisoned(0));
+
+    // Each channel is reported poisoned once, by whoever found it
+    let poisoned: Vec<(AllocatorEventKind, u32)> = allocator
+        .recent_events(2)
+        .iter()
+        .map(|event| (event.kind, event.channel_id))
+        .collect();
+    assert_eq!(
+        poisoned,
+        [
+            (AllocatorEventKind::Poisoned, 3),
+            (AllocatorEventKind::Poisoned, 4)
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn batch_receive_drains_a_burst_after_one_wakeup() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 128)?;
+    let consumer = Arc::new(consumer);
+
+    // Parked on an empty channel when the burst arrives
+    let waiter = {
+        let consumer = consumer.clone();
+        thread::spawn(move || consumer.receive_batch_blocking(64))
+    };
+    thread::sleep(Duration::from_millis(50));
+    let burst: Vec<Vec<u8>> = (0..50u32).map(|i| i.to_le_bytes().to_vec()).collect();
+    let refs: Vec<&[u8]> = burst.iter().map(Vec::as_slice).collect();
+    producer.send_batch(&refs)?;
+
+    let mut received = waiter.join().unwrap()?;
+    assert!(
+        received.len() >= 40,
+        "only {} of 50 in the batch",
+        received.len()
+    );
+    while received.len() < 50 {
+        received.extend(consumer.receive_batch_blocking(64)?);
+    }
+    let payloads: Vec<Vec<u8>> = received.into_iter().map(|(_, payload)| payload).collect();
+    assert_eq!(payloads, burst);
+
+    // Never more than `max`, and the rest stays in the channel
+    for i in 0..10u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    assert_eq!(consumer.receive_batch_blocking(4)?.len(), 4);
+    assert_eq!(consumer.receive_batch_blocking(64)?.len(), 6);
+    assert!(consumer.receive_batch_blocking(0)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn batch_receive_reports_a_corrupted_message_on_the_next_call() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .with_capacity(8)
+        .with_checksum(true)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(2)
+        .build_consumer()?;
+    for i in 0..5u8 {
+        producer.send([i])?;
+    }
+
+    // Corrupt the third message directly in shared memory
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    unsafe {
+        let entry = &*allocator.channel_table_ptr().add(2);
+        let band = allocator.shm().as_ptr().add(entry.band_offset as usize);
+        let payload = band.add(2 * RingBuffer::slot_stride(MSG_INLINE) + Slot::PAYLOAD_OFFSET);
+        *payload ^= 0xFF;
+    }
+
+    // The batch stops before it, and the next call reports it
+    let batch = consumer.receive_batch_blocking(8)?;
+    let payloads: Vec<Vec<u8>> = batch.into_iter().map(|(_, payload)| payload).collect();
+    assert_eq!(payloads, [[0], [1]]);
+    let err = consumer.receive_batch_blocking(8).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // The stream carries on after it
+    let batch = consumer.receive_batch_blocking(8)?;
+    let payloads: Vec<Vec<u8>> = batch.into_iter().map(|(_, payload)| payload).collect();
+    assert_eq!(payloads, [[3], [4]]);
+
+    Ok(())
+}
+
+#[test]
+fn sends_skip_the_wake_syscall_while_the_consumer_is_draining() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    const MESSAGES: u32 = 20_000;
+    let (producer, consumer) = build_pair(0, 1024)?;
+    let monitor = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    let wakes = || monitor.channel_metrics(0).unwrap().consumer_wakes;
+
+    // Nobody parked: no wakes at all
+    for i in 0..100u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    assert_eq!(consumer.drain()?.len(), 100);
+    assert_eq!(wakes(), 0);
+
+    // A consumer spinning on `receive` never parks, so steady traffic to it
+    // costs no syscalls
+    let drainer = thread::spawn(move || {
+        let mut received = 0u32;
+        while received < MESSAGES {
+            match consumer.receive() {
+                Ok(Some(_)) => received += 1,
+                Ok(None) => std::hint::spin_loop(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(consumer)
+    });
+    for i in 0..MESSAGES {
+        while let Err(e) = producer.send(i.to_le_bytes()) {
+            assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+            thread::yield_now();
+        }
+    }
+    let consumer = drainer.join().unwrap()?;
+    assert_eq!(wakes(), 0);
+
+    // A parked consumer is still woken, once
+    let waiter = thread::spawn(move || consumer.receive_blocking());
+    thread::sleep(Duration::from_millis(50));
+    producer.send(b"wake up")?;
+    assert_eq!(waiter.join().unwrap()?, b"wake up");
+    assert_eq!(wakes(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn slot_writer_and_reader_round_trip_through_io_traits() -> io::Result<()> {
+    use std::io::{Read, Write};
+
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 16)?;
+
+    let mut writer = producer.writer()?;
+    let (id, item) = (42, "widget");
+    write!(writer, "order {id} x {item}")?;
+    writer.write_all(b"; done")?;
+    assert_eq!(writer.len(), 23);
+    drop(writer);
+
+    let mut reader = consumer.reader()?.unwrap();
+    assert_eq!(reader.meta().payload_len, 23);
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    assert_eq!(text, "order 42 x widget; done");
+    drop(reader);
+    assert!(consumer.reader()?.is_none());
+
+    // Flushing publishes; the message is visible while the writer is alive
+    let mut writer = producer.writer()?;
+    writer.write_all(b"flushed")?;
+    writer.flush()?;
+    assert_eq!(consumer.receive()?.unwrap(), b"flushed");
+    assert!(writer.write_all(b"more").is_err());
+    drop(writer);
+
+    // Overrunning the slot fails the write and discards the message
+    let mut writer = producer.writer()?;
+    writer.write_all(b"partial")?;
+    let too_big = vec![0u8; writer.capacity()];
+    let err = writer.write_all(&too_big).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(
+        writer.flush().unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    drop(writer);
+    assert!(consumer.peek_with_meta()?.is_none());
+    producer.send(b"after")?;
+    assert_eq!(consumer.peek_with_meta()?.unwrap().1, b"after");
+    assert_eq!(consumer.receive()?.unwrap(), b"after");
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn high_water_remembers_the_deepest_backlog() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(2, 64)?;
+    let monitor = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    assert_eq!(monitor.channel_high_water(2), Some(0));
+
+    // A burst of 40 drained down to nothing, then a smaller one
+    for i in 0..40u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    assert_eq!(consumer.drain()?.len(), 40);
+    producer.send_batch(&[b"a", b"b", b"c"])?;
+    assert_eq!(consumer.drain()?.len(), 3);
+    assert_eq!(monitor.channel_high_water(2), Some(40));
+
+    // Interleaved traffic never builds a backlog past 1
+    monitor.reset_high_water(2)?;
+    assert_eq!(monitor.channel_high_water(2), Some(0));
+    for i in 0..100u32 {
+        producer.send(i.to_le_bytes())?;
+        assert!(consumer.receive()?.is_some());
+    }
+    assert_eq!(monitor.channel_high_water(2), Some(1));
+
+    assert_eq!(monitor.channel_high_water(3), None);
+    assert_eq!(
+        monitor.reset_high_water(3).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    Ok(())
+}
+
+#[test]
+fn iterators_yield_what_was_sent() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 64)?;
+    let sent: Vec<Vec<u8>> = (0..20u32).map(|i| i.to_le_bytes().to_vec()).collect();
+    for message in &sent {
+        producer.send(message)?;
+    }
+
+    // Non-blocking: stops at the first empty poll
+    let received = consumer.iter().collect::<io::Result<Vec<_>>>()?;
+    let payloads: Vec<Vec<u8>> = received.into_iter().map(|(_, payload)| payload).collect();
+    assert_eq!(payloads, sent);
+    assert!(consumer.iter().next().is_none());
+
+    // Blocking: waits for each message and ends when the producer closes
+    let reader = thread::spawn(move || {
+        consumer
+            .blocking_iter()
+            .map(|message| message.map(|(_, payload)| payload))
+            .collect::<io::Result<Vec<_>>>()
+    });
+    for message in &sent {
+        producer.send(message)?;
+        thread::sleep(Duration::from_millis(1));
+    }
+    producer.close();
+    assert_eq!(reader.join().unwrap()?, sent);
+
+    Ok(())
+}
+
+#[test]
+fn receive_into_reuses_a_caller_buffer() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 16)?;
+    let mut buf = [0u8; 16];
+    assert!(consumer.receive_into(&mut buf)?.is_none());
+
+    producer.send(b"fits")?;
+    let (meta, len) = consumer.receive_into(&mut buf)?.unwrap();
+    assert_eq!(&buf[..len], b"fits");
+    assert_eq!(meta.payload_len as usize, len);
+
+    // Too small: the error names the length needed
+    producer.send([7u8; 40])?;
+    let err = consumer.receive_into(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("16 < 40"), "{}", err);
+
+    producer.send(b"next")?;
+    let (_, len) = consumer.receive_into(&mut buf)?.unwrap();
+    assert_eq!(&buf[..len], b"next");
+
+    Ok(())
+}
+
+#[test]
+fn monotonic_timestamps_never_decrease() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+    const MESSAGES: usize = 10_000;
+
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16384)
+        .with_inline_size(8)
+        .with_clock_source(ClockSource::Monotonic)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    let before = ClockSource::Monotonic.now_ns();
+    for i in 0..MESSAGES as u64 {
+        producer.send(i.to_le_bytes())?;
+    }
+    producer.send_batch(&[b"batched"])?;
+    let after = ClockSource::Monotonic.now_ns();
+
+    let mut last = before;
+    for _ in 0..=MESSAGES {
+        let (meta, _) = consumer.receive_with_meta()?.unwrap();
+        assert_eq!(ClockSource::of(&meta), ClockSource::Monotonic);
+        assert_ne!(meta.flags & FLAG_MONOTONIC, 0);
+        assert!(meta.timestamp_ns >= last, "timestamp went backwards");
+        last = meta.timestamp_ns;
+    }
+    assert!(last <= after);
+
+    // A TTL counts on the clock the message was stamped with
+    producer.send_ttl(b"fresh", Duration::from_secs(60))?;
+    assert_eq!(consumer.receive()?.unwrap(), b"fresh");
+
+    // Wall-clock producers leave the flag clear
+    drop(producer);
+    let realtime = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_producer()?;
+    realtime.send(b"wall")?;
+    let (meta, _) = consumer.receive_with_meta()?.unwrap();
+    assert_eq!(ClockSource::of(&meta), ClockSource::Realtime);
+
+    Ok(())
+}
+
+#[test]
+fn single_consumer_channels_refuse_a_second_consumer() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let consumer_on = |channel_id| {
+        ChannelBuilder::new()
+            .with_buffer_size(SHM_SIZE)
+            .with_channel_id(channel_id)
+            .build_consumer()
+    };
+
+    let _single = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_single_consumer(true)
+        .build_producer()?;
+    let first = consumer_on(0)?;
+    let err = consumer_on(0)
+        .err()
+        .expect("second consumer must be refused");
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    let err = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .build_multi_consumer(&[0])
+        .err()
+        .expect("multi-consumer must be refused");
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    // Once the first consumer is gone another may take its place, and a
+    // MultiConsumer counts as that one consumer too
+    drop(first);
+    let multi = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .build_multi_consumer(&[0])?;
+    let err = consumer_on(0)
+        .err()
+        .expect("consumer beside a multi-consumer must be refused");
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    drop(multi);
+    let _replacement = consumer_on(0)?;
+
+    // Channels are multi-consumer unless asked otherwise
+    let _multi = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(1)
+        .build_producer()?;
+    let _a = consumer_on(1)?;
+    let _b = consumer_on(1)?;
+
+    Ok(())
+}
+
+#[test]
+fn flush_wakes_a_parked_consumer_and_is_free_otherwise() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 64)?;
+    let monitor = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    let wakes = || monitor.channel_metrics(0).unwrap().consumer_wakes;
+
+    // Nobody parked: no wake
+    producer.flush();
+    assert_eq!(wakes(), 0);
+
+    let waiter = thread::spawn(move || consumer.receive_timeout(Duration::from_secs(10)));
+    let waiters = || {
+        unsafe { &*monitor.channel_table_ptr() }
+            .data_waiters
+            .load(Ordering::SeqCst)
+    };
+    while waiters() == 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // Parked with nothing sent: flush alone wakes it, once
+    producer.flush();
+    assert_eq!(wakes(), 1);
+
+    // It finds nothing and parks again until a message arrives
+    producer.send(b"last")?;
+    assert_eq!(waiter.join().unwrap()?.as_deref(), Some(&b"last"[..]));
+
+    Ok(())
+}
+
+#[test]
+fn rate_limit_caps_sends_per_second() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    const RATE: u32 = 2000;
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(2048)
+        .build_producer()?
+        .with_rate_limit(RATE);
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+
+    // A tight loop gets the initial burst (a tenth of a second's worth) and
+    // then the configured rate
+    let window = Duration::from_millis(500);
+    let started = Instant::now();
+    let mut sent = 0u32;
+    while started.elapsed() < window {
+        match producer.send(b"tick") {
+            Ok(()) => sent += 1,
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::WouldBlock),
+        }
+    }
+    let expected = RATE / 10 + RATE / 2;
+    assert!(
+        sent > expected * 9 / 10 && sent < expected * 11 / 10,
+        "sent {} in {:?}, expected about {}",
+        sent,
+        window,
+        expected
+    );
+    assert_eq!(consumer.drain()?.len(), sent as usize);
+
+    // Blocking sends wait for their tokens instead of failing
+    let started = Instant::now();
+    for _ in 0..RATE / 10 {
+        producer.send_blocking(b"tick")?;
+    }
+    assert!(started.elapsed() >= Duration::from_millis(80));
+
+    let err = producer
+        .send_batch(&[&b"tick"[..]; RATE as usize / 5])
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    Ok(())
+}
+
+#[test]
+fn receive_filtered_returns_only_matching_types() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 64)?;
+    for i in 0..12u32 {
+        producer.send_typed(i.to_le_bytes(), 10 + i % 3, 0)?;
+    }
+
+    let mut received = Vec::new();
+    while let Some((meta, payload)) = consumer.receive_filtered(|meta| meta.message_type == 11)? {
+        assert_eq!(meta.message_type, 11);
+        received.push(u32::from_le_bytes(payload.try_into().unwrap()));
+    }
+    assert_eq!(received, [1, 4, 7, 10]);
+    // Every message of type 10 and 12 was dropped, the last one while
+    // looking for a fifth match
+    assert_eq!(consumer.filtered_count(), 8);
+    assert!(consumer.receive()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn receive_adaptive_spins_briefly_then_parks() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 64)?;
+
+    // A ready message is taken without waiting out the spin
+    producer.send(b"ready")?;
+    let started = Instant::now();
+    assert_eq!(consumer.receive_adaptive(Duration::from_secs(5))?, b"ready");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // Idle past the spin window: the thread parks instead of burning CPU
+    let waiter = thread::spawn(move || {
+        let thread_cpu = || {
+            let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+            unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) };
+            let to_duration =
+                |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+            to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+        };
+        let cpu = thread_cpu();
+        let message = consumer.receive_adaptive(Duration::from_millis(10));
+        (message, thread_cpu() - cpu)
+    });
+    thread::sleep(Duration::from_millis(400));
+    producer.send(b"late")?;
+    let (message, cpu) = waiter.join().unwrap();
+    assert_eq!(message?, b"late");
+    assert!(cpu < Duration::from_millis(150), "used {:?} of CPU", cpu);
+
+    Ok(())
+}
+
+#[test]
+fn tail_consumer_watches_without_taking_messages() -> io::Result<()> {
+    let _guard = TEST_LOCK.lock();
+    cleanup_shared_memory();
+
+    let (producer, consumer) = build_pair(0, 8)?;
+    let mut tail = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_tail_consumer()?;
+    let tailed = |event: Option<TailEvent>| match event {
+        Some(TailEvent::Message(_, payload)) => u32::from_le_bytes(payload.try_into().unwrap()),
+        other => panic!("expected a message, got {:?}", other),
+    };
+
+    // The tail sees messages whether or not the consumer has taken them yet,
+    // and the consumer still gets every one
+    for i in 0..3u32 {
+        producer.send(i.to_le_bytes())?;
+    }
+    assert_eq!(consumer.receive()?.unwrap(), 0u32.to_le_bytes());
+    assert_eq!(consumer.receive()?.unwrap(), 1u32.to_le_bytes());
+    assert_eq!(
+        (0..3)
+            .map(|_| tailed(tail.receive().unwrap()))
+            .collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+    assert!(tail.receive()?.is_none());
+    assert_eq!(consumer.receive()?.unwrap(), 2u32.to_le_bytes());
+
+    // Producers never wait for the tail: it falls behind and reports the gap
+    for i in 3..23u32 {
+        producer.send(i.to_le_bytes())?;
+        assert_eq!(consumer.receive()?.unwrap(), i.to_le_bytes());
+    }
+    assert!(matches!(tail.receive()?, Some(TailEvent::Lagged(12))));
+    assert_eq!(
+        (0..8)
+            .map(|_| tailed(tail.receive().unwrap()))
+            .collect::<Vec<_>>(),
+        (15..23).collect::<Vec<_>>()
+    );
+    assert!(tail.receive()?.is_none());
+    assert_eq!(tail.lagged(), 12);
+    assert_eq!(tail.position(), 23);
+    assert_eq!(consumer.receive()?, None);
+
+    Ok(())
+}
+
+// Clean up shared memory
+fn cleanup_shared_memory() {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    }
+}
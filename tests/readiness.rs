@@ -0,0 +1,80 @@
+// Consumer::readiness_fd with epoll; Linux only.
+#![cfg(target_os = "linux")]
+
+use dmxp_kvcache::MPMC::{ChannelBuilder, Consumer, Producer};
+use std::io;
+use std::os::fd::RawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+fn build_pair(channel_id: u32) -> io::Result<(Producer, Consumer)> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .with_capacity(16)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(channel_id)
+        .build_consumer()?;
+    Ok((producer, consumer))
+}
+
+/// Wait up to `timeout_ms` for `epfd` to report an event; returns how many.
+fn epoll_wait(epfd: RawFd, timeout_ms: i32) -> io::Result<usize> {
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    let n = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 4, timeout_ms) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+/// Reset the eventfd, as a reactor does before draining the channel.
+fn clear(fd: RawFd) {
+    let mut count = 0u64;
+    unsafe { libc::read(fd, &mut count as *mut u64 as *mut libc::c_void, 8) };
+}
+
+#[test]
+#[serial_test::serial]
+fn readiness_fd_wakes_epoll_after_a_send() -> io::Result<()> {
+    let (producer, consumer) = build_pair(0)?;
+    let fd = consumer.readiness_fd()?;
+    assert_eq!(consumer.readiness_fd()?, fd, "the fd is created once");
+
+    let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    assert!(epfd >= 0, "{}", io::Error::last_os_error());
+    let mut event = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: 0,
+    };
+    assert_eq!(
+        unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut event) },
+        0
+    );
+
+    // Readable at first in case messages were waiting; quiet once reset
+    assert_eq!(epoll_wait(epfd, 0)?, 1);
+    clear(fd);
+    assert_eq!(consumer.receive()?, None);
+    assert_eq!(epoll_wait(epfd, 0)?, 0);
+
+    let start = Instant::now();
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        producer.send(b"ready").map(|()| producer)
+    });
+    assert_eq!(epoll_wait(epfd, 5_000)?, 1);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    clear(fd);
+    assert_eq!(consumer.receive()?.as_deref(), Some(&b"ready"[..]));
+    assert_eq!(consumer.receive()?, None);
+    let _producer = sender.join().unwrap()?;
+
+    unsafe { libc::close(epfd) };
+    Ok(())
+}
@@ -1,27 +1,20 @@
-use crossbeam_utils::CachePadded;
-use dmxp_kvcache::MPMC::Buffer::layout::ChannelEntry;
-use dmxp_kvcache::MPMC::Buffer::RingBuffer;
+use dmxp_kvcache::MPMC::Buffer::layout::{ChannelEntry, CHANNEL_FLAG_CHECKSUM};
+use dmxp_kvcache::MPMC::Buffer::{EnqueueError, RingBuffer, Slot, SlotLayout, MSG_INLINE};
 use dmxp_kvcache::MPMC::Structs::Buffer_Structs::MessageMeta;
 use std::alloc::{alloc, Layout};
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::thread;
 
 fn create_dummy_channel_entry(capacity: u64) -> ChannelEntry {
     ChannelEntry {
-        channel_id: 0,
-        flags: 0,
         capacity,
-        band_offset: 0,
-        signal: std::sync::atomic::AtomicU32::new(0),
-        tail: CachePadded::new(AtomicU64::new(0)),
-        head: CachePadded::new(AtomicU64::new(0)),
-        _pad: [],
+        ..Default::default()
     }
 }
 
 fn make_aligned_backing(capacity: usize) -> (*mut u8, Layout) {
-    let size = capacity * RingBuffer::slot_stride();
+    let size = capacity * RingBuffer::slot_stride(MSG_INLINE);
     let layout = Layout::from_size_align(size, 128).unwrap();
     let ptr = unsafe { alloc(layout) };
     if ptr.is_null() {
@@ -38,7 +31,7 @@ fn simple_enqueue_dequeue() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let meta = MessageMeta::default();
@@ -67,7 +60,7 @@ fn full_buffer() {
     let entry = create_dummy_channel_entry(capacity as u64);
     let rb = unsafe { RingBuffer::new(&entry, ptr) };
     unsafe {
-        rb.init_slots();
+        rb.init_slots().unwrap();
     }
 
     let meta = MessageMeta::default();
@@ -107,7 +100,7 @@ fn small_mpmc_correctness() {
 
     let rb = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
     unsafe {
-        rb.0.init_slots();
+        rb.0.init_slots().unwrap();
     }
 
     let rb_prod = rb.clone();
@@ -141,3 +134,420 @@ fn small_mpmc_correctness() {
         std::alloc::dealloc(ptr, layout);
     }
 }
+
+#[test]
+fn dequeue_ref_holds_slot_until_drop() {
+    let capacity = 2;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+
+    let meta = MessageMeta::default();
+    assert!(rb.enqueue(meta, b"first").is_some());
+    assert!(rb.enqueue(meta, b"second").is_some());
+
+    {
+        let msg = rb.dequeue_ref().expect("message should be ready");
+        assert_eq!(&*msg, b"first");
+        assert_eq!(msg.meta().payload_len, 5);
+
+        // The borrowed slot must not be handed to a producer while the guard lives
+        assert!(rb.enqueue(meta, b"third").is_none());
+
+        // Other consumers keep making progress past the held slot
+        let (_meta, data) = rb.dequeue().expect("second message should be ready");
+        assert_eq!(data, b"second");
+    }
+
+    // Dropping the guard releases the slot
+    assert!(rb.enqueue(meta, b"third").is_some());
+    let msg = rb.dequeue_ref().expect("third message should be ready");
+    assert_eq!(msg.payload(), b"third");
+    drop(msg);
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn enqueue_dequeue_round_trips_meta_and_payload() {
+    let capacity = 8;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+
+    let meta = MessageMeta {
+        message_id: 42,
+        timestamp_ns: 1_700_000_000_000_000_000,
+        channel_id: 3,
+        message_type: 7,
+        sender_pid: 1234,
+        sender_runtime: 2,
+        flags: 0x1,
+        payload_len: 0, // overwritten by enqueue
+        ..Default::default()
+    };
+    let payload: Vec<u8> = (0..=255u8).collect();
+
+    assert!(rb.enqueue(meta, &payload).is_some());
+    assert!(rb.enqueue(meta, &[]).is_some());
+
+    let (meta_out, data) = rb.dequeue().unwrap();
+    assert_eq!(data, payload);
+    assert_eq!(meta_out.payload_len, payload.len() as u32);
+    assert_eq!(meta_out.message_id, 42);
+    assert_eq!(meta_out.timestamp_ns, meta.timestamp_ns);
+    assert_eq!(meta_out.channel_id, 3);
+    assert_eq!(meta_out.message_type, 7);
+    assert_eq!(meta_out.sender_pid, 1234);
+    assert_eq!(meta_out.sender_runtime, 2);
+    assert_eq!(meta_out.flags, 0x1);
+
+    // Zero-length payloads are valid messages
+    let (meta_out, data) = rb.dequeue().unwrap();
+    assert!(data.is_empty());
+    assert_eq!(meta_out.payload_len, 0);
+
+    assert!(rb.dequeue().is_none());
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn views_over_one_channel_entry_share_cursors() {
+    let capacity = 8;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let producer_view = unsafe { RingBuffer::new(&entry, ptr) };
+    let consumer_view = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        producer_view.init_slots().unwrap();
+    }
+
+    let meta = MessageMeta::default();
+    for i in 0..capacity as u8 {
+        assert!(producer_view.enqueue(meta, &[i]).is_some());
+    }
+
+    // The cursors live in the shared ChannelEntry, so every view sees the ring as full
+    assert_eq!(entry.tail.load(Ordering::Relaxed), capacity as u64);
+    assert!(consumer_view.enqueue(meta, &[0xFF]).is_none());
+
+    for i in 0..capacity as u8 {
+        let (_meta, data) = consumer_view.dequeue().unwrap();
+        assert_eq!(data, vec![i]);
+    }
+    assert_eq!(entry.head.load(Ordering::Relaxed), capacity as u64);
+    assert!(producer_view.dequeue().is_none());
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn checksum_detects_corrupted_payload() {
+    let capacity = 4;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let mut entry = create_dummy_channel_entry(capacity as u64);
+    entry.flags = AtomicU32::new(CHANNEL_FLAG_CHECKSUM);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+
+    let meta = MessageMeta::default();
+    assert!(rb.enqueue(meta, b"intact payload").is_some());
+    assert!(rb.enqueue(meta, b"corrupted payload").is_some());
+
+    let (meta_out, data) = rb.dequeue().unwrap();
+    assert_ne!(meta_out.checksum, 0);
+    assert!(rb.verify_checksum(&meta_out, &data));
+
+    // Flip one payload byte of the second slot behind the ring's back
+    unsafe {
+        let payload = ptr.add(RingBuffer::slot_stride(MSG_INLINE) + Slot::PAYLOAD_OFFSET);
+        *payload.add(3) ^= 0x01;
+    }
+    let (meta_out, data) = rb.dequeue().unwrap();
+    assert!(!rb.verify_checksum(&meta_out, &data));
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn drain_into_empties_half_full_ring() {
+    let capacity = 16;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+
+    let meta = MessageMeta::default();
+    for i in 0..capacity as u8 / 2 {
+        assert!(rb.enqueue(meta, &[i]).is_some());
+    }
+
+    let mut out = Vec::new();
+    assert_eq!(rb.drain_into(&mut out), capacity / 2);
+    let payloads: Vec<Vec<u8>> = out.into_iter().map(|(_, data)| data).collect();
+    let expected: Vec<Vec<u8>> = (0..capacity as u8 / 2).map(|i| vec![i]).collect();
+    assert_eq!(payloads, expected);
+
+    assert!(rb.dequeue().is_none());
+    let mut out = Vec::new();
+    assert_eq!(rb.drain_into(&mut out), 0);
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn oversize_payload_is_rejected_not_truncated() {
+    let capacity = 4;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+    assert_eq!(rb.max_payload(), MSG_INLINE);
+
+    let meta = MessageMeta::default();
+    assert!(rb.enqueue(meta, &vec![7u8; 2048]).is_none());
+    assert!(rb.enqueue(meta, &vec![7u8; MSG_INLINE + 1]).is_none());
+    let small = [1u8; 100];
+    let large = vec![2u8; MSG_INLINE + 1];
+    assert!(rb
+        .enqueue_batch(&[(&meta, &small[..]), (&meta, &large[..])])
+        .is_none());
+    assert!(rb.is_empty());
+
+    // Rejections are not full-ring failures
+    assert_eq!(entry.enqueue_full_failures.load(Ordering::Relaxed), 0);
+
+    // A payload of exactly the slot's size still fits
+    let exact = vec![3u8; MSG_INLINE];
+    assert!(rb.enqueue(meta, &exact).is_some());
+    assert_eq!(rb.dequeue().unwrap().1, exact);
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn try_enqueue_reports_full_only_when_ring_is_full() {
+    let capacity = 64;
+    let (ptr, layout) = make_aligned_backing(capacity);
+    let entry = Box::new(create_dummy_channel_entry(capacity as u64));
+    let entry_ptr: *const ChannelEntry = &*entry;
+
+    struct SendRingBuffer(RingBuffer);
+    unsafe impl Send for SendRingBuffer {}
+    unsafe impl Sync for SendRingBuffer {}
+
+    let rb = Arc::new(SendRingBuffer(unsafe { RingBuffer::new(entry_ptr, ptr) }));
+    unsafe {
+        rb.0.init_slots().unwrap();
+    }
+
+    // Producers race for the slots without consumers: losing a race must
+    // read as Contended, and Full may only come once every slot is taken
+    let barrier = Arc::new(std::sync::Barrier::new(8));
+    let producers: Vec<_> = (0..8)
+        .map(|_| {
+            let rb = rb.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut sent = 0usize;
+                loop {
+                    match rb.0.try_enqueue(MessageMeta::default(), b"x") {
+                        Ok(_) => sent += 1,
+                        Err(EnqueueError::Contended) => std::hint::spin_loop(),
+                        Err(EnqueueError::Full) => {
+                            assert_eq!(rb.0.len(), capacity);
+                            return sent;
+                        }
+                        Err(err) => panic!("unexpected {err}"),
+                    }
+                }
+            })
+        })
+        .collect();
+    let sent: usize = producers.into_iter().map(|p| p.join().unwrap()).sum();
+    assert_eq!(sent, capacity);
+
+    assert_eq!(
+        rb.0.try_enqueue(MessageMeta::default(), &[0u8; MSG_INLINE + 1]),
+        Err(EnqueueError::TooLarge)
+    );
+    assert!(rb.0.dequeue().is_some());
+    assert!(rb.0.try_enqueue(MessageMeta::default(), b"x").is_ok());
+    assert_eq!(
+        rb.0.try_enqueue(MessageMeta::default(), b"x"),
+        Err(EnqueueError::Full)
+    );
+
+    drop(rb);
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn dequeue_gives_up_on_a_stalled_producer() {
+    let capacity = 8;
+    let (ptr, layout) = make_aligned_backing(capacity);
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let mut rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe {
+        rb.init_slots().unwrap();
+    }
+    assert_eq!(rb.stall_spins(), dmxp_kvcache::MPMC::DEFAULT_STALL_SPINS);
+
+    // A producer claims position 0 and stalls before publishing it, while
+    // another one publishes position 1 behind it
+    entry.tail.fetch_add(1, Ordering::AcqRel);
+    assert!(rb.enqueue(MessageMeta::default(), b"behind").is_some());
+
+    // The consumer neither hangs nor skips ahead, with any budget
+    assert!(rb.dequeue().is_none());
+    rb.set_stall_spins(16);
+    assert!(rb.dequeue().is_none());
+    rb.set_stall_spins(0);
+    assert!(rb.dequeue().is_none());
+
+    // Once the stalled producer publishes, both messages come out in order
+    let slot = ptr.cast::<Slot>();
+    unsafe {
+        (*slot).meta.payload_len = 7;
+        let payload = ptr.add(Slot::PAYLOAD_OFFSET);
+        std::ptr::copy_nonoverlapping(b"stalled".as_ptr(), payload, 7);
+        (*slot).sequence.store(1, Ordering::Release);
+    }
+    assert_eq!(rb.dequeue().unwrap().1, b"stalled");
+    assert_eq!(rb.dequeue().unwrap().1, b"behind");
+    assert!(rb.dequeue().is_none());
+
+    drop(rb);
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn slot_written_by_hand_from_slot_layout_is_received() {
+    // An external producer knows only the documented wire format
+    let capacity = 4;
+    let inline = 256;
+    let entry = ChannelEntry {
+        capacity: capacity as u64,
+        inline_size: inline as u32,
+        ..Default::default()
+    };
+    let layout = Layout::from_size_align(capacity * Slot::stride(inline), 128).unwrap();
+    let ptr = unsafe { alloc(layout) };
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe { rb.init_slots().unwrap() };
+    let slots = rb.slot_layout();
+    assert_eq!(slots, SlotLayout::new(inline));
+
+    for (n, payload) in [&b"first"[..], &[7u8; 256][..], b"third"]
+        .into_iter()
+        .enumerate()
+    {
+        // Claim the tail slot, write it, publish it
+        let tail = entry.tail.load(Ordering::Relaxed);
+        let slot = unsafe { ptr.add((tail as usize % capacity) * slots.stride) };
+        let sequence = unsafe {
+            &*slot
+                .add(slots.sequence_offset)
+                .cast::<std::sync::atomic::AtomicU64>()
+        };
+        assert_eq!(sequence.load(Ordering::Acquire), tail);
+        entry
+            .tail
+            .compare_exchange(tail, tail + 1, Ordering::AcqRel, Ordering::Relaxed)
+            .unwrap();
+        let meta = MessageMeta {
+            message_id: n as u64,
+            payload_len: payload.len() as u32,
+            ..Default::default()
+        };
+        unsafe {
+            slot.add(slots.meta_offset)
+                .cast::<MessageMeta>()
+                .write_unaligned(meta);
+            std::ptr::copy_nonoverlapping(
+                payload.as_ptr(),
+                slot.add(slots.payload_offset),
+                payload.len(),
+            );
+        }
+        sequence.store(tail + 1, Ordering::Release);
+
+        let (meta, data) = rb.dequeue().unwrap();
+        assert_eq!(meta.message_id, n as u64);
+        assert_eq!(data, payload);
+    }
+    assert!(rb.dequeue().is_none());
+
+    unsafe { std::alloc::dealloc(ptr, layout) };
+}
+
+#[test]
+fn init_slots_refuses_a_channel_in_use() {
+    let capacity = 8;
+    let (ptr, layout) = make_aligned_backing(capacity);
+
+    let entry = create_dummy_channel_entry(capacity as u64);
+    let rb = unsafe { RingBuffer::new(&entry, ptr) };
+    unsafe { rb.init_slots().unwrap() };
+    // Nothing sent yet: running it again is harmless
+    unsafe { rb.init_slots().unwrap() };
+
+    let meta = MessageMeta::default();
+    rb.enqueue(meta, b"first").unwrap();
+    rb.enqueue(meta, b"second").unwrap();
+    assert_eq!(rb.dequeue().unwrap().1, b"first");
+
+    let err = unsafe { rb.init_slots() }.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    // The unread message survived the attempt
+    assert_eq!(rb.dequeue().unwrap().1, b"second");
+
+    // An explicit reset empties the ring and makes it usable from scratch
+    rb.enqueue(meta, b"lost").unwrap();
+    unsafe { rb.reinit_slots() };
+    assert!(rb.is_empty());
+    assert!(rb.dequeue().is_none());
+    unsafe { rb.init_slots().unwrap() };
+    rb.enqueue(meta, b"fresh").unwrap();
+    assert_eq!(rb.dequeue().unwrap().1, b"fresh");
+
+    unsafe {
+        std::alloc::dealloc(ptr, layout);
+    }
+}
@@ -0,0 +1,71 @@
+// Serde support; run with `cargo test --features serde`.
+#![cfg(feature = "serde")]
+
+use dmxp_kvcache::Core::alloc::{ChannelMetrics, SharedMemoryAllocator};
+use dmxp_kvcache::MPMC::Buffer::layout::DEFAULT_MAX_CHANNELS;
+use dmxp_kvcache::MPMC::Structs::Buffer_Structs::FLAG_COMPRESSED;
+use dmxp_kvcache::MPMC::Structs::MessageMeta;
+use std::io;
+
+fn fields(meta: &MessageMeta) -> (u64, u64, u32, u32, u32, u16, u16, u32, u32, u16, u32) {
+    (
+        meta.message_id,
+        meta.timestamp_ns,
+        meta.channel_id,
+        meta.message_type,
+        meta.sender_pid,
+        meta.sender_runtime,
+        meta.flags,
+        meta.payload_len,
+        meta.checksum,
+        meta.schema_version,
+        meta.ttl_ms,
+    )
+}
+
+#[test]
+fn message_meta_round_trips_through_json() {
+    let meta = MessageMeta {
+        message_id: u64::MAX,
+        timestamp_ns: 1_709_214_330_000_000_123,
+        channel_id: 7,
+        message_type: 42,
+        sender_pid: 1234,
+        sender_runtime: 1,
+        flags: FLAG_COMPRESSED | 0x01,
+        payload_len: 512,
+        checksum: 0xdeadbeef,
+        schema_version: 3,
+        ttl_ms: 250,
+    };
+
+    let json = serde_json::to_string(&meta).unwrap();
+    assert!(json.contains("\"schema_version\":3"), "{}", json);
+    let back: MessageMeta = serde_json::from_str(&json).unwrap();
+    assert_eq!(fields(&back), fields(&meta));
+}
+
+#[test]
+fn diagnostics_serialize_to_json() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let allocator = SharedMemoryAllocator::new(8 * 1024 * 1024, DEFAULT_MAX_CHANNELS)?;
+    let channel = allocator.create_channel(8, None)?;
+    channel
+        .buffer()
+        .enqueue(MessageMeta::default(), b"x")
+        .expect("ring full");
+
+    let diagnostics = allocator.diagnostics();
+    let json = serde_json::to_value(&diagnostics).unwrap();
+    assert_eq!(json["channel_count"], 1);
+    assert_eq!(json["channels"][0]["info"]["capacity"], 8);
+    assert_eq!(json["channels"][0]["metrics"]["enqueue_count"], 1);
+
+    let back = serde_json::from_value(json).unwrap();
+    assert_eq!(diagnostics, back);
+    let metrics: ChannelMetrics =
+        serde_json::from_str(&serde_json::to_string(&diagnostics.channels[0].metrics).unwrap())
+            .unwrap();
+    assert_eq!(metrics, diagnostics.channels[0].metrics);
+    Ok(())
+}
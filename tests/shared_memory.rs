@@ -116,6 +116,48 @@ mod linux_tests {
             }
         }
     }
+
+    #[test]
+    fn test_only_the_creator_unlinks_on_drop() {
+        let path = std::path::Path::new("/dev/shm/test_unlink");
+        let mut creator = create_shared_memory(4096, Some("test_unlink")).unwrap();
+        let mut attached = attach_shared_memory("test_unlink", 4096).unwrap();
+        assert!(creator.is_creator());
+        assert!(!attached.is_creator());
+
+        attached.set_unlink_on_drop(true);
+        drop(attached);
+        assert!(path.exists(), "an attached handle must not unlink");
+
+        creator.set_unlink_on_drop(true);
+        drop(creator);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_resident_bytes_climb_as_pages_are_touched() {
+        let page = 4096;
+        let size = 64 * page;
+        let shm = create_shared_memory(size, None).unwrap();
+        let untouched = shm.resident_bytes().unwrap();
+
+        // Prefault the first half, one write per page
+        for offset in (0..size / 2).step_by(page) {
+            unsafe { shm.as_ptr().add(offset).write_volatile(1) };
+        }
+        let half = shm.resident_bytes().unwrap();
+        assert!(half > untouched, "{} <= {}", half, untouched);
+        assert!(half >= size / 2, "{}", half);
+        assert_eq!(shm.resident_bytes_in(100, 50).unwrap(), 50);
+
+        for offset in (size / 2..size).step_by(page) {
+            unsafe { shm.as_ptr().add(offset).write_volatile(1) };
+        }
+        assert_eq!(shm.resident_bytes().unwrap(), size);
+
+        let err = shm.resident_bytes_in(size - 10, 11).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
 }
 
 #[cfg(not(target_os = "linux"))]
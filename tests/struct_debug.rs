@@ -0,0 +1,101 @@
+// Human-readable formatting of transport structs.
+
+use dmxp_kvcache::Core::alloc::SharedMemoryAllocator;
+use dmxp_kvcache::MPMC::ChannelBuilder;
+use dmxp_kvcache::MPMC::Structs::Buffer_Structs::{FLAG_COMPRESSED, FLAG_SPILLED};
+use dmxp_kvcache::MPMC::Structs::MessageMeta;
+use std::io;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+#[test]
+fn message_meta_display_decodes_fields() {
+    let meta = MessageMeta {
+        message_id: 42,
+        // 2024-02-29T13:45:30.000000123Z
+        timestamp_ns: 1_709_214_330_000_000_123,
+        channel_id: 7,
+        message_type: 1,
+        sender_pid: 1234,
+        sender_runtime: 1,
+        flags: FLAG_SPILLED | FLAG_COMPRESSED | 0x03,
+        payload_len: 100,
+        checksum: 0xdeadbeef,
+        schema_version: 2,
+        ttl_ms: 0,
+    };
+
+    let text = meta.to_string();
+    assert!(text.starts_with("MessageMeta {"), "{}", text);
+    assert!(
+        text.contains("timestamp: 2024-02-29T13:45:30.000000123Z"),
+        "{}",
+        text
+    );
+    assert!(text.contains("message_type: 1 (default)"), "{}", text);
+    assert!(text.contains("sender_runtime: 1 (rust)"), "{}", text);
+    assert!(
+        text.contains("flags: SPILLED | COMPRESSED | 0x0003"),
+        "{}",
+        text
+    );
+    assert!(text.contains("checksum: 0xdeadbeef"), "{}", text);
+
+    // The derived Debug keeps the raw integers for machine parsing
+    let raw = format!("{:?}", meta);
+    assert!(raw.contains("timestamp_ns: 1709214330000000123"), "{}", raw);
+    assert!(raw.contains("flags: 49155"), "{}", raw);
+}
+
+#[test]
+fn message_meta_display_handles_unset_fields() {
+    let meta = MessageMeta {
+        message_type: 9,
+        ..Default::default()
+    };
+    let text = meta.to_string();
+    assert!(text.contains("timestamp: unset"), "{}", text);
+    assert!(text.contains("message_type: 9,"), "{}", text);
+    assert!(text.contains("flags: 0x0000"), "{}", text);
+}
+
+#[test]
+fn ring_buffer_debug_shows_cursors_and_occupancy() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(8)
+        .build_producer()?;
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?;
+    for i in 0..5u8 {
+        producer.send([i])?;
+    }
+    consumer.receive()?;
+
+    let allocator = SharedMemoryAllocator::attach(SHM_SIZE)?;
+    let channel = allocator.get_channel(0).unwrap();
+    let text = format!("{:?}", channel.buffer());
+    for field in [
+        "capacity: 8",
+        "head: 1",
+        "tail: 5",
+        "len: 4",
+        "empty: false",
+        "full: false",
+    ] {
+        assert!(text.contains(field), "missing {:?} in {}", field, text);
+    }
+
+    for i in 5..9u8 {
+        producer.send([i])?;
+    }
+    let text = format!("{:?}", channel);
+    assert!(text.contains("channel_id: 0"), "{}", text);
+    assert!(text.contains("len: 8"), "{}", text);
+    assert!(text.contains("full: true"), "{}", text);
+    Ok(())
+}
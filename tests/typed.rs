@@ -0,0 +1,92 @@
+// Typed Pod messages; run with `cargo test --features bytemuck`.
+#![cfg(feature = "bytemuck")]
+
+use bytemuck::{Pod, Zeroable};
+use dmxp_kvcache::MPMC::ChannelBuilder;
+use std::io;
+use std::time::Duration;
+
+const SHM_SIZE: usize = 8 * 1024 * 1024;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct Quote {
+    instrument: u32,
+    venue: u16,
+    side: u8,
+    _pad: u8,
+    price: f64,
+    quantity: u64,
+}
+
+#[test]
+#[serial_test::serial]
+fn pod_records_round_trip() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .build_producer()?
+        .into_typed::<Quote>();
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?
+        .into_typed::<Quote>();
+
+    let quotes: Vec<Quote> = (0..5)
+        .map(|i| Quote {
+            instrument: 42 + i,
+            venue: 7,
+            side: (i % 2) as u8,
+            _pad: 0,
+            price: 101.25 + i as f64,
+            quantity: 1000 * u64::from(i),
+        })
+        .collect();
+    for quote in &quotes {
+        producer.send(quote)?;
+    }
+
+    assert_eq!(consumer.recv()?, Some(quotes[0]));
+    assert_eq!(consumer.recv_blocking()?, quotes[1]);
+    assert_eq!(
+        consumer.recv_timeout(Duration::from_millis(100))?,
+        Some(quotes[2])
+    );
+    let (meta, bytes) = consumer.inner().receive_with_meta()?.unwrap();
+    assert_eq!(meta.payload_len as usize, std::mem::size_of::<Quote>());
+    assert_eq!(bytes, bytemuck::bytes_of(&quotes[3]));
+    assert_eq!(consumer.recv()?, Some(quotes[4]));
+    assert_eq!(consumer.recv()?, None);
+
+    Ok(())
+}
+
+#[test]
+#[serial_test::serial]
+fn payloads_of_the_wrong_size_are_rejected() -> io::Result<()> {
+    let _ = std::fs::remove_file("/dev/shm/dmxp_alloc");
+    let producer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .with_capacity(16)
+        .build_producer()?
+        .into_typed::<u64>();
+    let consumer = ChannelBuilder::new()
+        .with_buffer_size(SHM_SIZE)
+        .with_channel_id(0)
+        .build_consumer()?
+        .into_typed::<u64>();
+
+    producer.inner().send(b"short")?;
+    producer.send(&7)?;
+
+    let err = consumer.recv().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    // The bad message was consumed; the next one decodes
+    assert_eq!(consumer.recv()?, Some(7));
+
+    Ok(())
+}